@@ -0,0 +1,11 @@
+// BOOK
+
+pub use crate::book::Book;
+pub use crate::book::BrokenRef;
+pub use crate::book::CodeBlockInfo;
+pub use crate::book::ImageInfo;
+pub use crate::book::IncludeInfo;
+pub use crate::book::LinkInfo;
+pub use crate::book::LinkKind;
+pub use crate::book::RefDefInfo;
+pub use crate::book::SummaryChapter;
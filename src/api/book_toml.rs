@@ -0,0 +1,7 @@
+pub use crate::book_toml::BookTable;
+pub use crate::book_toml::BookToml;
+pub use crate::book_toml::BuildTable;
+pub use crate::book_toml::HtmlOutput;
+pub use crate::book_toml::OutputTable;
+pub use crate::book_toml::PreprocessorTable;
+pub use crate::book_toml::RustTable;
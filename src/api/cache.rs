@@ -0,0 +1,4 @@
+// CACHE
+
+pub use crate::cache::CacheStats;
+pub use crate::cache::MarkdownCache;
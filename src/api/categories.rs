@@ -1,94 +1,233 @@
-use std::fs::File;
+use std::collections::HashMap;
 use std::io::Write;
 use std::path::Path;
+use std::path::PathBuf;
 
-use anyhow::Context;
-use anyhow::Result;
-
+use crate::Error;
+use crate::Result;
 use crate::fs;
-use crate::parser;
+use crate::generate;
+pub use crate::generate::CategoryIndexOptions;
+pub use crate::generate::CrateIndexOptions;
+pub use crate::generate::CrateSort;
+use crate::registry;
+use crate::summary;
 
 // MARKDOWN GENERATION
 
-/// Generate a listing of crates.io categories
-/// and write to a Markdown file.
+/// Generate a page of crates.io categories and write it to a Markdown
+/// file.
+///
+/// Categories are those of the crates mentioned in the Markdown files of
+/// the source directory (when available from the sources set in
+/// `options`) and those linked directly (e.g.
+/// `https://crates.io/categories/parsing`). Subcategories (e.g.
+/// `web-programming::websocket`) are nested under their parent. Each
+/// category lists its crates and the chapters that cover it, and is
+/// followed by `cat-*` reference definitions consistent with the
+/// `category` link rule.
+///
+/// src_dir_path: path to the source directory.
+///
+/// dest_file_path: path to the file to create and write into.
+///
+/// options: see [`CategoryIndexOptions`].
 pub fn generate_categories<P1: AsRef<Path>, P2: AsRef<Path>>(
     src_dir_path: P1,
     dest_file_path: P2,
+    options: CategoryIndexOptions,
 ) -> Result<()> {
-    fs::create_parent_dir_for(dest_file_path.as_ref())?;
-    let mut f = File::create(dest_file_path).context("Failed to create categories file.")?;
-    writeln!(f, "# Categories\n")?;
+    let src_dir_path = canonical_src_dir(src_dir_path)?;
+    let dest_file_path = dest_file_path.as_ref();
+    let dest_dir_path = canonical_dest_dir(dest_file_path)?;
+    let mut buf = Vec::new();
+    write_categories(&src_dir_path, &dest_dir_path, options, &mut buf)?;
+    std::fs::write(dest_file_path, buf).map_err(|e| Error::io(dest_file_path, e))
+}
 
-    let src_dir_path = fs::check_is_dir(src_dir_path)?;
-    let all_markdown = fs::read_to_string_all_markdown_files_in(src_dir_path)?;
-    let mut parser = parser::get_parser(all_markdown.as_ref());
-    let links = parser::extract_links(&mut parser);
-
-    let mut categories = std::collections::BTreeSet::new();
-    for l in links {
-        let url = l.get_url();
-        if url.contains("crates.io/categories/") {
-            let mut path = url.split('?').next().unwrap_or("");
-            if path.ends_with('/') {
-                path = &path[..path.len() - 1];
-            }
-            if let Some(name) = path.split('/').next_back()
-                && !name.is_empty()
-                && name != "categories"
-                && name
-                    .chars()
-                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
-            {
-                categories.insert(name.to_string());
-            }
-        }
-    }
+/// Generate a page of crates.io categories and write it to a writer
+/// (e.g. a File or a `Vec<u8>`), see [`generate_categories`]. Links to
+/// the chapters are relative to the source directory.
+///
+/// src_dir_path: path to the source directory.
+///
+/// options: see [`CategoryIndexOptions`].
+///
+/// w: writer to write into.
+pub fn generate_categories_to_writer<P: AsRef<Path>, W: Write>(
+    src_dir_path: P,
+    options: CategoryIndexOptions,
+    w: &mut W,
+) -> Result<()> {
+    let src_dir_path = canonical_src_dir(src_dir_path)?;
+    write_categories(&src_dir_path, &src_dir_path, options, w)
+}
+
+/// Write the categories page; links to the chapters are relative to
+/// `dest_dir_path`. Both paths are canonicalized.
+fn write_categories<W: Write>(
+    src_dir_path: &Path,
+    dest_dir_path: &Path,
+    options: CategoryIndexOptions,
+    w: &mut W,
+) -> Result<()> {
+    let metadata = generate::read_chapter_metadata(src_dir_path)?;
+    let crate_mentions = generate::find_crate_mentions(src_dir_path, &metadata, &options.parser)?;
+    let category_mentions = generate::find_link_mentions(
+        src_dir_path,
+        generate::category_slug_from_url,
+        &options.parser,
+    )?;
 
-    for c in categories {
-        writeln!(f, "- [{c}](https://crates.io/categories/{c})")?;
+    // Collect categories from the available sources, in increasing
+    // order of precedence
+    let mut registry = registry::CrateRegistry::new();
+    registry.add_category_mapping(&registry::bundled_categories()?);
+    if let Some(ref dir) = options.cargo_metadata_dir_path {
+        registry.add_package_metadata(&registry::run_cargo_metadata(dir)?);
+    }
+    if let Some(ref dir) = options.db_dump_dir_path {
+        let names = crate_mentions.keys().cloned().collect();
+        registry.add_db_dump(&registry::read_db_dump(dir, Some(&names))?);
     }
+    if let Some(ref mapping_path) = options.mapping_path {
+        registry.add_category_mapping(&registry::read_category_mapping(mapping_path)?);
+    }
+
+    let chapter_link = chapter_linker(src_dir_path, dest_dir_path);
+    let entries = generate::category_index_entries(
+        &crate_mentions,
+        &category_mentions,
+        &registry,
+        chapter_link,
+    );
 
+    generate::write_category_index(&entries, w)?;
     Ok(())
 }
 
-/// Generate a crate index and write to a Markdown file.
+/// Generate an index of the crates mentioned in the Markdown files of a
+/// source directory (via crates.io, docs.rs or lib.rs links) and write it
+/// to a Markdown file, as a table.
+///
+/// Crates listed in the `crates` key of a chapter's front matter count as
+/// mentioned by that chapter. Each crate is listed with its version(s),
+/// description, repository and categories, when available from the
+/// sources set in `options`, with the front matter tags of the chapters
+/// that mention it, if any, and with links to those chapters.
+///
+/// src_dir_path: path to the source directory.
+///
+/// dest_file_path: path to the file to create and write into.
+///
+/// options: see [`CrateIndexOptions`].
 pub fn generate_crates<P1: AsRef<Path>, P2: AsRef<Path>>(
     src_dir_path: P1,
     dest_file_path: P2,
+    options: CrateIndexOptions,
 ) -> Result<()> {
-    fs::create_parent_dir_for(dest_file_path.as_ref())?;
-    let mut f = File::create(dest_file_path).context("Failed to create crates file.")?;
-    writeln!(f, "# Crates\n")?;
+    let src_dir_path = canonical_src_dir(src_dir_path)?;
+    let dest_file_path = dest_file_path.as_ref();
+    let dest_dir_path = canonical_dest_dir(dest_file_path)?;
+    let mut buf = Vec::new();
+    write_crates(&src_dir_path, &dest_dir_path, options, &mut buf)?;
+    std::fs::write(dest_file_path, buf).map_err(|e| Error::io(dest_file_path, e))
+}
 
-    let src_dir_path = fs::check_is_dir(src_dir_path)?;
-    let all_markdown = fs::read_to_string_all_markdown_files_in(src_dir_path)?;
-    let mut parser = parser::get_parser(all_markdown.as_ref());
-    let links = parser::extract_links(&mut parser);
-
-    let mut crates = std::collections::BTreeSet::new();
-    for l in links {
-        let url = l.get_url();
-        if url.contains("crates.io/crates/") {
-            let mut path = url.split('?').next().unwrap_or("");
-            if path.ends_with('/') {
-                path = &path[..path.len() - 1];
-            }
-            if let Some(name) = path.split('/').next_back()
-                && !name.is_empty()
-                && name != "crates"
-                && name
-                    .chars()
-                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
-            {
-                crates.insert(name.to_string());
-            }
-        }
-    }
+/// Generate an index of the crates mentioned in the Markdown files of a
+/// source directory and write it to a writer (e.g. a File or a
+/// `Vec<u8>`), see [`generate_crates`]. Links to the chapters are
+/// relative to the source directory.
+///
+/// src_dir_path: path to the source directory.
+///
+/// options: see [`CrateIndexOptions`].
+///
+/// w: writer to write into.
+pub fn generate_crates_to_writer<P: AsRef<Path>, W: Write>(
+    src_dir_path: P,
+    options: CrateIndexOptions,
+    w: &mut W,
+) -> Result<()> {
+    let src_dir_path = canonical_src_dir(src_dir_path)?;
+    write_crates(&src_dir_path, &src_dir_path, options, w)
+}
+
+/// Write the crate index; links to the chapters are relative to
+/// `dest_dir_path`. Both paths are canonicalized.
+fn write_crates<W: Write>(
+    src_dir_path: &Path,
+    dest_dir_path: &Path,
+    options: CrateIndexOptions,
+    w: &mut W,
+) -> Result<()> {
+    let metadata = generate::read_chapter_metadata(src_dir_path)?;
+    let mentions = generate::find_crate_mentions(src_dir_path, &metadata, &options.parser)?;
 
-    for c in crates {
-        writeln!(f, "- [{c}](https://crates.io/crates/{c})")?;
+    // Collect crate metadata from the available sources
+    let mut registry = registry::CrateRegistry::new();
+    if let Some(ref cargo_lock_path) = options.cargo_lock_path {
+        registry.add_locked_packages(&registry::read_cargo_lock(cargo_lock_path)?);
+    }
+    if let Some(ref dir) = options.cargo_metadata_dir_path {
+        registry.add_package_metadata(&registry::run_cargo_metadata(dir)?);
+    }
+    if let Some(ref dir) = options.db_dump_dir_path {
+        let names = mentions.keys().cloned().collect();
+        registry.add_db_dump(&registry::read_db_dump(dir, Some(&names))?);
     }
 
+    let chapter_link = chapter_linker(src_dir_path, dest_dir_path);
+    let entries = generate::crate_index_entries(&mentions, &metadata, &registry, chapter_link);
+    generate::write_crate_index(entries, &registry, &options, w)?;
     Ok(())
 }
+
+/// Check that the source directory exists and canonicalize it
+fn canonical_src_dir<P: AsRef<Path>>(src_dir_path: P) -> Result<PathBuf> {
+    let src_dir_path = fs::check_is_dir(src_dir_path)?;
+    src_dir_path
+        .canonicalize()
+        .map_err(|e| Error::io(src_dir_path, e))
+}
+
+/// Create the parent directory of the destination file, if needed, and
+/// returns it, canonicalized
+fn canonical_dest_dir(dest_file_path: &Path) -> Result<PathBuf> {
+    fs::create_parent_dir_for(dest_file_path)?;
+    let dest_dir_path = dest_file_path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    dest_dir_path
+        .canonicalize()
+        .map_err(|e| Error::io(dest_dir_path, e))
+}
+
+/// Returns a function that gives the title and link of a chapter, given
+/// its path relative to the (canonicalized) source directory.
+///
+/// Titles come from SUMMARY.md, if possible; links are relative to the
+/// (canonicalized) directory of the destination file.
+fn chapter_linker(src_dir_path: &Path, dest_dir_path: &Path) -> impl Fn(&Path) -> (String, String) {
+    let titles: HashMap<PathBuf, String> = summary::read_summary(src_dir_path)
+        .map(|chapters| {
+            chapters
+                .into_iter()
+                .filter_map(|c| c.path.map(|p| (PathBuf::from(p), c.title)))
+                .collect()
+        })
+        .unwrap_or_default();
+    let src_dir_path = src_dir_path.to_path_buf();
+    let dest_dir_path = dest_dir_path.to_path_buf();
+    move |chapter_path: &Path| {
+        let title = titles.get(chapter_path).cloned().unwrap_or_else(|| {
+            chapter_path
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        });
+        let link = generate::relative_path(&src_dir_path.join(chapter_path), &dest_dir_path);
+        (title, link.to_string_lossy().replace('\\', "/"))
+    }
+}
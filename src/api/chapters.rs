@@ -0,0 +1,117 @@
+use std::io::Write;
+use std::path::Path;
+
+use crate::Error;
+use crate::Result;
+use crate::book_toml::BookToml;
+use crate::chapters;
+pub use crate::chapters::BrokenRedirect;
+pub use crate::chapters::ChapterMove;
+use crate::fs;
+use crate::parser::ParserOptions;
+use crate::summary;
+
+// MOVE CHAPTERS
+
+/// Move (or rename) a chapter: move its Markdown file, update
+/// `SUMMARY.md` and every relative link to the chapter across the book
+/// (preserving anchors), update the relative links and includes of the
+/// chapter itself, and add an `[output.html.redirect]` entry to
+/// `book.toml`, so that the old URL keeps working. The files are updated
+/// all at once, or not at all.
+///
+/// book_root_dir_path: path to the directory that contains `book.toml`.
+///
+/// old_chapter_path, new_chapter_path: paths of the chapter relative to
+/// the source directory e.g. `intro.md` and `getting_started/intro.md`.
+///
+/// parser_options: the Markdown dialect of the book.
+pub fn move_chapter<P: AsRef<Path>>(
+    book_root_dir_path: P,
+    old_chapter_path: &str,
+    new_chapter_path: &str,
+    parser_options: &ParserOptions,
+) -> Result<ChapterMove> {
+    let book_toml = BookToml::from_dir(book_root_dir_path)?;
+    chapters::move_chapter(
+        &book_toml,
+        old_chapter_path,
+        new_chapter_path,
+        parser_options,
+    )
+}
+
+/// Check the `[output.html.redirect]` entries of `book.toml`: each
+/// redirect must point to a chapter listed in `SUMMARY.md` (or to
+/// another website) and must not replace the page of an existing
+/// chapter.
+///
+/// book_root_dir_path: path to the directory that contains `book.toml`.
+///
+/// Returns the broken redirects.
+pub fn check_redirects<P: AsRef<Path>>(book_root_dir_path: P) -> Result<Vec<BrokenRedirect>> {
+    let book_toml = BookToml::from_dir(book_root_dir_path)?;
+    chapters::check_redirects(&book_toml)
+}
+
+// GENERATE SUMMARY.md
+
+/// Generate a `SUMMARY.md` file that lists the Markdown files of a source
+/// directory, and write it to a file.
+///
+/// `SUMMARY.md` itself and the files included by other files (e.g.
+/// `refs.md`) are left out. Titles come from the `title` key of the front
+/// matter, else from the first heading, else from the file name.
+/// Chapters are nested by directory, under the directory's `README.md`
+/// (or `index.md`), and siblings are sorted by the `weight` (or `order`)
+/// key of their front matter, then by path. Chapters with `draft: true`
+/// become draft chapters.
+///
+/// markdown_src_dir_path: path to the source directory.
+///
+/// dest_file_path: path to the file to create and write into e.g.
+/// `src/SUMMARY.md`.
+///
+/// parser_options: the Markdown dialect of the book.
+pub fn generate_summary<P1: AsRef<Path>, P2: AsRef<Path>>(
+    markdown_src_dir_path: P1,
+    dest_file_path: P2,
+    parser_options: &ParserOptions,
+) -> Result<()> {
+    let dest_file_path = dest_file_path.as_ref();
+    let mut buf = Vec::new();
+    generate_summary_to_writer(markdown_src_dir_path, parser_options, &mut buf)?;
+
+    fs::create_parent_dir_for(dest_file_path)?;
+    std::fs::write(dest_file_path, buf).map_err(|e| Error::io(dest_file_path, e))
+}
+
+/// Generate a `SUMMARY.md` file that lists the Markdown files of a source
+/// directory, and write it to a writer (e.g. a File or a `Vec<u8>`), see
+/// [`generate_summary`].
+///
+/// markdown_src_dir_path: path to the source directory.
+///
+/// parser_options: the Markdown dialect of the book.
+///
+/// w: writer to write into.
+pub fn generate_summary_to_writer<P: AsRef<Path>, W: Write>(
+    markdown_src_dir_path: P,
+    parser_options: &ParserOptions,
+    w: &mut W,
+) -> Result<()> {
+    let markdown_src_dir_path = fs::check_is_dir(markdown_src_dir_path)?;
+
+    let mut files = Vec::new();
+    for path in fs::find_markdown_files_in(&markdown_src_dir_path)? {
+        let contents = std::fs::read_to_string(&path).map_err(|e| Error::io(&path, e))?;
+        let rel_path = path
+            .strip_prefix(&markdown_src_dir_path)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        files.push((rel_path, contents));
+    }
+    summary::write_summary(&files, parser_options, w)?;
+    Ok(())
+}
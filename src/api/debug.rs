@@ -2,15 +2,17 @@
 use std::io::BufWriter;
 use std::io::Write;
 use std::path::Path;
+use std::path::PathBuf;
 
-use anyhow::Context;
-use anyhow::Result;
-
+use crate::Error;
+use crate::Result;
 use crate::fs;
 use crate::helper;
 use crate::parser;
+use crate::parser::ParserOptions;
 use crate::test_markdown;
 use crate::write_from_parser;
+pub use crate::write_from_parser::EventFormat;
 
 // DEBUG
 
@@ -33,20 +35,180 @@ pub fn debug_parse_to<P1, P2>(src_dir_path: P1, dest_file_path: P2) -> Result<()
     Ok(())
 }
 
+/// Parse in-memory Markdown, with the default parser options, and
+/// write all raw events to a writer (e.g. a File or a `Vec<u8>`) for
+/// debugging purposes.
+///
+/// markdown: Markdown to parse.
+///
+/// w: writer to write into.
+pub fn debug_parse_markdown<W: Write>(markdown: &str, w: &mut W) -> Result<()> {
+    let mut parser = parser::get_parser(markdown, &ParserOptions::default());
+    write_from_parser::write_raw_to(&mut parser, w)
+}
+
+/// Parse in-memory Markdown and write its events to a writer, in the
+/// given format.
+///
+/// Each event comes with its line, column and byte range in the
+/// Markdown. The `tree` and `json` formats nest the events by depth.
+///
+/// parser_options: see [`ParserOptions`].
+///
+/// ```
+/// use mdbook_utils::EventFormat;
+/// use mdbook_utils::ParserOptions;
+///
+/// let mut buf = Vec::new();
+/// mdbook_utils::debug_parse_markdown_as(
+///     "# Title\n",
+///     EventFormat::Tree,
+///     &ParserOptions::default(),
+///     &mut buf,
+/// )
+/// .unwrap();
+/// assert_eq!(
+///     String::from_utf8(buf).unwrap(),
+///     "Heading level: 1 1:1 0..8\n  Text \"Title\" 1:3 2..7\n"
+/// );
+/// ```
+pub fn debug_parse_markdown_as<W: Write>(
+    markdown: &str,
+    format: EventFormat,
+    parser_options: &ParserOptions,
+    w: &mut W,
+) -> Result<()> {
+    write_from_parser::write_events_to(markdown, format, parser_options, w)
+}
+
+/// Parse Markdown files of a source directory, each on its own, and
+/// write their events to a file, in the given format.
+///
+/// src_dir_path: path to the source directory.
+///
+/// file: a Markdown file, relative to the source directory, or a glob
+/// pattern matched against the paths relative to the source directory
+/// (e.g. `chapter/*.md`). All files if `None`. Files outside of the
+/// source directory (e.g. `../secret.md`) are rejected with an
+/// [`Error::PathEscape`].
+///
+/// format: see [`EventFormat`].
+///
+/// parser_options: the Markdown dialect of the book.
+///
+/// dest_file_path: path to the file to create and write into.
+///
+/// Returns the paths of the parsed files.
+pub fn debug_parse_files_to<P1, P2>(
+    src_dir_path: P1,
+    file: Option<&str>,
+    format: EventFormat,
+    parser_options: &ParserOptions,
+    dest_file_path: P2,
+) -> Result<Vec<PathBuf>>
+where
+    P1: AsRef<Path>,
+    P2: AsRef<Path>,
+{
+    let src_dir_path = fs::check_is_dir(src_dir_path)?;
+    let paths = select_markdown_files(&src_dir_path, file)?;
+    let files = paths
+        .iter()
+        .map(|p| {
+            let contents = std::fs::read_to_string(p).map_err(|e| Error::io(p, e))?;
+            let rel_path = p
+                .strip_prefix(&src_dir_path)
+                .unwrap_or(p)
+                .to_string_lossy()
+                .replace('\\', "/");
+            Ok((rel_path, contents))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let dest_file_path = dest_file_path.as_ref();
+    fs::create_parent_dir_for(dest_file_path)?;
+    let mut f =
+        BufWriter::new(File::create(dest_file_path).map_err(|e| Error::io(dest_file_path, e))?);
+    write_from_parser::write_events_of_files_to(&files, format, parser_options, &mut f)?;
+    f.flush().map_err(|e| Error::io(dest_file_path, e))?;
+    Ok(paths)
+}
+
+/// Returns the Markdown file, or the Markdown files of the source
+/// directory that match a glob pattern, or all of them
+fn select_markdown_files(src_dir_path: &Path, file: Option<&str>) -> Result<Vec<PathBuf>> {
+    let Some(file) = file else {
+        return fs::find_markdown_files_in(src_dir_path);
+    };
+    let path = src_dir_path.join(file);
+    if path.is_file() {
+        let path = fs::is_path_within(src_dir_path, &path)?;
+        let src_dir_path_canon = src_dir_path
+            .canonicalize()
+            .map_err(|e| Error::io(src_dir_path, e))?;
+        let rel_path = path.strip_prefix(&src_dir_path_canon).unwrap_or(&path);
+        return Ok(vec![src_dir_path.join(rel_path)]);
+    }
+    let globset = fs::build_globset(&[file.to_string()])?;
+    let paths: Vec<PathBuf> = fs::find_markdown_files_in(src_dir_path)?
+        .into_iter()
+        .filter(|p| globset.is_match(p.strip_prefix(src_dir_path).unwrap_or(p)))
+        .collect();
+    if paths.is_empty() {
+        return Err(Error::config(format!(
+            "No Markdown file matches `{file}` in {}",
+            src_dir_path.display()
+        )));
+    }
+    Ok(paths)
+}
+
 /// Test function that uses fake Markdown and writes events to
 /// `./book/temp/test.log`.
 pub fn test() -> Result<()> {
     fs::create_dir("./book/temp/")?;
 
     let dest_file_path = "./book/temp/test.log";
-    let mut f = BufWriter::new(File::create(dest_file_path).context(
-        "[test] Failed to create the destination file. Does the full directory path exist?",
-    )?);
+    let mut f =
+        BufWriter::new(File::create(dest_file_path).map_err(|e| Error::io(dest_file_path, e))?);
 
     let test_markdown = test_markdown::get_test_markdown();
-    let mut parser = parser::get_parser(test_markdown.as_ref());
+    let mut parser = parser::get_parser(test_markdown.as_ref(), &ParserOptions::default());
     write_from_parser::write_raw_to(&mut parser, &mut f)?;
-    f.flush()
-        .context("Not all bytes could be written due to I/O errors or EOF being reached.")?;
+    f.flush().map_err(|e| Error::io(dest_file_path, e))?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_markdown_files() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let src = dir.path().join("src");
+        std::fs::create_dir_all(src.join("ch"))?;
+        std::fs::write(src.join("ch").join("a.md"), "# A\n")?;
+        std::fs::write(src.join("b.md"), "# B\n")?;
+        std::fs::write(dir.path().join("secret.md"), "# Secret\n")?;
+
+        assert_eq!(
+            select_markdown_files(&src, Some("ch/a.md"))?,
+            vec![src.join("ch").join("a.md")]
+        );
+        assert_eq!(
+            select_markdown_files(&src, Some("ch/*.md"))?,
+            vec![src.join("ch").join("a.md")]
+        );
+        assert!(matches!(
+            select_markdown_files(&src, Some("../secret.md")),
+            Err(Error::PathEscape { .. })
+        ));
+        let secret = dir.path().join("secret.md");
+        assert!(matches!(
+            select_markdown_files(&src, secret.to_str()),
+            Err(Error::PathEscape { .. })
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,319 @@
+use std::io::Write;
+use std::path::Path;
+
+use crate::Error;
+use crate::Result;
+use crate::feed;
+pub use crate::feed::FeedFormat;
+pub use crate::feed::FeedOptions;
+use crate::fs;
+use crate::sitemap;
+use crate::summary;
+
+// FEED
+
+/// Create an Atom or RSS feed of the most recently updated chapters
+/// listed in the `SUMMARY.md` file of a source directory.
+///
+/// Chapter dates are read from the `updated` or `date` key of the
+/// chapters' front matter, if any; otherwise from the `git` history (if
+/// enabled) or the files' modification time. Summaries are read from the
+/// `summary` or `description` key, or from the first paragraph.
+///
+/// markdown_src_dir_path: path to the source directory.
+///
+/// base_url: base URL e.g. <https://john-cd.com/rust_howto/>.
+///
+/// feed_dest_file_path: the path to the destination file e.g.
+/// book/html/atom.xml.
+///
+/// options: see [`FeedOptions`].
+pub fn generate_feed<P1, P2>(
+    markdown_src_dir_path: P1,
+    base_url: url::Url,
+    feed_dest_file_path: P2,
+    options: FeedOptions,
+) -> Result<()>
+where
+    P1: AsRef<Path>,
+    P2: AsRef<Path>,
+{
+    let feed_dest_file_path = feed_dest_file_path.as_ref();
+    let mut buf = Vec::new();
+    generate_feed_to_writer(
+        markdown_src_dir_path,
+        base_url,
+        feed_file_name(feed_dest_file_path),
+        options,
+        &mut buf,
+    )?;
+    write_file(feed_dest_file_path, buf)
+}
+
+/// Create an Atom or RSS feed of the most recently updated chapters
+/// listed in the `SUMMARY.md` file of a source directory, and write it
+/// to a writer (e.g. a File or a `Vec<u8>`), see [`generate_feed`].
+///
+/// markdown_src_dir_path: path to the source directory.
+///
+/// base_url: base URL e.g. <https://john-cd.com/rust_howto/>.
+///
+/// feed_file_name: name of the feed in the book's root directory e.g.
+/// `atom.xml`, used for the feed's own URL.
+///
+/// options: see [`FeedOptions`].
+///
+/// w: writer to write into.
+pub fn generate_feed_to_writer<P, W>(
+    markdown_src_dir_path: P,
+    base_url: url::Url,
+    feed_file_name: &str,
+    options: FeedOptions,
+    w: &mut W,
+) -> Result<()>
+where
+    P: AsRef<Path>,
+    W: Write,
+{
+    let (site_root, chapters) = read_chapters(markdown_src_dir_path, base_url, &options)?;
+    write_feed(chapters, &site_root, feed_file_name, &options, w)
+}
+
+/// Create a JSON index of all chapters listed in the `SUMMARY.md` file
+/// of a source directory, with their URL, summary, date and headings,
+/// for use by external search engines.
+///
+/// markdown_src_dir_path: path to the source directory.
+///
+/// base_url: base URL e.g. <https://john-cd.com/rust_howto/>.
+///
+/// index_dest_file_path: the path to the destination file e.g.
+/// book/html/search_index.json.
+///
+/// options: see [`FeedOptions`]. `title`, `description`, `format` and
+/// `max_entries` are ignored.
+pub fn generate_search_index<P1, P2>(
+    markdown_src_dir_path: P1,
+    base_url: url::Url,
+    index_dest_file_path: P2,
+    options: &FeedOptions,
+) -> Result<()>
+where
+    P1: AsRef<Path>,
+    P2: AsRef<Path>,
+{
+    let mut buf = Vec::new();
+    generate_search_index_to_writer(markdown_src_dir_path, base_url, options, &mut buf)?;
+    write_file(index_dest_file_path.as_ref(), buf)
+}
+
+/// Create a JSON index of all chapters listed in the `SUMMARY.md` file
+/// of a source directory and write it to a writer (e.g. a File or a
+/// `Vec<u8>`), see [`generate_search_index`].
+///
+/// markdown_src_dir_path: path to the source directory.
+///
+/// base_url: base URL e.g. <https://john-cd.com/rust_howto/>.
+///
+/// options: see [`FeedOptions`]. `title`, `description`, `format` and
+/// `max_entries` are ignored.
+///
+/// w: writer to write into.
+pub fn generate_search_index_to_writer<P, W>(
+    markdown_src_dir_path: P,
+    base_url: url::Url,
+    options: &FeedOptions,
+    w: &mut W,
+) -> Result<()>
+where
+    P: AsRef<Path>,
+    W: Write,
+{
+    let (_, chapters) = read_chapters(markdown_src_dir_path, base_url, options)?;
+    feed::write_search_index(&chapters, w)?;
+    Ok(())
+}
+
+/// Create both a feed of the most recently updated chapters and a JSON
+/// index of all chapters, see [`generate_feed`] and
+/// [`generate_search_index`]. The chapters are read and parsed once.
+///
+/// markdown_src_dir_path: path to the source directory.
+///
+/// base_url: base URL e.g. <https://john-cd.com/rust_howto/>.
+///
+/// feed_dest_file_path: the path to the feed e.g. book/html/atom.xml.
+///
+/// index_dest_file_path: the path to the JSON index e.g.
+/// book/html/search_index.json.
+///
+/// options: see [`FeedOptions`].
+pub fn generate_feed_and_search_index<P1, P2, P3>(
+    markdown_src_dir_path: P1,
+    base_url: url::Url,
+    feed_dest_file_path: P2,
+    index_dest_file_path: P3,
+    options: FeedOptions,
+) -> Result<()>
+where
+    P1: AsRef<Path>,
+    P2: AsRef<Path>,
+    P3: AsRef<Path>,
+{
+    let feed_dest_file_path = feed_dest_file_path.as_ref();
+    let (site_root, chapters) = read_chapters(markdown_src_dir_path, base_url, &options)?;
+    let mut index = Vec::new();
+    feed::write_search_index(&chapters, &mut index)?;
+    let mut buf = Vec::new();
+    write_feed(
+        chapters,
+        &site_root,
+        feed_file_name(feed_dest_file_path),
+        &options,
+        &mut buf,
+    )?;
+    write_file(feed_dest_file_path, buf)?;
+    write_file(index_dest_file_path.as_ref(), index)
+}
+
+/// Write the feed of the chapters, whose own URL is `feed_file_name`
+/// in the book's root directory.
+fn write_feed<W: Write>(
+    chapters: Vec<feed::ChapterInfo>,
+    site_root: &url::Url,
+    feed_file_name: &str,
+    options: &FeedOptions,
+    w: &mut W,
+) -> Result<()> {
+    let feed_url = site_root
+        .join(feed_file_name)
+        .map_err(|e| Error::config(format!("Invalid feed URL for {feed_file_name}: {e}")))?;
+    feed::write_feed(chapters, site_root, &feed_url, options, w)
+}
+
+/// Name of the feed file e.g. `atom.xml`, used for the feed's own URL.
+fn feed_file_name(feed_dest_file_path: &Path) -> &str {
+    feed_dest_file_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("feed.xml")
+}
+
+/// Write a file, creating its parent directory if needed.
+fn write_file(path: &Path, contents: Vec<u8>) -> Result<()> {
+    fs::create_parent_dir_for(path)?;
+    std::fs::write(path, contents).map_err(|e| Error::io(path, e))
+}
+
+/// Read SUMMARY.md and collect information about each chapter.
+///
+/// Returns the URL of the root of the book and the chapters.
+fn read_chapters<P: AsRef<Path>>(
+    markdown_src_dir_path: P,
+    base_url: url::Url,
+    options: &FeedOptions,
+) -> Result<(url::Url, Vec<feed::ChapterInfo>)> {
+    if base_url.cannot_be_a_base() {
+        return Err(Error::config(format!(
+            "Invalid URL - cannot be a base: {base_url}"
+        )));
+    }
+    let site_root = sitemap::site_root_url(&base_url, options.site_url.as_deref())?;
+    let markdown_src_dir_path = fs::check_is_dir(markdown_src_dir_path)?;
+    let chapters = summary::read_summary(&markdown_src_dir_path)?;
+    let chapters = feed::collect_chapter_info(
+        &markdown_src_dir_path,
+        &chapters,
+        &site_root,
+        &options.exclude,
+        options.use_git,
+        &options.parser,
+    )?;
+    Ok((site_root, chapters))
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use super::*;
+
+    #[test]
+    fn test_generate_feed_and_search_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        std::fs::create_dir(&src).unwrap();
+        std::fs::write(
+            src.join("SUMMARY.md"),
+            "# Summary\n\n[Intro](README.md)\n\n- [Chapter](ch/a.md)\n- [Missing](missing.md)\n- [Draft]()\n",
+        )
+        .unwrap();
+        std::fs::write(
+            src.join("README.md"),
+            "---\ndate: 2024-01-01\n---\n# Intro\n\nWelcome.\n",
+        )
+        .unwrap();
+        std::fs::create_dir(src.join("ch")).unwrap();
+        std::fs::write(
+            src.join("ch").join("a.md"),
+            "---\ndate: 2024-02-01\n---\n# A\n\nFirst recipe.\n",
+        )
+        .unwrap();
+
+        let base_url = Url::parse("https://example.com/book/").unwrap();
+        let options = FeedOptions {
+            title: "Test".into(),
+            use_git: false,
+            ..Default::default()
+        };
+        let dest = dir.path().join("book").join("atom.xml");
+        generate_feed(&src, base_url.clone(), &dest, options.clone()).unwrap();
+        let xml = std::fs::read_to_string(&dest).unwrap();
+        let a = xml.find("https://example.com/book/ch/a.html").unwrap();
+        let intro = xml
+            .find("<id>https://example.com/book/index.html</id>")
+            .unwrap();
+        // Most recent first
+        assert!(a < intro);
+        assert!(xml.contains("<summary>First recipe.</summary>"));
+
+        let dest = dir.path().join("book").join("index.json");
+        generate_search_index(&src, base_url.clone(), &dest, &options).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&dest).unwrap()).unwrap();
+        assert_eq!(json["chapters"].as_array().unwrap().len(), 2);
+        assert_eq!(json["chapters"][0]["title"], "Intro");
+
+        // To a writer
+        let mut buf = Vec::new();
+        generate_search_index_to_writer(&src, base_url.clone(), &options, &mut buf).unwrap();
+        assert_eq!(buf, std::fs::read(&dest).unwrap());
+        let mut buf = Vec::new();
+        generate_feed_to_writer(
+            &src,
+            base_url.clone(),
+            "atom.xml",
+            options.clone(),
+            &mut buf,
+        )
+        .unwrap();
+        let xml = String::from_utf8(buf).unwrap();
+        assert!(xml.contains("https://example.com/book/atom.xml"));
+
+        // Both at once
+        let out = dir.path().join("out");
+        generate_feed_and_search_index(
+            &src,
+            base_url,
+            out.join("atom.xml"),
+            out.join("index.json"),
+            options,
+        )
+        .unwrap();
+        assert_eq!(std::fs::read_to_string(out.join("atom.xml")).unwrap(), xml);
+        assert_eq!(
+            std::fs::read(out.join("index.json")).unwrap(),
+            std::fs::read(&dest).unwrap()
+        );
+    }
+}
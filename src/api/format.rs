@@ -0,0 +1,46 @@
+use std::path::Path;
+
+use crate::Result;
+pub use crate::formatter::FenceStyle;
+pub use crate::formatter::FormatChanges;
+pub use crate::formatter::FormatOptions;
+pub use crate::formatter::LinkStyle;
+pub use crate::formatter::RefDefPlacement;
+
+// FORMAT
+
+/// Format the Markdown files of a source directory: link style (see
+/// [`LinkStyle`]), placement and order of the reference definitions
+/// (see [`RefDefPlacement`]), code block fences and info strings.
+/// Everything else is left as written; `SUMMARY.md` is left untouched.
+///
+/// src_dir_path: path to the source directory.
+///
+/// options: see [`FormatOptions`].
+///
+/// Returns the changes, as text edits per file. Nothing is modified
+/// until [`FormatChanges::apply`] is called; an empty result means that
+/// the files are already formatted.
+pub fn format_markdown<P: AsRef<Path>>(
+    src_dir_path: P,
+    options: &FormatOptions,
+) -> Result<FormatChanges> {
+    crate::formatter::format(src_dir_path, options)
+}
+
+/// Format a single Markdown document, without the other files of the
+/// book: its references are resolved with its own reference
+/// definitions only.
+///
+/// ```
+/// use mdbook_utils::FormatOptions;
+///
+/// let md = "[b]: https://b.com\n\nSee [a] and [b].\n\n~~~ rust, editable\nfn main() {}\n~~~\n\n[a]: https://a.com\n";
+/// assert_eq!(
+///     mdbook_utils::format_markdown_str(md, &FormatOptions::default()),
+///     "See [a] and [b].\n\n```rust,editable\nfn main() {}\n```\n\n[a]: https://a.com\n[b]: https://b.com\n"
+/// );
+/// ```
+pub fn format_markdown_str(markdown: &str, options: &FormatOptions) -> String {
+    crate::formatter::format_str(markdown, options)
+}
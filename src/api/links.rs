@@ -1,14 +1,7 @@
-use std::fs::File;
 use std::path::Path;
 
-use anyhow::Context;
-use anyhow::Result;
-use pulldown_cmark::LinkType;
-
-use crate::fs;
-use crate::helper;
-use crate::link;
-use crate::parser;
+use crate::Result;
+use crate::book::Book;
 
 // LINKS
 
@@ -24,25 +17,7 @@ pub fn write_inline_links<P1, P2>(src_dir_path: P1, dest_file_path: P2) -> Resul
     P1: AsRef<Path>,
     P2: AsRef<Path>,
 {
-    helper(src_dir_path, dest_file_path, |parser, f| {
-        let links: Vec<link::Link<'_>> = parser::extract_links(parser);
-        let links: Vec<_> = links
-            .into_iter()
-            .filter(|l| {
-                [LinkType::Inline, LinkType::Autolink]
-                    .iter()
-                    .any(|&x| l.get_link_type().unwrap() == x)
-            })
-            .filter(|l| {
-                let url = l.get_url();
-                url.starts_with("http")
-            })
-            .collect();
-        link::write_reference_style_links_to(links, f)?;
-        Ok(())
-    })?;
-
-    Ok(())
+    Book::load(src_dir_path)?.write_to_file(dest_file_path, Book::write_inline_links)
 }
 
 /// Parse Markdown from all .md files in a given source directory,
@@ -56,20 +31,7 @@ pub fn write_all_links<P1, P2>(src_dir_path: P1, dest_file_path: P2) -> Result<(
     P1: AsRef<Path>,
     P2: AsRef<Path>,
 {
-    helper(src_dir_path, dest_file_path, |parser, f| {
-        let links: Vec<link::Link<'_>> = parser::extract_links(parser);
-        let links: Vec<_> = links
-            .into_iter()
-            .filter(|l| {
-                let url = l.get_url();
-                url.starts_with("http")
-            })
-            .collect();
-        link::write_reference_style_links_to(links, f)?;
-        Ok(())
-    })?;
-
-    Ok(())
+    Book::load(src_dir_path)?.write_to_file(dest_file_path, Book::write_all_links)
 }
 
 /// Parse Markdown from all .md files in a given source directory,
@@ -83,25 +45,7 @@ pub fn write_duplicate_links<P1, P2>(src_dir_path: P1, dest_file_path: P2) -> Re
     P1: AsRef<Path>,
     P2: AsRef<Path>,
 {
-    helper(src_dir_path, dest_file_path, |parser, f| {
-        let links: Vec<link::Link<'_>> = parser::extract_links(parser);
-        let links: Vec<_> = links
-            .into_iter()
-            .filter(|l| {
-                let url = l.get_url();
-                url.starts_with("http")
-            })
-            .collect();
-        let mut counts = std::collections::HashMap::new();
-        for l in &links {
-            *counts.entry(l.clone()).or_insert(0) += 1;
-        }
-        let duplicates: Vec<_> = links.into_iter().filter(|l| counts[l] > 1).collect();
-        link::write_duplicate_links_to(duplicates, f)?;
-        Ok(())
-    })?;
-
-    Ok(())
+    Book::load(src_dir_path)?.write_to_file(dest_file_path, Book::write_duplicate_links)
 }
 
 /// Parse Markdown from all .md files in a given source directory,
@@ -115,32 +59,7 @@ pub fn write_broken_links<P1, P2>(src_dir_path: P1, dest_file_path: P2) -> Resul
     P1: AsRef<Path>,
     P2: AsRef<Path>,
 {
-    let src_dir_path = fs::check_is_dir(src_dir_path)?;
-    fs::create_parent_dir_for(dest_file_path.as_ref())?;
-
-    let mut f = File::create(dest_file_path.as_ref()).with_context(|| {
-        format!(
-            "[write_broken_links] Could not create file {}",
-            dest_file_path.as_ref().display()
-        )
-    })?;
-
-    let all_markdown = fs::read_to_string_all_markdown_files_in(src_dir_path)?;
-    let handler = parser::Handler::new();
-    let parser = parser::get_parser_with_broken_links_handler(&all_markdown, handler.clone());
-
-    // We need to consume the parser to trigger the callbacks
-    for _ in parser {}
-
-    let broken_links = handler
-        .broken_links
-        .lock()
-        .unwrap_or_else(|e| e.into_inner())
-        .clone();
-
-    link::write_broken_links_to(broken_links, &mut f)?;
-
-    Ok(())
+    Book::load(src_dir_path)?.write_to_file(dest_file_path, Book::write_broken_links)
 }
 
 #[cfg(test)]
@@ -0,0 +1,34 @@
+use std::path::Path;
+
+use crate::Result;
+pub use crate::lint::FileFix;
+pub use crate::lint::LintFinding;
+pub use crate::lint::LintFixes;
+pub use crate::lint::LintOptions;
+pub use crate::lint::LintReport;
+pub use crate::lint::LintRule;
+pub use crate::lint::Severity;
+pub use crate::lint::TextEdit;
+
+// LINT
+
+/// Parse all Markdown files of a source directory once and run the lint
+/// rules (undefined references, duplicate links, files not in
+/// `SUMMARY.md`, unused code examples and reference definitions,
+/// repeated reference definitions, links to missing files, label
+/// casing, bare URLs).
+///
+/// src_dir_path: path to the source directory.
+///
+/// options: code directory and severity of each rule, see
+/// [`LintOptions`].
+///
+/// Returns the findings that are not suppressed by
+/// `<!-- mdbook-utils: allow(...) -->` comments, and the fixes of those
+/// that can be fixed automatically (see [`LintFixes`]): missing
+/// reference definitions that follow a template, unused or repeated
+/// reference definitions, label casing, bare URLs and links to moved
+/// chapters. Nothing is modified until [`LintFixes::apply`] is called.
+pub fn lint<P: AsRef<Path>>(src_dir_path: P, options: &LintOptions) -> Result<LintReport> {
+    crate::lint::lint(src_dir_path, options)
+}
@@ -2,9 +2,8 @@
 use std::path::PathBuf;
 use std::sync::LazyLock;
 
-use anyhow::Result;
-use anyhow::bail;
-
+use crate::Error;
+use crate::Result;
 use crate::fs;
 use crate::parser;
 
@@ -18,14 +17,21 @@ pub fn identify_files_not_in_summary<P: AsRef<Path>>(
 
     let summary_path = markdown_src_dir_path.join("SUMMARY.md");
     if !summary_path.exists() {
-        bail!(
-            "SUMMARY.md not found in {}",
-            markdown_src_dir_path.display()
-        );
+        return Err(Error::io(
+            &summary_path,
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!(
+                    "SUMMARY.md not found in {}",
+                    markdown_src_dir_path.display()
+                ),
+            ),
+        ));
     }
 
-    let summary_content = std::fs::read_to_string(&summary_path)?;
-    let mut parser = parser::get_parser(&summary_content);
+    let summary_content =
+        std::fs::read_to_string(&summary_path).map_err(|e| Error::io(&summary_path, e))?;
+    let mut parser = parser::get_parser(&summary_content, &parser::ParserOptions::default());
     let links = parser::extract_links(&mut parser);
 
     let mut files_in_summary = std::collections::HashSet::new();
@@ -90,7 +96,7 @@ pub fn identify_unused_rs_examples<P1: AsRef<Path>, P2: AsRef<Path>>(
     });
 
     for md_file in md_files {
-        let content = std::fs::read_to_string(&md_file)?;
+        let content = std::fs::read_to_string(&md_file).map_err(|e| Error::io(&md_file, e))?;
         for cap in RE.captures_iter(&content) {
             let rel_path = &cap["path"];
             if let Some(parent) = md_file.parent() {
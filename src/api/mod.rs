@@ -1,15 +1,33 @@
 //! Public API
 
+mod book;
+mod book_toml;
+mod cache;
 mod categories;
+mod chapters;
 mod debug;
+mod feed;
+mod format;
 mod links;
+mod lint;
 mod markdown;
+mod parser;
 mod refdefs;
+mod render;
 mod sitemap;
 
+pub use book::*;
+pub use book_toml::*;
+pub use cache::*;
 pub use categories::*;
+pub use chapters::*;
 pub use debug::*;
+pub use feed::*;
+pub use format::*;
 pub use links::*;
+pub use lint::*;
 pub use markdown::*;
+pub use parser::*;
 pub use refdefs::*;
+pub use render::*;
 pub use sitemap::*;
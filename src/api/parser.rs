@@ -0,0 +1,5 @@
+pub use crate::parser::ChapterMetadata;
+pub use crate::parser::MarkdownExtension;
+pub use crate::parser::MarkdownPreset;
+pub use crate::parser::MetadataValue;
+pub use crate::parser::ParserOptions;
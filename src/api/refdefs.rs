@@ -1,13 +1,21 @@
+use std::io::Write;
 use std::path::Path;
 
-use anyhow::Result;
-
+use crate::Error;
+use crate::Result;
+use crate::book::Book;
 use crate::dependencies;
+pub use crate::dependencies::CrateNameMismatch;
+pub use crate::dependencies::DependencyAudit;
+pub use crate::dependencies::DependencyKind;
+pub use crate::dependencies::DependencyOptions;
+pub use crate::dependencies::VersionUpgrade;
+use crate::fs;
 use crate::generate;
-use crate::helper;
+pub use crate::generate::RefDefTemplate;
 use crate::link;
 use crate::parser;
-use crate::write_from_parser;
+use crate::parser::ParserOptions;
 
 // REFERENCE DEFINITIONS
 
@@ -22,12 +30,7 @@ pub fn write_refdefs_to<P1, P2>(src_dir_path: P1, dest_file_path: P2) -> Result<
     P1: AsRef<Path>,
     P2: AsRef<Path>,
 {
-    helper(
-        src_dir_path,
-        dest_file_path,
-        write_from_parser::write_refdefs_to,
-    )?;
-    Ok(())
+    Book::load(src_dir_path)?.write_to_file(dest_file_path, Book::write_refdefs)
 }
 
 /// Parse Markdown from all .md files in a given source directory,
@@ -44,12 +47,7 @@ pub fn generate_badges<P1, P2>(src_dir_path: P1, dest_file_path: P2) -> Result<(
     P1: AsRef<Path>,
     P2: AsRef<Path>,
 {
-    helper(
-        src_dir_path,
-        dest_file_path,
-        write_from_parser::write_github_repo_badge_refdefs,
-    )?;
-    Ok(())
+    Book::load(src_dir_path)?.write_to_file(dest_file_path, Book::write_badges)
 }
 
 // GENERATE REF DEFS FROM DEPENDENCIES
@@ -58,6 +56,10 @@ pub fn generate_badges<P1, P2>(src_dir_path: P1, dest_file_path: P2) -> Result<(
 /// generate reference definitions from code dependencies
 /// and write them to a file.
 ///
+/// Dependencies are read from `Cargo.toml` (including workspace members)
+/// and `Cargo.lock`, without invoking `cargo` unless
+/// `options.cargo_metadata` is set.
+///
 /// cargo_toml_dir_path: path to the directory containing `Cargo.toml`.
 ///
 /// markdown_dir_path: path to the directory containing Markdown files.
@@ -65,41 +67,285 @@ pub fn generate_badges<P1, P2>(src_dir_path: P1, dest_file_path: P2) -> Result<(
 /// refdef_dest_file_path: path to the file to create and
 /// write into.
 ///
-/// log_file_path: optional path to a log file where the output of `cargo tree`
-/// will be written.
-pub fn generate_refdefs_to<P1, P2, P3, P4>(
+/// options: see [`DependencyOptions`]. The reference definitions created
+/// for each dependency follow `options.templates` (see
+/// [`RefDefTemplate`]), or, by default, point to docs.rs, crates.io and
+/// the repository. If `options.pin_versions` is set, the default docs.rs
+/// and crates.io URLs are pinned to the versions locked in `Cargo.lock`
+/// e.g. `https://docs.rs/serde/1.0.228/serde/`. Setting both
+/// `options.templates` and `options.pin_versions` is an error: templates
+/// pin their URLs with `${version}`.
+pub fn generate_refdefs_to<P1, P2, P3>(
     cargo_toml_dir_path: P1,
     markdown_dir_path: P2,
     refdef_dest_file_path: P3,
-    log_file_path: Option<P4>,
+    options: DependencyOptions,
 ) -> Result<()>
 where
     P1: AsRef<Path>,
     P2: AsRef<Path>,
     P3: AsRef<Path>,
-    P4: AsRef<Path>,
 {
+    let refdef_dest_file_path = refdef_dest_file_path.as_ref();
+    let mut buf = Vec::new();
+    generate_refdefs_to_writer(cargo_toml_dir_path, markdown_dir_path, options, &mut buf)?;
+
+    fs::create_parent_dir_for(refdef_dest_file_path)?;
+    std::fs::write(refdef_dest_file_path, buf).map_err(|e| Error::io(refdef_dest_file_path, e))
+}
+
+/// Given a Cargo.toml path, generate reference definitions from code
+/// dependencies and write them to a writer (e.g. a File or a
+/// `Vec<u8>`), see [`generate_refdefs_to`].
+///
+/// cargo_toml_dir_path: path to the directory containing `Cargo.toml`.
+///
+/// markdown_dir_path: path to the directory containing Markdown files.
+///
+/// options: see [`DependencyOptions`].
+///
+/// w: writer to write into.
+pub fn generate_refdefs_to_writer<P1, P2, W>(
+    cargo_toml_dir_path: P1,
+    markdown_dir_path: P2,
+    options: DependencyOptions,
+    w: &mut W,
+) -> Result<()>
+where
+    P1: AsRef<Path>,
+    P2: AsRef<Path>,
+    W: Write,
+{
+    if options.pin_versions && options.templates.is_some() {
+        return Err(Error::config(
+            "`pin_versions` cannot be combined with templates: use `${version}` in the template URLs instead.",
+        ));
+    }
+    let markdown_dir_path = fs::check_is_dir(markdown_dir_path)?;
+
     // Generate ref defs from dependencies
-    let deps = dependencies::get_dependencies(&cargo_toml_dir_path, log_file_path)?;
-    // for (_, d) in &deps {
-    //     tracing::info!("{d:?}");
-    // }
-    let mut new_links = generate::generate_refdefs_from(deps);
+    let deps = dependencies::get_dependencies(&cargo_toml_dir_path, &options)?;
+    let templates = options
+        .templates
+        .unwrap_or_else(|| RefDefTemplate::defaults(options.pin_versions));
+    let mut new_links = generate::generate_refdefs_from(deps, &templates);
 
+    // Read existing ref defs
     // TODO can we read just the *-refs.md files?
-    helper(
+    let all_markdown =
+        fs::read_to_string_all_markdown_files_in(markdown_dir_path, &options.parser)?;
+    let existing_links: Vec<link::Link> =
+        parser::extract_links(parser::get_parser(all_markdown.as_ref(), &options.parser));
+
+    let links = generate::merge_links(existing_links, &mut new_links);
+    link::write_refdefs_to(links, w)
+}
+
+/// Read the templates of the reference definitions to generate for each
+/// dependency from a TOML file, e.g.:
+///
+/// ```toml
+/// [[templates]]
+/// label = "${crate}-lib-rs"
+/// url = "https://lib.rs/crates/${package}"
+///
+/// [[templates]]
+/// label = "${crate}-homepage"
+/// url = "${homepage}"
+/// badge = "https://img.shields.io/badge/${crate}-homepage-blue"
+/// ```
+///
+/// See [`RefDefTemplate`] for the available variables.
+///
+/// templates_file_path: path to the TOML file.
+pub fn read_refdef_templates<P: AsRef<Path>>(
+    templates_file_path: P,
+) -> Result<Vec<RefDefTemplate>> {
+    generate::read_refdef_templates(templates_file_path)
+}
+
+// UPGRADE PINNED VERSIONS
+
+/// Within each Markdown file in a source directory, rewrite the pinned
+/// docs.rs and crates.io URLs (e.g. `https://docs.rs/serde/1.0.200/serde/`)
+/// whose version is no longer locked in `Cargo.lock`, using the most
+/// recent locked version of the crate.
+///
+/// `Cargo.lock` is looked for in the directory containing `Cargo.toml`
+/// and its parents (the workspace root).
+///
+/// cargo_toml_dir_path: path to the directory containing `Cargo.toml`.
+///
+/// markdown_dir_path: path to the directory containing Markdown files.
+///
+/// dry_run: if `true`, do not modify the Markdown files.
+///
+/// Returns the list of changes, in file order.
+pub fn upgrade_versions<P1, P2>(
+    cargo_toml_dir_path: P1,
+    markdown_dir_path: P2,
+    dry_run: bool,
+) -> Result<Vec<VersionUpgrade>>
+where
+    P1: AsRef<Path>,
+    P2: AsRef<Path>,
+{
+    let cargo_toml_dir_path = cargo_toml_dir_path.as_ref();
+    let markdown_dir_path = fs::check_is_dir(markdown_dir_path)?;
+    let cargo_lock_path = dependencies::find_cargo_lock(cargo_toml_dir_path).ok_or_else(|| {
+        Error::io(
+            cargo_toml_dir_path.join("Cargo.lock"),
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "not found in this directory or its parents",
+            ),
+        )
+    })?;
+    dependencies::upgrade_versions_in_all_markdown_files_in(
+        markdown_dir_path,
+        cargo_lock_path,
+        dry_run,
+    )
+}
+
+/// Rewrite the pinned docs.rs and crates.io URLs of a Markdown string
+/// whose version is no longer locked in `Cargo.lock`, see
+/// [`upgrade_versions`].
+///
+/// markdown: the Markdown to upgrade.
+///
+/// cargo_lock: the contents of `Cargo.lock`.
+///
+/// Returns the upgraded Markdown and the list of changes, whose
+/// `file_path` is empty.
+///
+/// ```
+/// let cargo_lock = r#"
+/// [[package]]
+/// name = "serde"
+/// version = "1.0.228"
+/// source = "registry+https://github.com/rust-lang/crates.io-index"
+/// "#;
+/// let (markdown, upgrades) = mdbook_utils::upgrade_versions_in_str(
+///     "[serde]: https://docs.rs/serde/1.0.200/serde/\n",
+///     cargo_lock,
+/// )?;
+/// assert_eq!(markdown, "[serde]: https://docs.rs/serde/1.0.228/serde/\n");
+/// assert_eq!(upgrades[0].old_version, "1.0.200");
+/// # Ok::<(), mdbook_utils::Error>(())
+/// ```
+pub fn upgrade_versions_in_str(
+    markdown: &str,
+    cargo_lock: &str,
+) -> Result<(String, Vec<VersionUpgrade>)> {
+    dependencies::upgrade_versions_in_str(markdown, cargo_lock)
+}
+
+// AUDIT DEPENDENCIES
+
+/// Compare the crates mentioned in a book with the dependencies declared
+/// in `Cargo.toml`, and write a report to a Markdown file.
+///
+/// Crates are mentioned by `crates.io`, `docs.rs` or `lib.rs` links, and
+/// by `use foo::`, `extern crate foo` or `#[foo::...]` in the Rust code
+/// blocks of the Markdown files and in the `.rs` files of the directory
+/// containing `Cargo.toml`.
+///
+/// The report lists the crates used but not declared, the dependencies
+/// never mentioned, and the reference definitions whose crate name is
+/// spelled differently from the package name (hyphen vs. underscore).
+///
+/// cargo_toml_dir_path: path to the directory containing `Cargo.toml`.
+///
+/// markdown_dir_path: path to the directory containing Markdown files.
+///
+/// dest_file_path: path to the report to create and write into.
+///
+/// kinds: the kinds of declared dependencies to take into account.
+///
+/// parser_options: the Markdown dialect of the book.
+pub fn audit_dependencies<P1, P2, P3>(
+    cargo_toml_dir_path: P1,
+    markdown_dir_path: P2,
+    dest_file_path: P3,
+    kinds: &[DependencyKind],
+    parser_options: &ParserOptions,
+) -> Result<DependencyAudit>
+where
+    P1: AsRef<Path>,
+    P2: AsRef<Path>,
+    P3: AsRef<Path>,
+{
+    let dest_file_path = dest_file_path.as_ref();
+    let mut buf = Vec::new();
+    let report = audit_dependencies_to_writer(
+        cargo_toml_dir_path,
         markdown_dir_path,
-        refdef_dest_file_path,
-        move |parser, f| {
-            // Read existing ref defs
-            let existing_links: Vec<link::Link<'_>> = parser::extract_links(parser);
-            let existing_links_static: Vec<link::Link<'static>> =
-                existing_links.into_iter().map(|l| l.to_static()).collect();
-
-            let links = generate::merge_links(existing_links_static, &mut new_links);
-            link::write_refdefs_to(links, f)?;
-            Ok(())
-        },
+        kinds,
+        parser_options,
+        &mut buf,
+    )?;
+
+    fs::create_parent_dir_for(dest_file_path)?;
+    std::fs::write(dest_file_path, buf).map_err(|e| Error::io(dest_file_path, e))?;
+    Ok(report)
+}
+
+/// Compare the crates mentioned in a book with the dependencies declared
+/// in `Cargo.toml`, and write a report to a writer (e.g. a File or a
+/// `Vec<u8>`), see [`audit_dependencies`].
+///
+/// cargo_toml_dir_path: path to the directory containing `Cargo.toml`.
+///
+/// markdown_dir_path: path to the directory containing Markdown files.
+///
+/// kinds: the kinds of declared dependencies to take into account.
+///
+/// parser_options: the Markdown dialect of the book.
+///
+/// w: writer to write into.
+pub fn audit_dependencies_to_writer<P1, P2, W>(
+    cargo_toml_dir_path: P1,
+    markdown_dir_path: P2,
+    kinds: &[DependencyKind],
+    parser_options: &ParserOptions,
+    w: &mut W,
+) -> Result<DependencyAudit>
+where
+    P1: AsRef<Path>,
+    P2: AsRef<Path>,
+    W: Write,
+{
+    let cargo_toml_dir_path = cargo_toml_dir_path.as_ref();
+    let markdown_dir_path = fs::check_is_dir(markdown_dir_path)?;
+    let declared = dependencies::declared_dependencies(cargo_toml_dir_path, kinds)?;
+    let (uses, refdefs) = dependencies::find_crate_uses(
+        &markdown_dir_path,
+        Some(cargo_toml_dir_path),
+        parser_options,
     )?;
-    Ok(())
+    let local_packages = dependencies::local_package_names(cargo_toml_dir_path)?;
+    let report = dependencies::audit(&uses, &refdefs, &declared, &local_packages);
+    dependencies::write_audit(&report, w)?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pin_versions_with_templates() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = DependencyOptions {
+            pin_versions: true,
+            templates: Some(RefDefTemplate::defaults(false)),
+            ..DependencyOptions::default()
+        };
+        let mut buf = Vec::new();
+        let err =
+            generate_refdefs_to_writer(dir.path(), dir.path(), options, &mut buf).unwrap_err();
+        assert!(matches!(err, Error::Config(_)));
+        assert!(buf.is_empty());
+    }
 }
@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::Error;
+use crate::Result;
+use crate::fs;
+pub use crate::render::DEFAULT_HTML_TEMPLATE;
+pub use crate::render::RenderOptions;
+
+// RENDER
+
+/// Render a chapter of a book to HTML, without building the book: its
+/// `{{#include}}`, `{{#rustdoc_include}}` and `{{#playground}}`
+/// statements are expanded, its references are resolved with the
+/// reference definitions of the whole book and its links to other
+/// chapters point to their HTML files.
+///
+/// src_dir_path: path to the source directory.
+///
+/// chapter_path: path of the chapter's Markdown file, as given or
+/// relative to the source directory e.g. `chapter_1/intro.md`.
+///
+/// options: see [`RenderOptions`].
+pub fn render_chapter<P1, P2>(
+    src_dir_path: P1,
+    chapter_path: P2,
+    options: &RenderOptions,
+) -> Result<String>
+where
+    P1: AsRef<Path>,
+    P2: AsRef<Path>,
+{
+    crate::render::render_chapter(src_dir_path.as_ref(), chapter_path.as_ref(), options)
+}
+
+/// Render a chapter of a book to an HTML file, see [`render_chapter`].
+///
+/// src_dir_path: path to the source directory.
+///
+/// chapter_path: path of the chapter's Markdown file, as given or
+/// relative to the source directory.
+///
+/// options: see [`RenderOptions`].
+///
+/// dest_file_path: path to the file to create and write into.
+pub fn render_chapter_to<P1, P2, P3>(
+    src_dir_path: P1,
+    chapter_path: P2,
+    options: &RenderOptions,
+    dest_file_path: P3,
+) -> Result<()>
+where
+    P1: AsRef<Path>,
+    P2: AsRef<Path>,
+    P3: AsRef<Path>,
+{
+    let html = render_chapter(src_dir_path, chapter_path, options)?;
+    let dest_file_path = dest_file_path.as_ref();
+    fs::create_parent_dir_for(dest_file_path)?;
+    std::fs::write(dest_file_path, html).map_err(|e| Error::io(dest_file_path, e))
+}
+
+/// Render in-memory Markdown to HTML. Include statements are left as
+/// written, and references are resolved with the Markdown's own
+/// reference definitions only.
+///
+/// ```
+/// use mdbook_utils::RenderOptions;
+///
+/// let md = "# Intro\n\nSee [the next chapter](next.md).\n";
+/// assert_eq!(
+///     mdbook_utils::render_markdown_str(md, &RenderOptions::default()),
+///     "<h1>Intro</h1>\n<p>See <a href=\"next.html\">the next chapter</a>.</p>\n"
+/// );
+/// ```
+pub fn render_markdown_str(markdown: &str, options: &RenderOptions) -> String {
+    crate::render::render_str(markdown, &HashMap::new(), "", options)
+}
@@ -1,79 +1,137 @@
+use std::collections::HashMap;
 use std::fs::File;
+use std::io::Write;
 use std::path::Path;
 
-use anyhow::Context;
-use anyhow::Result;
-use anyhow::bail;
-
+use crate::Error;
+use crate::Result;
+use crate::feed;
 use crate::fs;
-use crate::link;
-use crate::parser;
+use crate::parser::ChapterMetadata;
 use crate::sitemap as sitemap_mod;
+pub use crate::sitemap::SitemapOptions;
+use crate::summary;
 
 // SITEMAP
 
-/// Create a sitemap.xml file from the list of Markdown files in a
-/// source directory.
+/// Create a sitemap.xml file from the chapters listed in the
+/// `SUMMARY.md` file of a source directory, using mdBook's URL rules.
+/// Draft chapters and chapters matched by `options.exclude` are skipped.
+/// The `updated` (or `date`) key of a chapter's front matter, if any,
+/// becomes its `<lastmod>` date. Unless disabled, also write a
+/// `robots.txt` file next to the sitemap, if the book is served from the
+/// root of its host (see [`SitemapOptions::robots_txt`]).
 ///
-/// src_dir_path: path to the source directory.
+/// markdown_src_dir_path: path to the source directory.
 ///
-/// domain: base URL e.g. <https://john-cd.com/rust_howto/>.
+/// base_url: base URL e.g. <https://john-cd.com/rust_howto/>.
 ///
-/// dest_file_path: the path to the destination file e.g.
+/// sitemap_dest_file_path: the path to the destination file e.g.
 /// book/html/sitemap.xml.
+///
+/// options: see [`SitemapOptions`].
 pub fn generate_sitemap<P1, P2>(
     markdown_src_dir_path: P1,
     base_url: url::Url,
     sitemap_dest_file_path: P2,
-    map_index: Option<(String, String)>,
+    options: SitemapOptions,
 ) -> Result<()>
 where
     P1: AsRef<Path>,
     P2: AsRef<Path>,
 {
-    // Returns an error whether the base URL is a 'cannot-be-a-base' URL,
-    // meaning that parsing a relative URL string with this URL
-    // as the base will return an error.
-    if base_url.cannot_be_a_base() {
-        bail!("Invalid URL - cannot be a base: {base_url}");
-    }
+    let site_root = site_root(&base_url, &options)?;
 
     // Verify source path.
     let markdown_src_dir_path = fs::check_is_dir(markdown_src_dir_path)?;
 
+    let chapters = summary::read_summary(&markdown_src_dir_path)?;
+
+    // Dates of the chapters, from their front matter
+    let lastmod: HashMap<String, time::Date> = chapters
+        .iter()
+        .filter_map(|c| c.path.as_deref())
+        .filter_map(|p| {
+            let markdown = std::fs::read_to_string(markdown_src_dir_path.join(p)).ok()?;
+            let date = feed::parse_date(ChapterMetadata::from_markdown(&markdown)?.date()?)?;
+            Some((p.to_string(), date.date()))
+        })
+        .collect();
+
     // Create the parent folders of the destination file, if needed.
-    fs::create_parent_dir_for(sitemap_dest_file_path.as_ref())?;
+    let sitemap_dest_file_path = sitemap_dest_file_path.as_ref();
+    fs::create_parent_dir_for(sitemap_dest_file_path)?;
 
     // Create the `sitemap.xml` file.
     // `File::create` will create a file if it does not exist,
     // and will truncate it if it does.
-    let mut f = File::create(sitemap_dest_file_path.as_ref()).with_context(|| {
-        format!(
-            "Failed to create the sitemap file {}. The full directory path may not exist or required permissions may be missing.",
-            sitemap_dest_file_path.as_ref().display()
-        )
-    })?;
-
-    let summary_md_path = markdown_src_dir_path.join("SUMMARY.md");
-    tracing::debug!("SUMMARY.md path: {}", summary_md_path.display());
-    let markdown = std::fs::read_to_string(&summary_md_path).with_context(|| {
-        format!(
-            "[generate_sitemap] Could not read {}. Does the file exist?",
-            summary_md_path.display()
-        )
-    })?;
-    let mut parser = parser::get_parser(markdown.as_str());
-    let links: Vec<link::Link<'_>> = parser::extract_links(&mut parser);
+    let mut f =
+        File::create(sitemap_dest_file_path).map_err(|e| Error::io(sitemap_dest_file_path, e))?;
+    sitemap_mod::generate_sitemap(&chapters, &site_root, &options, &lastmod, &mut f)?;
 
-    sitemap_mod::generate_sitemap(links, base_url, &mut f, map_index)?;
+    if options.robots_txt && site_root.path() != "/" {
+        tracing::warn!(
+            "robots.txt not written: crawlers only read it at the root of the host, but the book is served from {site_root}"
+        );
+    } else if options.robots_txt {
+        let sitemap_file_name = sitemap_dest_file_path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("sitemap.xml");
+        let sitemap_url = site_root.join(sitemap_file_name).map_err(|e| {
+            Error::config(format!("Invalid sitemap URL for {sitemap_file_name}: {e}"))
+        })?;
+        let robots_txt_path = sitemap_dest_file_path.with_file_name("robots.txt");
+        let mut f = File::create(&robots_txt_path).map_err(|e| Error::io(&robots_txt_path, e))?;
+        sitemap_mod::write_robots_txt(&sitemap_url, &mut f)?;
+        tracing::info!("robots.txt created.");
+    }
 
     Ok(())
 }
 
+/// Create a sitemap from the contents of a `SUMMARY.md` file, using
+/// mdBook's URL rules, and write it to a writer (e.g. a File or a
+/// `Vec<u8>`). Draft chapters and chapters matched by `options.exclude`
+/// are skipped. `options.robots_txt` is ignored. As the chapters are not
+/// read, there are no `<lastmod>` dates.
+///
+/// summary_markdown: the contents of `SUMMARY.md`.
+///
+/// base_url: base URL e.g. <https://john-cd.com/rust_howto/>.
+///
+/// options: see [`SitemapOptions`].
+///
+/// w: writer to write into.
+pub fn write_sitemap<W: Write>(
+    summary_markdown: &str,
+    base_url: &url::Url,
+    options: &SitemapOptions,
+    w: &mut W,
+) -> Result<()> {
+    let site_root = site_root(base_url, options)?;
+    let chapters = summary::parse_summary(summary_markdown);
+    sitemap_mod::generate_sitemap(&chapters, &site_root, options, &HashMap::new(), w)
+}
+
+/// Check the base URL and compute the URL of the root of the book
+fn site_root(base_url: &url::Url, options: &SitemapOptions) -> Result<url::Url> {
+    // Returns an error whether the base URL is a 'cannot-be-a-base' URL,
+    // meaning that parsing a relative URL string with this URL
+    // as the base will return an error.
+    if base_url.cannot_be_a_base() {
+        return Err(Error::config(format!(
+            "Invalid URL - cannot be a base: {base_url}"
+        )));
+    }
+    sitemap_mod::site_root_url(base_url, options.site_url.as_deref())
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::PathBuf;
 
+    use anyhow::Result;
     use url::Url;
 
     use super::*;
@@ -83,13 +141,11 @@ fn test_generate_sitemap_invalid_base_url() {
         let markdown_src_dir_path = PathBuf::from("non_existent_src");
         let base_url = Url::parse("mailto:test@example.com").unwrap();
         let sitemap_dest_file_path = PathBuf::from("sitemap.xml");
-        let map_index = None;
-
         let result = generate_sitemap(
             markdown_src_dir_path,
             base_url,
             sitemap_dest_file_path,
-            map_index,
+            SitemapOptions::default(),
         );
 
         assert!(result.is_err());
@@ -100,4 +156,93 @@ fn test_generate_sitemap_invalid_base_url() {
                 .contains("Invalid URL - cannot be a base")
         );
     }
+
+    #[test]
+    fn test_generate_sitemap_with_robots_txt() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        std::fs::create_dir(&src).unwrap();
+        std::fs::write(
+            src.join("SUMMARY.md"),
+            "# Summary\n\n[Intro](README.md)\n\n- [Chapter](ch/a.md)\n- [Refs](refs.md)\n",
+        )
+        .unwrap();
+        std::fs::create_dir(src.join("ch")).unwrap();
+        std::fs::write(src.join("ch/a.md"), "+++\ndate = 2024-05-01\n+++\n# A\n").unwrap();
+        let dest = dir.path().join("book").join("sitemap.xml");
+        let robots_txt_path = dir.path().join("book").join("robots.txt");
+        // robots.txt by default
+        generate_sitemap(
+            &src,
+            Url::parse("https://example.com").unwrap(),
+            &dest,
+            SitemapOptions::default(),
+        )
+        .unwrap();
+        let robots = std::fs::read_to_string(&robots_txt_path).unwrap();
+        assert!(robots.contains("Sitemap: https://example.com/sitemap.xml"));
+        std::fs::remove_file(&robots_txt_path).unwrap();
+
+        // Not at the root of the host: robots.txt is skipped
+        let options = SitemapOptions {
+            site_url: Some("/mybook/".into()),
+            ..Default::default()
+        };
+        generate_sitemap(
+            &src,
+            Url::parse("https://example.com").unwrap(),
+            &dest,
+            options,
+        )
+        .unwrap();
+
+        let xml = std::fs::read_to_string(&dest).unwrap();
+        assert!(xml.contains("<loc>https://example.com/mybook/index.html</loc>"));
+        assert!(xml.contains("<loc>https://example.com/mybook/ch/a.html</loc>"));
+        assert_eq!(xml.matches("<lastmod>2024-05-01</lastmod>").count(), 1);
+        assert!(!xml.contains("refs"));
+        assert!(!robots_txt_path.exists());
+
+        let options = SitemapOptions {
+            robots_txt: false,
+            ..Default::default()
+        };
+        generate_sitemap(
+            &src,
+            Url::parse("https://example.com").unwrap(),
+            &dest,
+            options,
+        )
+        .unwrap();
+        assert!(!robots_txt_path.exists());
+    }
+
+    #[test]
+    fn test_generate_sitemap_without_summary() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = generate_sitemap(
+            dir.path(),
+            Url::parse("https://example.com").unwrap(),
+            dir.path().join("sitemap.xml"),
+            SitemapOptions::default(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::Io { .. }));
+        assert_eq!(err.path(), Some(dir.path().join("SUMMARY.md").as_path()));
+    }
+
+    #[test]
+    fn test_write_sitemap() -> Result<()> {
+        let mut buf = Vec::new();
+        write_sitemap(
+            "# Summary\n\n- [Chapter](ch/README.md)\n- [Draft]()\n",
+            &Url::parse("https://example.com/book")?,
+            &SitemapOptions::default(),
+            &mut buf,
+        )?;
+        let xml = String::from_utf8(buf)?;
+        assert!(xml.contains("<loc>https://example.com/book/ch/index.html</loc>"));
+        assert!(!xml.contains("Draft"));
+        Ok(())
+    }
 }
@@ -0,0 +1,641 @@
+//! The Markdown sources of a book, read and parsed once: files,
+//! `SUMMARY.md`, reference definitions, links, code blocks, includes and
+//! front matter.
+//!
+//! [`Book`] answers queries with typed values, and the functions that
+//! write links or reference definitions to a file are built on it.
+mod types;
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use pulldown_cmark::LinkType;
+use rayon::prelude::*;
+pub use types::*;
+
+use crate::Error;
+use crate::Result;
+use crate::cache::FileData;
+use crate::cache::RefDef;
+use crate::cache::UNRESOLVED_URL;
+use crate::fs;
+use crate::link;
+use crate::link::Link;
+use crate::parser::ChapterMetadata;
+use crate::parser::ParserOptions;
+use crate::summary;
+use crate::write_from_parser;
+
+/// Markdown file of a book and the data extracted from it
+#[derive(Debug, Clone)]
+pub(crate) struct BookFile {
+    pub(crate) path: PathBuf,
+    pub(crate) data: FileData,
+}
+
+/// The Markdown files of a book's source directory, parsed once.
+///
+/// Load it with [`Book::load`] (or [`crate::MarkdownCache::load`], which
+/// only parses the files that changed since the last run), then query
+/// it as many times as needed e.g. from a `build.rs` build script:
+///
+/// ```no_run
+/// # fn main() -> anyhow::Result<()> {
+/// let book = mdbook_utils::Book::load("./src/")?;
+/// for broken in book.broken_refs() {
+///     println!("{}: [{}]", broken.file_path.display(), broken.reference);
+/// }
+/// let http_links = book
+///     .links()
+///     .into_iter()
+///     .filter(|l| l.url.starts_with("http"))
+///     .count();
+/// # Ok(())
+/// # }
+/// ```
+///
+/// As with mdBook's `{{#include}}`-ed reference definitions (e.g. a
+/// `refs.md` file), reference-style links are resolved against the
+/// definitions of the whole book; the first definition of a label wins.
+#[derive(Debug, Clone, Default)]
+pub struct Book {
+    src_dir_path: PathBuf,
+    /// In the order they are read by the other functions
+    files: Vec<BookFile>,
+    summary: Vec<SummaryChapter>,
+    parser_options: ParserOptions,
+}
+
+impl Book {
+    /// Read and parse all Markdown files of a source directory, in
+    /// parallel, and `SUMMARY.md`, if present, with the default parser
+    /// options.
+    ///
+    /// src_dir_path: path to the source directory.
+    pub fn load<P: AsRef<Path>>(src_dir_path: P) -> Result<Self> {
+        Self::load_with_options(src_dir_path, &ParserOptions::default())
+    }
+
+    /// Read and parse all Markdown files of a source directory, see
+    /// [`Book::load`], in the given Markdown dialect.
+    ///
+    /// src_dir_path: path to the source directory.
+    ///
+    /// parser_options: see [`ParserOptions`].
+    pub fn load_with_options<P: AsRef<Path>>(
+        src_dir_path: P,
+        parser_options: &ParserOptions,
+    ) -> Result<Self> {
+        let src_dir_path = fs::check_is_dir(src_dir_path)?;
+        let files = fs::find_markdown_files_in(&src_dir_path)?
+            .into_par_iter()
+            .map(|path| {
+                let contents = std::fs::read_to_string(&path).map_err(|e| Error::io(&path, e))?;
+                let data = FileData::extract(&contents, parser_options);
+                Ok(BookFile { path, data })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Self::from_files(src_dir_path, files, parser_options)
+    }
+
+    /// Create a book from in-memory Markdown files, given as (virtual
+    /// path, contents) pairs e.g. a `HashMap<String, String>`. The files
+    /// are sorted by path, and `SUMMARY.md`, if present, lists the
+    /// chapters.
+    ///
+    /// ```
+    /// use mdbook_utils::Book;
+    ///
+    /// let book = Book::from_sources([
+    ///     ("SUMMARY.md", "- [Intro](intro.md)\n"),
+    ///     (
+    ///         "intro.md",
+    ///         "See [serde].\n\n[serde]: https://docs.rs/serde\n",
+    ///     ),
+    /// ]);
+    /// assert_eq!(book.summary().len(), 1);
+    /// assert_eq!(
+    ///     book.refdefs_to_string().unwrap(),
+    ///     "[serde]: https://docs.rs/serde\n"
+    /// );
+    /// ```
+    pub fn from_sources<I, P, S>(sources: I) -> Self
+    where
+        I: IntoIterator<Item = (P, S)>,
+        P: Into<PathBuf>,
+        S: AsRef<str>,
+    {
+        Self::from_sources_with_options(sources, &ParserOptions::default())
+    }
+
+    /// Create a book from in-memory Markdown files, see
+    /// [`Book::from_sources`], in the given Markdown dialect.
+    ///
+    /// parser_options: see [`ParserOptions`].
+    pub fn from_sources_with_options<I, P, S>(sources: I, parser_options: &ParserOptions) -> Self
+    where
+        I: IntoIterator<Item = (P, S)>,
+        P: Into<PathBuf>,
+        S: AsRef<str>,
+    {
+        let mut sources: Vec<(PathBuf, S)> = sources
+            .into_iter()
+            .map(|(path, contents)| (path.into(), contents))
+            .collect();
+        sources.sort_by(|a, b| a.0.cmp(&b.0));
+        let summary = sources
+            .iter()
+            .find(|(path, _)| path == Path::new("SUMMARY.md"))
+            .map(|(_, contents)| summary::parse_summary(contents.as_ref()))
+            .unwrap_or_default();
+        let files = sources
+            .into_iter()
+            .map(|(path, contents)| BookFile {
+                path,
+                data: FileData::extract(contents.as_ref(), parser_options),
+            })
+            .collect();
+        Self::new(PathBuf::new(), files, summary, parser_options)
+    }
+
+    /// Create a book from a single in-memory Markdown document, whose
+    /// path is empty
+    pub fn from_markdown(markdown: &str) -> Self {
+        Self::from_sources([("", markdown)])
+    }
+
+    /// Create a book from the data of its Markdown files, parsed with
+    /// the given options, reading `SUMMARY.md`, if present.
+    pub(crate) fn from_files(
+        src_dir_path: PathBuf,
+        files: Vec<BookFile>,
+        parser_options: &ParserOptions,
+    ) -> Result<Self> {
+        let summary = if src_dir_path.join("SUMMARY.md").is_file() {
+            summary::read_summary(&src_dir_path)?
+        } else {
+            Vec::new()
+        };
+        Ok(Self::new(src_dir_path, files, summary, parser_options))
+    }
+
+    fn new(
+        src_dir_path: PathBuf,
+        files: Vec<BookFile>,
+        summary: Vec<summary::Chapter>,
+        parser_options: &ParserOptions,
+    ) -> Self {
+        let summary = summary
+            .into_iter()
+            .map(|c| SummaryChapter {
+                title: c.title,
+                path: c.path,
+                depth: c.depth,
+            })
+            .collect();
+        Self {
+            src_dir_path,
+            files,
+            summary,
+            parser_options: parser_options.clone(),
+        }
+    }
+
+    /// Returns the source directory
+    pub fn src_dir_path(&self) -> &Path {
+        &self.src_dir_path
+    }
+
+    /// Returns the options the Markdown files were parsed with
+    pub fn parser_options(&self) -> &ParserOptions {
+        &self.parser_options
+    }
+
+    /// Returns the paths of the Markdown files
+    pub fn file_paths(&self) -> Vec<&Path> {
+        self.files.iter().map(|f| f.path.as_path()).collect()
+    }
+
+    /// Returns the chapters listed in `SUMMARY.md`, in order; empty if
+    /// there is no `SUMMARY.md`
+    pub fn summary(&self) -> &[SummaryChapter] {
+        &self.summary
+    }
+
+    /// Returns the links of all files, in order. Reference-style links
+    /// are resolved; those whose reference is not defined anywhere are
+    /// not links (see [`Book::broken_refs`]).
+    pub fn links(&self) -> Vec<LinkInfo> {
+        let refdefs = self.refdefs_by_label();
+        self.files
+            .iter()
+            .flat_map(|f| {
+                f.data
+                    .links
+                    .iter()
+                    .filter_map(|l| self.resolve(l, &refdefs))
+                    .map(|l| LinkInfo {
+                        file_path: f.path.clone(),
+                        kind: l.link_type.map_or(LinkKind::Inline, LinkKind::from),
+                        text: l.text.as_deref().unwrap_or_default().to_string(),
+                        url: l.get_url().to_string(),
+                        title: l.title.as_deref().map(String::from),
+                        label: l.label.as_deref().map(String::from),
+                        image: l.image_url.as_deref().map(|url| ImageInfo {
+                            alt_text: l.image_alt_text.as_deref().unwrap_or_default().to_string(),
+                            url: url.to_string(),
+                            label: l.image_label.as_deref().map(String::from),
+                        }),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Returns the reference-style links whose reference is not defined
+    /// anywhere in the book, in order
+    pub fn broken_refs(&self) -> Vec<BrokenRef> {
+        let refdefs = self.refdefs_by_label();
+        self.files
+            .iter()
+            .flat_map(|f| {
+                f.data
+                    .unresolved
+                    .iter()
+                    .filter(|u| !refdefs.contains_key(&u.reference.to_lowercase()))
+                    .map(|u| BrokenRef {
+                        file_path: f.path.clone(),
+                        reference: u.reference.clone(),
+                        kind: match u.link_type.as_str() {
+                            "Reference" => LinkKind::Reference,
+                            "Collapsed" => LinkKind::Collapsed,
+                            _ => LinkKind::Shortcut,
+                        },
+                    })
+            })
+            .collect()
+    }
+
+    /// Returns all reference definitions, in order of appearance,
+    /// including those that repeat a label defined earlier
+    pub fn refdefs(&self) -> Vec<RefDefInfo> {
+        self.files
+            .iter()
+            .flat_map(|f| {
+                f.data.refdefs.iter().map(|d| RefDefInfo {
+                    file_path: f.path.clone(),
+                    label: d.label.clone(),
+                    url: d.dest.clone(),
+                    title: d.title.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the code blocks of all files, in order
+    pub fn code_blocks(&self) -> Vec<CodeBlockInfo> {
+        self.files
+            .iter()
+            .flat_map(|f| {
+                f.data.code_blocks.iter().map(|c| CodeBlockInfo {
+                    file_path: f.path.clone(),
+                    info: c.info.clone(),
+                    code: c.code.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the `{{#include ...}}` statements of all files, in order
+    pub fn includes(&self) -> Vec<IncludeInfo> {
+        self.files
+            .iter()
+            .flat_map(|f| {
+                f.data.includes.iter().map(|path| IncludeInfo {
+                    file_path: f.path.clone(),
+                    path: path.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the front matter of the files that have one, by path
+    pub fn metadata(&self) -> BTreeMap<PathBuf, ChapterMetadata> {
+        self.files
+            .iter()
+            .filter(|f| !f.data.metadata.is_empty())
+            .map(|f| (f.path.clone(), f.data.metadata.clone()))
+            .collect()
+    }
+
+    // Internal queries, used by the functions that write to a file
+
+    /// Returns the reference definitions of the book by lowercased
+    /// label. As in a single Markdown document, the first definition of
+    /// a label wins.
+    pub(crate) fn refdefs_by_label(&self) -> HashMap<String, &RefDef> {
+        let mut refdefs = HashMap::new();
+        for d in self.files.iter().flat_map(|f| f.data.refdefs.iter()) {
+            refdefs.entry(d.label.to_lowercase()).or_insert(d);
+        }
+        refdefs
+    }
+
+    /// Resolve a reference-style link defined in another file, if
+    /// needed. Returns `None` if its reference is not defined anywhere.
+    fn resolve(&self, l: &Link, refdefs: &HashMap<String, &RefDef>) -> Option<Link> {
+        let find =
+            |label: Option<&str>| label.and_then(|l| refdefs.get(&l.to_lowercase()).copied());
+        let mut l = l.clone();
+        if l.get_url() == UNRESOLVED_URL {
+            let d = find(l.label.as_deref())?;
+            l.url = Some(d.dest.clone());
+            l.title = d.title.clone();
+            l.link_type = l.link_type.map(to_known);
+        }
+        if l.image_url.as_deref() == Some(UNRESOLVED_URL) {
+            let d = find(l.image_label.as_deref())?;
+            l.image_url = Some(d.dest.clone());
+            l.image_title = d.title.clone();
+            l.image_link_type = l.image_link_type.map(to_known);
+        }
+        Some(l)
+    }
+
+    /// Returns the links of the book, resolving the reference-style
+    /// links defined in other files
+    pub(crate) fn all_links(&self) -> Vec<Link> {
+        let refdefs = self.refdefs_by_label();
+        self.files
+            .iter()
+            .flat_map(|f| f.data.links.iter())
+            .filter_map(|l| self.resolve(l, &refdefs))
+            .collect()
+    }
+
+    /// Returns the references that are not defined anywhere in the book,
+    /// as (reference, text, link type) triples, see
+    /// [`link::write_broken_links_to`]. The link types are those of
+    /// links with an unknown reference e.g. `ShortcutUnknown`.
+    pub(crate) fn broken_links(&self) -> Vec<(String, String, String)> {
+        self.broken_refs()
+            .into_iter()
+            .map(|b| (b.reference, String::new(), format!("{:?}Unknown", b.kind)))
+            .collect()
+    }
+
+    // Functions that write to a writer
+
+    /// Write the reference definitions, sorted by label, to a writer
+    /// (e.g. a File). If a label is defined more than once, the first
+    /// definition wins.
+    pub fn write_refdefs<W: Write>(&self, w: &mut W) -> Result<()> {
+        let refdefs: BTreeMap<_, _> = self
+            .refdefs_by_label()
+            .into_values()
+            .map(|d| (d.label.as_str(), (d.dest.as_str(), d.title.as_deref())))
+            .collect();
+        write_from_parser::write_sorted_refdefs_to(refdefs, w)
+    }
+
+    /// Write all links (except internal links) as reference-style links
+    /// and reference definitions to a writer (e.g. a File)
+    pub fn write_all_links<W: Write>(&self, w: &mut W) -> Result<()> {
+        link::write_reference_style_links_to(link::http_links(self.all_links()), w)
+    }
+
+    /// Write the inline links and autolinks (i.e., not written as
+    /// reference-style links) as reference-style links and reference
+    /// definitions to a writer (e.g. a File)
+    pub fn write_inline_links<W: Write>(&self, w: &mut W) -> Result<()> {
+        link::write_reference_style_links_to(link::inline_links(self.all_links()), w)
+    }
+
+    /// Write the links whose URL appears more than once to a writer
+    /// (e.g. a File)
+    pub fn write_duplicate_links<W: Write>(&self, w: &mut W) -> Result<()> {
+        link::write_duplicate_links_to(link::duplicate_links(self.all_links()), w)
+    }
+
+    /// Write the links whose reference is not defined to a writer (e.g.
+    /// a File)
+    pub fn write_broken_links<W: Write>(&self, w: &mut W) -> Result<()> {
+        link::write_broken_links_to(self.broken_links(), w)
+    }
+
+    /// Identify the reference definitions whose URL is a GitHub repo,
+    /// and write badges for them (links, then reference definitions) to a
+    /// writer (e.g. a File)
+    pub fn write_badges<W: Write>(&self, w: &mut W) -> Result<()> {
+        let refdefs: BTreeMap<_, _> = self
+            .refdefs_by_label()
+            .into_values()
+            .map(|d| (d.label.as_str(), d.dest.as_str()))
+            .collect();
+        write_from_parser::write_github_repo_badge_refdefs(refdefs, w)
+    }
+
+    // Functions that return a string
+
+    /// See [`Book::write_refdefs`]
+    pub fn refdefs_to_string(&self) -> Result<String> {
+        self.write_to_string(Self::write_refdefs)
+    }
+
+    /// See [`Book::write_all_links`]
+    pub fn all_links_to_string(&self) -> Result<String> {
+        self.write_to_string(Self::write_all_links)
+    }
+
+    /// See [`Book::write_inline_links`]
+    pub fn inline_links_to_string(&self) -> Result<String> {
+        self.write_to_string(Self::write_inline_links)
+    }
+
+    /// See [`Book::write_duplicate_links`]
+    pub fn duplicate_links_to_string(&self) -> Result<String> {
+        self.write_to_string(Self::write_duplicate_links)
+    }
+
+    /// See [`Book::write_broken_links`]
+    pub fn broken_links_to_string(&self) -> Result<String> {
+        self.write_to_string(Self::write_broken_links)
+    }
+
+    /// See [`Book::write_badges`]
+    pub fn badges_to_string(&self) -> Result<String> {
+        self.write_to_string(Self::write_badges)
+    }
+
+    /// Write to a buffer and return it as a string
+    fn write_to_string<F>(&self, func: F) -> Result<String>
+    where
+        F: FnOnce(&Self, &mut Vec<u8>) -> Result<()>,
+    {
+        let mut buf = Vec::new();
+        func(self, &mut buf)?;
+        String::from_utf8(buf).map_err(|e| Error::Other(e.into()))
+    }
+
+    /// Create a file, creating its parent directories if needed, and
+    /// write to it e.g. `book.write_to_file(dest, Book::write_refdefs)`
+    pub fn write_to_file<P, F>(&self, dest_file_path: P, func: F) -> Result<()>
+    where
+        P: AsRef<Path>,
+        F: FnOnce(&Self, &mut File) -> Result<()>,
+    {
+        fs::create_parent_dir_for(dest_file_path.as_ref())?;
+        let mut f = File::create(dest_file_path.as_ref())
+            .map_err(|e| Error::io(dest_file_path.as_ref(), e))?;
+        func(self, &mut f).map_err(|e| e.in_file(dest_file_path.as_ref()))
+    }
+}
+
+/// Returns the link type of a reference-style link whose definition was
+/// found, e.g. `Shortcut` for `ShortcutUnknown`
+fn to_known(link_type: LinkType) -> LinkType {
+    match link_type {
+        LinkType::ReferenceUnknown => LinkType::Reference,
+        LinkType::CollapsedUnknown => LinkType::Collapsed,
+        LinkType::ShortcutUnknown => LinkType::Shortcut,
+        lt => lt,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book(files: &[&str]) -> Book {
+        Book {
+            files: files
+                .iter()
+                .enumerate()
+                .map(|(i, contents)| BookFile {
+                    path: PathBuf::from(format!("{i}.md")),
+                    data: FileData::extract(contents, &ParserOptions::default()),
+                })
+                .collect(),
+            ..Book::default()
+        }
+    }
+
+    #[test]
+    fn test_book_links() {
+        let book = book(&[
+            "[a][x] [b] [c](https://c.com) [missing]\n",
+            "[X]: https://x.com\n[b]: https://b.com \"B\"\n",
+        ]);
+        let links = book.all_links();
+        let urls: Vec<_> = links.iter().map(|l| l.get_url()).collect();
+        assert_eq!(
+            urls,
+            vec!["https://x.com", "https://b.com", "https://c.com"]
+        );
+        assert_eq!(links[1].get_link_type(), Some(LinkKind::Shortcut));
+        assert_eq!(links[1].title.as_deref(), Some("B"));
+        assert_eq!(
+            book.broken_links(),
+            vec![("missing".into(), String::new(), "ShortcutUnknown".into())]
+        );
+
+        let links = book.links();
+        assert_eq!(links[0].kind, LinkKind::Reference);
+        assert_eq!(links[0].text, "a");
+        assert_eq!(links[0].label.as_deref(), Some("x"));
+        assert_eq!(links[2].file_path, PathBuf::from("0.md"));
+        assert_eq!(
+            book.broken_refs(),
+            vec![BrokenRef {
+                file_path: PathBuf::from("0.md"),
+                reference: "missing".into(),
+                kind: LinkKind::Shortcut,
+            }]
+        );
+        assert_eq!(book.refdefs()[0].file_path, PathBuf::from("1.md"));
+    }
+
+    #[test]
+    fn test_book_load() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let src = dir.path().join("src");
+        std::fs::create_dir(&src)?;
+        std::fs::write(src.join("SUMMARY.md"), "[Intro](intro.md)\n\n- [A](a.md)\n")?;
+        std::fs::write(
+            src.join("a.md"),
+            "[![badge][b-badge]][b]\n\n```rust,editable\nfn main() {}\n```\n\n\
+             {{#include ../code/a.rs:main}}\n\n[b]: https://b.com\n[b-badge]: https://b.com/badge.svg\n",
+        )?;
+        let book = Book::load(&src)?;
+        assert_eq!(
+            book.file_paths(),
+            vec![src.join("SUMMARY.md"), src.join("a.md")]
+        );
+        assert_eq!(
+            book.summary()
+                .iter()
+                .map(|c| (c.path.as_deref(), c.depth))
+                .collect::<Vec<_>>(),
+            vec![(Some("intro.md"), 0), (Some("a.md"), 1)]
+        );
+        let links = book.links();
+        assert_eq!(links.len(), 3);
+        assert_eq!(
+            links[2].image.as_ref().map(|i| i.url.as_str()),
+            Some("https://b.com/badge.svg")
+        );
+        assert_eq!(book.code_blocks()[0].lang(), Some("rust"));
+        assert_eq!(book.includes()[0].path, "../code/a.rs");
+
+        let dest = dir.path().join("out/refdefs.md");
+        book.write_to_file(&dest, Book::write_refdefs)?;
+        assert_eq!(
+            std::fs::read_to_string(&dest)?,
+            "[b]: https://b.com\n[b-badge]: https://b.com/badge.svg\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_book_from_sources() -> Result<()> {
+        let sources = HashMap::from([
+            ("b.md", "---\ntitle: B\n---\n[repo] [missing]\n"),
+            (
+                "a.md",
+                "[repo]: https://github.com/john-cd/mdbook-utils\n[repo]: https://x.com\n",
+            ),
+        ]);
+        let book = Book::from_sources(sources);
+        assert_eq!(
+            book.file_paths(),
+            vec![Path::new("a.md"), Path::new("b.md")]
+        );
+        assert!(book.summary().is_empty());
+        assert_eq!(
+            book.metadata()
+                .get(Path::new("b.md"))
+                .and_then(ChapterMetadata::title),
+            Some("B")
+        );
+        assert_eq!(
+            book.refdefs_to_string()?,
+            "[repo]: https://github.com/john-cd/mdbook-utils\n"
+        );
+        assert_eq!(
+            book.badges_to_string()?,
+            "# Links and Reference Definitions\n\n[![repo][repo-badge]][repo]\n\n\
+             [repo]: https://github.com/john-cd/mdbook-utils\n\
+             [repo-badge]: https://img.shields.io/badge/mdbook-utils-steelblue?logo=github\n"
+        );
+        assert!(book.broken_links_to_string()?.contains("missing"));
+
+        let book = Book::from_markdown("<https://a.com>\n");
+        assert_eq!(book.links()[0].kind, LinkKind::Autolink);
+        assert!(book.inline_links_to_string()?.contains("https://a.com"));
+        Ok(())
+    }
+}
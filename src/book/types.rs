@@ -0,0 +1,106 @@
+//! Values returned by the queries of [`super::Book`]
+
+use std::path::PathBuf;
+
+pub use crate::link::LinkKind;
+
+/// Link of a Markdown file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkInfo {
+    /// Markdown file that contains the link
+    pub file_path: PathBuf,
+    /// Kind of link
+    pub kind: LinkKind,
+    /// Text of the link
+    pub text: String,
+    /// URL. The URL of a reference-style link is that of its reference
+    /// definition, which may be in another file.
+    pub url: String,
+    /// Optional title
+    pub title: Option<String>,
+    /// Label of a reference-style link
+    pub label: Option<String>,
+    /// Image within the link e.g. a badge: `[![alt](image_url)](url)`
+    pub image: Option<ImageInfo>,
+}
+
+/// Image within a link
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageInfo {
+    /// Alternative text
+    pub alt_text: String,
+    /// URL of the image
+    pub url: String,
+    /// Label of a reference-style image
+    pub label: Option<String>,
+}
+
+/// Reference-style link whose reference is not defined anywhere in the
+/// book
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenRef {
+    /// Markdown file that contains the link
+    pub file_path: PathBuf,
+    /// Reference, as written
+    pub reference: String,
+    /// `Reference`, `Collapsed` or `Shortcut`
+    pub kind: LinkKind,
+}
+
+/// Reference definition: `[label]: url "title"`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefDefInfo {
+    /// Markdown file that contains the definition
+    pub file_path: PathBuf,
+    /// Label, as written
+    pub label: String,
+    /// URL
+    pub url: String,
+    /// Optional title
+    pub title: Option<String>,
+}
+
+/// Fenced or indented code block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeBlockInfo {
+    /// Markdown file that contains the code block
+    pub file_path: PathBuf,
+    /// Info string e.g. `rust,editable`; empty for indented code blocks
+    pub info: String,
+    /// Code
+    pub code: String,
+}
+
+impl CodeBlockInfo {
+    /// Returns the language of the code block e.g. `rust` for
+    /// `rust,editable`, if any
+    pub fn lang(&self) -> Option<&str> {
+        self.info
+            .split([',', ' '])
+            .next()
+            .filter(|lang| !lang.is_empty())
+    }
+}
+
+/// `{{#include ...}}` statement (or `rustdoc_include`, `playground`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncludeInfo {
+    /// Markdown file that contains the statement
+    pub file_path: PathBuf,
+    /// Path of the included file, relative to the Markdown file, without
+    /// any anchor or line range
+    pub path: String,
+}
+
+/// Chapter listed in `SUMMARY.md`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SummaryChapter {
+    /// Title
+    pub title: String,
+    /// Path of the Markdown file, relative to the source directory;
+    /// `None` for draft chapters
+    pub path: Option<String>,
+    /// Nesting level: 0 for prefix / suffix chapters, 1 for top-level
+    /// numbered chapters, 2 for their sub-chapters, etc.
+    pub depth: usize,
+}
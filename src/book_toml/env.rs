@@ -0,0 +1,87 @@
+//! `MDBOOK_*` environment variable overrides of `book.toml`
+
+use serde_json::Value as JsonValue;
+use toml::Table;
+use toml::Value;
+use tracing::debug;
+use tracing::warn;
+
+/// Apply the overrides of the `MDBOOK_*` (environment) variables to the
+/// contents of `book.toml`, following mdBook's rules:
+///
+/// - the prefix is removed, the name is lowercased, `__` is replaced by `.` and
+///   `_` by `-` to obtain the key e.g. `MDBOOK_BOOK__SRC` sets `book.src`;
+/// - the value is parsed as JSON, if possible, otherwise used as a string;
+/// - a JSON object assigned to `MDBOOK_BOOK` or `MDBOOK_BUILD` sets each of its
+///   keys, instead of replacing the whole table.
+pub(super) fn apply_env_overrides<I>(table: &mut Table, vars: I)
+where
+    I: IntoIterator<Item = (String, String)>,
+{
+    for (name, value) in vars {
+        let Some(key) = parse_env_key(&name) else {
+            continue;
+        };
+        let value = serde_json::from_str(&value).unwrap_or(JsonValue::String(value));
+        let value = match Value::try_from(value) {
+            Ok(v) => v,
+            Err(e) => {
+                warn!("Ignoring the environment variable {name}: {e}");
+                continue;
+            }
+        };
+        debug!("{name} overrides `{key}` in book.toml");
+        match value {
+            Value::Table(t) if key == "book" || key == "build" => {
+                for (k, v) in t {
+                    set(table, &format!("{key}.{k}"), v);
+                }
+            }
+            v => set(table, &key, v),
+        }
+    }
+}
+
+/// Convert the name of an environment variable into a dotted key
+/// e.g. `MDBOOK_OUTPUT__HTML__SITE_URL` into `output.html.site-url`.
+fn parse_env_key(name: &str) -> Option<String> {
+    name.strip_prefix("MDBOOK_")
+        .map(|key| key.to_lowercase().replace("__", ".").replace('_', "-"))
+}
+
+/// Set the value of a dotted key, creating the intermediate tables.
+fn set(table: &mut Table, key: &str, value: Value) {
+    let mut table = table;
+    let mut parts = key.split('.').peekable();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            table.insert(part.to_string(), value);
+            return;
+        }
+        let entry = table
+            .entry(part)
+            .or_insert_with(|| Value::Table(Table::new()));
+        if !entry.is_table() {
+            *entry = Value::Table(Table::new());
+        }
+        let Value::Table(t) = entry else {
+            unreachable!()
+        };
+        table = t;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_env_key() {
+        assert_eq!(
+            parse_env_key("MDBOOK_OUTPUT__HTML__SITE_URL").as_deref(),
+            Some("output.html.site-url")
+        );
+        assert_eq!(parse_env_key("MDBOOK_BOOK").as_deref(), Some("book"));
+        assert_eq!(parse_env_key("BOOK_ROOT_DIR_PATH"), None);
+    }
+}
@@ -0,0 +1,463 @@
+//! Model of `book.toml`, the configuration file of `mdbook`.
+//!
+//! Paths are resolved as `mdbook` does: any relative path is taken
+//! relative to the root directory of the book, where `book.toml` is
+//! located.
+mod env;
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use tracing::debug;
+
+use crate::Error;
+use crate::Result;
+
+/// Contents of `book.toml`, with the defaults used by `mdbook`.
+///
+/// See <https://rust-lang.github.io/mdBook/format/configuration/index.html>.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct BookToml {
+    /// Root directory of the book, which contains `book.toml`
+    #[serde(skip)]
+    root: PathBuf,
+
+    /// `[book]` table
+    pub book: BookTable,
+
+    /// `[build]` table
+    pub build: BuildTable,
+
+    /// `[rust]` table
+    pub rust: RustTable,
+
+    /// `[output.*]` tables, by renderer name
+    pub output: BTreeMap<String, OutputTable>,
+
+    /// `[preprocessor.*]` tables, by preprocessor name
+    pub preprocessor: BTreeMap<String, PreprocessorTable>,
+
+    /// `[output.html]` table, with defaults if absent
+    #[serde(skip)]
+    html: HtmlOutput,
+
+    /// The whole file, for the keys of custom renderers and
+    /// preprocessors
+    #[serde(skip)]
+    table: toml::Table,
+}
+
+/// `[book]` table: metadata of the book
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct BookTable {
+    /// Title of the book
+    pub title: Option<String>,
+    /// Authors of the book
+    pub authors: Vec<String>,
+    /// Description of the book, added as meta information in the HTML
+    pub description: Option<String>,
+    /// Main language of the book. Defaults to `en`.
+    pub language: Option<String>,
+    /// Unused by `mdbook`, kept for compatibility
+    pub multilingual: bool,
+    /// Source directory, relative to the book's root. Defaults to `src`.
+    pub src: PathBuf,
+}
+
+impl Default for BookTable {
+    fn default() -> Self {
+        Self {
+            title: None,
+            authors: Vec::new(),
+            description: None,
+            language: Some("en".into()),
+            multilingual: false,
+            src: "src".into(),
+        }
+    }
+}
+
+/// `[build]` table: options of `mdbook build`
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct BuildTable {
+    /// Build directory, relative to the book's root. Defaults to `book`.
+    pub build_dir: PathBuf,
+    /// Create the files listed in `SUMMARY.md` that are missing
+    pub create_missing: bool,
+    /// Run the `links` and `index` preprocessors by default
+    pub use_default_preprocessors: bool,
+    /// Additional directories watched by `mdbook watch` and `mdbook serve`,
+    /// relative to the book's root
+    pub extra_watch_dirs: Vec<PathBuf>,
+}
+
+impl Default for BuildTable {
+    fn default() -> Self {
+        Self {
+            build_dir: "book".into(),
+            create_missing: true,
+            use_default_preprocessors: true,
+            extra_watch_dirs: Vec::new(),
+        }
+    }
+}
+
+/// `[rust]` table: options of the Rust code blocks
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct RustTable {
+    /// Default Rust edition of the code blocks e.g. `2021`
+    pub edition: Option<String>,
+}
+
+/// `[output.*]` table: the keys common to all renderers. The other keys
+/// are read with [`BookToml::output_config`].
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct OutputTable {
+    /// Command of a custom renderer e.g. `mdbook-pdf`
+    pub command: Option<String>,
+    /// If `true`, a missing renderer command is not an error
+    pub optional: bool,
+}
+
+/// `[preprocessor.*]` table: the keys common to all preprocessors. The
+/// other keys are read with [`BookToml::preprocessor_config`].
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct PreprocessorTable {
+    /// Command of a custom preprocessor e.g. `mdbook-admonish`
+    pub command: Option<String>,
+    /// Renderers the preprocessor runs for; all of them if empty
+    pub renderers: Vec<String>,
+    /// Preprocessors that must run after this one
+    pub before: Vec<String>,
+    /// Preprocessors that must run before this one
+    pub after: Vec<String>,
+    /// If `true`, a missing preprocessor command is not an error
+    pub optional: bool,
+}
+
+/// The keys of the `[output.html]` table used to derive URLs
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct HtmlOutput {
+    /// Path of the book relative to the website's root e.g. `/mybook/`
+    pub site_url: Option<String>,
+    /// URL of the git repository of the book
+    pub git_repository_url: Option<String>,
+    /// Template of the "suggest an edit" URLs, where `{path}` is replaced by
+    /// the path of the chapter's source file e.g.
+    /// `https://github.com/org/repo/edit/main/{path}`
+    pub edit_url_template: Option<String>,
+    /// Redirects from old paths (e.g. `/old.html`) to new URLs
+    pub redirect: BTreeMap<String, String>,
+    /// Curly quotes, dashes and ellipses (`curly-quotes` in older
+    /// versions of mdBook)
+    #[serde(alias = "curly-quotes")]
+    pub smart_punctuation: bool,
+}
+
+impl BookToml {
+    /// Read `book.toml` from the root directory of a book and apply the
+    /// `MDBOOK_*` environment variable overrides, as `mdbook` does
+    /// (e.g. `MDBOOK_BOOK__TITLE` overrides `book.title`;
+    /// `MDBOOK_OUTPUT__HTML__SITE_URL` overrides `output.html.site-url`).
+    ///
+    /// book_root_dir_path: the path to the directory that contains
+    /// `book.toml`.
+    ///
+    /// Failure to open `book.toml` or to parse it returns an Error.
+    pub fn from_dir<P: AsRef<Path>>(book_root_dir_path: P) -> Result<Self> {
+        let book_toml_path = book_root_dir_path.as_ref().join("book.toml");
+        debug!("BookToml::from_dir: {}", book_toml_path.display());
+        let contents =
+            std::fs::read_to_string(&book_toml_path).map_err(|e| Error::io(&book_toml_path, e))?;
+        Self::parse_with_env(&contents, book_root_dir_path, std::env::vars())
+    }
+
+    /// Parse the contents of `book.toml`, without environment variable
+    /// overrides.
+    ///
+    /// book_root_dir_path: the path to the directory that contains
+    /// `book.toml`, against which relative paths are resolved.
+    pub fn parse<P: AsRef<Path>>(contents: &str, book_root_dir_path: P) -> Result<Self> {
+        Self::parse_with_env(contents, book_root_dir_path, [])
+    }
+
+    /// Parse the contents of `book.toml`, then apply the overrides of
+    /// the `MDBOOK_*` (environment) variables.
+    fn parse_with_env<P, I>(contents: &str, book_root_dir_path: P, vars: I) -> Result<Self>
+    where
+        P: AsRef<Path>,
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let book_toml_path = book_root_dir_path.as_ref().join("book.toml");
+        let mut table: toml::Table =
+            toml::from_str(contents).map_err(|e| Error::parse(&book_toml_path, e))?;
+        env::apply_env_overrides(&mut table, vars);
+        let mut book_toml: Self = table
+            .clone()
+            .try_into()
+            .map_err(|e| Error::parse(&book_toml_path, e))?;
+        book_toml.root = book_root_dir_path.as_ref().to_path_buf();
+        book_toml.table = table;
+        book_toml.html = book_toml.output_config("html")?.unwrap_or_default();
+        Ok(book_toml)
+    }
+
+    /// Root directory of the book
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Markdown source directory e.g. `{root}/src`
+    pub fn src_dir(&self) -> PathBuf {
+        self.root.join(&self.book.src)
+    }
+
+    /// Build directory e.g. `{root}/book`
+    pub fn build_dir(&self) -> PathBuf {
+        self.root.join(&self.build.build_dir)
+    }
+
+    /// Additional directories watched by `mdbook`, relative to the
+    /// book's root
+    pub fn extra_watch_dirs(&self) -> Vec<PathBuf> {
+        self.build
+            .extra_watch_dirs
+            .iter()
+            .map(|d| self.root.join(d))
+            .collect()
+    }
+
+    /// Names of the renderers run by `mdbook build`: one per `[output.*]`
+    /// table, or `html` if there is none.
+    pub fn renderers(&self) -> Vec<&str> {
+        if self.output.is_empty() {
+            vec!["html"]
+        } else {
+            self.output.keys().map(String::as_str).collect()
+        }
+    }
+
+    /// Output directory of a renderer, or `None` if the renderer is not
+    /// run by `mdbook build`.
+    ///
+    /// mdBook places the output directly in the build directory if there
+    /// is only one renderer. If there is more than one, the output of
+    /// each renderer is placed in a subdirectory named after it (e.g.
+    /// `book/html` and `book/markdown`).
+    /// <https://rust-lang.github.io/mdBook/format/configuration/renderers.html>
+    pub fn renderer_build_dir(&self, renderer: &str) -> Option<PathBuf> {
+        let renderers = self.renderers();
+        if !renderers.contains(&renderer) {
+            return None;
+        }
+        let build_dir = self.build_dir();
+        if renderers.len() > 1 {
+            Some(build_dir.join(renderer))
+        } else {
+            Some(build_dir)
+        }
+    }
+
+    /// Output directory of the HTML renderer, if run
+    pub fn html_build_dir(&self) -> Option<PathBuf> {
+        self.renderer_build_dir("html")
+    }
+
+    /// Output directory of the Markdown renderer (the book's fully
+    /// expanded Markdown), if run
+    pub fn markdown_build_dir(&self) -> Option<PathBuf> {
+        self.renderer_build_dir("markdown")
+    }
+
+    /// The `[output.html]` table, with defaults if absent.
+    pub fn html(&self) -> &HtmlOutput {
+        &self.html
+    }
+
+    /// Deserialize the `[output.{renderer}]` table e.g. the
+    /// configuration of a custom renderer.
+    ///
+    /// Returns `None` if the table is absent.
+    pub fn output_config<T: DeserializeOwned>(&self, renderer: &str) -> Result<Option<T>> {
+        self.config("output", renderer)
+    }
+
+    /// Deserialize the `[preprocessor.{preprocessor}]` table e.g. the
+    /// configuration of a custom preprocessor.
+    ///
+    /// Returns `None` if the table is absent.
+    pub fn preprocessor_config<T: DeserializeOwned>(
+        &self,
+        preprocessor: &str,
+    ) -> Result<Option<T>> {
+        self.config("preprocessor", preprocessor)
+    }
+
+    /// Deserialize the `[{kind}.{name}]` table, if present.
+    fn config<T: DeserializeOwned>(&self, kind: &str, name: &str) -> Result<Option<T>> {
+        self.table
+            .get(kind)
+            .and_then(|t| t.get(name))
+            .map(|v| {
+                v.clone()
+                    .try_into()
+                    .map_err(|e| Error::parse(self.root.join("book.toml"), e))
+            })
+            .transpose()
+    }
+
+    /// URL to edit the source of a chapter, built from
+    /// `output.html.edit-url-template`, if set.
+    ///
+    /// chapter_path: path of the chapter relative to the source directory
+    /// e.g. `intro.md`.
+    pub fn edit_url<P: AsRef<Path>>(&self, chapter_path: P) -> Option<String> {
+        let template = self.html().edit_url_template.as_deref()?;
+        // As mdBook does, `{path}` is the source directory followed by
+        // the chapter path
+        let path = format!(
+            "{}/{}",
+            self.book.src.to_string_lossy(),
+            chapter_path.as_ref().to_string_lossy()
+        );
+        Some(template.replace("{path}", &path.replace('\\', "/")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults() -> Result<()> {
+        let book_toml = BookToml::parse("[book]\ntitle = \"test\"", "/book")?;
+        assert_eq!(book_toml.book.title.as_deref(), Some("test"));
+        assert_eq!(book_toml.book.language.as_deref(), Some("en"));
+        assert!(book_toml.build.create_missing);
+        assert_eq!(book_toml.src_dir(), Path::new("/book/src"));
+        assert_eq!(book_toml.renderers(), vec!["html"]);
+        assert_eq!(book_toml.html_build_dir(), Some("/book/book".into()));
+        assert_eq!(book_toml.markdown_build_dir(), None);
+        assert_eq!(book_toml.html(), &HtmlOutput::default());
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_dirs() -> Result<()> {
+        let book_toml = BookToml::parse(
+            "[build]\nbuild-dir = \"out\"\nextra-watch-dirs = [\"code\"]\n\n[output.markdown]\n",
+            "/book",
+        )?;
+        assert_eq!(book_toml.html_build_dir(), None);
+        assert_eq!(book_toml.markdown_build_dir(), Some("/book/out".into()));
+        assert_eq!(
+            book_toml.extra_watch_dirs(),
+            vec![PathBuf::from("/book/code")]
+        );
+
+        // Any `[output.*]` table, even that of a custom renderer, counts
+        let book_toml = BookToml::parse("[output.html]\n[output.pdf]\n", "/book")?;
+        assert_eq!(book_toml.html_build_dir(), Some("/book/book/html".into()));
+        assert_eq!(
+            book_toml.renderer_build_dir("pdf"),
+            Some("/book/book/pdf".into())
+        );
+
+        let book_toml = BookToml::parse("[output.pdf]\n", "/book")?;
+        assert_eq!(book_toml.html_build_dir(), None);
+        assert_eq!(
+            book_toml.renderer_build_dir("pdf"),
+            Some("/book/book".into())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_html() -> Result<()> {
+        let book_toml = BookToml::parse(
+            r#"[output.html]
+site-url = "/mybook/"
+git-repository-url = "https://github.com/org/repo"
+edit-url-template = "https://github.com/org/repo/edit/main/{path}"
+
+[output.html.redirect]
+"/old.html" = "new.html"
+"#,
+            "/book",
+        )?;
+        let html = book_toml.html();
+        assert_eq!(html.site_url.as_deref(), Some("/mybook/"));
+        assert_eq!(html.redirect["/old.html"], "new.html");
+        assert_eq!(
+            book_toml.edit_url("a/b.md").as_deref(),
+            Some("https://github.com/org/repo/edit/main/src/a/b.md")
+        );
+
+        assert!(BookToml::parse("[output.html]\nsite-url = 1\n", "/book").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_tables() -> Result<()> {
+        let book_toml = BookToml::parse(
+            r#"[output.html]
+[output.pdf]
+command = "mdbook-pdf"
+optional = true
+paper-width = 8.5
+
+[preprocessor.admonish]
+command = "mdbook-admonish"
+before = ["links"]
+assets_version = "3.0.0"
+"#,
+            "/book",
+        )?;
+        assert_eq!(
+            book_toml.output["pdf"].command.as_deref(),
+            Some("mdbook-pdf")
+        );
+        assert!(book_toml.output["pdf"].optional);
+        assert_eq!(book_toml.preprocessor["admonish"].before, vec!["links"]);
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "kebab-case")]
+        struct Pdf {
+            paper_width: f64,
+        }
+        let pdf: Pdf = book_toml.output_config("pdf")?.unwrap();
+        assert_eq!(pdf.paper_width, 8.5);
+        let admonish: toml::Table = book_toml.preprocessor_config("admonish")?.unwrap();
+        assert_eq!(admonish["assets_version"].as_str(), Some("3.0.0"));
+        assert!(book_toml.output_config::<Pdf>("html").is_err());
+        assert!(book_toml.output_config::<Pdf>("epub")?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_env_overrides() -> Result<()> {
+        let vars = [
+            ("MDBOOK_BOOK__TITLE", "From env"),
+            ("MDBOOK_OUTPUT__HTML__SITE_URL", "/env/"),
+            ("MDBOOK_BUILD", r#"{"build-dir": "out"}"#),
+            ("MDBOOK_PATH", "/usr/bin/mdbook"),
+            ("OTHER", "x"),
+        ]
+        .map(|(k, v)| (k.to_string(), v.to_string()));
+        let book_toml = BookToml::parse_with_env("[book]\ntitle = \"test\"", "/book", vars)?;
+        assert_eq!(book_toml.book.title.as_deref(), Some("From env"));
+        assert_eq!(book_toml.html().site_url.as_deref(), Some("/env/"));
+        assert_eq!(book_toml.build_dir(), Path::new("/book/out"));
+        Ok(())
+    }
+}
@@ -3,9 +3,8 @@
 use std::path::Path;
 use std::process::Command;
 
-use anyhow::Context;
-use anyhow::Result;
-use anyhow::anyhow;
+use crate::Error;
+use crate::Result;
 
 /// Invokes `mdbook build`.
 ///
@@ -25,18 +24,24 @@ pub(crate) fn build_book(root_path: &Path, mdbook_path: &Path) -> Result<()> {
         .args(["build"])
         .current_dir(root_path)
         .output()
-        .context("[build_book] Failed to execute `mdbook`. Is it installed?")?; // return if failed to execute process
+        .map_err(|e| {
+            Error::command(
+                "mdbook build",
+                format!("could not be run ({e}). Is `mdbook` installed?"),
+            )
+        })?; // return if failed to execute process
 
     // write_log(&output.stdout, &output.stderr)?;
 
     if !output.status.success() {
-        return Err(anyhow!(
-            "Book building failed. Status: {}. Output: {}\n{}",
-            output.status,
-            String::from_utf8(output.stdout)
-                .context("[build_book] Failed to read a string from stdout.")?,
-            String::from_utf8(output.stderr)
-                .context("[build_book] Failed to read a string from stderr.")?
+        return Err(Error::command(
+            "mdbook build",
+            format!(
+                "Book building failed. Status: {}. Output: {}\n{}",
+                output.status,
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            ),
         ));
     }
     Ok(())
@@ -0,0 +1,188 @@
+//! Data extracted from a single Markdown file: links, reference
+//! definitions, unresolved references, code blocks, includes and front
+//! matter
+
+use pulldown_cmark::BrokenLink;
+use pulldown_cmark::CodeBlockKind;
+use pulldown_cmark::CowStr;
+use pulldown_cmark::Event;
+use pulldown_cmark::Parser;
+use pulldown_cmark::Tag;
+use pulldown_cmark::TagEnd;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::link::Link;
+use crate::parser;
+use crate::parser::ChapterMetadata;
+use crate::parser::ParserOptions;
+
+/// URL given to the reference-style links whose reference definition is
+/// not in the same file. It is replaced by the URL of the definition
+/// found in another file of the book (e.g. `refs.md`), if any.
+pub(crate) const UNRESOLVED_URL: &str = "\0unresolved";
+
+/// Reference definition: \[label\]: dest "title"
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct RefDef {
+    /// Label, as written
+    pub(crate) label: String,
+    /// URL
+    pub(crate) dest: String,
+    /// Optional title
+    pub(crate) title: Option<String>,
+}
+
+/// Reference without definition in the file it is used in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct UnresolvedRef {
+    /// Reference, as written
+    pub(crate) reference: String,
+    /// `Reference`, `Collapsed` or `Shortcut`
+    pub(crate) link_type: String,
+}
+
+/// Fenced or indented code block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct CodeBlock {
+    /// Info string e.g. `rust,editable`; empty for indented code blocks
+    pub(crate) info: String,
+    /// Code
+    pub(crate) code: String,
+}
+
+/// Data extracted from a Markdown file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct FileData {
+    /// Links, in order of appearance. Reference-style links defined
+    /// elsewhere have the [`UNRESOLVED_URL`] URL.
+    pub(crate) links: Vec<Link>,
+    /// Reference definitions, sorted by label
+    pub(crate) refdefs: Vec<RefDef>,
+    /// References without definition in the file, in order of
+    /// appearance
+    pub(crate) unresolved: Vec<UnresolvedRef>,
+    /// Code blocks, in order of appearance
+    pub(crate) code_blocks: Vec<CodeBlock>,
+    /// Paths of the included files, as written, in order of appearance
+    pub(crate) includes: Vec<String>,
+    /// Front matter, empty if none
+    #[serde(default)]
+    pub(crate) metadata: ChapterMetadata,
+}
+
+impl FileData {
+    /// Parse the contents of a Markdown file.
+    ///
+    /// options: the Markdown dialect, see [`ParserOptions`].
+    pub(crate) fn extract(contents: &str, options: &ParserOptions) -> Self {
+        let mut unresolved = Vec::new();
+        let mut parser = Parser::new_with_broken_link_callback(
+            contents,
+            options.to_options(),
+            Some(|link: BrokenLink<'_>| {
+                unresolved.push(UnresolvedRef {
+                    reference: link.reference.to_string(),
+                    link_type: format!("{:?}", link.link_type),
+                });
+                Some((CowStr::Borrowed(UNRESOLVED_URL), CowStr::Borrowed("")))
+            }),
+        );
+
+        let mut refdefs: Vec<RefDef> = parser
+            .reference_definitions()
+            .iter()
+            .map(|(label, def)| RefDef {
+                label: label.to_string(),
+                dest: def.dest.to_string(),
+                title: def.title.as_ref().map(|t| t.to_string()),
+            })
+            .collect();
+        refdefs.sort_by(|a, b| a.label.cmp(&b.label));
+
+        let mut code_blocks = Vec::new();
+        let mut current: Option<CodeBlock> = None;
+        let events = parser.by_ref().inspect(|event| match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let info = match kind {
+                    CodeBlockKind::Fenced(info) => info.to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                current = Some(CodeBlock {
+                    info,
+                    code: String::new(),
+                });
+            }
+            Event::Text(t) => {
+                if let Some(ref mut c) = current {
+                    c.code.push_str(t);
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                code_blocks.extend(current.take());
+            }
+            _ => {}
+        });
+        let links = parser::extract_links(events);
+        drop(parser);
+
+        let includes = parser::find_includes(contents)
+            .map(|i| i.path.to_string())
+            .collect();
+
+        Self {
+            links,
+            refdefs,
+            unresolved,
+            code_blocks,
+            includes,
+            metadata: ChapterMetadata::from_markdown(contents).unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract() {
+        let contents = "---\ntags: [a]\n---\n# Title\n\n[inline](https://a.com) [local][l] [elsewhere][e] [none]\n\n\
+                        ```rust,editable\nfn main() {}\n```\n\n{{#include ../code/main.rs:5}}\n\n\
+                        [l]: https://l.com \"L\"\n";
+        let data = FileData::extract(contents, &ParserOptions::default());
+        let urls: Vec<_> = data.links.iter().map(|l| l.get_url()).collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://a.com",
+                "https://l.com",
+                UNRESOLVED_URL,
+                UNRESOLVED_URL
+            ]
+        );
+        assert_eq!(
+            data.refdefs,
+            vec![RefDef {
+                label: "l".into(),
+                dest: "https://l.com".into(),
+                title: Some("L".into()),
+            }]
+        );
+        let references: Vec<_> = data
+            .unresolved
+            .iter()
+            .map(|u| u.reference.as_str())
+            .collect();
+        assert_eq!(references, vec!["e", "none"]);
+        assert_eq!(
+            data.code_blocks,
+            vec![CodeBlock {
+                info: "rust,editable".into(),
+                code: "fn main() {}\n".into(),
+            }]
+        );
+        assert_eq!(data.includes, vec!["../code/main.rs"]);
+        assert_eq!(data.metadata.tags(), vec!["a"]);
+    }
+}
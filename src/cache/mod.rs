@@ -0,0 +1,388 @@
+//! Persistent, per-file cache of the data extracted from the Markdown
+//! files (links, reference definitions, code blocks), so that repeated
+//! runs only parse the files that changed.
+//!
+//! The cache is a JSON index stored in a cache directory. It records the
+//! content hash of each file, and is discarded when its format version or
+//! the parser options and link rules it was built with change.
+mod file_data;
+
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+pub(crate) use file_data::*;
+use rayon::prelude::*;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+use tracing::debug;
+use tracing::info;
+
+use crate::Error;
+use crate::Result;
+use crate::book::Book;
+use crate::book::BookFile;
+use crate::fs;
+use crate::link;
+use crate::parser::ParserOptions;
+
+/// Version of the cache format. Increment it whenever [`FileData`] (or
+/// the way it is extracted) changes.
+const CACHE_VERSION: u32 = 3;
+
+/// Name of the index file, within the cache directory
+const INDEX_FILE_NAME: &str = "index.json";
+
+/// Contents of the index file
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheIndex {
+    /// See [`CACHE_VERSION`]
+    version: u32,
+    /// See [`fingerprint`]
+    fingerprint: String,
+    /// Absolute path of each Markdown file -> cached data
+    files: BTreeMap<PathBuf, CacheEntry>,
+}
+
+/// Cached data of a Markdown file
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    /// SHA-256 hash of the file's contents
+    hash: String,
+    /// Data extracted from the file
+    data: FileData,
+}
+
+/// Number of Markdown files parsed, read from the cache, or removed from
+/// the cache (because they no longer exist) by the last operation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Files that were (re)parsed, because they are new or changed
+    pub parsed: usize,
+    /// Files whose data was read from the cache
+    pub reused: usize,
+    /// Files removed from the cache
+    pub removed: usize,
+}
+
+/// Persistent cache of the links, reference definitions and code blocks
+/// of the Markdown files of one or more source directories.
+///
+/// Each operation updates the cache (parsing only the new or modified
+/// files), writes its output, then saves the cache to disk.
+#[derive(Debug)]
+pub struct MarkdownCache {
+    cache_dir_path: PathBuf,
+    parser_options: ParserOptions,
+    index: CacheIndex,
+    stats: CacheStats,
+}
+
+impl MarkdownCache {
+    /// Open the cache stored in a directory, which is created on the
+    /// first save if it does not exist. The Markdown files are parsed
+    /// with the default parser options.
+    ///
+    /// A cache written by another version of the cache format, or with
+    /// other parser options or link rules, is discarded.
+    pub fn open<P: AsRef<Path>>(cache_dir_path: P) -> Result<Self> {
+        Self::open_with_options(cache_dir_path, &ParserOptions::default())
+    }
+
+    /// Open the cache stored in a directory, see [`MarkdownCache::open`],
+    /// parsing the Markdown files in the given dialect.
+    ///
+    /// parser_options: see [`ParserOptions`].
+    pub fn open_with_options<P: AsRef<Path>>(
+        cache_dir_path: P,
+        parser_options: &ParserOptions,
+    ) -> Result<Self> {
+        let cache_dir_path = cache_dir_path.as_ref().to_path_buf();
+        let index_path = cache_dir_path.join(INDEX_FILE_NAME);
+        let fingerprint = fingerprint(parser_options);
+        let new_index = || CacheIndex {
+            version: CACHE_VERSION,
+            fingerprint: fingerprint.clone(),
+            ..CacheIndex::default()
+        };
+        let index = match std::fs::read_to_string(&index_path) {
+            Ok(contents) => match serde_json::from_str::<CacheIndex>(&contents) {
+                Ok(index) if index.version == CACHE_VERSION && index.fingerprint == fingerprint => {
+                    debug!("Read the cache index {}", index_path.display());
+                    index
+                }
+                Ok(_) => {
+                    info!("The cache {} is outdated.", index_path.display());
+                    new_index()
+                }
+                Err(e) => {
+                    info!(
+                        "The cache {} could not be parsed and is discarded: {e}",
+                        index_path.display()
+                    );
+                    new_index()
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => new_index(),
+            Err(e) => {
+                return Err(Error::io(index_path, e));
+            }
+        };
+        Ok(Self {
+            cache_dir_path,
+            parser_options: parser_options.clone(),
+            index,
+            stats: CacheStats::default(),
+        })
+    }
+
+    /// Returns the directory where the cache is stored
+    pub fn cache_dir_path(&self) -> &Path {
+        &self.cache_dir_path
+    }
+
+    /// Returns the number of files parsed or read from the cache by the
+    /// last operation
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Save the cache to disk.
+    pub fn save(&self) -> Result<()> {
+        fs::create_dir(&self.cache_dir_path)?;
+        let index_path = self.cache_dir_path.join(INDEX_FILE_NAME);
+        // Write to a temporary file first, so that an interrupted save
+        // does not leave a truncated index behind
+        let tmp_path = index_path.with_extension("json.tmp");
+        let mut w = BufWriter::new(File::create(&tmp_path).map_err(|e| Error::io(&tmp_path, e))?);
+        serde_json::to_writer(&mut w, &self.index).map_err(|e| Error::io(&tmp_path, e.into()))?;
+        w.flush().map_err(|e| Error::io(&tmp_path, e))?;
+        drop(w);
+        std::fs::rename(&tmp_path, &index_path).map_err(|e| Error::io(&index_path, e))?;
+        Ok(())
+    }
+
+    /// Update the cache with the Markdown files of a source directory,
+    /// parsing the files that are new or changed, and return the book.
+    ///
+    /// Call [`MarkdownCache::save`] to persist the updated cache.
+    pub fn load<P: AsRef<Path>>(&mut self, src_dir_path: P) -> Result<Book> {
+        let src_dir_path = fs::check_is_dir(src_dir_path)?;
+        let src_dir_path = src_dir_path
+            .canonicalize()
+            .map_err(|e| Error::io(&src_dir_path, e))?;
+        let paths = fs::find_markdown_files_in(&src_dir_path)?;
+
+        let files = &self.index.files;
+        let parser_options = &self.parser_options;
+        let parsed: Vec<(PathBuf, String, Option<FileData>)> = paths
+            .into_par_iter()
+            .map(|p| -> Result<_> {
+                let contents = std::fs::read_to_string(&p).map_err(|e| Error::io(&p, e))?;
+                let hash = content_hash(&contents);
+                let data = match files.get(&p) {
+                    Some(entry) if entry.hash == hash => None,
+                    _ => {
+                        debug!("Parsing {}", p.display());
+                        Some(FileData::extract(&contents, parser_options))
+                    }
+                };
+                Ok((p, hash, data))
+            })
+            .collect::<Result<_>>()?;
+
+        let mut stats = CacheStats::default();
+        let found: HashSet<&PathBuf> = parsed.iter().map(|(p, _, _)| p).collect();
+        let before = self.index.files.len();
+        self.index
+            .files
+            .retain(|p, _| !p.starts_with(&src_dir_path) || found.contains(p));
+        stats.removed = before - self.index.files.len();
+
+        let mut book = Vec::with_capacity(parsed.len());
+        for (p, hash, data) in parsed {
+            if let Some(data) = data {
+                stats.parsed += 1;
+                self.index
+                    .files
+                    .insert(p.clone(), CacheEntry { hash, data });
+            } else {
+                stats.reused += 1;
+            }
+            let data = self.index.files[&p].data.clone();
+            book.push(BookFile { path: p, data });
+        }
+        self.stats = stats;
+        Book::from_files(src_dir_path, book, &self.parser_options)
+    }
+
+    /// Update the cache, then run an operation that writes to a file,
+    /// and save the cache.
+    fn write_with<P, F>(&mut self, src_dir_path: P, func: F) -> Result<()>
+    where
+        P: AsRef<Path>,
+        F: FnOnce(&Book) -> Result<()>,
+    {
+        let book = self.load(src_dir_path)?;
+        func(&book)?;
+        self.save()
+    }
+
+    /// Same as [`crate::write_refdefs_to`], using the cache.
+    pub fn write_refdefs_to<P1, P2>(&mut self, src_dir_path: P1, dest_file_path: P2) -> Result<()>
+    where
+        P1: AsRef<Path>,
+        P2: AsRef<Path>,
+    {
+        self.write_with(src_dir_path, |book| {
+            book.write_to_file(dest_file_path, Book::write_refdefs)
+        })
+    }
+
+    /// Same as [`crate::write_all_links`], using the cache.
+    pub fn write_all_links<P1, P2>(&mut self, src_dir_path: P1, dest_file_path: P2) -> Result<()>
+    where
+        P1: AsRef<Path>,
+        P2: AsRef<Path>,
+    {
+        self.write_with(src_dir_path, |book| {
+            book.write_to_file(dest_file_path, Book::write_all_links)
+        })
+    }
+
+    /// Same as [`crate::write_inline_links`], using the cache.
+    pub fn write_inline_links<P1, P2>(&mut self, src_dir_path: P1, dest_file_path: P2) -> Result<()>
+    where
+        P1: AsRef<Path>,
+        P2: AsRef<Path>,
+    {
+        self.write_with(src_dir_path, |book| {
+            book.write_to_file(dest_file_path, Book::write_inline_links)
+        })
+    }
+
+    /// Same as [`crate::write_duplicate_links`], using the cache.
+    pub fn write_duplicate_links<P1, P2>(
+        &mut self,
+        src_dir_path: P1,
+        dest_file_path: P2,
+    ) -> Result<()>
+    where
+        P1: AsRef<Path>,
+        P2: AsRef<Path>,
+    {
+        self.write_with(src_dir_path, |book| {
+            book.write_to_file(dest_file_path, Book::write_duplicate_links)
+        })
+    }
+
+    /// Same as [`crate::write_broken_links`], using the cache.
+    pub fn write_broken_links<P1, P2>(&mut self, src_dir_path: P1, dest_file_path: P2) -> Result<()>
+    where
+        P1: AsRef<Path>,
+        P2: AsRef<Path>,
+    {
+        self.write_with(src_dir_path, |book| {
+            book.write_to_file(dest_file_path, Book::write_broken_links)
+        })
+    }
+}
+
+/// Returns the hex-encoded SHA-256 hash of a string
+fn content_hash(contents: &str) -> String {
+    Sha256::digest(contents.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Returns a hash of everything, besides the contents of the files, that
+/// the cached data depends on: the version of this crate, the parser
+/// options and the link rules.
+fn fingerprint(parser_options: &ParserOptions) -> String {
+    let mut s = format!(
+        "{}\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        parser_options.to_options().bits()
+    );
+    for rule in link::DEFAULT_RULES.iter() {
+        s.push_str(&format!(
+            "{}\t{}\t{}\t{}\n",
+            rule.name(),
+            rule.pattern(),
+            rule.label_pattern(),
+            rule.badge_url_pattern().unwrap_or_default()
+        ));
+    }
+    content_hash(&s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_cache() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let src = dir.path().join("src");
+        std::fs::create_dir(&src)?;
+        std::fs::write(src.join("a.md"), "[a](https://a.com)\n")?;
+        std::fs::write(src.join("b.md"), "[b](https://b.com)\n")?;
+        let cache_dir = dir.path().join("cache");
+
+        let mut cache = MarkdownCache::open(&cache_dir)?;
+        cache.write_all_links(&src, dir.path().join("links.md"))?;
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                parsed: 2,
+                reused: 0,
+                removed: 0
+            }
+        );
+
+        std::fs::write(src.join("b.md"), "[b2](https://b2.com)\n")?;
+        std::fs::write(src.join("c.md"), "[c](https://c.com)\n")?;
+        std::fs::remove_file(src.join("a.md"))?;
+        let mut cache = MarkdownCache::open(&cache_dir)?;
+        let book = cache.load(&src)?;
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                parsed: 2,
+                reused: 0,
+                removed: 1
+            }
+        );
+        assert_eq!(book.links().len(), 2);
+        cache.save()?;
+
+        let mut cache = MarkdownCache::open(&cache_dir)?;
+        cache.load(&src)?;
+        assert_eq!(cache.stats().reused, 2);
+
+        // An outdated cache is discarded
+        let index_path = cache_dir.join(INDEX_FILE_NAME);
+        let contents = std::fs::read_to_string(&index_path)?;
+        std::fs::write(
+            &index_path,
+            contents.replace(&fingerprint(&ParserOptions::default()), "other"),
+        )?;
+        let mut cache = MarkdownCache::open(&cache_dir)?;
+        cache.load(&src)?;
+        assert_eq!(cache.stats().parsed, 2);
+        cache.save()?;
+
+        // So is a cache built with other parser options
+        let mut cache = MarkdownCache::open_with_options(&cache_dir, &ParserOptions::mdbook())?;
+        cache.load(&src)?;
+        assert_eq!(cache.stats().parsed, 2);
+        Ok(())
+    }
+}
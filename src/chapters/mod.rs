@@ -0,0 +1,70 @@
+//! Move chapters, update the links that point to them and maintain the
+//! `[output.html.redirect]` table of `book.toml`
+mod move_chapter;
+mod redirects;
+
+use std::path::Path;
+
+pub use move_chapter::ChapterMove;
+pub(crate) use move_chapter::*;
+pub use redirects::BrokenRedirect;
+pub(crate) use redirects::*;
+
+use crate::generate;
+
+/// Resolve a relative path against a directory, both using `/` as
+/// separator, without accessing the file system: `.` and `..` components
+/// are removed, except for leading `..`.
+///
+/// e.g. `resolve("a/b", "../c.md")` returns `a/c.md`.
+pub(crate) fn resolve(dir: &str, path: &str) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+    for part in dir.split('/').chain(path.split('/')) {
+        match part {
+            "" | "." => {}
+            ".." if parts.last().is_some_and(|p| *p != "..") => {
+                parts.pop();
+            }
+            p => parts.push(p),
+        }
+    }
+    parts.join("/")
+}
+
+/// Returns the path of `path` relative to the directory `dir`, both
+/// relative to the same root and using `/` as separator.
+pub(crate) fn relative_to(path: &str, dir: &str) -> String {
+    generate::relative_path(Path::new(path), Path::new(dir))
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Returns the directory of a path that uses `/` as separator, or an
+/// empty string.
+pub(crate) fn parent(path: &str) -> &str {
+    path.rsplit_once('/')
+        .map(|(dir, _)| dir)
+        .unwrap_or_default()
+}
+
+/// Returns `true` if the destination is not a path relative to the
+/// current file e.g. a URL, an e-mail address or an absolute path.
+pub(crate) fn is_external(dest: &str) -> bool {
+    dest.contains("://") || dest.starts_with("mailto:") || dest.starts_with('/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        assert_eq!(resolve("a/b", "../c.md"), "a/c.md");
+        assert_eq!(resolve("", "./a/./b.md"), "a/b.md");
+        assert_eq!(resolve("a", "../../code/x.rs"), "../code/x.rs");
+        assert_eq!(relative_to("../code/x.rs", "b/c"), "../../../code/x.rs");
+        assert_eq!(relative_to("a/x.md", "a"), "x.md");
+        assert_eq!(parent("a/b/c.md"), "a/b");
+        assert_eq!(parent("c.md"), "");
+    }
+}
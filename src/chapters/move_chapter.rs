@@ -0,0 +1,509 @@
+//! Move a chapter and update the links that point to it
+
+use std::ops::Range;
+use std::path::Path;
+use std::path::PathBuf;
+
+use pulldown_cmark::Event;
+use pulldown_cmark::LinkType;
+use pulldown_cmark::Parser;
+use pulldown_cmark::Tag;
+use tracing::debug;
+use tracing::info;
+
+use super::is_external;
+use super::parent;
+use super::redirects;
+use super::relative_to;
+use super::resolve;
+use crate::Error;
+use crate::Result;
+use crate::book_toml::BookToml;
+use crate::fs;
+use crate::lint::FileFix;
+use crate::lint::apply_file_fixes;
+use crate::lint::side_path;
+use crate::parser;
+use crate::parser::ParserOptions;
+use crate::sitemap;
+use crate::summary;
+
+/// Result of moving a chapter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChapterMove {
+    /// New path of the chapter's Markdown file
+    pub new_file_path: PathBuf,
+
+    /// Other Markdown files (including `SUMMARY.md`) whose links were
+    /// updated
+    pub modified_files: Vec<PathBuf>,
+
+    /// Redirect added to the `[output.html.redirect]` table of
+    /// `book.toml`: old path (e.g. `/old.html`) and new URL, relative to
+    /// the old path (e.g. `new/page.html`).
+    /// `None` if the chapter is not listed in `SUMMARY.md` or the book
+    /// has no HTML output.
+    pub redirect: Option<(String, String)>,
+}
+
+/// Move a chapter's Markdown file, update the relative links (and
+/// includes) that point to it in every Markdown file of the source
+/// directory, including `SUMMARY.md`, update the relative links of the
+/// moved file itself, and add a redirect from the old HTML page to the
+/// new one in `book.toml`.
+///
+/// Anchors (e.g. `#section`) are preserved. The Markdown files that
+/// the book includes from outside the source directory (e.g.
+/// `{{#include ../refs.md}}`) are updated too; as every other file, their
+/// links and reference definitions are relative to the file itself.
+///
+/// All files are updated, or none: see [`apply_file_fixes`].
+///
+/// book_toml: the book's configuration, see [`BookToml::from_dir`].
+///
+/// old_chapter_path, new_chapter_path: paths of the chapter relative to
+/// the source directory e.g. `intro.md` and `getting_started/intro.md`.
+///
+/// parser_options: the Markdown dialect of the book.
+pub(crate) fn move_chapter(
+    book_toml: &BookToml,
+    old_chapter_path: &str,
+    new_chapter_path: &str,
+    parser_options: &ParserOptions,
+) -> Result<ChapterMove> {
+    let src_dir_path = fs::check_is_dir(book_toml.src_dir())?;
+    let (Some(old), Some(new)) = (
+        summary::normalize_chapter_path(old_chapter_path),
+        summary::normalize_chapter_path(new_chapter_path),
+    ) else {
+        return Err(Error::config("The chapter paths must not be empty."));
+    };
+    if !old.ends_with(".md") || !new.ends_with(".md") {
+        return Err(Error::config(format!(
+            "The chapter paths must be Markdown files: {old} -> {new}"
+        )));
+    }
+    if old == new {
+        return Err(Error::config(format!(
+            "The old and new paths of the chapter are identical: {old}"
+        )));
+    }
+    let old_file_path = src_dir_path.join(&old);
+    let new_file_path = src_dir_path.join(&new);
+    if !old_file_path.is_file() {
+        return Err(Error::io(
+            &old_file_path,
+            std::io::ErrorKind::NotFound.into(),
+        ));
+    }
+    if new_file_path.exists() {
+        return Err(Error::io(
+            &new_file_path,
+            std::io::ErrorKind::AlreadyExists.into(),
+        ));
+    }
+    let listed_in_summary = summary::read_summary(&src_dir_path)?
+        .iter()
+        .any(|c| c.path.as_deref() == Some(old.as_str()));
+
+    // Paths are relative to the book's root directory from here on, so
+    // that the files included from outside the source directory can be
+    // updated as well
+    let root = book_toml.root();
+    let src_rel = resolve(
+        "",
+        &relative_to(&src_dir_path.to_string_lossy(), &root.to_string_lossy()),
+    );
+    let old_rel = resolve(&src_rel, &old);
+    let new_rel = resolve(&src_rel, &new);
+    let mut files = Vec::new();
+    for p in fs::find_markdown_files_in(&src_dir_path)? {
+        let rel = relative_to(&p.to_string_lossy(), &root.to_string_lossy());
+        let contents = std::fs::read_to_string(&p).map_err(|e| Error::io(&p, e))?;
+        files.push((p, rel, contents));
+    }
+    let mut i = 0;
+    while i < files.len() {
+        let included: Vec<String> = parser::find_includes(&files[i].2)
+            .map(|inc| resolve(parent(&files[i].1), inc.path))
+            .filter(|rel| rel.ends_with(".md") && !rel.starts_with(".."))
+            .collect();
+        for rel in included {
+            let p = root.join(&rel);
+            if files.iter().any(|(_, r, _)| *r == rel) || !p.is_file() {
+                continue;
+            }
+            let contents = std::fs::read_to_string(&p).map_err(|e| Error::io(&p, e))?;
+            files.push((p, rel, contents));
+        }
+        i += 1;
+    }
+
+    // Compute the new contents of all files before modifying any
+    let mut moved_contents = None;
+    let mut fixes = Vec::new();
+    for (p, rel, contents) in files {
+        if rel == old_rel {
+            moved_contents = Some(rewrite_paths(
+                &contents,
+                &old_rel,
+                &new_rel,
+                &old_rel,
+                &new_rel,
+                parser_options,
+            ));
+        } else {
+            let new_contents =
+                rewrite_paths(&contents, &rel, &rel, &old_rel, &new_rel, parser_options);
+            if new_contents != contents {
+                fixes.push(FileFix::replace_contents(p, contents, new_contents));
+            }
+        }
+    }
+    let moved_contents = moved_contents
+        .ok_or_else(|| Error::io(&old_file_path, std::io::ErrorKind::NotFound.into()))?;
+    let modified_files: Vec<PathBuf> = fixes.iter().map(|f| f.file_path.clone()).collect();
+
+    let redirect = if listed_in_summary && book_toml.renderers().contains(&"html") {
+        let book_toml_path = root.join("book.toml");
+        let contents =
+            std::fs::read_to_string(&book_toml_path).map_err(|e| Error::io(&book_toml_path, e))?;
+        let (new_contents, redirect) = redirects::add_redirect(&contents, &old, &new)
+            .map_err(|e| e.in_file(&book_toml_path))?;
+        fixes.push(FileFix::replace_contents(
+            book_toml_path,
+            contents,
+            new_contents,
+        ));
+        Some(redirect)
+    } else {
+        debug!("No redirect added for {old}");
+        None
+    };
+
+    info!(
+        "Moving {} to {}",
+        old_file_path.display(),
+        new_file_path.display()
+    );
+    move_file(&old_file_path, &new_file_path, &moved_contents, || {
+        apply_file_fixes(&fixes)
+    })?;
+
+    Ok(ChapterMove {
+        new_file_path,
+        modified_files,
+        redirect,
+    })
+}
+
+/// Write the new contents of a moved file to its new path, then set the
+/// old file aside and update the other files. If updating the other
+/// files fails, the old file is restored and the new one removed.
+///
+/// update: updates the other files, all at once.
+fn move_file<F>(old_file_path: &Path, new_file_path: &Path, contents: &str, update: F) -> Result<()>
+where
+    F: FnOnce() -> Result<()>,
+{
+    // Topmost directory created for the new file, if any
+    let new_dir = new_file_path
+        .ancestors()
+        .skip(1)
+        .take_while(|d| !d.exists())
+        .last()
+        .map(Path::to_path_buf);
+    fs::create_parent_dir_for(new_file_path)?;
+    let remove_new_file = || {
+        let _ = std::fs::remove_file(new_file_path);
+        if let Some(dir) = new_dir.as_ref() {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+    };
+    // Fails if the new file was created in the meantime
+    let written = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(new_file_path)
+        .and_then(|mut f| std::io::Write::write_all(&mut f, contents.as_bytes()));
+    if let Err(e) = written {
+        if e.kind() != std::io::ErrorKind::AlreadyExists {
+            remove_new_file();
+        }
+        return Err(Error::io(new_file_path, e));
+    }
+
+    let backup_path = side_path(old_file_path, "move.bak");
+    if let Err(e) = std::fs::rename(old_file_path, &backup_path) {
+        remove_new_file();
+        return Err(Error::io(old_file_path, e));
+    }
+    if let Err(e) = update() {
+        let _ = std::fs::rename(&backup_path, old_file_path);
+        remove_new_file();
+        return Err(e);
+    }
+    let _ = std::fs::remove_file(&backup_path);
+    Ok(())
+}
+
+/// Rewrite the relative paths of the links, images, reference
+/// definitions and includes of a Markdown file, when the file itself
+/// and / or the chapter it links to moves.
+///
+/// file_from, file_to: old and new path of the Markdown file, relative
+/// to the source directory (identical if the file does not move).
+///
+/// old, new: old and new path of the moved chapter.
+///
+/// parser_options: the Markdown dialect of the file.
+fn rewrite_paths(
+    contents: &str,
+    file_from: &str,
+    file_to: &str,
+    old: &str,
+    new: &str,
+    parser_options: &ParserOptions,
+) -> String {
+    let old_html = sitemap::chapter_html_path(old);
+    let new_html = sitemap::chapter_html_path(new);
+
+    let mut buf = String::with_capacity(contents.len());
+    let mut last = 0;
+    for range in find_paths(contents, parser_options) {
+        let path = &contents[range.clone()];
+        if path.is_empty() || is_external(path) {
+            continue;
+        }
+        let target = resolve(parent(file_from), path);
+        let new_target = if target == old {
+            new
+        } else if target == old_html {
+            &new_html
+        } else if file_from == file_to {
+            continue;
+        } else {
+            &target
+        };
+        let mut new_path = relative_to(new_target, parent(file_to));
+        if path.starts_with("./") && !new_path.starts_with("../") {
+            new_path.insert_str(0, "./");
+        }
+        if new_path != path {
+            buf.push_str(&contents[last..range.start]);
+            buf.push_str(&new_path);
+            last = range.end;
+        }
+    }
+    buf.push_str(&contents[last..]);
+    buf
+}
+
+/// Find the byte ranges of the paths of the inline links and images,
+/// reference definitions and includes of a Markdown file, without any
+/// fragment or query, in increasing order.
+fn find_paths(contents: &str, parser_options: &ParserOptions) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut iter = Parser::new_ext(contents, parser_options.to_options()).into_offset_iter();
+    for (event, range) in iter.by_ref() {
+        if let Event::Start(
+            Tag::Link {
+                link_type: LinkType::Inline,
+                dest_url,
+                ..
+            }
+            | Tag::Image {
+                link_type: LinkType::Inline,
+                dest_url,
+                ..
+            },
+        ) = event
+        {
+            // The destination follows the last `](` of the link
+            let slice = &contents[range.clone()];
+            if let Some(start) = slice.rfind("](").map(|i| range.start + i + 2)
+                && let Some(r) = find_dest(contents, start, range.end, &dest_url)
+            {
+                ranges.push(r);
+            }
+        }
+    }
+    for (_, def) in iter.reference_definitions().iter() {
+        let slice = &contents[def.span.clone()];
+        if let Some(start) = slice.find("]:").map(|i| def.span.start + i + 2)
+            && let Some(r) = find_dest(contents, start, def.span.end, &def.dest)
+        {
+            ranges.push(r);
+        }
+    }
+    ranges.extend(parser::find_includes(contents).map(|i| i.path_span));
+
+    ranges.sort_by_key(|r| r.start);
+    ranges.dedup_by(|b, a| b.start < a.end);
+    ranges
+}
+
+/// Find a destination in `contents[start..end]` and return the range of
+/// its path, without any fragment or query.
+///
+/// Destinations that are escaped (and thus differ from their source
+/// text) are not found.
+pub(crate) fn find_dest(
+    contents: &str,
+    start: usize,
+    end: usize,
+    dest: &str,
+) -> Option<Range<usize>> {
+    if dest.is_empty() {
+        return None;
+    }
+    let i = contents[start..end].find(dest)? + start;
+    let path_len = dest.find(['#', '?']).unwrap_or(dest.len());
+    Some(i..i + path_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_paths() {
+        let contents = "See [intro](./intro.md#start), [intro][i], [other](other.md), \
+                        [html](intro.html) and [anchor](#x).\n\n\
+                        `[code](intro.md)`\n\n\
+                        [i]: intro.md\n";
+        assert_eq!(
+            rewrite_paths(
+                contents,
+                "a.md",
+                "a.md",
+                "intro.md",
+                "guide/intro.md",
+                &ParserOptions::default()
+            ),
+            "See [intro](./guide/intro.md#start), [intro][i], [other](other.md), \
+             [html](guide/intro.html) and [anchor](#x).\n\n\
+             `[code](intro.md)`\n\n\
+             [i]: guide/intro.md\n"
+        );
+
+        // The links of the moved file itself
+        let contents = "[a](a.md) [self](intro.md#x) ![img](img/x.png)\n\n\
+                        ```rust\n{{#include ../code/main.rs:5:}}\n```\n";
+        assert_eq!(
+            rewrite_paths(
+                contents,
+                "guide/intro.md",
+                "intro.md",
+                "guide/intro.md",
+                "intro.md",
+                &ParserOptions::default()
+            ),
+            "[a](guide/a.md) [self](intro.md#x) ![img](guide/img/x.png)\n\n\
+             ```rust\n{{#include code/main.rs:5:}}\n```\n"
+        );
+    }
+
+    #[test]
+    fn test_move_chapter() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let root = dir.path().join("book");
+        let src = root.join("src");
+        std::fs::create_dir_all(src.join("part"))?;
+        std::fs::write(root.join("book.toml"), "[book]\ntitle = \"test\"\n")?;
+        std::fs::write(
+            src.join("SUMMARY.md"),
+            "# Summary\n\n- [Intro](intro.md)\n- [One](part/one.md)\n",
+        )?;
+        std::fs::write(src.join("intro.md"), "# Intro\n\n[One](part/one.md)\n")?;
+        std::fs::write(src.join("part").join("one.md"), "[Intro](../intro.md)\n")?;
+
+        let book_toml = BookToml::from_dir(&root)?;
+        let options = ParserOptions::default();
+        let moved = move_chapter(&book_toml, "part/one.md", "one.md", &options)?;
+        assert_eq!(moved.modified_files.len(), 2);
+        assert_eq!(
+            moved.redirect,
+            Some(("/part/one.html".into(), "../one.html".into()))
+        );
+        assert!(!src.join("part").join("one.md").exists());
+        assert_eq!(
+            std::fs::read_to_string(src.join("one.md"))?,
+            "[Intro](intro.md)\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(src.join("SUMMARY.md"))?,
+            "# Summary\n\n- [Intro](intro.md)\n- [One](one.md)\n"
+        );
+        assert!(std::fs::read_to_string(root.join("book.toml"))?.contains(
+            "[book]\ntitle = \"test\"\n\n[output.html.redirect]\n\"/part/one.html\" = \"../one.html\"\n"
+        ));
+
+        assert!(move_chapter(&book_toml, "missing.md", "other.md", &options).is_err());
+        assert!(move_chapter(&book_toml, "intro.md", "one.md", &options).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_move_chapter_updates_included_refs() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let root = dir.path().join("book");
+        let src = root.join("src");
+        std::fs::create_dir_all(src.join("part"))?;
+        std::fs::write(root.join("book.toml"), "[book]\ntitle = \"test\"\n")?;
+        std::fs::write(src.join("SUMMARY.md"), "- [Intro](intro.md)\n")?;
+        std::fs::write(
+            src.join("intro.md"),
+            "[One][one] [Two][two]\n\n{{#include ../refs.md}}\n{{#include part/refs.md}}\n",
+        )?;
+        std::fs::write(src.join("part").join("one.md"), "# One\n")?;
+        // Relative to the refs files, not to the chapter that includes them
+        std::fs::write(root.join("refs.md"), "[one]: src/part/one.md\n")?;
+        std::fs::write(src.join("part").join("refs.md"), "[two]: one.md#x\n")?;
+
+        let book_toml = BookToml::from_dir(&root)?;
+        let moved = move_chapter(
+            &book_toml,
+            "part/one.md",
+            "one.md",
+            &ParserOptions::default(),
+        )?;
+        assert_eq!(moved.modified_files.len(), 2);
+        assert_eq!(
+            std::fs::read_to_string(root.join("refs.md"))?,
+            "[one]: src/one.md\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(src.join("part").join("refs.md"))?,
+            "[two]: ../one.md#x\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_move_chapter_rolls_back() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let root = dir.path().join("book");
+        let src = root.join("src");
+        std::fs::create_dir_all(&src)?;
+        std::fs::write(root.join("book.toml"), "[book]\ntitle = \"test\"\n")?;
+        let summary = "- [Intro](intro.md)\n";
+        std::fs::write(src.join("SUMMARY.md"), summary)?;
+        std::fs::write(src.join("intro.md"), "# Intro\n")?;
+        // Prevent `book.toml` from being replaced
+        std::fs::create_dir_all(root.join(".book.toml.fix.bak").join("x"))?;
+
+        let book_toml = BookToml::from_dir(&root)?;
+        let options = ParserOptions::default();
+        assert!(move_chapter(&book_toml, "intro.md", "part/intro.md", &options).is_err());
+        assert_eq!(std::fs::read_to_string(src.join("intro.md"))?, "# Intro\n");
+        assert_eq!(std::fs::read_to_string(src.join("SUMMARY.md"))?, summary);
+        assert_eq!(
+            std::fs::read_to_string(root.join("book.toml"))?,
+            "[book]\ntitle = \"test\"\n"
+        );
+        assert!(!src.join("part").exists());
+        assert_eq!(std::fs::read_dir(&src)?.count(), 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,204 @@
+//! Add and validate the entries of the `[output.html.redirect]` table
+//! of `book.toml`
+//!
+//! See <https://rust-lang.github.io/mdBook/format/configuration/renderers.html#outputhtmlredirect>.
+
+use std::collections::BTreeSet;
+
+use toml_edit::DocumentMut;
+use toml_edit::Item;
+use toml_edit::Table;
+
+use super::parent;
+use super::relative_to;
+use super::resolve;
+use crate::Error;
+use crate::Result;
+use crate::book_toml::BookToml;
+use crate::sitemap;
+use crate::summary;
+
+/// A redirect of `book.toml` that mdBook cannot honor
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BrokenRedirect {
+    /// Old path e.g. `/old.html`
+    pub from: String,
+    /// New URL, as written in `book.toml`
+    pub to: String,
+    /// Why the redirect is broken
+    pub reason: String,
+}
+
+/// Add a redirect from the HTML page of a moved chapter to its new page
+/// to the contents of `book.toml`, preserving its formatting and comments.
+///
+/// Existing redirects that point to the old page are updated to point to
+/// the new page, and any redirect from the new page is removed, since
+/// mdBook refuses to overwrite an existing page with a redirect.
+///
+/// old, new: old and new path of the chapter, relative to the source
+/// directory.
+///
+/// Returns the new contents of `book.toml` and the added redirect.
+pub(crate) fn add_redirect(
+    book_toml_contents: &str,
+    old: &str,
+    new: &str,
+) -> Result<(String, (String, String))> {
+    let mut doc: DocumentMut = book_toml_contents
+        .parse()
+        .map_err(|e| Error::parse("book.toml", e))?;
+    let old_html = sitemap::chapter_html_path(old);
+    let new_html = sitemap::chapter_html_path(new);
+
+    let output = implicit_table(doc.as_table_mut(), "output")?;
+    let html = implicit_table(output, "html")?;
+    let redirect = html
+        .entry("redirect")
+        .or_insert(toml_edit::table())
+        .as_table_like_mut()
+        .ok_or_else(|| Error::config("`output.html.redirect` is not a table in book.toml"))?;
+
+    // Update the redirects to the old page
+    for (from, to) in redirect.iter_mut() {
+        let Some(to_str) = to.as_str() else {
+            continue;
+        };
+        if let Some((target, suffix)) = resolve_redirect(&from, to_str, "/")
+            && target == old_html
+        {
+            let new_to = relative_to(&new_html, parent(from.trim_start_matches('/'))) + suffix;
+            *to = toml_edit::value(new_to);
+        }
+    }
+    redirect.remove(&format!("/{new_html}"));
+
+    let from = format!("/{old_html}");
+    let to = relative_to(&new_html, parent(&old_html));
+    redirect.insert(&from, toml_edit::value(to.as_str()));
+    Ok((doc.to_string(), (from, to)))
+}
+
+/// Returns the sub-table of a table, creating it as an implicit table
+/// (i.e. without a `[key]` header of its own) if needed.
+fn implicit_table<'a>(table: &'a mut Table, key: &str) -> Result<&'a mut Table> {
+    table
+        .entry(key)
+        .or_insert_with(|| {
+            let mut t = Table::new();
+            t.set_implicit(true);
+            Item::Table(t)
+        })
+        .as_table_mut()
+        .ok_or_else(|| Error::config(format!("`{key}` is not a table in book.toml")))
+}
+
+/// Check the redirects of `book.toml`: the old path of a redirect must
+/// not be the page of a chapter of `SUMMARY.md` (mdBook does not
+/// overwrite it), and a redirect to a page of the book must point to a
+/// chapter of `SUMMARY.md`.
+///
+/// Redirects to other websites are not checked.
+pub(crate) fn check_redirects(book_toml: &BookToml) -> Result<Vec<BrokenRedirect>> {
+    let html = book_toml.html();
+    let site_url = html.site_url.as_deref().unwrap_or("/");
+    let pages: BTreeSet<String> = summary::read_summary(book_toml.src_dir())?
+        .into_iter()
+        .filter_map(|c| c.path)
+        .map(|p| sitemap::chapter_html_path(&p))
+        .collect();
+
+    let mut broken = Vec::new();
+    for (from, to) in html.redirect.iter() {
+        let reason = if pages.contains(from.trim_start_matches('/')) {
+            "the old path is the page of an existing chapter"
+        } else {
+            match resolve_redirect(from, to, site_url) {
+                Some((target, _)) if !pages.contains(&target) => {
+                    "the new URL is not the page of a chapter listed in SUMMARY.md"
+                }
+                _ => continue,
+            }
+        };
+        broken.push(BrokenRedirect {
+            from: from.clone(),
+            to: to.clone(),
+            reason: reason.to_string(),
+        });
+    }
+    Ok(broken)
+}
+
+/// Resolve the new URL of a redirect into the path of a page, relative
+/// to the build directory, followed by any fragment or query.
+///
+/// URLs that are absolute (e.g. `/other/`) outside of `site_url`, or
+/// point to other websites, return `None`.
+pub(crate) fn resolve_redirect<'a>(
+    from: &str,
+    to: &'a str,
+    site_url: &str,
+) -> Option<(String, &'a str)> {
+    if to.contains("://") || to.starts_with("mailto:") {
+        return None;
+    }
+    let i = to.find(['#', '?']).unwrap_or(to.len());
+    let (path, suffix) = to.split_at(i);
+    let target = if path.starts_with('/') {
+        let site_url = format!("/{}/", site_url.trim_matches('/')).replace("//", "/");
+        resolve("", path.strip_prefix(&site_url)?)
+    } else {
+        resolve(parent(from.trim_start_matches('/')), path)
+    };
+    Some((target, suffix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_redirect() -> Result<()> {
+        let contents = "# My book\n[book]\ntitle = \"test\" # title\n\n\
+                        [output.html.redirect]\n\"/a.html\" = \"old/page.html#x\"\n\"/new/page.html\" = \"../b.html\"\n";
+        let (contents, redirect) = add_redirect(contents, "old/page.md", "new/page.md")?;
+        assert_eq!(
+            redirect,
+            ("/old/page.html".to_string(), "../new/page.html".to_string())
+        );
+        assert_eq!(
+            contents,
+            "# My book\n[book]\ntitle = \"test\" # title\n\n\
+             [output.html.redirect]\n\"/a.html\" = \"new/page.html#x\"\n\"/old/page.html\" = \"../new/page.html\"\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_redirects() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let src = dir.path().join("src");
+        std::fs::create_dir(&src)?;
+        std::fs::write(
+            src.join("SUMMARY.md"),
+            "- [Intro](README.md)\n- [One](part/one.md)\n",
+        )?;
+        let book_toml = BookToml::parse(
+            r#"[output.html]
+site-url = "/mybook/"
+
+[output.html.redirect]
+"/index.html" = "part/one.html"
+"/old.html" = "part/one.html#x"
+"/part/old.html" = "/mybook/index.html"
+"/gone.html" = "gone/new.html"
+"/external.html" = "https://example.com/"
+"#,
+            dir.path(),
+        )?;
+        let broken = check_redirects(&book_toml)?;
+        let from: Vec<_> = broken.iter().map(|b| b.from.as_str()).collect();
+        assert_eq!(from, vec!["/gone.html", "/index.html"]);
+        Ok(())
+    }
+}
@@ -5,6 +5,8 @@
 use anyhow::anyhow;
 use clap::Args;
 
+use super::watch::WatchTask;
+
 /// Destination file
 #[derive(Args, Debug, Clone)]
 pub(crate) struct DestFileArgs {
@@ -83,9 +85,267 @@ pub(crate) struct DependenciesDirAndDestFileArgs {
     #[command(flatten)]
     pub(crate) dest: DestFileArgs,
 
-    /// Path to the log file for dependencies
+    /// Path to a log file, where the dependencies that were found are
+    /// listed
     #[arg(long = "log-file", value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
     pub(crate) log_file: Option<PathBuf>,
+
+    /// Kinds of dependencies to list: `normal` (default), `dev` or `build`.
+    /// May be repeated.
+    #[arg(long = "kind", value_name = "KIND", value_parser = parse_dependency_kind)]
+    pub(crate) kinds: Vec<mdbook_utils::DependencyKind>,
+
+    /// Retrieve the repository URLs missing from the local registry cache
+    /// by running `cargo metadata --offline`
+    #[arg(long = "cargo-metadata")]
+    pub(crate) cargo_metadata: bool,
+
+    /// Pin the docs.rs and crates.io URLs to the versions locked in
+    /// Cargo.lock. Not compatible with --templates: use ${version} in the
+    /// template URLs instead
+    #[arg(long = "pin-versions", conflicts_with = "templates")]
+    pub(crate) pin_versions: bool,
+
+    /// TOML file of templates of the reference definitions to generate
+    /// for each dependency (label, URL and optional badge patterns)
+    #[arg(long = "templates", value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    pub(crate) templates: Option<PathBuf>,
+}
+
+/// Upgrade of pinned docs.rs / crates.io URLs
+#[derive(Debug, Args)]
+#[command(flatten_help = true)]
+pub(crate) struct UpgradeVersionsArgs {
+    /// Source directory containing the Markdown files
+    #[command(flatten)]
+    pub(crate) src: MarkdownDirArgs,
+
+    /// Source directory containing Cargo.toml (Cargo.lock is looked for
+    /// there and in its parents)
+    #[command(flatten)]
+    pub(crate) manifest: CargoTomlDirArgs,
+
+    /// Report the changes without modifying the Markdown files
+    #[arg(long = "dry-run")]
+    pub(crate) dry_run: bool,
+}
+
+/// Move of a chapter
+#[derive(Debug, Args)]
+pub(crate) struct MoveChapterArgs {
+    /// Current path of the chapter, relative to the source directory
+    /// e.g. `intro.md`
+    pub(crate) old: String,
+
+    /// New path of the chapter, relative to the source directory
+    /// e.g. `getting_started/intro.md`
+    pub(crate) new: String,
+}
+
+/// Watch mode
+#[derive(Debug, Args)]
+#[command(flatten_help = true)]
+pub(crate) struct WatchArgs {
+    /// Commands to re-run: `refdefs`, `broken-links`, `crates` and / or
+    /// `audit` (default: all). May be repeated.
+    #[arg(long = "task", value_name = "COMMAND", value_parser = parse_watch_task)]
+    pub(crate) tasks: Vec<WatchTask>,
+
+    /// Source directory containing the Markdown files
+    #[command(flatten)]
+    pub(crate) src: MarkdownDirArgs,
+
+    /// Source directory containing Cargo.toml and the code examples
+    #[command(flatten)]
+    pub(crate) manifest: CargoTomlDirArgs,
+
+    /// Directory where the commands write their outputs
+    #[command(flatten)]
+    pub(crate) dest: DestDirArgs,
+
+    /// Delay, in milliseconds, to wait for further changes before
+    /// re-running the commands
+    #[arg(long = "debounce", value_name = "MS", default_value_t = 500)]
+    pub(crate) debounce: u64,
+}
+
+/// Lint
+#[derive(Debug, Args)]
+#[command(flatten_help = true)]
+pub(crate) struct LintArgs {
+    /// Source directory containing the Markdown files
+    #[command(flatten)]
+    pub(crate) src: MarkdownDirArgs,
+
+    /// Directory of the code examples (`.rs` files) included by the
+    /// Markdown files. The `unused-rs-example` rule is checked only if
+    /// set.
+    #[arg(long = "code-dir", value_name = "DIR", value_hint = clap::ValueHint::DirPath)]
+    pub(crate) code_dir_path: Option<PathBuf>,
+
+    /// Severity of a rule, by ID or name: `error`, `warning` or `off`
+    /// e.g. `MU002=off` or `unused-refdef=error`. May be repeated.
+    #[arg(long = "severity", value_name = "RULE=LEVEL", value_parser = parse_rule_severity)]
+    pub(crate) severities: Vec<(mdbook_utils::LintRule, mdbook_utils::Severity)>,
+
+    /// Fix the findings that can be fixed automatically: missing
+    /// reference definitions that follow a template, unused or repeated
+    /// reference definitions, label casing, bare URLs and links to moved
+    /// chapters
+    #[arg(long = "fix")]
+    pub(crate) fix: bool,
+
+    /// With `--fix`, print the changes as a diff without modifying the
+    /// Markdown files
+    #[arg(long = "dry-run", requires = "fix")]
+    pub(crate) dry_run: bool,
+}
+
+/// Parse Markdown files and write their events
+#[derive(Debug, Args)]
+#[command(flatten_help = true)]
+pub(crate) struct DebugArgs {
+    /// Source directory containing the Markdown files
+    #[command(flatten)]
+    pub(crate) src: MarkdownDirArgs,
+
+    /// Path to the file to create
+    #[command(flatten)]
+    pub(crate) dest: DestFileArgs,
+
+    /// Markdown file or glob pattern, relative to the source directory
+    /// (e.g. `chapter/*.md`), to parse instead of all files
+    #[arg(short = 'f', long = "file", value_name = "FILE|GLOB")]
+    pub(crate) file: Option<String>,
+
+    /// Output format: `text` (one line per event, with line, column and
+    /// byte range), `tree` (events nested by depth) or `json`
+    #[arg(long = "format", value_name = "FORMAT", default_value_t = mdbook_utils::EventFormat::Text)]
+    pub(crate) format: mdbook_utils::EventFormat,
+}
+
+/// Render a chapter to HTML
+#[derive(Debug, Args)]
+#[command(flatten_help = true)]
+pub(crate) struct RenderArgs {
+    /// Source directory containing the Markdown files
+    #[command(flatten)]
+    pub(crate) src: MarkdownDirArgs,
+
+    /// Markdown file of the chapter, as given or relative to the source
+    /// directory (e.g. `chapter_1/intro.md`)
+    #[arg(value_name = "CHAPTER", value_hint = clap::ValueHint::FilePath)]
+    pub(crate) chapter: PathBuf,
+
+    /// Path of the HTML file to create (default: standard output)
+    #[command(flatten)]
+    pub(crate) dest: DestFileArgs,
+
+    /// Write a standalone HTML page (with a title) instead of an HTML
+    /// fragment
+    #[arg(long = "standalone")]
+    pub(crate) standalone: bool,
+
+    /// HTML template, whose `{{title}}` and `{{content}}` placeholders
+    /// are replaced by the title of the chapter and its HTML
+    #[arg(long = "template", value_name = "FILE", conflicts_with = "standalone", value_hint = clap::ValueHint::FilePath)]
+    pub(crate) template: Option<PathBuf>,
+
+    /// Do not resolve the references with the reference definitions of
+    /// the other files of the book
+    #[arg(long = "no-refdefs")]
+    pub(crate) no_refdefs: bool,
+
+    /// Leave the links to Markdown files as written, instead of pointing
+    /// them to the HTML files that mdBook renders
+    #[arg(long = "keep-md-links")]
+    pub(crate) keep_md_links: bool,
+}
+
+/// Format the Markdown files
+#[derive(Debug, Args)]
+#[command(flatten_help = true)]
+pub(crate) struct FormatArgs {
+    /// Source directory containing the Markdown files
+    #[command(flatten)]
+    pub(crate) src: MarkdownDirArgs,
+
+    /// Style of the links to websites: `keep`, `reference` (turn inline
+    /// links into reference-style links) or `inline`
+    #[arg(long = "links", value_name = "STYLE", default_value_t = mdbook_utils::LinkStyle::Keep)]
+    pub(crate) link_style: mdbook_utils::LinkStyle,
+
+    /// Placement of the reference definitions: `keep`, `end` (end of
+    /// the file) or `refs-file` (the `*refs.md` file included by the
+    /// chapter)
+    #[arg(long = "refdefs", value_name = "PLACEMENT", default_value_t = mdbook_utils::RefDefPlacement::End)]
+    pub(crate) refdefs: mdbook_utils::RefDefPlacement,
+
+    /// Do not sort the reference definitions by label
+    #[arg(long = "no-sort")]
+    pub(crate) no_sort: bool,
+
+    /// Character of the code block fences: `keep`, `backtick` or `tilde`
+    #[arg(long = "fence", value_name = "STYLE", default_value_t = mdbook_utils::FenceStyle::Backtick)]
+    pub(crate) fence: mdbook_utils::FenceStyle,
+
+    /// Leave the info strings of the code blocks as written
+    #[arg(long = "keep-info-strings")]
+    pub(crate) keep_info_strings: bool,
+
+    /// Print the changes as a diff without modifying the Markdown
+    /// files, and fail if any file is not formatted
+    #[arg(long = "check")]
+    pub(crate) check: bool,
+}
+
+/// Parse a lint rule and its severity e.g. `MU002=off`
+pub(crate) fn parse_rule_severity(
+    s: &str,
+) -> Result<
+    (mdbook_utils::LintRule, mdbook_utils::Severity),
+    Box<dyn std::error::Error + Send + Sync + 'static>,
+> {
+    let (rule, severity) = s
+        .split_once('=')
+        .ok_or_else(|| format!("`{s}` is not in the `RULE=LEVEL` format"))?;
+    Ok((rule.parse()?, severity.parse()?))
+}
+
+/// Parse the name of a command re-run by `watch`
+fn parse_watch_task(
+    s: &str,
+) -> Result<WatchTask, Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Ok(s.parse()?)
+}
+
+/// Dependency audit
+#[derive(Debug, Args)]
+#[command(flatten_help = true)]
+pub(crate) struct AuditDependenciesArgs {
+    /// Source directory containing the Markdown files
+    #[command(flatten)]
+    pub(crate) src: MarkdownDirArgs,
+
+    /// Source directory containing Cargo.toml (and the code examples)
+    #[command(flatten)]
+    pub(crate) manifest: CargoTomlDirArgs,
+
+    /// Path to the report to create
+    #[command(flatten)]
+    pub(crate) dest: DestFileArgs,
+
+    /// Kinds of declared dependencies to take into account: `normal`,
+    /// `dev` or `build`. May be repeated.
+    #[arg(long = "kind", value_name = "KIND", default_values = ["normal", "dev", "build"], value_parser = parse_dependency_kind)]
+    pub(crate) kinds: Vec<mdbook_utils::DependencyKind>,
+}
+
+/// Parse a dependency kind
+fn parse_dependency_kind(
+    s: &str,
+) -> Result<mdbook_utils::DependencyKind, Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Ok(s.parse()?)
 }
 
 /// Markdown source directory and destination directory
@@ -115,9 +375,145 @@ pub(crate) struct MarkdownSrcDirUrlAndDestFileArgs {
     #[command(flatten)]
     pub(crate) dest: DestFileArgs,
 
-    /// Map a filename to another (e.g., 'intro.md' to 'index.md')
+    /// Map a chapter path to another (e.g., 'intro.md' to 'index.md').
+    /// May be repeated.
     #[arg(long = "map-index", value_name = "FROM:TO")]
-    pub(crate) map_index: Option<String>,
+    pub(crate) map_index: Vec<String>,
+
+    /// Exclude the chapters whose path matches a glob pattern (e.g.,
+    /// '*refs.md'). May be repeated.
+    #[arg(long = "exclude", value_name = "GLOB")]
+    pub(crate) exclude: Vec<String>,
+
+    /// Do not write a `robots.txt` file that points to the sitemap, next
+    /// to it (by default, an existing `robots.txt` is replaced)
+    #[arg(long = "no-robots-txt")]
+    pub(crate) no_robots_txt: bool,
+}
+
+/// Crate index generation
+#[derive(Debug, Args)]
+#[command(flatten_help = true)]
+pub(crate) struct GenerateCratesArgs {
+    /// Source directory containing the source Markdown files
+    #[command(flatten)]
+    pub(crate) src: MarkdownDirArgs,
+
+    /// Directory containing Cargo.toml (and Cargo.lock)
+    #[command(flatten)]
+    pub(crate) manifest: CargoTomlDirArgs,
+
+    /// Path to the file to create
+    #[command(flatten)]
+    pub(crate) dest: DestFileArgs,
+
+    /// Retrieve crate descriptions, repositories and categories by
+    /// running `cargo metadata --offline` in the Cargo.toml directory
+    #[arg(long = "cargo-metadata")]
+    pub(crate) cargo_metadata: bool,
+
+    /// Directory of an extracted crates.io database dump, used to
+    /// retrieve crate descriptions, repositories and categories
+    #[arg(long = "db-dump", value_name = "DIR", value_hint = clap::ValueHint::DirPath)]
+    pub(crate) db_dump_dir_path: Option<PathBuf>,
+
+    /// Sort order: `name`, `mentions` or `category`
+    #[arg(long = "sort", value_name = "ORDER", default_value = "name", value_parser = parse_crate_sort)]
+    pub(crate) sort: mdbook_utils::CrateSort,
+
+    /// Do not add version badges
+    #[arg(long = "no-badges")]
+    pub(crate) no_badges: bool,
+}
+
+/// Categories page generation
+#[derive(Debug, Args)]
+#[command(flatten_help = true)]
+pub(crate) struct GenerateCategoriesArgs {
+    /// Source directory containing the source Markdown files
+    #[command(flatten)]
+    pub(crate) src: MarkdownDirArgs,
+
+    /// Directory containing Cargo.toml
+    #[command(flatten)]
+    pub(crate) manifest: CargoTomlDirArgs,
+
+    /// Path to the file to create
+    #[command(flatten)]
+    pub(crate) dest: DestFileArgs,
+
+    /// Retrieve the categories of the crates by running
+    /// `cargo metadata --offline` in the Cargo.toml directory
+    #[arg(long = "cargo-metadata")]
+    pub(crate) cargo_metadata: bool,
+
+    /// Directory of an extracted crates.io database dump, used to
+    /// retrieve the categories of the crates
+    #[arg(long = "db-dump", value_name = "DIR", value_hint = clap::ValueHint::DirPath)]
+    pub(crate) db_dump_dir_path: Option<PathBuf>,
+
+    /// TOML file that assigns categories to crates (`[crates]` table)
+    /// and / or defines categories (`[categories."slug"]` tables)
+    #[arg(long = "mapping", value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    pub(crate) mapping_path: Option<PathBuf>,
+}
+
+/// Parse a crate index sort order
+fn parse_crate_sort(
+    s: &str,
+) -> Result<mdbook_utils::CrateSort, Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Ok(s.parse()?)
+}
+
+/// Feed (and search index) generation
+#[derive(Debug, Args)]
+#[command(flatten_help = true)]
+pub(crate) struct FeedArgs {
+    /// Source directory containing the source Markdown files
+    #[command(flatten)]
+    pub(crate) src: MarkdownDirArgs,
+
+    /// Base URL
+    #[command(flatten)]
+    pub(crate) base: UrlArgs,
+
+    /// Path to the file to create
+    #[command(flatten)]
+    pub(crate) dest: DestFileArgs,
+
+    /// Feed format: `atom` (default) or `rss`
+    #[arg(long = "format", value_name = "FORMAT", value_parser = parse_feed_format)]
+    pub(crate) format: Option<mdbook_utils::FeedFormat>,
+
+    /// Title of the feed (defaults to the book's title in `book.toml`)
+    #[arg(long = "title", value_name = "TITLE")]
+    pub(crate) title: Option<String>,
+
+    /// Maximum number of chapters in the feed (default: 20)
+    #[arg(long = "max-entries", value_name = "N")]
+    pub(crate) max_entries: Option<usize>,
+
+    /// Also write a JSON index of all chapters and their headings,
+    /// for external search engines
+    #[arg(long = "json-index", value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    pub(crate) json_index: Option<PathBuf>,
+
+    /// Exclude the chapters whose path matches a glob pattern (e.g.,
+    /// '*refs.md'). May be repeated.
+    #[arg(long = "exclude", value_name = "GLOB")]
+    pub(crate) exclude: Vec<String>,
+
+    /// Do not use `git` to date chapters; use the files' modification time
+    /// when the front matter has no date.
+    #[arg(long = "no-git")]
+    pub(crate) no_git: bool,
+}
+
+/// Parse a feed format
+fn parse_feed_format(
+    s: &str,
+) -> Result<mdbook_utils::FeedFormat, Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Ok(s.parse()?)
 }
 
 #[cfg(test)]
@@ -1,11 +1,17 @@
 //! Handles CLI configuration, environment variables, and defaults
+//!
+//! Precedence, from highest to lowest: command-line arguments;
+//! environment variables (and `.env`); `mdbook-utils.toml`;
+//! `[output.utils]` / `[preprocessor.utils]` in `book.toml`; defaults.
 
+use std::collections::BTreeMap;
 use std::ffi::OsStr;
+use std::path::Path;
 use std::path::PathBuf;
+use std::sync::OnceLock;
 
 use anyhow::Context;
 use anyhow::Result;
-use serde::Deserialize;
 use tracing::debug;
 
 use super::GlobalOpts;
@@ -14,19 +20,74 @@
 use super::args::DestFileArgs;
 use super::args::MarkdownDirArgs;
 use super::args::UrlArgs;
-
-/// Stores environment variables into a Configuration struct.
-/// Defaults apply if not present.
+use super::config_file;
+use super::config_file::ConfigSource;
+use super::config_file::ConfigValues;
+use super::config_file::FeedConfig;
+use super::config_file::LintConfig;
+use super::config_file::ParserConfig;
+use super::config_file::RefDefsConfig;
+
+/// Merges, in increasing order of precedence, the defaults, the
+/// `[output.utils]` / `[preprocessor.utils]` table of `book.toml`,
+/// `mdbook-utils.toml` and the environment variables into a Configuration
+/// struct, then validates it.
 pub(crate) fn init(global_opts: GlobalOpts) -> Result<Configuration> {
-    // Serialize environment variables into the Configuration struct
-    let mut c = envy::from_env::<Configuration>()?;
+    let env = config_file::env_values()?;
+    let config_file_path = config_file::find_config_file(
+        global_opts.config.as_deref(),
+        env.book_root_dir_path.as_deref(),
+    )?;
+    let file = config_file_path
+        .as_deref()
+        .map(config_file::read_config_file)
+        .transpose()?;
+
+    // `book.toml` is found in the book's root directory
+    let book_root_dir_path = env
+        .book_root_dir_path
+        .clone()
+        .or_else(|| file.as_ref().and_then(|f| f.book_root_dir_path.clone()))
+        .unwrap_or_else(|| Configuration::default().book_root_dir_path);
+    let book_toml = mdbook_utils::BookToml::from_dir(&book_root_dir_path);
+    let book = match &book_toml {
+        Ok(book_toml) => super::book_toml::utils_table(book_toml)
+            .map_err(anyhow::Error::from)
+            .and_then(|table| {
+                Ok(table
+                    .map(toml::Table::try_into::<ConfigValues>)
+                    .transpose()?)
+            })
+            .context("[init] Invalid `[output.utils]` / `[preprocessor.utils]` table in book.toml")?
+            .map(|values| values.resolve_paths(&book_root_dir_path)),
+        Err(e) => {
+            debug!("`book.toml` does not exist or is not parseable: {e}");
+            None
+        }
+    };
+
+    let mut c = Configuration::default();
+    for (values, source) in [
+        (book, ConfigSource::BookToml),
+        (file, ConfigSource::ConfigFile),
+        (Some(env), ConfigSource::Env),
+    ] {
+        if let Some(values) = values {
+            c.apply(values, source);
+        }
+    }
+    if c.book_root_dir_path == book_root_dir_path {
+        // `book.toml` is parsed once
+        let _ = c.book_toml.set(book_toml);
+    }
+    c.config_file_path = config_file_path;
     c.global_opts = global_opts;
+    c.validate()?;
     Ok(c)
 }
 
 /// Application configuration and environment variables
-#[derive(Deserialize, Debug)]
-#[serde(default)]
+#[derive(Debug)]
 pub(crate) struct Configuration {
     /// BOOK_ROOT_DIR_PATH environment variable:
     /// the book's root directory (which contains `book.toml`),
@@ -59,18 +120,57 @@ pub(crate) struct Configuration {
     /// It is used to build sitemaps.
     base_url: String,
     /// SITEMAP_MAP_INDEX environment variable:
-    /// Map a filename to another (e.g., 'intro.md' to 'index.md')
+    /// Comma-separated list of chapter path mappings
+    /// (e.g., 'intro.md:index.md')
     sitemap_map_index: Option<String>,
+    /// SITEMAP_EXCLUDE environment variable:
+    /// Comma-separated list of glob patterns of chapters to leave out of
+    /// the sitemap (e.g., '*refs.md,drafts/**')
+    sitemap_exclude: Option<String>,
+    /// CRATES_DB_DUMP_DIR_PATH environment variable:
+    /// Directory of an extracted crates.io database dump
+    /// (<https://static.crates.io/db-dump.tar.gz>)
+    crates_db_dump_dir_path: Option<PathBuf>,
     /// MDBOOK_PATH environment variable:
     /// Path to the `mdbook` binary
     /// typically `mdbook`
     mdbook_path: Option<PathBuf>,
+    /// CACHE_DIR_PATH environment variable:
+    /// Directory where the data extracted from the Markdown files is
+    /// cached between runs, if set
+    cache_dir_path: Option<PathBuf>,
+
+    /// `[refdefs]` table of the configuration file(s)
+    refdefs: RefDefsConfig,
+    /// `[feed]` table of the configuration file(s)
+    feed: FeedConfig,
+    /// `[lint]` table of the configuration file(s)
+    lint: LintConfig,
+    /// `[parser]` table of the configuration file(s)
+    parser: ParserConfig,
+
+    /// Source of each value that is not a default, by key
+    /// e.g. `base_url` or `refdefs.kinds`
+    sources: BTreeMap<String, ConfigSource>,
+    /// Path to `mdbook-utils.toml`, if found
+    config_file_path: Option<PathBuf>,
+    /// `book.toml` of the book's root directory, parsed on first use
+    book_toml: OnceLock<mdbook_utils::Result<mdbook_utils::BookToml>>,
 
     /// Global options that apply to all (sub)commands.
-    #[serde(skip)]
     global_opts: GlobalOpts,
 }
 
+/// Split a comma-separated list, skipping empty items
+fn split_list(s: Option<&str>) -> Vec<String> {
+    s.unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect()
+}
+
 /// Defaults if the environment variables are not set
 impl Default for Configuration {
     fn default() -> Self {
@@ -83,13 +183,251 @@ fn default() -> Self {
             default_dest_dir_path: None,
             base_url: String::from("http://example.com/mybook/"),
             sitemap_map_index: Some("intro.md:index.md".to_string()),
+            sitemap_exclude: None,
+            crates_db_dump_dir_path: None,
             mdbook_path: None,
+            cache_dir_path: None,
+            refdefs: RefDefsConfig::default(),
+            feed: FeedConfig::default(),
+            lint: LintConfig::default(),
+            parser: ParserConfig::default(),
+            sources: BTreeMap::new(),
+            config_file_path: None,
+            book_toml: OnceLock::new(),
             global_opts: GlobalOpts::default(),
         }
     }
 }
 
 impl Configuration {
+    /// Override the configuration by the values that are set,
+    /// and record their source.
+    fn apply(&mut self, values: ConfigValues, source: ConfigSource) {
+        let mut set = |key: &str, is_set: bool| {
+            if is_set {
+                self.sources.insert(key.to_string(), source);
+            }
+        };
+        set("book_root_dir_path", values.book_root_dir_path.is_some());
+        set("markdown_dir_path", values.markdown_dir_path.is_some());
+        set(
+            "book_html_build_dir_path",
+            values.book_html_build_dir_path.is_some(),
+        );
+        set(
+            "book_markdown_build_dir_path",
+            values.book_markdown_build_dir_path.is_some(),
+        );
+        set("cargo_toml_dir_path", values.cargo_toml_dir_path.is_some());
+        set(
+            "default_dest_dir_path",
+            values.default_dest_dir_path.is_some(),
+        );
+        set("base_url", values.base_url.is_some());
+        set("sitemap_map_index", values.sitemap_map_index.is_some());
+        set("sitemap_exclude", values.sitemap_exclude.is_some());
+        set(
+            "crates_db_dump_dir_path",
+            values.crates_db_dump_dir_path.is_some(),
+        );
+        set("mdbook_path", values.mdbook_path.is_some());
+        set("cache_dir_path", values.cache_dir_path.is_some());
+        set("refdefs.kinds", values.refdefs.kinds.is_some());
+        set(
+            "refdefs.cargo_metadata",
+            values.refdefs.cargo_metadata.is_some(),
+        );
+        set(
+            "refdefs.pin_versions",
+            values.refdefs.pin_versions.is_some(),
+        );
+        set("refdefs.templates", values.refdefs.templates.is_some());
+        set("feed.title", values.feed.title.is_some());
+        set("feed.format", values.feed.format.is_some());
+        set("feed.max_entries", values.feed.max_entries.is_some());
+        set("feed.use_git", values.feed.use_git.is_some());
+        set("lint.code_dir_path", values.lint.code_dir_path.is_some());
+        set("lint.severity", values.lint.severity.is_some());
+        set("parser.preset", values.parser.preset.is_some());
+        set("parser.extensions", values.parser.extensions.is_some());
+        set("parser.front_matter", values.parser.front_matter.is_some());
+
+        if let Some(v) = values.book_root_dir_path {
+            self.book_root_dir_path = v;
+        }
+        if let Some(v) = values.base_url {
+            self.base_url = v;
+        }
+        self.markdown_dir_path = values.markdown_dir_path.or(self.markdown_dir_path.take());
+        self.book_html_build_dir_path = values
+            .book_html_build_dir_path
+            .or(self.book_html_build_dir_path.take());
+        self.book_markdown_build_dir_path = values
+            .book_markdown_build_dir_path
+            .or(self.book_markdown_build_dir_path.take());
+        self.cargo_toml_dir_path = values
+            .cargo_toml_dir_path
+            .or(self.cargo_toml_dir_path.take());
+        self.default_dest_dir_path = values
+            .default_dest_dir_path
+            .or(self.default_dest_dir_path.take());
+        self.sitemap_map_index = values.sitemap_map_index.or(self.sitemap_map_index.take());
+        self.sitemap_exclude = values.sitemap_exclude.or(self.sitemap_exclude.take());
+        self.crates_db_dump_dir_path = values
+            .crates_db_dump_dir_path
+            .or(self.crates_db_dump_dir_path.take());
+        self.mdbook_path = values.mdbook_path.or(self.mdbook_path.take());
+        self.cache_dir_path = values.cache_dir_path.or(self.cache_dir_path.take());
+
+        let r = values.refdefs;
+        self.refdefs.kinds = r.kinds.or(self.refdefs.kinds.take());
+        self.refdefs.cargo_metadata = r.cargo_metadata.or(self.refdefs.cargo_metadata);
+        self.refdefs.pin_versions = r.pin_versions.or(self.refdefs.pin_versions);
+        self.refdefs.templates = r.templates.or(self.refdefs.templates.take());
+        let f = values.feed;
+        self.feed.title = f.title.or(self.feed.title.take());
+        self.feed.format = f.format.or(self.feed.format.take());
+        self.feed.max_entries = f.max_entries.or(self.feed.max_entries);
+        self.feed.use_git = f.use_git.or(self.feed.use_git);
+        let l = values.lint;
+        self.lint.code_dir_path = l.code_dir_path.or(self.lint.code_dir_path.take());
+        // Severities are merged rule by rule
+        if let Some(severity) = l.severity {
+            self.lint.severity.get_or_insert_default().extend(severity);
+        }
+        let p = values.parser;
+        self.parser.preset = p.preset.or(self.parser.preset.take());
+        self.parser.extensions = p.extensions.or(self.parser.extensions.take());
+        self.parser.front_matter = p.front_matter.or(self.parser.front_matter);
+    }
+
+    /// Returns the source of a value e.g. `base_url` or `refdefs.kinds`.
+    fn source(&self, key: &str) -> ConfigSource {
+        self.sources.get(key).copied().unwrap_or_default()
+    }
+
+    /// Check the values that must be parsed.
+    /// Errors name the offending key and where it was set.
+    fn validate(&self) -> Result<()> {
+        let invalid = |key: &str, e: &dyn std::fmt::Display| -> anyhow::Error {
+            anyhow::anyhow!(
+                "[validate] Invalid value for `{key}` (set in {}): {e}",
+                self.source(key)
+            )
+        };
+        url::Url::parse(&self.base_url).map_err(|e| invalid("base_url", &e))?;
+        for mapping in split_list(self.sitemap_map_index.as_deref()) {
+            if mapping.split(':').count() != 2 {
+                return Err(invalid(
+                    "sitemap_map_index",
+                    &format!("`{mapping}` is not in the `FROM:TO` format"),
+                ));
+            }
+        }
+        for kind in self.refdefs.kinds.iter().flatten() {
+            kind.parse::<mdbook_utils::DependencyKind>()
+                .map_err(|e| invalid("refdefs.kinds", &e))?;
+        }
+        if let Some(ref format) = self.feed.format {
+            format
+                .parse::<mdbook_utils::FeedFormat>()
+                .map_err(|e| invalid("feed.format", &e))?;
+        }
+        for (rule, severity) in self.lint.severity.iter().flatten() {
+            rule.parse::<mdbook_utils::LintRule>()
+                .map_err(|e| invalid("lint.severity", &e))?;
+            severity
+                .parse::<mdbook_utils::Severity>()
+                .map_err(|e| invalid("lint.severity", &e))?;
+        }
+        if let Some(ref preset) = self.parser.preset {
+            preset
+                .parse::<mdbook_utils::MarkdownPreset>()
+                .map_err(|e| invalid("parser.preset", &e))?;
+        }
+        for extension in self.parser.extensions.iter().flatten() {
+            extension
+                .parse::<mdbook_utils::MarkdownExtension>()
+                .map_err(|e| invalid("parser.extensions", &e))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the effective configuration, as `(key, value, source)`
+    /// triples. Values that are not set are `None`.
+    pub(crate) fn entries(&self) -> Vec<(String, Option<String>, ConfigSource)> {
+        let path = |p: &Option<PathBuf>| p.as_ref().map(|p| p.display().to_string());
+        let r = &self.refdefs;
+        let f = &self.feed;
+        let l = &self.lint;
+        let p = &self.parser;
+        let entries: Vec<(&str, Option<String>)> = vec![
+            (
+                "book_root_dir_path",
+                Some(self.book_root_dir_path.display().to_string()),
+            ),
+            ("markdown_dir_path", path(&self.markdown_dir_path)),
+            (
+                "book_html_build_dir_path",
+                path(&self.book_html_build_dir_path),
+            ),
+            (
+                "book_markdown_build_dir_path",
+                path(&self.book_markdown_build_dir_path),
+            ),
+            ("cargo_toml_dir_path", path(&self.cargo_toml_dir_path)),
+            ("default_dest_dir_path", path(&self.default_dest_dir_path)),
+            ("base_url", Some(self.base_url.clone())),
+            ("sitemap_map_index", self.sitemap_map_index.clone()),
+            ("sitemap_exclude", self.sitemap_exclude.clone()),
+            (
+                "crates_db_dump_dir_path",
+                path(&self.crates_db_dump_dir_path),
+            ),
+            ("mdbook_path", path(&self.mdbook_path)),
+            ("cache_dir_path", path(&self.cache_dir_path)),
+            ("refdefs.kinds", r.kinds.as_ref().map(|k| k.join(","))),
+            (
+                "refdefs.cargo_metadata",
+                r.cargo_metadata.map(|b| b.to_string()),
+            ),
+            (
+                "refdefs.pin_versions",
+                r.pin_versions.map(|b| b.to_string()),
+            ),
+            ("refdefs.templates", path(&r.templates)),
+            ("feed.title", f.title.clone()),
+            ("feed.format", f.format.clone()),
+            ("feed.max_entries", f.max_entries.map(|n| n.to_string())),
+            ("feed.use_git", f.use_git.map(|b| b.to_string())),
+            ("lint.code_dir_path", path(&l.code_dir_path)),
+            (
+                "lint.severity",
+                l.severity.as_ref().map(|s| {
+                    s.iter()
+                        .map(|(rule, severity)| format!("{rule}={severity}"))
+                        .collect::<Vec<_>>()
+                        .join(",")
+                }),
+            ),
+            ("parser.preset", p.preset.clone()),
+            (
+                "parser.extensions",
+                p.extensions.as_ref().map(|e| e.join(",")),
+            ),
+            ("parser.front_matter", p.front_matter.map(|b| b.to_string())),
+        ];
+        entries
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value, self.source(key)))
+            .collect()
+    }
+
+    /// Returns the path to `mdbook-utils.toml`, if found
+    pub(crate) fn config_file_path(&self) -> Option<&Path> {
+        self.config_file_path.as_deref()
+    }
+
     /// Returns the Markdown source directory provided by the
     /// command-line argument (if set);
     /// the MARKDOWN_DIR_PATH environment variable (if set);
@@ -130,18 +468,30 @@ pub(crate) fn markdown_src_dir_path<S: AsRef<OsStr>>(
     ///
     /// Does not propagate errors, since having a `book.toml` is optional.
     fn get_markdown_dir_path_from_book_toml(&self) -> Option<PathBuf> {
-        match super::book_toml::try_parse_book_toml(self.book_root_dir_path.clone()) {
-            // `book.toml` exists and is parseable
-            Ok((src, _, _)) => Some(src),
-            Err(e) => {
+        self.book_toml().map(|b| b.src_dir())
+    }
+
+    /// Returns the model of `book.toml`, if it exists in the book's root
+    /// directory and is parseable.
+    ///
+    /// Does not propagate errors, since having a `book.toml` is optional.
+    pub(crate) fn book_toml(&self) -> Option<&mdbook_utils::BookToml> {
+        self.parsed_book_toml().as_ref().ok()
+    }
+
+    /// Parses `book.toml` on first use, then returns the cached result.
+    fn parsed_book_toml(&self) -> &mdbook_utils::Result<mdbook_utils::BookToml> {
+        self.book_toml.get_or_init(|| {
+            let book_toml = mdbook_utils::BookToml::from_dir(&self.book_root_dir_path);
+            if let Err(e) = &book_toml {
                 debug!(
-                    "`book.toml` does not exist in {} or is not parseable. Error: {}",
+                    "`book.toml` does not exist in {} or is not parseable. Error: {:?}",
                     self.book_root_dir_path.display(),
                     e
                 );
-                None
             }
-        }
+            book_toml
+        })
     }
 
     /// Returns the directory where `mdbook` outputs the book's fully expanded
@@ -167,15 +517,15 @@ pub(crate) fn book_markdown_build_dir_path<S: AsRef<OsStr>>(
                 debug!("BOOK_MARKDOWN_BUILD_DIR_PATH set: {}", mdp.display());
                 mdp.clone()
             } else {
-                match super::book_toml::try_parse_book_toml(self.book_root_dir_path.clone()) {
-                    Ok((_, _, Some(p))) => {
+                match self.book_toml().and_then(|b| b.markdown_build_dir()) {
+                    Some(p) => {
                         debug!(
                             "book_markdown_build_dir_path set from `book.toml`: {}",
                             p.display()
                         );
                         p
                     }
-                    _ => {
+                    None => {
                         debug!(
                             "book_markdown_build_dir_path set to default: {:?}",
                             default_dir_path.as_ref()
@@ -245,18 +595,93 @@ pub(crate) fn base_url(&self, args: UrlArgs) -> Result<url::Url> {
         ))
     }
 
-    /// Returns the sitemap index file mapping
-    pub(crate) fn sitemap_map_index(&self, map_index: Option<String>) -> Option<(String, String)> {
+    /// Returns the sitemap chapter path mappings, as provided by the
+    /// command-line arguments (if any); or the SITEMAP_MAP_INDEX
+    /// environment variable, otherwise the default value.
+    /// Mappings that are not in the `FROM:TO` format are ignored.
+    pub(crate) fn sitemap_map_index(&self, map_index: Vec<String>) -> Vec<(String, String)> {
+        let map_index = if map_index.is_empty() {
+            split_list(self.sitemap_map_index.as_deref())
+        } else {
+            map_index
+        };
         map_index
-            .or_else(|| self.sitemap_map_index.clone())
-            .and_then(|s| {
+            .iter()
+            .filter_map(|s| {
                 let parts: Vec<&str> = s.split(':').collect();
                 if parts.len() == 2 {
                     Some((parts[0].to_string(), parts[1].to_string()))
                 } else {
+                    tracing::warn!("Ignoring invalid sitemap mapping: {s}");
                     None
                 }
             })
+            .collect()
+    }
+
+    /// Returns the glob patterns of chapters to exclude from the sitemap
+    /// and feeds,
+    /// as provided by the command-line arguments (if any); or the
+    /// SITEMAP_EXCLUDE environment variable (if set); or `*refs.md`.
+    pub(crate) fn sitemap_exclude(&self, exclude: Vec<String>) -> Vec<String> {
+        if !exclude.is_empty() {
+            exclude
+        } else if self.sitemap_exclude.is_some() {
+            split_list(self.sitemap_exclude.as_deref())
+        } else {
+            mdbook_utils::SitemapOptions::default().exclude
+        }
+    }
+
+    /// Returns `output.html.site-url` from `book.toml`, if it exists and
+    /// is set.
+    ///
+    /// Fails if `book.toml` exists but cannot be parsed e.g. if its
+    /// `[output.html]` table is invalid.
+    pub(crate) fn site_url(&self) -> Result<Option<String>> {
+        match self.parsed_book_toml() {
+            Ok(book_toml) => Ok(book_toml.html().site_url.clone()),
+            Err(mdbook_utils::Error::Io { .. }) => Ok(None),
+            Err(e) => {
+                let source = std::error::Error::source(e)
+                    .map(|s| format!(": {s}"))
+                    .unwrap_or_default();
+                anyhow::bail!("[site_url] {e}{source}")
+            }
+        }
+    }
+
+    /// Returns the directory of the crates.io database dump, as provided
+    /// by the command-line argument (if set) or the
+    /// CRATES_DB_DUMP_DIR_PATH environment variable (if set).
+    pub(crate) fn crates_db_dump_dir_path(&self, dir_path: Option<PathBuf>) -> Option<PathBuf> {
+        dir_path.or_else(|| self.crates_db_dump_dir_path.clone())
+    }
+
+    /// Returns the directory where the data extracted from the Markdown
+    /// files is cached, as provided by the CACHE_DIR_PATH environment
+    /// variable or the configuration files, unless `--no-cache` is passed.
+    /// `None` if caching is disabled.
+    pub(crate) fn cache_dir_path(&self) -> Option<PathBuf> {
+        if self.global_opts.no_cache {
+            None
+        } else {
+            self.cache_dir_path.clone()
+        }
+    }
+
+    /// Opens the cache of the data extracted from the Markdown files, if
+    /// caching is enabled (see `cache_dir_path`). The files are parsed
+    /// with the `parser_options`.
+    pub(crate) fn markdown_cache(&self) -> Result<Option<mdbook_utils::MarkdownCache>> {
+        let Some(cache_dir_path) = self.cache_dir_path() else {
+            return Ok(None);
+        };
+        let cache = mdbook_utils::MarkdownCache::open_with_options(
+            cache_dir_path,
+            &self.parser_options()?,
+        )?;
+        Ok(Some(cache))
     }
 
     /// Returns the sitemap output file path, as provided by
@@ -264,28 +689,195 @@ pub(crate) fn sitemap_map_index(&self, map_index: Option<String>) -> Option<(Str
     /// where the HTML output path is retrieved from `book.toml`, if possible,
     /// or the default (`./book`) otherwise.
     pub(crate) fn sitemap_file_path(&self, args: DestFileArgs) -> PathBuf {
-        if let Some(file_path) = args.file_path {
-            file_path
+        args.file_path
+            .unwrap_or_else(|| self.book_html_build_dir_path().join("sitemap.xml"))
+    }
+
+    /// Returns the directory where `mdbook` outputs the book's HTML, as
+    /// provided by the BOOK_HTML_BUILD_DIR_PATH environment variable (if
+    /// set); or retrieved from `book.toml`, if possible; or the default
+    /// (`./book`) otherwise.
+    fn book_html_build_dir_path(&self) -> PathBuf {
+        if let Some(ref d) = self.book_html_build_dir_path {
+            d.clone()
         } else {
-            let dir: PathBuf = if let Some(ref d) = self.book_html_build_dir_path {
-                d.clone()
-            } else {
-                match super::book_toml::try_parse_book_toml(self.book_root_dir_path.clone()) {
-                    Ok((_, Some(html_output_dir), _)) => {
-                        // `book.toml`` exists, is parseable and build.build-dir is defined
-                        html_output_dir
-                    }
-                    _ => "./book".into(),
-                }
-            };
-            dir.join("sitemap.xml")
+            // `book.toml` exists, is parseable and the HTML renderer is run
+            self.book_toml()
+                .and_then(|b| b.html_build_dir())
+                .unwrap_or_else(|| "./book".into())
         }
     }
 
+    /// Returns the feed output file path, as provided by
+    /// the command-line argument (if set); or {path}/atom.xml (or rss.xml),
+    /// where the HTML output path is determined as for the sitemap.
+    pub(crate) fn feed_file_path(
+        &self,
+        args: DestFileArgs,
+        format: mdbook_utils::FeedFormat,
+    ) -> PathBuf {
+        let file_name = match format {
+            mdbook_utils::FeedFormat::Atom => "atom.xml",
+            mdbook_utils::FeedFormat::Rss => "rss.xml",
+        };
+        args.file_path
+            .unwrap_or_else(|| self.book_html_build_dir_path().join(file_name))
+    }
+
+    /// Returns the title of the book, as provided by `book.toml`, if
+    /// possible.
+    pub(crate) fn book_title(&self) -> Option<String> {
+        self.book_toml().and_then(|b| b.book.title.clone())
+    }
+
+    /// Returns the kinds of dependencies of `refdefs generate`, as provided
+    /// by the command-line arguments (if any); or the `refdefs.kinds` key
+    /// of the configuration files; otherwise normal dependencies.
+    pub(crate) fn dependency_kinds(
+        &self,
+        kinds: Vec<mdbook_utils::DependencyKind>,
+    ) -> Result<Vec<mdbook_utils::DependencyKind>> {
+        if !kinds.is_empty() {
+            return Ok(kinds);
+        }
+        match self.refdefs.kinds {
+            Some(ref kinds) => kinds.iter().map(|k| Ok(k.parse()?)).collect(),
+            None => Ok(mdbook_utils::DependencyOptions::default().kinds),
+        }
+    }
+
+    /// Returns `true` if the command-line flag is set, otherwise the
+    /// `refdefs.cargo_metadata` key of the configuration files.
+    pub(crate) fn cargo_metadata(&self, flag: bool) -> bool {
+        flag || self.refdefs.cargo_metadata.unwrap_or_default()
+    }
+
+    /// Returns `true` if the command-line flag is set, otherwise the
+    /// `refdefs.pin_versions` key of the configuration files.
+    pub(crate) fn pin_versions(&self, flag: bool) -> bool {
+        flag || self.refdefs.pin_versions.unwrap_or_default()
+    }
+
+    /// Returns the reference definition templates file, as provided by
+    /// the command-line argument (if set) or the `refdefs.templates` key
+    /// of the configuration files.
+    pub(crate) fn refdef_templates_path(&self, file_path: Option<PathBuf>) -> Option<PathBuf> {
+        file_path.or_else(|| self.refdefs.templates.clone())
+    }
+
+    /// Returns the options of the Markdown parser: the preset, as
+    /// provided by the command-line argument (if set) or the
+    /// `parser.preset` key of the configuration files; the extensions of
+    /// the command-line arguments and of the `parser.extensions` key; and
+    /// the `parser.front_matter` key (default: `true`).
+    ///
+    /// The `mdbook` preset enables smart punctuation if
+    /// `output.html.smart-punctuation` is set in `book.toml`, as mdBook
+    /// does.
+    pub(crate) fn parser_options(&self) -> Result<mdbook_utils::ParserOptions> {
+        let preset = match (self.global_opts.markdown_preset, &self.parser.preset) {
+            (Some(preset), _) => preset,
+            (None, Some(preset)) => preset.parse()?,
+            (None, None) => mdbook_utils::MarkdownPreset::default(),
+        };
+        let mut extensions = self.global_opts.markdown_extension.clone();
+        for extension in self.parser.extensions.iter().flatten() {
+            extensions.push(extension.parse()?);
+        }
+        if preset == mdbook_utils::MarkdownPreset::MdBook
+            && self.book_toml().is_some_and(|b| b.html().smart_punctuation)
+        {
+            extensions.push(mdbook_utils::MarkdownExtension::SmartPunctuation);
+        }
+        Ok(mdbook_utils::ParserOptions {
+            preset,
+            extensions,
+            front_matter: self.parser.front_matter.unwrap_or(true),
+        })
+    }
+
+    /// Returns the feed format, as provided by the command-line argument
+    /// (if set); or the `feed.format` key of the configuration files;
+    /// otherwise Atom.
+    pub(crate) fn feed_format(
+        &self,
+        format: Option<mdbook_utils::FeedFormat>,
+    ) -> Result<mdbook_utils::FeedFormat> {
+        match (format, &self.feed.format) {
+            (Some(format), _) => Ok(format),
+            (None, Some(format)) => Ok(format.parse()?),
+            (None, None) => Ok(mdbook_utils::FeedOptions::default().format),
+        }
+    }
+
+    /// Returns the feed title, as provided by the command-line argument
+    /// (if set); or the `feed.title` key of the configuration files; or
+    /// the title of the book in `book.toml`, if any.
+    pub(crate) fn feed_title(&self, title: Option<String>) -> Option<String> {
+        title
+            .or_else(|| self.feed.title.clone())
+            .or_else(|| self.book_title())
+    }
+
+    /// Returns the maximum number of chapters in the feed, as provided by
+    /// the command-line argument (if set); or the `feed.max_entries` key
+    /// of the configuration files; otherwise 20.
+    pub(crate) fn feed_max_entries(&self, max_entries: Option<usize>) -> usize {
+        max_entries.or(self.feed.max_entries).unwrap_or(20)
+    }
+
+    /// Returns `false` if the `--no-git` flag is set, otherwise the
+    /// `feed.use_git` key of the configuration files (`true` by default).
+    pub(crate) fn feed_use_git(&self, no_git: bool) -> bool {
+        !no_git && self.feed.use_git.unwrap_or(true)
+    }
+
+    /// Returns the options of `lint`: the code directory, as provided by
+    /// the command-line argument (if set) or the `lint.code_dir_path` key
+    /// of the configuration files; and the severities of the
+    /// `lint.severity` table, overridden by the command-line arguments;
+    /// the reference definition templates of `refdefs.templates`, if set;
+    /// the redirects of `book.toml`, if any; and the `parser_options`.
+    pub(crate) fn lint_options(
+        &self,
+        code_dir_path: Option<PathBuf>,
+        severities: Vec<(mdbook_utils::LintRule, mdbook_utils::Severity)>,
+    ) -> Result<mdbook_utils::LintOptions> {
+        let mut options = mdbook_utils::LintOptions {
+            code_dir_path: code_dir_path.or_else(|| self.lint.code_dir_path.clone()),
+            templates: self
+                .refdef_templates_path(None)
+                .map(mdbook_utils::read_refdef_templates)
+                .transpose()?,
+            parser: self.parser_options()?,
+            ..Default::default()
+        };
+        if let Some(book_toml) = self.book_toml() {
+            options.redirects = book_toml.html().redirect.clone();
+        }
+        for (rule, severity) in self.lint.severity.iter().flatten() {
+            options.severities.insert(rule.parse()?, severity.parse()?);
+        }
+        options.severities.extend(severities);
+        Ok(options)
+    }
+
+    /// Returns the root directory of the book, which contains `book.toml`,
+    /// as provided by the BOOK_ROOT_DIR_PATH environment variable (if set),
+    /// or the current working directory otherwise.
+    pub(crate) fn book_root_dir_path(&self) -> &Path {
+        &self.book_root_dir_path
+    }
+
     /// if true, skip confirmation prompts
     pub(crate) fn skip_confirm(&self) -> bool {
         self.global_opts.yes
     }
+
+    /// Returns the global options given on the command line
+    pub(crate) fn global_opts(&self) -> &GlobalOpts {
+        &self.global_opts
+    }
 }
 
 #[cfg(test)]
@@ -354,6 +946,8 @@ fn test_markdown_src_dir_path() -> Result<()> {
 src = "toml_src"
 "#,
         )?;
+        // `book.toml` is parsed once: forget the previous result
+        config.book_toml = OnceLock::new();
         let args = MarkdownDirArgs {
             markdown_dir_path: None,
         };
@@ -408,6 +1002,7 @@ fn test_book_markdown_build_dir_path() -> Result<()> {
             r#"[output.markdown]
 "#,
         )?;
+        config.book_toml = OnceLock::new();
         let args = MarkdownDirArgs {
             markdown_dir_path: None,
         };
@@ -540,24 +1135,51 @@ fn test_sitemap_map_index() {
 
         // 1. Default
         assert_eq!(
-            config.sitemap_map_index(None),
-            Some(("intro.md".into(), "index.md".into()))
+            config.sitemap_map_index(vec![]),
+            vec![("intro.md".into(), "index.md".into())]
         );
 
-        // 2. Argument
+        // 2. Arguments
         assert_eq!(
-            config.sitemap_map_index(Some("a.md:b.md".into())),
-            Some(("a.md".into(), "b.md".into()))
+            config.sitemap_map_index(vec!["a.md:b.md".into(), "c.md:d.md".into()]),
+            vec![
+                ("a.md".into(), "b.md".into()),
+                ("c.md".into(), "d.md".into())
+            ]
         );
 
         // 3. Invalid format
-        assert_eq!(config.sitemap_map_index(Some("invalid".into())), None);
+        assert_eq!(config.sitemap_map_index(vec!["invalid".into()]), vec![]);
 
         // 4. Configuration override
-        config.sitemap_map_index = Some("conf.md:idx.md".into());
+        config.sitemap_map_index = Some("conf.md:idx.md, x.md:y.md".into());
         assert_eq!(
-            config.sitemap_map_index(None),
-            Some(("conf.md".into(), "idx.md".into()))
+            config.sitemap_map_index(vec![]),
+            vec![
+                ("conf.md".into(), "idx.md".into()),
+                ("x.md".into(), "y.md".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sitemap_exclude() {
+        let mut config = Configuration::default();
+
+        // 1. Default
+        assert_eq!(config.sitemap_exclude(vec![]), vec!["*refs.md".to_string()]);
+
+        // 2. Configuration override
+        config.sitemap_exclude = Some("a.md,drafts/**".into());
+        assert_eq!(
+            config.sitemap_exclude(vec![]),
+            vec!["a.md".to_string(), "drafts/**".to_string()]
+        );
+
+        // 3. Arguments
+        assert_eq!(
+            config.sitemap_exclude(vec!["b.md".into()]),
+            vec!["b.md".to_string()]
         );
     }
 
@@ -599,10 +1221,11 @@ fn test_sitemap_file_path() -> Result<()> {
 
         // 4. Default
         fs::remove_file(dir.path().join("book.toml"))?;
+        config.book_toml = OnceLock::new();
         let args = DestFileArgs { file_path: None };
         // The default in the code is hardcoded as
-        // PathBuf::from("./book").join("sitemap.xml") when try_parse_book_toml
-        // fails.
+        // PathBuf::from("./book").join("sitemap.xml") when `book.toml`
+        // cannot be read.
         assert_eq!(
             config.sitemap_file_path(args),
             PathBuf::from("./book").join("sitemap.xml")
@@ -611,6 +1234,72 @@ fn test_sitemap_file_path() -> Result<()> {
         Ok(())
     }
 
+    #[test]
+    fn test_apply_and_validate() -> Result<()> {
+        let mut config = Configuration::default();
+        let book = config_file::parse_config_values(
+            "base_url = \"https://book.example.com/\"\nmarkdown_dir_path = \"src\"\n",
+        )?;
+        let file = config_file::parse_config_values(
+            "base_url = \"https://file.example.com/\"\n[refdefs]\nkinds = [\"dev\"]\n",
+        )?;
+        let env = ConfigValues {
+            markdown_dir_path: Some("env_src".into()),
+            ..ConfigValues::default()
+        };
+        config.apply(book, ConfigSource::BookToml);
+        config.apply(file, ConfigSource::ConfigFile);
+        config.apply(env, ConfigSource::Env);
+        config.validate()?;
+
+        assert_eq!(config.base_url, "https://file.example.com/");
+        assert_eq!(config.source("base_url"), ConfigSource::ConfigFile);
+        assert_eq!(config.markdown_dir_path, Some("env_src".into()));
+        assert_eq!(config.source("markdown_dir_path"), ConfigSource::Env);
+        assert_eq!(config.source("sitemap_exclude"), ConfigSource::Default);
+        assert_eq!(
+            config.dependency_kinds(vec![])?,
+            vec![mdbook_utils::DependencyKind::Dev]
+        );
+        assert_eq!(
+            config.dependency_kinds(vec![mdbook_utils::DependencyKind::Build])?,
+            vec![mdbook_utils::DependencyKind::Build]
+        );
+        assert_eq!(
+            config.parser_options()?,
+            mdbook_utils::ParserOptions::default()
+        );
+        config.apply(
+            config_file::parse_config_values(
+                "[parser]\npreset = \"commonmark\"\nextensions = [\"math\"]\n",
+            )?,
+            ConfigSource::ConfigFile,
+        );
+        config.global_opts.markdown_extension = vec![mdbook_utils::MarkdownExtension::Gfm];
+        let parser_options = config.parser_options()?;
+        assert_eq!(
+            parser_options.preset,
+            mdbook_utils::MarkdownPreset::CommonMark
+        );
+        assert_eq!(
+            parser_options.extensions,
+            vec![
+                mdbook_utils::MarkdownExtension::Gfm,
+                mdbook_utils::MarkdownExtension::Math
+            ]
+        );
+
+        // Invalid values name the key and its source
+        config.apply(
+            config_file::parse_config_values("[feed]\nformat = \"json\"\n")?,
+            ConfigSource::ConfigFile,
+        );
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("`feed.format`"));
+        assert!(err.contains("mdbook-utils.toml"));
+        Ok(())
+    }
+
     #[test]
     fn test_skip_confirm() {
         let mut config = Configuration::default();
@@ -0,0 +1,41 @@
+//! Command-line subcommands to inspect the configuration
+use anyhow::Result;
+use clap::Subcommand;
+use console::style;
+
+use super::config::Configuration;
+
+/// Command-line subcommands to inspect the configuration
+#[derive(Subcommand, Debug)]
+pub(crate) enum ConfigSubCommand {
+    /// Print the effective configuration values and where each came from
+    /// (command-line arguments, which take precedence, are not shown)
+    Show,
+}
+
+/// "config" subcommands of the command-line interface
+pub(crate) fn run(subcmd: ConfigSubCommand, config: Configuration) -> Result<()> {
+    match subcmd {
+        ConfigSubCommand::Show => {
+            match config.config_file_path() {
+                Some(p) => println!("Configuration file: {}", style(p.display()).cyan()),
+                None => println!("Configuration file: {}", style("none").dim()),
+            }
+            println!();
+            for (key, value, source) in config.entries() {
+                let value = match value {
+                    Some(v) => style(v).cyan(),
+                    None => style("(not set)".to_string()).dim(),
+                };
+                let source = match source {
+                    super::config_file::ConfigSource::Env => {
+                        format!("{source}: {}", key.to_uppercase())
+                    }
+                    _ => source.to_string(),
+                };
+                println!("{key} = {value} {}", style(format!("[{source}]")).dim());
+            }
+        }
+    }
+    Ok(())
+}
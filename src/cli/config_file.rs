@@ -0,0 +1,302 @@
+//! Configuration values read from `mdbook-utils.toml`, from the
+//! `[output.utils]` / `[preprocessor.utils]` table of `book.toml`, or from
+//! environment variables
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::bail;
+use serde::Deserialize;
+use tracing::debug;
+
+/// Name of the configuration file, looked up in the current directory
+/// and in the book's root directory
+pub(crate) const CONFIG_FILE_NAME: &str = "mdbook-utils.toml";
+
+/// Names of the keys that may be set by environment variables (in
+/// uppercase) or in configuration files
+pub(crate) const KEYS: [&str; 12] = [
+    "book_root_dir_path",
+    "markdown_dir_path",
+    "book_html_build_dir_path",
+    "book_markdown_build_dir_path",
+    "cargo_toml_dir_path",
+    "default_dest_dir_path",
+    "base_url",
+    "sitemap_map_index",
+    "sitemap_exclude",
+    "crates_db_dump_dir_path",
+    "mdbook_path",
+    "cache_dir_path",
+];
+
+/// Where a configuration value comes from, in increasing order of
+/// precedence (command-line arguments take precedence over all)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub(crate) enum ConfigSource {
+    /// Hard-coded default
+    #[default]
+    Default,
+    /// `[output.utils]` or `[preprocessor.utils]` in `book.toml`
+    BookToml,
+    /// `mdbook-utils.toml`
+    ConfigFile,
+    /// Environment variable (or `.env` file)
+    Env,
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Default => "default",
+            Self::BookToml => "book.toml",
+            Self::ConfigFile => CONFIG_FILE_NAME,
+            Self::Env => "environment",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// Configuration values, all optional.
+///
+/// The keys are the names of the environment variables, in lowercase.
+#[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct ConfigValues {
+    pub(crate) book_root_dir_path: Option<PathBuf>,
+    pub(crate) markdown_dir_path: Option<PathBuf>,
+    pub(crate) book_html_build_dir_path: Option<PathBuf>,
+    pub(crate) book_markdown_build_dir_path: Option<PathBuf>,
+    pub(crate) cargo_toml_dir_path: Option<PathBuf>,
+    pub(crate) default_dest_dir_path: Option<PathBuf>,
+    pub(crate) base_url: Option<String>,
+    pub(crate) sitemap_map_index: Option<String>,
+    pub(crate) sitemap_exclude: Option<String>,
+    pub(crate) crates_db_dump_dir_path: Option<PathBuf>,
+    pub(crate) mdbook_path: Option<PathBuf>,
+    pub(crate) cache_dir_path: Option<PathBuf>,
+
+    /// `[refdefs]` table: options of `refdefs generate`
+    pub(crate) refdefs: RefDefsConfig,
+
+    /// `[feed]` table: options of `feed`
+    pub(crate) feed: FeedConfig,
+
+    /// `[lint]` table: options of `lint`
+    pub(crate) lint: LintConfig,
+
+    /// `[parser]` table: Markdown dialect of all commands
+    pub(crate) parser: ParserConfig,
+}
+
+/// Options of `refdefs generate`
+#[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct RefDefsConfig {
+    /// Kinds of dependencies: `normal`, `dev` and / or `build`
+    pub(crate) kinds: Option<Vec<String>>,
+    /// Run `cargo metadata --offline` to retrieve missing repository URLs
+    pub(crate) cargo_metadata: Option<bool>,
+    /// Pin docs.rs and crates.io URLs to the versions in `Cargo.lock`
+    pub(crate) pin_versions: Option<bool>,
+    /// TOML file of reference definition templates
+    pub(crate) templates: Option<PathBuf>,
+}
+
+/// Options of `feed`
+#[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct FeedConfig {
+    /// Title of the feed
+    pub(crate) title: Option<String>,
+    /// `atom` or `rss`
+    pub(crate) format: Option<String>,
+    /// Maximum number of chapters in the feed
+    pub(crate) max_entries: Option<usize>,
+    /// Use `git` to date chapters
+    pub(crate) use_git: Option<bool>,
+}
+
+/// Options of `lint`
+#[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct LintConfig {
+    /// Directory of the code examples included by the Markdown files
+    pub(crate) code_dir_path: Option<PathBuf>,
+    /// Severity (`error`, `warning` or `off`) by rule ID or name
+    pub(crate) severity: Option<BTreeMap<String, String>>,
+}
+
+/// Markdown dialect
+#[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct ParserConfig {
+    /// `default`, `mdbook`, `commonmark` or `all`
+    pub(crate) preset: Option<String>,
+    /// Extensions enabled in addition to those of the preset e.g.
+    /// `math`
+    pub(crate) extensions: Option<Vec<String>>,
+    /// Recognize and skip front matter blocks
+    pub(crate) front_matter: Option<bool>,
+}
+
+impl ConfigValues {
+    /// Resolve the relative paths against a base directory e.g. the
+    /// directory of the configuration file.
+    pub(crate) fn resolve_paths(mut self, base_dir_path: &Path) -> Self {
+        let resolve = |p: &mut Option<PathBuf>| {
+            if let Some(path) = p
+                && path.is_relative()
+            {
+                *path = base_dir_path.join(&path);
+            }
+        };
+        resolve(&mut self.book_root_dir_path);
+        resolve(&mut self.markdown_dir_path);
+        resolve(&mut self.book_html_build_dir_path);
+        resolve(&mut self.book_markdown_build_dir_path);
+        resolve(&mut self.cargo_toml_dir_path);
+        resolve(&mut self.default_dest_dir_path);
+        resolve(&mut self.crates_db_dump_dir_path);
+        resolve(&mut self.cache_dir_path);
+        resolve(&mut self.refdefs.templates);
+        resolve(&mut self.lint.code_dir_path);
+        // `mdbook_path` may be a command name, looked up in the PATH
+        self
+    }
+}
+
+/// Parse the contents of a configuration file.
+///
+/// Unknown keys and values of the wrong type are reported with the name
+/// of the key.
+pub(crate) fn parse_config_values(contents: &str) -> Result<ConfigValues> {
+    Ok(toml::from_str(contents)?)
+}
+
+/// Read and parse `mdbook-utils.toml` (or another configuration file).
+pub(crate) fn read_config_file(config_file_path: &Path) -> Result<ConfigValues> {
+    let contents = std::fs::read_to_string(config_file_path).with_context(|| {
+        format!(
+            "[read_config_file] Could not read the configuration file {}",
+            config_file_path.display()
+        )
+    })?;
+    let values = parse_config_values(&contents).with_context(|| {
+        format!(
+            "[read_config_file] Invalid configuration file {}",
+            config_file_path.display()
+        )
+    })?;
+    let base_dir_path = config_file_path.parent().unwrap_or(Path::new(""));
+    Ok(values.resolve_paths(base_dir_path))
+}
+
+/// Locate the configuration file: the path passed on the command line
+/// (which must exist), otherwise `mdbook-utils.toml` in the current
+/// directory or in the book's root directory, if any.
+pub(crate) fn find_config_file(
+    config_file_path: Option<&Path>,
+    book_root_dir_path: Option<&Path>,
+) -> Result<Option<PathBuf>> {
+    if let Some(p) = config_file_path {
+        if !p.is_file() {
+            bail!(
+                "[find_config_file] The configuration file {} does not exist.",
+                p.display()
+            );
+        }
+        return Ok(Some(p.to_path_buf()));
+    }
+    let found = [Some(Path::new(".")), book_root_dir_path]
+        .into_iter()
+        .flatten()
+        .map(|d| d.join(CONFIG_FILE_NAME))
+        .find(|p| p.is_file());
+    debug!("Configuration file: {found:?}");
+    Ok(found)
+}
+
+/// Read the configuration values from the environment variables
+/// e.g. `MARKDOWN_DIR_PATH`.
+pub(crate) fn env_values() -> Result<ConfigValues> {
+    env_values_from(std::env::vars())
+}
+
+/// Read the configuration values from (environment) variables,
+/// ignoring the variables that are empty or not configuration keys.
+fn env_values_from<I: IntoIterator<Item = (String, String)>>(vars: I) -> Result<ConfigValues> {
+    let vars = vars
+        .into_iter()
+        .filter(|(k, v)| !v.is_empty() && KEYS.iter().any(|key| key.to_uppercase() == *k));
+    envy::from_iter(vars).context("[env_values] Invalid environment variable")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config_values() -> Result<()> {
+        let values = parse_config_values(
+            r#"
+markdown_dir_path = "src"
+base_url = "https://example.com/book/"
+
+[refdefs]
+kinds = ["normal", "dev"]
+pin_versions = true
+
+[feed]
+max_entries = 10
+
+[lint]
+code_dir_path = "../code"
+severity = { MU002 = "off", unused-refdef = "error" }
+
+[parser]
+preset = "mdbook"
+extensions = ["math"]
+"#,
+        )?;
+        assert_eq!(values.markdown_dir_path, Some(PathBuf::from("src")));
+        assert_eq!(values.refdefs.pin_versions, Some(true));
+        assert_eq!(values.feed.max_entries, Some(10));
+        assert_eq!(
+            values.lint.severity.as_ref().and_then(|s| s.get("MU002")),
+            Some(&"off".to_string())
+        );
+        assert_eq!(values.parser.preset.as_deref(), Some("mdbook"));
+        let values = values.resolve_paths(Path::new("/book"));
+        assert_eq!(values.markdown_dir_path, Some(PathBuf::from("/book/src")));
+        assert_eq!(
+            values.lint.code_dir_path,
+            Some(PathBuf::from("/book/../code"))
+        );
+
+        let err = parse_config_values("markdown_dir = \"src\"").unwrap_err();
+        assert!(err.to_string().contains("markdown_dir"));
+        let err = parse_config_values("[feed]\nmax_entries = \"ten\"").unwrap_err();
+        assert!(err.to_string().contains("max_entries"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_env_values_from() -> Result<()> {
+        let values = env_values_from([
+            ("MARKDOWN_DIR_PATH".to_string(), "./src/".to_string()),
+            ("BASE_URL".to_string(), "https://example.com/".to_string()),
+            ("PATH".to_string(), "/usr/bin".to_string()),
+            ("SITEMAP_EXCLUDE".to_string(), String::new()),
+        ])?;
+        assert_eq!(values.markdown_dir_path, Some(PathBuf::from("./src/")));
+        assert_eq!(values.base_url.as_deref(), Some("https://example.com/"));
+        assert_eq!(values.sitemap_exclude, None);
+        assert_eq!(values.refdefs, RefDefsConfig::default());
+        Ok(())
+    }
+}
@@ -1,28 +1,88 @@
+use std::path::Path;
+
 use dialoguer::Completion;
 
-pub(super) struct MyCompletion {
-    options: Vec<String>,
-}
+/// Completes the last component of a file system path with the names of
+/// the files and directories that start with it; if several match, with
+/// their longest common prefix. Directories get a trailing `/`.
+#[derive(Default)]
+pub(crate) struct PathCompletion;
 
-impl Default for MyCompletion {
-    fn default() -> Self {
-        Self { options: vec![] }
+impl Completion for PathCompletion {
+    fn get(&self, input: &str) -> Option<String> {
+        let (dir, prefix) = match input.rfind(['/', '\\']) {
+            Some(i) => input.split_at(i + 1),
+            None => ("", input),
+        };
+        let dir_path = if dir.is_empty() {
+            Path::new(".")
+        } else {
+            Path::new(dir)
+        };
+        let names: Vec<String> = std::fs::read_dir(dir_path)
+            .ok()?
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let name = entry.file_name().into_string().ok()?;
+                // Hidden files are completed only if asked for
+                if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.'))
+                {
+                    return None;
+                }
+                if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                    Some(name + "/")
+                } else {
+                    Some(name)
+                }
+            })
+            .collect();
+        let first = names.first()?;
+        let common_len = names
+            .iter()
+            .skip(1)
+            .fold(first.chars().count(), |len, name| {
+                first
+                    .chars()
+                    .zip(name.chars())
+                    .take(len)
+                    .take_while(|(a, b)| a == b)
+                    .count()
+            });
+        let common: String = first.chars().take(common_len).collect();
+        (common.len() > prefix.len()).then(|| format!("{dir}{common}"))
     }
 }
 
-impl Completion for MyCompletion {
-    /// Simple completion implementation based on substring
-    fn get(&self, input: &str) -> Option<String> {
-        let matches = self
-            .options
-            .iter()
-            .filter(|option| option.starts_with(input))
-            .collect::<Vec<_>>();
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
 
-        if matches.len() == 1 {
-            Some(matches[0].to_string())
-        } else {
-            None
-        }
+    use super::*;
+
+    #[test]
+    fn test_path_completion() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        std::fs::create_dir(dir.path().join("src"))?;
+        std::fs::write(dir.path().join("src").join("chapter_1.md"), "")?;
+        std::fs::write(dir.path().join("src").join("chapter_2.md"), "")?;
+        std::fs::write(dir.path().join(".env"), "")?;
+        let root = format!("{}/", dir.path().display());
+
+        let completion = PathCompletion;
+        assert_eq!(
+            completion.get(&format!("{root}s")),
+            Some(format!("{root}src/"))
+        );
+        assert_eq!(
+            completion.get(&format!("{root}src/c")),
+            Some(format!("{root}src/chapter_"))
+        );
+        assert_eq!(completion.get(&format!("{root}src/chapter_")), None);
+        assert_eq!(
+            completion.get(&format!("{root}.e")),
+            Some(format!("{root}.env"))
+        );
+        assert_eq!(completion.get(&format!("{root}x")), None);
+        Ok(())
     }
 }
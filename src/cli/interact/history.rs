@@ -1,31 +1,119 @@
-use dialoguer::BasicHistory;
+//! History of the values entered at the prompts, optionally persisted
+//! to a file so that it is available in the next sessions
+use std::collections::VecDeque;
+use std::path::PathBuf;
+
 use dialoguer::History;
+use tracing::warn;
+
+/// Maximum number of entries kept in the history
+const MAX_ENTRIES: usize = 500;
 
-pub(super) struct MyHistory {
-    history: BasicHistory,
+#[derive(Default)]
+pub(crate) struct MyHistory {
+    /// Most recent entry first
+    entries: VecDeque<String>,
+    /// File where the history is persisted, if any: one entry per line,
+    /// the most recent last
+    file_path: Option<PathBuf>,
 }
 
 impl MyHistory {
-    /// Creates a new history value
-    pub(super) fn new() -> Self {
+    /// Creates a new, in-memory history
+    pub(crate) fn new() -> Self {
         Self::default()
     }
-}
 
-impl Default for MyHistory {
-    fn default() -> Self {
+    /// Loads the history persisted in a file (if it exists), and saves it
+    /// there whenever an entry is added
+    pub(crate) fn load(file_path: PathBuf) -> Self {
+        let entries = match std::fs::read_to_string(&file_path) {
+            Ok(contents) => contents
+                .lines()
+                .rev()
+                .filter(|l| !l.trim().is_empty())
+                .take(MAX_ENTRIES)
+                .map(str::to_string)
+                .collect(),
+            Err(e) => {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    warn!("Could not read the history {}: {e}", file_path.display());
+                }
+                VecDeque::new()
+            }
+        };
         Self {
-            history: BasicHistory::new().no_duplicates(true),
+            entries,
+            file_path: Some(file_path),
         }
     }
+
+    /// Returns the entries, the most recent first
+    pub(crate) fn entries(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(String::as_str)
+    }
+
+    /// Write the history to its file, if any
+    fn save(&self) -> std::io::Result<()> {
+        let Some(file_path) = self.file_path.as_ref() else {
+            return Ok(());
+        };
+        if let Some(dir_path) = file_path.parent() {
+            std::fs::create_dir_all(dir_path)?;
+        }
+        let contents: String = self
+            .entries
+            .iter()
+            .rev()
+            .map(|e| e.clone() + "\n")
+            .collect();
+        std::fs::write(file_path, contents)
+    }
 }
 
 impl<T: ToString> History<T> for MyHistory {
     fn read(&self, pos: usize) -> Option<String> {
-        <BasicHistory as History<T>>::read(&self.history, pos)
+        self.entries.get(pos).cloned()
     }
 
     fn write(&mut self, val: &T) {
-        self.history.write(val);
+        // One entry per line in the file
+        let val = val.to_string().replace(['\n', '\r'], " ");
+        if val.trim().is_empty() {
+            return;
+        }
+        // No duplicates: the entry moves to the front
+        self.entries.retain(|e| *e != val);
+        self.entries.push_front(val);
+        self.entries.truncate(MAX_ENTRIES);
+        if let Err(e) = self.save() {
+            warn!("Could not save the history: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_history_persistence() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("data").join("history");
+        let mut history = MyHistory::load(file_path.clone());
+        for val in ["./src", "./book", "./src"] {
+            History::<&str>::write(&mut history, &val);
+        }
+        assert_eq!(std::fs::read_to_string(&file_path)?, "./book\n./src\n");
+
+        let history = MyHistory::load(file_path);
+        assert_eq!(history.entries().collect::<Vec<_>>(), ["./src", "./book"]);
+        assert_eq!(
+            History::<&str>::read(&history, 1),
+            Some("./book".to_string())
+        );
+        Ok(())
     }
 }
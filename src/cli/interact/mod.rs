@@ -1,52 +1,61 @@
+//! Prompts of the interactive mode: text input with history and path
+//! completion, and selection in a list
 mod completion;
 mod history;
 
 use std::fmt::Display;
 
 use anyhow::Result;
-use completion::*;
-use dialoguer::theme::ColorfulTheme;
+pub(crate) use completion::*;
 use dialoguer::Input;
 use dialoguer::Select;
-use history::*;
+use dialoguer::theme::ColorfulTheme;
+pub(crate) use history::*;
 
-pub(crate) fn input<S>(prompt: S) -> Result<String>
-where
-    S: Into<String>,
-{
+/// Print how to use the history and the completion of the text prompts
+pub(crate) fn print_input_help() {
     println!("Use the Up/Down arrows to scroll through history");
     println!("Use the Right arrow or Tab to complete your command");
     println!();
+}
 
-    let mut history = MyHistory::new();
-    let completion = MyCompletion::default();
-
-    Ok(Input::<String>::with_theme(&ColorfulTheme::default())
-            .with_prompt(prompt)
-            .history_with(&mut history)
-            .completion_with(&completion)
-            // .with_initial_text("".to_string())
-            // .validate_with(|input: &String| -> Result<(), &str> {
-            //     if input.contains('@') {
-            //         Ok(())
-            //     } else {
-            //         Err("This is not a mail address")
-            //     }
-            // })
-            .interact_text()?)
+/// Prompt for a text value, e.g. a path, with history and path
+/// completion.
+///
+/// default: value returned if the input is empty, if any; otherwise, an
+/// empty input is accepted only if `allow_empty` is set.
+pub(crate) fn input<S>(
+    prompt: S,
+    default: Option<String>,
+    allow_empty: bool,
+    history: &mut MyHistory,
+) -> Result<String>
+where
+    S: Into<String>,
+{
+    let completion = PathCompletion;
+    let theme = ColorfulTheme::default();
+    let mut input = Input::<String>::with_theme(&theme)
+        .with_prompt(prompt)
+        .history_with(history)
+        .completion_with(&completion)
+        .allow_empty(allow_empty);
+    if let Some(default) = default {
+        input = input.default(default);
+    }
+    Ok(input.interact_text()?)
 }
 
-pub(crate) fn select<S>(prompt: S, items: Vec<S>) -> Result<String>
+/// Prompt for one of the items of a list.
+///
+/// Returns its index, or `None` if the user pressed Esc or `q`.
+pub(crate) fn select<S>(prompt: &str, items: &[S]) -> Result<Option<usize>>
 where
-    S: Into<String> + Display,
+    S: Display,
 {
-    println!("Select one of the following commands:");
-    println!();
-    let selection = Select::with_theme(&ColorfulTheme::default())
+    Ok(Select::with_theme(&ColorfulTheme::default())
         .with_prompt(prompt)
-        .items(&items)
+        .items(items)
         .default(0)
-        .interact()?;
-
-    Ok(items[selection].to_string())
+        .interact_opt()?)
 }
@@ -0,0 +1,455 @@
+//! Interactive mode: pick a command from a menu, answer the prompts for
+//! its paths (with defaults from the configuration), preview what it
+//! will change, then run it.
+//!
+//! The values entered at the prompts and the commands that were run are
+//! kept in the user's data directory, for the next sessions.
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use anyhow::bail;
+use clap::Parser;
+use console::Term;
+use console::style;
+use dialoguer::Confirm;
+use dialoguer::theme::ColorfulTheme;
+
+use super::Cli;
+use super::GlobalOpts;
+use super::args::DestFileArgs;
+use super::args::MarkdownDirArgs;
+use super::args::UrlArgs;
+use super::config::Configuration;
+use super::interact;
+use super::interact::MyHistory;
+use super::lint;
+
+/// Number of previous commands offered by the menu
+const RECENT_COMMANDS: usize = 5;
+
+/// Commands offered by the menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    RefDefsWrite,
+    LinksWriteAll,
+    LinksWriteInline,
+    LinksDuplicate,
+    LinksBroken,
+    GenerateSummary,
+    Fmt,
+    Lint,
+    LintFix,
+    SiteMap,
+    Render,
+    Debug,
+}
+
+impl Action {
+    const ALL: [Action; 12] = [
+        Action::RefDefsWrite,
+        Action::LinksWriteAll,
+        Action::LinksWriteInline,
+        Action::LinksDuplicate,
+        Action::LinksBroken,
+        Action::GenerateSummary,
+        Action::Fmt,
+        Action::Lint,
+        Action::LintFix,
+        Action::SiteMap,
+        Action::Render,
+        Action::Debug,
+    ];
+
+    /// Label in the menu
+    fn label(self) -> &'static str {
+        match self {
+            Action::RefDefsWrite => {
+                "refdefs write: write the existing reference definitions to a file"
+            }
+            Action::LinksWriteAll => "links write-all: write all existing links to a file",
+            Action::LinksWriteInline => {
+                "links write-inline: write the inline / auto links to a file"
+            }
+            Action::LinksDuplicate => "links duplicate-links: write the duplicate links to a file",
+            Action::LinksBroken => "links broken-links: write the broken links to a file",
+            Action::GenerateSummary => {
+                "markdown generate-summary: generate a SUMMARY.md from the front matter"
+            }
+            Action::Fmt => "markdown fmt: format the Markdown files",
+            Action::Lint => "lint: run all checks",
+            Action::LintFix => "lint --fix: fix the findings that can be fixed automatically",
+            Action::SiteMap => "sitemap: generate a sitemap.xml file",
+            Action::Render => "render: render a chapter to HTML",
+            Action::Debug => "debug: write the parser events to a file",
+        }
+    }
+
+    /// Command and subcommand
+    fn command(self) -> &'static [&'static str] {
+        match self {
+            Action::RefDefsWrite => &["refdefs", "write"],
+            Action::LinksWriteAll => &["links", "write-all"],
+            Action::LinksWriteInline => &["links", "write-inline"],
+            Action::LinksDuplicate => &["links", "duplicate-links"],
+            Action::LinksBroken => &["links", "broken-links"],
+            Action::GenerateSummary => &["markdown", "generate-summary"],
+            Action::Fmt => &["markdown", "fmt"],
+            Action::Lint | Action::LintFix => &["lint"],
+            Action::SiteMap => &["sitemap"],
+            Action::Render => &["render"],
+            Action::Debug => &["debug"],
+        }
+    }
+
+    /// Name of the file that the command writes by default, in the
+    /// default destination directory
+    fn default_file_name(self) -> Option<&'static str> {
+        match self {
+            Action::RefDefsWrite => Some("existing_refs.md"),
+            Action::LinksWriteAll => Some("all_links.md"),
+            Action::LinksWriteInline => Some("inline_links.md"),
+            Action::LinksDuplicate => Some("duplicate_links.md"),
+            Action::LinksBroken => Some("broken_links.md"),
+            Action::GenerateSummary => Some("SUMMARY.md"),
+            Action::Debug => Some("debug.log"),
+            _ => None,
+        }
+    }
+}
+
+/// What a command will change, shown before it runs
+#[derive(Debug)]
+enum Preview {
+    /// Writes a file
+    WritesFile(PathBuf),
+    /// Formats the Markdown files of a source directory
+    Format(PathBuf),
+    /// Fixes the lint findings of a source directory
+    LintFixes(PathBuf),
+    /// Does not modify any file
+    ReadOnly,
+}
+
+/// Item of the menu
+enum MenuItem {
+    Action(Action),
+    /// Command line run in a previous session
+    Repeat(String),
+    Quit,
+}
+
+/// Run the interactive mode, until the user quits
+pub(crate) fn run(config: Configuration) -> Result<()> {
+    if !Term::stdout().is_term() {
+        bail!("[interactive] The interactive mode requires a terminal.");
+    }
+    let data_dir_path = dirs::data_dir().map(|d| d.join("mdbook-utils"));
+    let mut inputs = data_dir_path
+        .as_ref()
+        .map_or_else(MyHistory::new, |d| MyHistory::load(d.join("input_history")));
+    let mut commands = data_dir_path.as_ref().map_or_else(MyHistory::new, |d| {
+        MyHistory::load(d.join("command_history"))
+    });
+
+    interact::print_input_help();
+    loop {
+        let mut items: Vec<MenuItem> = Action::ALL.into_iter().map(MenuItem::Action).collect();
+        items.extend(
+            commands
+                .entries()
+                .take(RECENT_COMMANDS)
+                .map(|c| MenuItem::Repeat(c.to_string())),
+        );
+        items.push(MenuItem::Quit);
+        let labels: Vec<String> = items
+            .iter()
+            .map(|item| match item {
+                MenuItem::Action(action) => action.label().to_string(),
+                MenuItem::Repeat(command_line) => format!("repeat: {command_line}"),
+                MenuItem::Quit => "quit".to_string(),
+            })
+            .collect();
+
+        println!();
+        let Some(i) = interact::select("Select a command (Esc to quit)", &labels)? else {
+            break;
+        };
+        let (args, preview, yes) = match &items[i] {
+            MenuItem::Quit => break,
+            MenuItem::Repeat(command_line) => {
+                let Some(args) = split_command_line(command_line) else {
+                    println!("{}", style("Invalid command line.").red());
+                    continue;
+                };
+                // The command asks for confirmation itself, if needed
+                (args, None, false)
+            }
+            MenuItem::Action(action) => {
+                let (args, preview) = prompt_for(*action, &config, &mut inputs)?;
+                (args, Some(preview), true)
+            }
+        };
+
+        let command_line = join_command_line(&args);
+        println!("{} mdbook-utils {command_line}", style("Command:").bold());
+        if let Some(preview) = preview {
+            match show_preview(&preview, &config) {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(e) => {
+                    println!("{}", style(format!("Error: {e:#}")).red());
+                    continue;
+                }
+            }
+        }
+        let confirmation = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt("Run this command?")
+            .default(true)
+            .interact()?;
+        if !confirmation {
+            println!("{}", style("Cancelled.").yellow());
+            continue;
+        }
+        dialoguer::History::write(&mut commands, &command_line);
+        // A failing command (e.g. lint errors) does not end the session
+        if let Err(e) = run_args(&args, config.global_opts(), yes) {
+            println!("{}", style(format!("Error: {e:#}")).red());
+        }
+    }
+    Ok(())
+}
+
+/// Prompt for the paths (and URL) of a command, with defaults from the
+/// configuration.
+///
+/// Returns the arguments of the command and what it will change.
+fn prompt_for(
+    action: Action,
+    config: &Configuration,
+    inputs: &mut MyHistory,
+) -> Result<(Vec<String>, Preview)> {
+    let mut args: Vec<String> = action.command().iter().map(|s| s.to_string()).collect();
+
+    let default_src = config
+        .markdown_src_dir_path(
+            MarkdownDirArgs {
+                markdown_dir_path: None,
+            },
+            "./src/",
+        )
+        .map_or_else(|_| "./src/".to_string(), |p| display_path(&p));
+    let src = interact::input(
+        "Markdown source directory",
+        Some(default_src),
+        false,
+        inputs,
+    )?;
+    let src_path = PathBuf::from(&src);
+    if action == Action::Render {
+        let chapter = interact::input("Chapter (Markdown file)", None, false, inputs)?;
+        args.push(chapter);
+    }
+    args.extend(["-m".to_string(), src]);
+
+    let preview = match action {
+        Action::Fmt => Preview::Format(src_path),
+        Action::Lint => Preview::ReadOnly,
+        Action::LintFix => {
+            args.push("--fix".to_string());
+            Preview::LintFixes(src_path)
+        }
+        Action::SiteMap => {
+            let default_url = config
+                .base_url(UrlArgs { url: None })
+                .map(|u| u.to_string())
+                .ok();
+            let url = interact::input("Base URL of the book", default_url, false, inputs)?;
+            let default_dest = config.sitemap_file_path(DestFileArgs { file_path: None });
+            let dest = prompt_dest(Some(&default_dest), inputs)?;
+            args.extend(["-b".to_string(), url, "-o".to_string(), dest.clone()]);
+            Preview::WritesFile(dest.into())
+        }
+        Action::Render => {
+            // An empty answer prints the HTML
+            let dest = prompt_dest(None, inputs)?;
+            if dest.is_empty() {
+                Preview::ReadOnly
+            } else {
+                args.extend(["-o".to_string(), dest.clone()]);
+                Preview::WritesFile(dest.into())
+            }
+        }
+        _ => {
+            let default_dest = action
+                .default_file_name()
+                .map(|f| config.dest_file_path(DestFileArgs { file_path: None }, f));
+            let dest = prompt_dest(default_dest.as_deref(), inputs)?;
+            args.extend(["-o".to_string(), dest.clone()]);
+            Preview::WritesFile(dest.into())
+        }
+    };
+    Ok((args, preview))
+}
+
+/// Prompt for the destination file. Without default, the answer may be
+/// empty.
+fn prompt_dest(default: Option<&Path>, inputs: &mut MyHistory) -> Result<String> {
+    match default {
+        Some(default) => interact::input(
+            "Destination file",
+            Some(display_path(default)),
+            false,
+            inputs,
+        ),
+        None => interact::input(
+            "Destination file (empty: standard output)",
+            None,
+            true,
+            inputs,
+        ),
+    }
+}
+
+/// Show what a command will change.
+///
+/// Returns `false` if there is nothing to do.
+fn show_preview(preview: &Preview, config: &Configuration) -> Result<bool> {
+    match preview {
+        Preview::WritesFile(path) => {
+            let what = if path.exists() { "overwrite" } else { "create" };
+            println!("This command will {what} {}.", style(path.display()).cyan());
+        }
+        Preview::Format(src) => {
+            let options = mdbook_utils::FormatOptions {
+                parser: config.parser_options()?,
+                ..Default::default()
+            };
+            let changes = mdbook_utils::format_markdown(src, &options)?;
+            if changes.is_empty() {
+                println!("{}", style("All files are formatted.").green());
+                return Ok(false);
+            }
+            lint::print_diff(&changes.diff());
+            println!(
+                "This command will make {} edit(s) in {} file(s).",
+                changes.len(),
+                changes.files.len()
+            );
+        }
+        Preview::LintFixes(src) => {
+            let options = config.lint_options(None, Vec::new())?;
+            let fixes = mdbook_utils::lint(src, &options)?.fixes;
+            if fixes.is_empty() {
+                println!("{}", style("Nothing to fix.").green());
+                return Ok(false);
+            }
+            lint::print_diff(&fixes.diff());
+            println!(
+                "This command will make {} edit(s) in {} file(s).",
+                fixes.len(),
+                fixes.files.len()
+            );
+        }
+        Preview::ReadOnly => println!("This command does not modify any file."),
+    }
+    Ok(true)
+}
+
+/// Parse the arguments of a command, as on the command line, and run
+/// it with the global options of the session.
+///
+/// yes: do not ask for confirmation again, since the changes were
+/// previewed.
+fn run_args(args: &[String], global_opts: &GlobalOpts, yes: bool) -> Result<()> {
+    let cli = Cli::try_parse_from(
+        std::iter::once("mdbook-utils").chain(args.iter().map(String::as_str)),
+    )?;
+    let global_opts = GlobalOpts {
+        yes: yes || global_opts.yes,
+        ..global_opts.clone()
+    };
+    crate::run_command(cli.command, global_opts)
+}
+
+/// Returns a path relative to the current directory, if possible
+fn display_path(path: &Path) -> String {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let path = path.strip_prefix(&cwd).unwrap_or(path);
+    match path.to_string_lossy() {
+        p if p.is_empty() => ".".to_string(),
+        p => p.into_owned(),
+    }
+}
+
+/// Join arguments into a command line, quoting those with spaces or
+/// quotes, as a POSIX shell would read them
+fn join_command_line(args: &[String]) -> String {
+    args.iter()
+        .map(|a| {
+            if !a.is_empty() && !a.contains(|c: char| c.is_whitespace() || c == '\'' || c == '"') {
+                a.clone()
+            } else {
+                format!("'{}'", a.replace('\'', r"'\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Split a command line created by [`join_command_line`] into
+/// arguments.
+///
+/// Returns `None` if a quote is not closed.
+fn split_command_line(command_line: &str) -> Option<Vec<String>> {
+    let mut args = Vec::new();
+    let mut arg: Option<String> = None;
+    let mut chars = command_line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                let arg = arg.get_or_insert_with(String::new);
+                loop {
+                    match chars.next()? {
+                        '\'' => break,
+                        c => arg.push(c),
+                    }
+                }
+            }
+            '\\' => arg.get_or_insert_with(String::new).push(chars.next()?),
+            c if c.is_whitespace() => args.extend(arg.take()),
+            c => arg.get_or_insert_with(String::new).push(c),
+        }
+    }
+    args.extend(arg);
+    Some(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_line() {
+        let args: Vec<String> = ["render", "my chapter.md", "-o", "it's.html", ""]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let command_line = join_command_line(&args);
+        assert_eq!(command_line, r"render 'my chapter.md' -o 'it'\''s.html' ''");
+        assert_eq!(split_command_line(&command_line), Some(args));
+        assert_eq!(split_command_line("lint 'src"), None);
+    }
+
+    #[test]
+    fn test_actions_parse() {
+        for action in Action::ALL {
+            let mut args: Vec<&str> = vec!["mdbook-utils"];
+            args.extend(action.command());
+            if action == Action::Render {
+                args.push("intro.md");
+            }
+            assert!(Cli::try_parse_from(args).is_ok(), "{action:?}");
+        }
+    }
+}
@@ -1,6 +1,9 @@
 //! Command-line subcommands to handle links
 #![allow(dead_code)]
 
+use std::fs::File;
+use std::path::Path;
+
 use anyhow::Context;
 use anyhow::Result;
 use clap::Subcommand;
@@ -38,8 +41,20 @@ pub(crate) fn run(subcmd: LinksSubCommand, config: Configuration) -> Result<()>
                 style(markdown_src_dir_path.display()).cyan(),
                 style(links_dest_path.display()).cyan()
             );
-            mdbook_utils::write_all_links(markdown_src_dir_path, links_dest_path)
+            if let Some(mut cache) = config.markdown_cache()? {
+                cache
+                    .write_all_links(markdown_src_dir_path, links_dest_path)
+                    .context("[run] Failed to write links to a file.")?;
+                print_cache_stats(&cache);
+            } else {
+                write_book_to(
+                    &config,
+                    markdown_src_dir_path,
+                    links_dest_path,
+                    mdbook_utils::Book::write_all_links,
+                )
                 .context("[run] Failed to write links to a file.")?;
+            }
             println!("{}", style("Done.").green());
         }
         LinksSubCommand::WriteInline(args) => {
@@ -50,8 +65,20 @@ pub(crate) fn run(subcmd: LinksSubCommand, config: Configuration) -> Result<()>
                 style(markdown_src_dir_path.display()).cyan(),
                 style(links_dest_path.display()).cyan()
             );
-            mdbook_utils::write_inline_links(markdown_src_dir_path, links_dest_path)
+            if let Some(mut cache) = config.markdown_cache()? {
+                cache
+                    .write_inline_links(markdown_src_dir_path, links_dest_path)
+                    .context("[run] Failed to write inline links to a file.")?;
+                print_cache_stats(&cache);
+            } else {
+                write_book_to(
+                    &config,
+                    markdown_src_dir_path,
+                    links_dest_path,
+                    mdbook_utils::Book::write_inline_links,
+                )
                 .context("[run] Failed to write inline links to a file.")?;
+            }
             println!("{}", style("Done.").green());
         }
         LinksSubCommand::DuplicateLinks(args) => {
@@ -62,8 +89,20 @@ pub(crate) fn run(subcmd: LinksSubCommand, config: Configuration) -> Result<()>
                 style(markdown_src_dir_path.display()).cyan(),
                 style(links_dest_path.display()).cyan()
             );
-            mdbook_utils::write_duplicate_links(markdown_src_dir_path, links_dest_path)
+            if let Some(mut cache) = config.markdown_cache()? {
+                cache
+                    .write_duplicate_links(markdown_src_dir_path, links_dest_path)
+                    .context("[run] Failed to write duplicate links to a file.")?;
+                print_cache_stats(&cache);
+            } else {
+                write_book_to(
+                    &config,
+                    markdown_src_dir_path,
+                    links_dest_path,
+                    mdbook_utils::Book::write_duplicate_links,
+                )
                 .context("[run] Failed to write duplicate links to a file.")?;
+            }
             println!("{}", style("Done.").green());
         }
         LinksSubCommand::BrokenLinks(args) => {
@@ -74,8 +113,20 @@ pub(crate) fn run(subcmd: LinksSubCommand, config: Configuration) -> Result<()>
                 style(markdown_src_dir_path.display()).cyan(),
                 style(links_dest_path.display()).cyan()
             );
-            mdbook_utils::write_broken_links(markdown_src_dir_path, links_dest_path)
+            if let Some(mut cache) = config.markdown_cache()? {
+                cache
+                    .write_broken_links(markdown_src_dir_path, links_dest_path)
+                    .context("[run] Failed to write broken links to a file.")?;
+                print_cache_stats(&cache);
+            } else {
+                write_book_to(
+                    &config,
+                    markdown_src_dir_path,
+                    links_dest_path,
+                    mdbook_utils::Book::write_broken_links,
+                )
                 .context("[run] Failed to write broken links to a file.")?;
+            }
             println!("{}", style("Done.").green());
         } /* _ => {
            *     println!("NOT IMPLEMENTED");
@@ -83,3 +134,33 @@ pub(crate) fn run(subcmd: LinksSubCommand, config: Configuration) -> Result<()>
     }
     Ok(())
 }
+
+/// Parse the Markdown files of a source directory with the parser
+/// options of the configuration, then create a file and write to it
+/// e.g. `write_book_to(config, src, dest, Book::write_all_links)`
+pub(crate) fn write_book_to<P1, P2, F>(
+    config: &Configuration,
+    src_dir_path: P1,
+    dest_file_path: P2,
+    func: F,
+) -> Result<()>
+where
+    P1: AsRef<Path>,
+    P2: AsRef<Path>,
+    F: FnOnce(&mdbook_utils::Book, &mut File) -> mdbook_utils::Result<()>,
+{
+    mdbook_utils::Book::load_with_options(src_dir_path, &config.parser_options()?)?
+        .write_to_file(dest_file_path, func)?;
+    Ok(())
+}
+
+/// Print how many Markdown files were parsed or read from the cache
+pub(crate) fn print_cache_stats(cache: &mdbook_utils::MarkdownCache) {
+    let stats = cache.stats();
+    println!(
+        "Parsed {} Markdown file(s), read {} from the cache in {}.",
+        stats.parsed,
+        stats.reused,
+        style(cache.cache_dir_path().display()).cyan()
+    );
+}
@@ -0,0 +1,124 @@
+//! `lint` command: run all checks in a single pass, report the findings
+//! with their rule IDs and, optionally, fix them
+
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::bail;
+use console::style;
+use dialoguer::Confirm;
+use dialoguer::theme::ColorfulTheme;
+
+use super::args::LintArgs;
+use super::config::Configuration;
+
+/// Lint the Markdown sources, print the findings and, with `--fix`, fix
+/// them.
+///
+/// Fails (non-zero exit code) if any finding has the `error` severity.
+pub(crate) fn run(args: LintArgs, config: Configuration) -> Result<()> {
+    let markdown_src_dir_path = config.markdown_src_dir_path(args.src, "./src/")?;
+    let options = config.lint_options(args.code_dir_path, args.severities)?;
+    println!(
+        "Linting the Markdown files in {}...",
+        style(markdown_src_dir_path.display()).cyan()
+    );
+    let mut report = mdbook_utils::lint(&markdown_src_dir_path, &options)?;
+
+    if args.fix && !report.fixes.is_empty() {
+        let fixes = &report.fixes;
+        print_diff(&fixes.diff());
+        let summary = format!("{} edit(s) in {} file(s)", fixes.len(), fixes.files.len());
+        if args.dry_run {
+            println!(
+                "{}",
+                style(format!("{summary} to apply (dry run).")).yellow()
+            );
+        } else {
+            let confirmation = config.skip_confirm()
+                || Confirm::with_theme(&ColorfulTheme::default())
+                    .with_prompt(
+                        "This command will modify your Markdown files. Do you want to continue?",
+                    )
+                    .default(false)
+                    .interact()
+                    .context("Failed to obtain user confirmation.")?;
+            if !confirmation {
+                println!("{}", style("Cancelled.").yellow());
+                return Ok(());
+            }
+            fixes.apply().context("[lint] Failed to apply the fixes.")?;
+            println!("{summary} applied.");
+            if fixes.skipped > 0 {
+                println!(
+                    "{}",
+                    style(format!(
+                        "{} overlapping edit(s) skipped; run `lint --fix` again to apply them.",
+                        fixes.skipped
+                    ))
+                    .yellow()
+                );
+            }
+            report = mdbook_utils::lint(&markdown_src_dir_path, &options)?;
+        }
+    }
+
+    let cwd = std::env::current_dir().unwrap_or_default();
+    for finding in &report.findings {
+        let path = finding
+            .file_path
+            .strip_prefix(&cwd)
+            .unwrap_or(&finding.file_path);
+        let location = match finding.line {
+            Some(line) => format!("{}:{line}", path.display()),
+            None => path.display().to_string(),
+        };
+        let severity = match finding.severity {
+            mdbook_utils::Severity::Error => style(finding.severity.to_string()).red().bold(),
+            _ => style(finding.severity.to_string()).yellow().bold(),
+        };
+        println!(
+            "{}: {severity}[{}]: {}",
+            style(location).cyan(),
+            finding.rule,
+            finding.message
+        );
+    }
+
+    let fixable = report.findings.iter().filter(|f| f.fixable).count();
+    if fixable > 0 && !args.fix {
+        println!("{fixable} finding(s) can be fixed with `--fix`.");
+    }
+    let summary = format!(
+        "{} error(s), {} warning(s), {} suppressed.",
+        report.errors(),
+        report.warnings(),
+        report.suppressed
+    );
+    if report.errors() > 0 {
+        bail!("[lint] {summary}");
+    }
+    if report.warnings() > 0 {
+        println!("{}", style(summary).yellow());
+    } else {
+        println!("{}", style(summary).green());
+    }
+    Ok(())
+}
+
+/// Print a unified diff, with the removed lines in red and the added
+/// lines in green
+pub(crate) fn print_diff(diff: &str) {
+    for line in diff.lines() {
+        if line.starts_with("---") || line.starts_with("+++") {
+            println!("{}", style(line).bold());
+        } else if line.starts_with("@@") {
+            println!("{}", style(line).cyan());
+        } else if line.starts_with('-') {
+            println!("{}", style(line).red());
+        } else if line.starts_with('+') {
+            println!("{}", style(line).green());
+        } else {
+            println!("{line}");
+        }
+    }
+}
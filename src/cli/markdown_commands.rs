@@ -1,6 +1,7 @@
 //! Command-line subcommands to manipulate Markdown
 use anyhow::Context;
 use anyhow::Result;
+use anyhow::bail;
 use clap::Subcommand;
 use console::style;
 use dialoguer::Confirm;
@@ -8,6 +9,7 @@
 
 use super::args::*;
 use super::config::Configuration;
+use super::lint;
 
 /// Command-line subcommands to manipulate Markdown
 #[derive(Subcommand, Debug)]
@@ -26,18 +28,42 @@ pub(crate) enum MarkdownSubCommand {
     /// (and replace them by a hard-coded string)
     RemoveIncludes(MarkdownDirArgs),
 
-    /// Generate a listing of crates.io dependencies
-    /// and write to a Markdown file
-    GenerateCategories(MarkdownSrcDirAndDestFileArgs),
+    /// Remove the YAML / TOML front matter from Markdown files, so that
+    /// the mdBook HTML renderer does not show it. Run on a copy of the
+    /// sources.
+    StripFrontMatter(MarkdownDirArgs),
+
+    /// Generate a SUMMARY.md from the Markdown files and their front
+    /// matter (title, weight, draft) and write to a Markdown file
+    GenerateSummary(MarkdownSrcDirAndDestFileArgs),
+
+    /// Generate a page of the crates.io categories covered by the book
+    /// (with their crates and backlinks to chapters) and write to a
+    /// Markdown file
+    GenerateCategories(GenerateCategoriesArgs),
+
+    /// Generate a crate index (a table of the crates mentioned in the
+    /// book, with their metadata and backlinks to chapters) and write
+    /// to a Markdown file
+    GenerateCrates(GenerateCratesArgs),
+    /// Format the Markdown files: link style, placement and order of
+    /// the reference definitions, code block fences and info strings
+    Fmt(FormatArgs),
 
-    /// Generate a crate index and write to a Markdown file
-    GenerateCrates(MarkdownSrcDirAndDestFileArgs),
-    // TODO autoreplace autolinks / inline links by ref links
     /// Identify .md files not in SUMMARY.md
     IdentifyFilesNotInSummary(MarkdownDirArgs),
 
     /// Identify .rs examples not used in Markdown files
     IdentifyUnusedRsExamples(MarkdownSrcDirAndDestDirArgs),
+
+    /// Move (or rename) a chapter: update SUMMARY.md and the links to
+    /// the chapter, and add a redirect from its old URL to `book.toml`
+    MoveChapter(MoveChapterArgs),
+
+    /// Check that the redirects of `book.toml` point to existing chapters
+    ///
+    /// Fails (non-zero exit code) if any redirect is broken.
+    CheckRedirects,
 }
 
 /// Process "markdown" subcommands of the command-line interface
@@ -138,27 +164,138 @@ pub(crate) fn run(subcmd: MarkdownSubCommand, config: Configuration) -> Result<(
                 println!("{}", style("Cancelled.").yellow());
             }
         }
+        MarkdownSubCommand::StripFrontMatter(args) => {
+            let markdown_dir_path = config.markdown_src_dir_path(args, "./src/")?;
+            println!(
+                "About to remove the front matter of the Markdown files in {}...",
+                style(markdown_dir_path.display()).cyan()
+            );
+            let confirmation = config.skip_confirm()
+                || Confirm::with_theme(&ColorfulTheme::default())
+                    .with_prompt(
+                        "This command will modify your Markdown files. Do you want to continue?",
+                    )
+                    .default(false)
+                    .interact()
+                    .context("Failed to obtain user confirmation.")?;
+            if confirmation {
+                let modified_files =
+                    mdbook_utils::markdown::strip_front_matter_in_all_markdown_files_in(
+                        markdown_dir_path,
+                    )
+                    .context("[run] Failed to remove the front matter.")?;
+                for f in modified_files.iter() {
+                    println!("Modified: {}", style(f.display()).cyan())
+                }
+                println!("{}", style("Done.").green());
+            } else {
+                println!("{}", style("Cancelled.").yellow());
+            }
+        }
+        MarkdownSubCommand::GenerateSummary(args) => {
+            let markdown_src_dir_path = config.markdown_src_dir_path(args.src, "./src/")?;
+            let summary_dest_path = config.dest_file_path(args.dest, "SUMMARY.md");
+            println!(
+                "Writing a SUMMARY.md to {} from Markdown sources in {}...",
+                style(summary_dest_path.display()).cyan(),
+                style(markdown_src_dir_path.display()).cyan()
+            );
+            mdbook_utils::generate_summary(
+                markdown_src_dir_path,
+                summary_dest_path,
+                &config.parser_options()?,
+            )
+            .context("[run] Failed to generate SUMMARY.md.")?;
+            println!("{}", style("Done.").green());
+        }
+        MarkdownSubCommand::Fmt(args) => {
+            let markdown_src_dir_path = config.markdown_src_dir_path(args.src, "./src/")?;
+            let options = mdbook_utils::FormatOptions {
+                link_style: args.link_style,
+                refdefs: args.refdefs,
+                sort_refdefs: !args.no_sort,
+                fence: args.fence,
+                info_strings: !args.keep_info_strings,
+                parser: config.parser_options()?,
+            };
+            println!(
+                "Formatting the Markdown files in {}...",
+                style(markdown_src_dir_path.display()).cyan()
+            );
+            let changes = mdbook_utils::format_markdown(&markdown_src_dir_path, &options)
+                .context("[run] Failed to format the Markdown files.")?;
+            if changes.is_empty() {
+                println!("{}", style("All files are formatted.").green());
+                return Ok(());
+            }
+            lint::print_diff(&changes.diff());
+            let summary = format!(
+                "{} edit(s) in {} file(s)",
+                changes.len(),
+                changes.files.len()
+            );
+            if args.check {
+                bail!("[run] {summary} needed: run `markdown fmt` to format the files.");
+            }
+            let confirmation = config.skip_confirm()
+                || Confirm::with_theme(&ColorfulTheme::default())
+                    .with_prompt(
+                        "This command will modify your Markdown files. Do you want to continue?",
+                    )
+                    .default(false)
+                    .interact()
+                    .context("Failed to obtain user confirmation.")?;
+            if confirmation {
+                changes
+                    .apply()
+                    .context("[run] Failed to write the formatted files.")?;
+                println!("{}", style(format!("{summary} applied.")).green());
+            } else {
+                println!("{}", style("Cancelled.").yellow());
+            }
+        }
         MarkdownSubCommand::GenerateCategories(args) => {
             let markdown_src_dir_path = config.markdown_src_dir_path(args.src, "./src/")?;
             let categories_dest_path = config.dest_file_path(args.dest, "categories.md");
+            let options = mdbook_utils::CategoryIndexOptions {
+                cargo_metadata_dir_path: config
+                    .cargo_toml_dir_path(args.manifest)
+                    .ok()
+                    .filter(|_| args.cargo_metadata),
+                db_dump_dir_path: config.crates_db_dump_dir_path(args.db_dump_dir_path),
+                mapping_path: args.mapping_path,
+                parser: config.parser_options()?,
+            };
             println!(
                 "Writing crates.io categories to {} from Markdown sources in {}...",
                 style(categories_dest_path.display()).cyan(),
                 style(markdown_src_dir_path.display()).cyan(),
             );
-            mdbook_utils::generate_categories(markdown_src_dir_path, categories_dest_path)
+            mdbook_utils::generate_categories(markdown_src_dir_path, categories_dest_path, options)
                 .context("[run] Failed to generate categories.")?;
             println!("{}", style("Done.").green());
         }
         MarkdownSubCommand::GenerateCrates(args) => {
             let markdown_src_dir_path = config.markdown_src_dir_path(args.src, "./src/")?;
             let crates_dest_path = config.dest_file_path(args.dest, "crates.md");
+            let cargo_toml_dir_path = config.cargo_toml_dir_path(args.manifest).ok();
+            let options = mdbook_utils::CrateIndexOptions {
+                cargo_lock_path: cargo_toml_dir_path
+                    .as_ref()
+                    .map(|d| d.join("Cargo.lock"))
+                    .filter(|p| p.is_file()),
+                cargo_metadata_dir_path: cargo_toml_dir_path.filter(|_| args.cargo_metadata),
+                db_dump_dir_path: config.crates_db_dump_dir_path(args.db_dump_dir_path),
+                sort: args.sort,
+                badges: !args.no_badges,
+                parser: config.parser_options()?,
+            };
             println!(
                 "Writing crate index to {} from Markdown sources in {}...",
                 style(crates_dest_path.display()).cyan(),
                 style(markdown_src_dir_path.display()).cyan()
             );
-            mdbook_utils::generate_crates(markdown_src_dir_path, crates_dest_path)
+            mdbook_utils::generate_crates(markdown_src_dir_path, crates_dest_path, options)
                 .context("[run] Failed to generate crate index.")?;
             println!("{}", style("Done.").green());
         }
@@ -198,6 +335,63 @@ pub(crate) fn run(subcmd: MarkdownSubCommand, config: Configuration) -> Result<(
                     println!("{}", f.display());
                 }
             }
+        }
+        MarkdownSubCommand::MoveChapter(args) => {
+            let book_root_dir_path = config.book_root_dir_path();
+            println!(
+                "About to move the chapter {} to {} in the book in {}, update the links to it and add a redirect to `book.toml`...",
+                style(&args.old).cyan(),
+                style(&args.new).cyan(),
+                style(book_root_dir_path.display()).cyan()
+            );
+            let confirmation = config.skip_confirm()
+                || Confirm::with_theme(&ColorfulTheme::default())
+                    .with_prompt(
+                        "This command will modify your Markdown files. Do you want to continue?",
+                    )
+                    .default(false)
+                    .interact()
+                    .context("Failed to obtain user confirmation.")?;
+            if confirmation {
+                let moved = mdbook_utils::move_chapter(
+                    book_root_dir_path,
+                    &args.old,
+                    &args.new,
+                    &config.parser_options()?,
+                )
+                .context("[run] Failed to move the chapter.")?;
+                println!("Moved to: {}", style(moved.new_file_path.display()).cyan());
+                for f in moved.modified_files.iter() {
+                    println!("Modified: {}", style(f.display()).cyan())
+                }
+                if let Some((from, to)) = moved.redirect {
+                    println!("Redirect: {} -> {}", style(from).cyan(), style(to).cyan());
+                }
+                println!("{}", style("Done.").green());
+            } else {
+                println!("{}", style("Cancelled.").yellow());
+            }
+        }
+        MarkdownSubCommand::CheckRedirects => {
+            let book_root_dir_path = config.book_root_dir_path();
+            println!(
+                "Checking the redirects of {}...",
+                style(book_root_dir_path.join("book.toml").display()).cyan(),
+            );
+            let broken = mdbook_utils::check_redirects(book_root_dir_path)
+                .context("[run] Failed to check the redirects.")?;
+            if broken.is_empty() {
+                println!(
+                    "{}",
+                    style("All redirects point to existing chapters.").green()
+                );
+            } else {
+                println!("{}", style("Broken redirects:").yellow());
+                for b in broken.iter() {
+                    println!("{} -> {}: {}", b.from, b.to, b.reason);
+                }
+                bail!("[run] {} broken redirect(s) in book.toml.", broken.len());
+            }
         } /* _ => {
            *     println!("NOT IMPLEMENTED");
            * } */
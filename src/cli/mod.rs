@@ -10,14 +10,21 @@
 mod args;
 mod book_toml;
 pub(crate) mod config;
+pub(crate) mod config_commands;
+mod config_file;
+mod interact;
+pub(crate) mod interactive;
 pub(crate) mod links_commands;
+pub(crate) mod lint;
 pub(crate) mod markdown_commands;
 pub(crate) mod refdefs_commands;
+pub(crate) mod watch;
 
 use args::*;
 use clap::Args;
 use clap::Parser;
 use clap::Subcommand;
+use config_commands::ConfigSubCommand;
 use links_commands::LinksSubCommand;
 use markdown_commands::MarkdownSubCommand;
 use refdefs_commands::RefDefsSubCommand;
@@ -56,14 +63,40 @@ pub(crate) enum Command {
     #[command(subcommand)]
     Markdown(MarkdownSubCommand),
 
-    /// Generate a sitemap.xml file from the list of Markdown files
-    /// in a source directory
+    /// Generate a sitemap.xml file (and a robots.txt file)
+    /// from the chapters listed in SUMMARY.md
     #[command(name = "sitemap")]
     SiteMap(MarkdownSrcDirUrlAndDestFileArgs),
 
-    /// Parse the entire Markdown code as events
-    /// and write them to a file.
-    Debug(MarkdownSrcDirAndDestFileArgs),
+    /// Generate an Atom or RSS feed of the most recently updated
+    /// chapters (and, optionally, a JSON search index)
+    Feed(FeedArgs),
+
+    /// Run all checks (undefined references, duplicate links, files not
+    /// in SUMMARY.md, unused examples and reference definitions, links to
+    /// missing files...) in a single pass
+    Lint(LintArgs),
+
+    /// Watch the Markdown sources, the code examples, Cargo.toml and
+    /// Cargo.lock, and re-run selected commands when they change
+    Watch(WatchArgs),
+
+    /// Show the configuration (`mdbook-utils.toml`, `book.toml`,
+    /// environment variables)
+    #[command(subcommand)]
+    Config(ConfigSubCommand),
+
+    /// Pick a command from a menu, answer prompts for its paths, preview
+    /// its changes and run it
+    Interactive,
+
+    /// Render a chapter to HTML (after expanding its includes and
+    /// resolving its references), optionally as a standalone page
+    Render(RenderArgs),
+
+    /// Parse the Markdown files (or a single file or glob) as events
+    /// and write them to a file, as text, an indented tree or JSON.
+    Debug(DebugArgs),
 
     /// Test Markdown parsing
     #[allow(dead_code)]
@@ -72,7 +105,7 @@ pub(crate) enum Command {
 }
 
 /// Global options that apply to all (sub)commands
-#[derive(Debug, Args, Default)]
+#[derive(Debug, Args, Clone, Default)]
 pub(crate) struct GlobalOpts {
     /// Automatically answer `yes` to any user confirmation request.
     #[clap(long, short = 'y', global = true)]
@@ -81,6 +114,26 @@ pub(crate) struct GlobalOpts {
     /// Set the logging level (error, warn, info, debug, trace).
     #[clap(long, global = true, env = "RUST_LOG")]
     pub(crate) log: Option<String>,
+
+    /// Path to the configuration file (default: `mdbook-utils.toml` in the
+    /// current directory or in the book's root directory).
+    #[clap(long, global = true, value_name = "FILE", env = "MDBOOK_UTILS_CONFIG", value_hint = clap::ValueHint::FilePath)]
+    pub(crate) config: Option<std::path::PathBuf>,
+
+    /// Do not use the cache directory set by `cache_dir_path`
+    /// (`CACHE_DIR_PATH`), if any: parse all Markdown files.
+    #[clap(long, global = true)]
+    pub(crate) no_cache: bool,
+
+    /// Markdown dialect: `default` (strikethrough, footnotes, tables),
+    /// `mdbook` (mdBook's extensions), `commonmark` or `all`.
+    #[clap(long, global = true, value_name = "PRESET")]
+    pub(crate) markdown_preset: Option<mdbook_utils::MarkdownPreset>,
+
+    /// Markdown extension(s) to enable in addition to those of the
+    /// preset e.g. `math,gfm,definition-list`.
+    #[clap(long, global = true, value_name = "EXTENSION", value_delimiter = ',')]
+    pub(crate) markdown_extension: Vec<mdbook_utils::MarkdownExtension>,
 }
 
 #[cfg(test)]
@@ -3,6 +3,8 @@
 use anyhow::Result;
 use clap::Subcommand;
 use console::style;
+use dialoguer::Confirm;
+use dialoguer::theme::ColorfulTheme;
 
 use super::args::*;
 use super::config::Configuration;
@@ -18,6 +20,14 @@ pub(crate) enum RefDefsSubCommand {
     /// examples)
     #[command(name = "generate")]
     Generate(GenerateArgs),
+
+    /// Compare the crates mentioned in the book (links and code) with the
+    /// dependencies declared in Cargo.toml, and write a report
+    AuditDependencies(AuditDependenciesArgs),
+
+    /// Rewrite the pinned docs.rs and crates.io URLs whose version is no
+    /// longer locked in Cargo.lock
+    UpgradeVersions(UpgradeVersionsArgs),
 }
 
 /// Arguments for generating reference definitions
@@ -47,8 +57,20 @@ pub(crate) fn run(subcmd: RefDefsSubCommand, config: Configuration) -> Result<()
                 style(markdown_src_dir_path.display()).cyan(),
                 style(refdef_dest_path.display()).cyan()
             );
-            mdbook_utils::write_refdefs_to(markdown_src_dir_path, refdef_dest_path)
+            if let Some(mut cache) = config.markdown_cache()? {
+                cache
+                    .write_refdefs_to(markdown_src_dir_path, refdef_dest_path)
+                    .context("[run] Failed to write reference definitions to a file.")?;
+                super::links_commands::print_cache_stats(&cache);
+            } else {
+                super::links_commands::write_book_to(
+                    &config,
+                    markdown_src_dir_path,
+                    refdef_dest_path,
+                    mdbook_utils::Book::write_refdefs,
+                )
                 .context("[run] Failed to write reference definitions to a file.")?;
+            }
             println!("{}", style("Done.").green());
         }
         RefDefsSubCommand::Generate(args) => {
@@ -73,8 +95,13 @@ pub(crate) fn run(subcmd: RefDefsSubCommand, config: Configuration) -> Result<()
                     style(markdown_src_dir_path.display()).cyan(),
                     style(refdef_dest_path.display()).cyan()
                 );
-                mdbook_utils::generate_badges(markdown_src_dir_path, refdef_dest_path)
-                    .context("[run] Failed to generate badges.")?;
+                super::links_commands::write_book_to(
+                    &config,
+                    markdown_src_dir_path,
+                    refdef_dest_path,
+                    mdbook_utils::Book::write_badges,
+                )
+                .context("[run] Failed to generate badges.")?;
                 println!("{}", style("Done.").green());
             }
 
@@ -90,16 +117,112 @@ pub(crate) fn run(subcmd: RefDefsSubCommand, config: Configuration) -> Result<()
                     style(cargo_toml_dir_path.display()).cyan(),
                     style(markdown_src_dir_path.display()).cyan(),
                 );
+                let templates = config
+                    .refdef_templates_path(args.args.templates)
+                    .map(mdbook_utils::read_refdef_templates)
+                    .transpose()
+                    .context("[run] Failed to read the reference definition templates.")?;
                 mdbook_utils::generate_refdefs_to(
                     cargo_toml_dir_path,
                     markdown_src_dir_path,
                     refdef_dest_file_path,
-                    args.args.log_file.as_ref(),
+                    mdbook_utils::DependencyOptions {
+                        kinds: config.dependency_kinds(args.args.kinds)?,
+                        cargo_metadata: config.cargo_metadata(args.args.cargo_metadata),
+                        pin_versions: config.pin_versions(args.args.pin_versions),
+                        templates,
+                        log_file_path: args.args.log_file,
+                        parser: config.parser_options()?,
+                    },
                 )
                 .context("[run] Failed to generate reference definitions from dependencies.")?;
                 println!("{}", style("Done.").green());
             }
         }
+        RefDefsSubCommand::AuditDependencies(args) => {
+            let markdown_src_dir_path = config.markdown_src_dir_path(args.src, "./src/")?;
+            let cargo_toml_dir_path = config.cargo_toml_dir_path(args.manifest)?;
+            let report_dest_path = config.dest_file_path(args.dest, "dependency_audit.md");
+            println!(
+                "Comparing the crates mentioned in {} with the dependencies declared in {} and writing the report to {}...",
+                style(markdown_src_dir_path.display()).cyan(),
+                style(cargo_toml_dir_path.display()).cyan(),
+                style(report_dest_path.display()).cyan(),
+            );
+            let report = mdbook_utils::audit_dependencies(
+                cargo_toml_dir_path,
+                markdown_src_dir_path,
+                report_dest_path,
+                &args.kinds,
+                &config.parser_options()?,
+            )
+            .context("[run] Failed to audit dependencies.")?;
+            if report.undeclared.is_empty()
+                && report.unused.is_empty()
+                && report.name_mismatches.is_empty()
+            {
+                println!("{}", style("No issues found.").green());
+            } else {
+                println!(
+                    "{}",
+                    style(format!(
+                        "{} undeclared crate(s), {} unused dependency(ies), {} crate name mismatch(es).",
+                        report.undeclared.len(),
+                        report.unused.len(),
+                        report.name_mismatches.len()
+                    ))
+                    .yellow()
+                );
+            }
+            println!("{}", style("Done.").green());
+        }
+        RefDefsSubCommand::UpgradeVersions(args) => {
+            let markdown_src_dir_path = config.markdown_src_dir_path(args.src, "./src/")?;
+            let cargo_toml_dir_path = config.cargo_toml_dir_path(args.manifest)?;
+            println!(
+                "About to upgrade the pinned docs.rs and crates.io URLs in {} to the versions locked in the Cargo.lock of {}...",
+                style(markdown_src_dir_path.display()).cyan(),
+                style(cargo_toml_dir_path.display()).cyan(),
+            );
+            let confirmation = args.dry_run
+                || config.skip_confirm()
+                || Confirm::with_theme(&ColorfulTheme::default())
+                    .with_prompt(
+                        "This command will modify your Markdown files. Do you want to continue?",
+                    )
+                    .default(false)
+                    .interact()
+                    .context("Failed to obtain user confirmation.")?;
+            if confirmation {
+                let upgrades = mdbook_utils::upgrade_versions(
+                    cargo_toml_dir_path,
+                    markdown_src_dir_path,
+                    args.dry_run,
+                )
+                .context("[run] Failed to upgrade pinned versions.")?;
+                for u in upgrades.iter() {
+                    println!(
+                        "{}:{}: {} {} -> {}",
+                        style(u.file_path.display()).cyan(),
+                        u.line,
+                        u.crate_name,
+                        style(&u.old_version).red(),
+                        style(&u.new_version).green(),
+                    );
+                }
+                if args.dry_run {
+                    println!(
+                        "{}",
+                        style(format!("{} URL(s) to upgrade (dry run).", upgrades.len())).yellow()
+                    );
+                } else {
+                    println!("{} URL(s) upgraded.", upgrades.len());
+                }
+                println!("{}", style("Done.").green());
+            } else {
+                println!("{}", style("Cancelled.").yellow());
+            }
+        }
     }
     Ok(())
 }
@@ -0,0 +1,474 @@
+//! `watch` command: re-run selected commands when the Markdown sources,
+//! the code examples, `Cargo.toml` or `Cargo.lock` change
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::mpsc;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use anyhow::Context;
+use anyhow::Result;
+use console::style;
+use notify_debouncer_mini::new_debouncer;
+use notify_debouncer_mini::notify::RecursiveMode;
+use tracing::debug;
+
+use super::args::*;
+use super::config::Configuration;
+
+/// Maximum number of added or removed findings printed per command
+const MAX_PRINTED_FINDINGS: usize = 20;
+
+/// A command re-run by `watch`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum WatchTask {
+    /// `refdefs generate --from-dependencies`
+    Refdefs,
+    /// `links broken-links`
+    BrokenLinks,
+    /// `markdown generate-crates`
+    Crates,
+    /// `refdefs audit-dependencies`
+    Audit,
+}
+
+impl WatchTask {
+    /// All commands, in the order in which they run
+    pub(crate) const ALL: [Self; 4] = [Self::Refdefs, Self::BrokenLinks, Self::Crates, Self::Audit];
+
+    /// Name of the file the command writes to the destination directory
+    fn file_name(self) -> &'static str {
+        match self {
+            Self::Refdefs => "dependencies_refs.md",
+            Self::BrokenLinks => "broken_links.md",
+            Self::Crates => "crates.md",
+            Self::Audit => "dependency_audit.md",
+        }
+    }
+
+    /// Returns `true` if the output of the command depends on a kind of
+    /// file
+    fn is_affected_by(self, change: Change) -> bool {
+        match self {
+            Self::Refdefs => matches!(
+                change,
+                Change::Markdown | Change::Manifest | Change::Lockfile
+            ),
+            Self::BrokenLinks => change == Change::Markdown,
+            Self::Crates => matches!(change, Change::Markdown | Change::Lockfile),
+            Self::Audit => matches!(change, Change::Markdown | Change::Code | Change::Manifest),
+        }
+    }
+}
+
+impl FromStr for WatchTask {
+    type Err = mdbook_utils::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "refdefs" => Ok(Self::Refdefs),
+            "broken-links" => Ok(Self::BrokenLinks),
+            "crates" => Ok(Self::Crates),
+            "audit" => Ok(Self::Audit),
+            _ => Err(mdbook_utils::Error::Config(format!(
+                "Invalid command: {s}. Expected `refdefs`, `broken-links`, `crates` or `audit`."
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for WatchTask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Refdefs => "refdefs",
+            Self::BrokenLinks => "broken-links",
+            Self::Crates => "crates",
+            Self::Audit => "audit",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// Kind of a modified file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Change {
+    /// Markdown file of the source directory
+    Markdown,
+    /// Rust code example
+    Code,
+    /// `Cargo.toml`
+    Manifest,
+    /// `Cargo.lock`
+    Lockfile,
+}
+
+/// Directories and files watched (or ignored) by `watch`
+struct WatchedPaths {
+    markdown_dir_path: PathBuf,
+    cargo_toml_dir_path: PathBuf,
+    /// Files written by the commands, whose changes are ignored
+    output_file_paths: BTreeSet<PathBuf>,
+    /// Last known modification time of the watched files. File system
+    /// notifications are also sent when files are merely read.
+    mtimes: HashMap<PathBuf, Option<SystemTime>>,
+}
+
+impl WatchedPaths {
+    /// Classify a modified file, or return `None` if it does not affect
+    /// any command (e.g. build artifacts or the commands' own outputs).
+    fn classify(&self, path: &Path) -> Option<Change> {
+        if self.output_file_paths.contains(path)
+            || path
+                .components()
+                .any(|c| c.as_os_str() == "target" || c.as_os_str() == ".git")
+        {
+            return None;
+        }
+        match path.file_name()?.to_str()? {
+            "Cargo.toml" => return Some(Change::Manifest),
+            "Cargo.lock" => return Some(Change::Lockfile),
+            _ => {}
+        }
+        match path.extension()?.to_str()? {
+            "md" if path.starts_with(&self.markdown_dir_path) => Some(Change::Markdown),
+            "rs" if path.starts_with(&self.cargo_toml_dir_path) => Some(Change::Code),
+            _ => None,
+        }
+    }
+
+    /// Record the modification time of the files of a directory.
+    fn snapshot(&mut self, dir_path: &Path, recursive: bool) {
+        let walker = walkdir::WalkDir::new(dir_path)
+            .max_depth(if recursive { usize::MAX } else { 1 })
+            .into_iter()
+            .filter_entry(|e| {
+                e.depth() == 0
+                    || !(e.file_name() == "target"
+                        || e.file_name().to_string_lossy().starts_with('.'))
+            });
+        for e in walker.flatten().filter(|e| e.file_type().is_file()) {
+            if self.classify(e.path()).is_some() {
+                self.has_changed(e.path());
+            }
+        }
+    }
+
+    /// Returns `true` if the modification time of a file differs from the
+    /// last one recorded (or the file was created or removed), and
+    /// records the new one.
+    fn has_changed(&mut self, path: &Path) -> bool {
+        let mtime = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        self.mtimes.insert(path.to_path_buf(), mtime) != Some(mtime)
+    }
+}
+
+/// Returns the findings that were added and removed between two runs
+fn diff_findings<'a>(
+    old: &'a BTreeSet<String>,
+    new: &'a BTreeSet<String>,
+) -> (Vec<&'a String>, Vec<&'a String>) {
+    (new.difference(old).collect(), old.difference(new).collect())
+}
+
+/// Watch the Markdown sources, the code examples, `Cargo.toml` and
+/// `Cargo.lock`, and re-run the commands affected by each (debounced)
+/// batch of changes. The findings of each command (the lines of the file
+/// it writes) are compared with those of its previous run.
+pub(crate) fn run(args: WatchArgs, config: Configuration) -> Result<()> {
+    let tasks: BTreeSet<WatchTask> = if args.tasks.is_empty() {
+        WatchTask::ALL.into_iter().collect()
+    } else {
+        args.tasks.iter().copied().collect()
+    };
+    let markdown_dir_path = config.markdown_src_dir_path(args.src, "./src/")?;
+    let cargo_toml_dir_path = config.cargo_toml_dir_path(args.manifest)?;
+    let dest_dir_path = config.dest_dir_path(args.dest);
+    std::fs::create_dir_all(&dest_dir_path).with_context(|| {
+        format!(
+            "[watch] Could not create the destination directory {}",
+            dest_dir_path.display()
+        )
+    })?;
+    let dest_dir_path = dest_dir_path
+        .canonicalize()
+        .context("[watch] Could not resolve the destination directory.")?;
+    let mut paths = WatchedPaths {
+        output_file_paths: WatchTask::ALL
+            .iter()
+            .map(|t| dest_dir_path.join(t.file_name()))
+            .collect(),
+        markdown_dir_path,
+        cargo_toml_dir_path,
+        mtimes: HashMap::new(),
+    };
+
+    let (tx, rx) = mpsc::channel();
+    let mut debouncer = new_debouncer(Duration::from_millis(args.debounce), tx)
+        .context("[watch] Could not create the file watcher.")?;
+    let mut watched_dir_paths = vec![
+        (paths.markdown_dir_path.clone(), RecursiveMode::Recursive),
+        (paths.cargo_toml_dir_path.clone(), RecursiveMode::Recursive),
+    ];
+    // `Cargo.lock` may be in a parent directory e.g. a workspace root
+    if let Some(cargo_lock_dir_path) = paths
+        .cargo_toml_dir_path
+        .ancestors()
+        .skip(1)
+        .find(|d| d.join("Cargo.lock").is_file())
+    {
+        watched_dir_paths.push((
+            cargo_lock_dir_path.to_path_buf(),
+            RecursiveMode::NonRecursive,
+        ));
+    }
+    for (p, mode) in watched_dir_paths {
+        debug!("Watching {}", p.display());
+        paths.snapshot(&p, mode == RecursiveMode::Recursive);
+        debouncer
+            .watcher()
+            .watch(&p, mode)
+            .with_context(|| format!("[watch] Could not watch {}", p.display()))?;
+    }
+    println!(
+        "Watching {} and {}; running {} on changes (press Ctrl-C to stop)...",
+        style(paths.markdown_dir_path.display()).cyan(),
+        style(paths.cargo_toml_dir_path.display()).cyan(),
+        style(
+            tasks
+                .iter()
+                .map(WatchTask::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+        .cyan(),
+    );
+
+    let mut findings: BTreeMap<WatchTask, BTreeSet<String>> = BTreeMap::new();
+    let mut to_run = tasks.clone();
+    loop {
+        for task in to_run.iter() {
+            let dest_file_path = dest_dir_path.join(task.file_name());
+            match run_task(*task, &paths, &dest_file_path, &config) {
+                Ok(new) => {
+                    print_findings(*task, findings.get(task), &new, &dest_file_path);
+                    findings.insert(*task, new);
+                }
+                Err(e) => println!(
+                    "{} {}",
+                    style(format!("[{task}]")).bold(),
+                    style(format!("{e:#}")).red()
+                ),
+            }
+        }
+
+        // Wait for the next batch of changes
+        to_run = BTreeSet::new();
+        while to_run.is_empty() {
+            let events = match rx.recv().context("[watch] The file watcher stopped.")? {
+                Ok(events) => events,
+                Err(e) => {
+                    println!("{}", style(format!("Watch error: {e}")).red());
+                    continue;
+                }
+            };
+            let changes: Vec<Change> = events
+                .iter()
+                .filter_map(|e| {
+                    let change = paths.classify(&e.path)?;
+                    paths.has_changed(&e.path).then_some(change)
+                })
+                .collect();
+            debug!("Changes: {changes:?}");
+            to_run = tasks
+                .iter()
+                .copied()
+                .filter(|t| changes.iter().any(|c| t.is_affected_by(*c)))
+                .collect();
+        }
+    }
+}
+
+/// Run a command and return its findings: the non-empty lines of the
+/// file it writes.
+fn run_task(
+    task: WatchTask,
+    paths: &WatchedPaths,
+    dest_file_path: &Path,
+    config: &Configuration,
+) -> Result<BTreeSet<String>> {
+    match task {
+        WatchTask::Refdefs => {
+            let templates = config
+                .refdef_templates_path(None)
+                .map(mdbook_utils::read_refdef_templates)
+                .transpose()?;
+            mdbook_utils::generate_refdefs_to(
+                &paths.cargo_toml_dir_path,
+                &paths.markdown_dir_path,
+                dest_file_path,
+                mdbook_utils::DependencyOptions {
+                    kinds: config.dependency_kinds(Vec::new())?,
+                    cargo_metadata: config.cargo_metadata(false),
+                    pin_versions: config.pin_versions(false),
+                    templates,
+                    log_file_path: None,
+                    parser: config.parser_options()?,
+                },
+            )?;
+        }
+        WatchTask::BrokenLinks => {
+            if let Some(mut cache) = config.markdown_cache()? {
+                cache.write_broken_links(&paths.markdown_dir_path, dest_file_path)?;
+            } else {
+                super::links_commands::write_book_to(
+                    config,
+                    &paths.markdown_dir_path,
+                    dest_file_path,
+                    mdbook_utils::Book::write_broken_links,
+                )?;
+            }
+        }
+        WatchTask::Crates => {
+            let options = mdbook_utils::CrateIndexOptions {
+                cargo_lock_path: paths
+                    .cargo_toml_dir_path
+                    .ancestors()
+                    .map(|d| d.join("Cargo.lock"))
+                    .find(|p| p.is_file()),
+                db_dump_dir_path: config.crates_db_dump_dir_path(None),
+                parser: config.parser_options()?,
+                ..Default::default()
+            };
+            mdbook_utils::generate_crates(&paths.markdown_dir_path, dest_file_path, options)?;
+        }
+        WatchTask::Audit => {
+            mdbook_utils::audit_dependencies(
+                &paths.cargo_toml_dir_path,
+                &paths.markdown_dir_path,
+                dest_file_path,
+                &[
+                    mdbook_utils::DependencyKind::Normal,
+                    mdbook_utils::DependencyKind::Dev,
+                    mdbook_utils::DependencyKind::Build,
+                ],
+                &config.parser_options()?,
+            )?;
+        }
+    }
+    let contents = std::fs::read_to_string(dest_file_path)
+        .with_context(|| format!("[watch] Could not read {}", dest_file_path.display()))?;
+    Ok(contents
+        .lines()
+        .map(str::trim_end)
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// Print the findings of a command: their number on the first run, the
+/// added (`+`) and removed (`-`) findings afterwards.
+fn print_findings(
+    task: WatchTask,
+    old: Option<&BTreeSet<String>>,
+    new: &BTreeSet<String>,
+    dest_file_path: &Path,
+) {
+    let header = style(format!("[{task}]")).bold();
+    let Some(old) = old else {
+        println!(
+            "{header} {} finding(s) in {}",
+            new.len(),
+            style(dest_file_path.display()).cyan()
+        );
+        return;
+    };
+    let (added, removed) = diff_findings(old, new);
+    if added.is_empty() && removed.is_empty() {
+        println!("{header} no changes");
+        return;
+    }
+    println!("{header} +{} -{}", added.len(), removed.len());
+    for (sign, lines) in [("+", added), ("-", removed)] {
+        for l in lines.iter().take(MAX_PRINTED_FINDINGS) {
+            let line = format!("{sign} {l}");
+            if sign == "+" {
+                println!("  {}", style(line).green());
+            } else {
+                println!("  {}", style(line).red());
+            }
+        }
+        if lines.len() > MAX_PRINTED_FINDINGS {
+            println!("  ... and {} more", lines.len() - MAX_PRINTED_FINDINGS);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        let paths = WatchedPaths {
+            markdown_dir_path: "/book/src".into(),
+            cargo_toml_dir_path: "/book".into(),
+            output_file_paths: BTreeSet::from(["/book/src/crates.md".into()]),
+            mtimes: HashMap::new(),
+        };
+        let classify = |p: &str| paths.classify(Path::new(p));
+        assert_eq!(classify("/book/src/a/b.md"), Some(Change::Markdown));
+        assert_eq!(classify("/book/src/crates.md"), None);
+        assert_eq!(classify("/book/README.md"), None);
+        assert_eq!(classify("/book/code/main.rs"), Some(Change::Code));
+        assert_eq!(classify("/book/target/debug/build.rs"), None);
+        assert_eq!(classify("/book/Cargo.lock"), Some(Change::Lockfile));
+        assert_eq!(classify("/Cargo.toml"), Some(Change::Manifest));
+
+        assert!(WatchTask::Crates.is_affected_by(Change::Lockfile));
+        assert!(!WatchTask::BrokenLinks.is_affected_by(Change::Code));
+    }
+
+    #[test]
+    fn test_has_changed() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file_path = dir.path().join("a.md");
+        std::fs::write(&file_path, "# A")?;
+        let mut paths = WatchedPaths {
+            markdown_dir_path: dir.path().into(),
+            cargo_toml_dir_path: dir.path().into(),
+            output_file_paths: BTreeSet::new(),
+            mtimes: HashMap::new(),
+        };
+        paths.snapshot(dir.path(), true);
+        // Reading a file does not modify it
+        std::fs::read_to_string(&file_path)?;
+        assert!(!paths.has_changed(&file_path));
+        std::fs::remove_file(&file_path)?;
+        assert!(paths.has_changed(&file_path));
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_findings() {
+        let old = BTreeSet::from(["a".to_string(), "b".to_string()]);
+        let new = BTreeSet::from(["b".to_string(), "c".to_string()]);
+        let (added, removed) = diff_findings(&old, &new);
+        assert_eq!(added, vec!["c"]);
+        assert_eq!(removed, vec!["a"]);
+    }
+
+    #[test]
+    fn test_watch_task_from_str() {
+        assert_eq!(
+            "broken-links".parse::<WatchTask>().unwrap(),
+            WatchTask::BrokenLinks
+        );
+        assert!("links".parse::<WatchTask>().is_err());
+    }
+}
@@ -0,0 +1,566 @@
+//! Cross-check the crates mentioned in a book (in links and in Rust
+//! code) against the dependencies declared in `Cargo.toml`
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+use pulldown_cmark::CodeBlockKind;
+use pulldown_cmark::Event;
+use pulldown_cmark::Tag;
+use pulldown_cmark::TagEnd;
+use regex::Regex;
+use tracing::warn;
+
+use super::DeclaredDependency;
+use crate::Result;
+use crate::fs;
+use crate::generate::crate_name_from_url;
+use crate::parser;
+use crate::parser::ParserOptions;
+use crate::registry::normalize_crate_name;
+
+/// Crates referenced by `use foo::`, `use foo;`, `use foo as bar`,
+/// `extern crate foo` or attributes like `#[tokio::main]`
+static CRATE_USE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?m)(?:\buse\s+(?:::)?|\bextern\s+crate\s+|#\[)(?<name>[A-Za-z_][A-Za-z0-9_]*)(?:\s*::|\s*;|\s+as\b)",
+    )
+    .unwrap()
+});
+
+/// Crates referenced by a fully-qualified path, without `use` e.g.
+/// `serde_json::to_string(..)` or `::rand::random()`. The path must not
+/// follow another path segment, a `.` (method call) or a `$`.
+static CRATE_PATH_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:^|[^A-Za-z0-9_:.$])(?:::)?(?<name>[a-z_][a-z0-9_]*)::[A-Za-z_{*]").unwrap()
+});
+
+/// `use` statements e.g. `use std::{fs, io};`
+static USE_STATEMENT_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\buse\s+[^;]*;").unwrap());
+
+/// Identifiers
+static IDENT_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[A-Za-z_][A-Za-z0-9_]*").unwrap());
+
+/// Modules declared in a code example e.g. `mod foo`
+static MOD_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\bmod\s+(?<name>[A-Za-z_][A-Za-z0-9_]*)").unwrap());
+
+/// Start of a raw string literal e.g. `r#"`
+static RAW_STRING_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r##"^b?r(?<hashes>#*)""##).unwrap());
+
+/// Names that paths may start with, but are not crates
+const NOT_CRATES: &[&str] = &[
+    "std",
+    "core",
+    "alloc",
+    "proc_macro",
+    "test",
+    "crate",
+    "self",
+    "super",
+    "Self",
+    // Built-in attributes with paths
+    "rustfmt",
+    "clippy",
+    "diagnostic",
+    // Primitive types e.g. `u8::MAX`
+    "bool",
+    "char",
+    "str",
+    "i8",
+    "i16",
+    "i32",
+    "i64",
+    "i128",
+    "isize",
+    "u8",
+    "u16",
+    "u32",
+    "u64",
+    "u128",
+    "usize",
+    "f32",
+    "f64",
+];
+
+/// Report of the comparison between the crates mentioned in a book and
+/// the dependencies declared in `Cargo.toml`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DependencyAudit {
+    /// Crates used in the book (in links or code) but not declared, with
+    /// the files that mention them
+    pub undeclared: BTreeMap<String, BTreeSet<PathBuf>>,
+
+    /// Declared dependencies (package names) that the book never
+    /// mentions
+    pub unused: Vec<String>,
+
+    /// Reference definitions whose crate name differs from the package
+    /// name (e.g. `docs.rs/serde-json` vs. `serde_json`)
+    pub name_mismatches: Vec<CrateNameMismatch>,
+}
+
+/// A reference definition that points at a declared crate under a
+/// different spelling
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CrateNameMismatch {
+    /// Label of the reference definition (lowercased)
+    pub label: String,
+    /// URL of the reference definition
+    pub url: String,
+    /// Crate name, as written in the URL
+    pub name_in_url: String,
+    /// Package name, as declared in `Cargo.toml`
+    pub package: String,
+}
+
+/// Find the crates mentioned in the Markdown files of a source directory
+/// (links and Rust code blocks) and in the `.rs` files of a code
+/// directory.
+///
+/// options: the Markdown dialect of the book.
+///
+/// Returns normalized crate name -> (name as written, paths of the files
+/// that mention it) and all reference definitions (lowercased label ->
+/// URL).
+#[allow(clippy::type_complexity)]
+pub(crate) fn find_crate_uses(
+    markdown_src_dir_path: &Path,
+    code_dir_path: Option<&Path>,
+    options: &ParserOptions,
+) -> Result<(
+    BTreeMap<String, (String, BTreeSet<PathBuf>)>,
+    BTreeMap<String, String>,
+)> {
+    let mut files = Vec::new();
+    for path in fs::find_markdown_files_in(markdown_src_dir_path)? {
+        if path.file_name().is_some_and(|f| f == "SUMMARY.md") {
+            continue;
+        }
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => files.push((path, contents)),
+            Err(e) => warn!("Could not read {}: {e}", path.display()),
+        }
+    }
+    let mut refdefs = HashMap::new();
+    for (_, contents) in files.iter() {
+        refdefs.extend(parser::collect_reference_definitions(contents, options));
+    }
+
+    let mut uses: BTreeMap<String, (String, BTreeSet<PathBuf>)> = BTreeMap::new();
+    let mut add = |name: &str, path: &Path| {
+        uses.entry(normalize_crate_name(name))
+            .or_insert_with(|| (name.to_string(), BTreeSet::new()))
+            .1
+            .insert(path.to_path_buf());
+    };
+
+    for (path, contents) in files.iter() {
+        let rel_path = path.strip_prefix(markdown_src_dir_path).unwrap_or(path);
+        let mut code: Option<String> = None;
+        for event in parser::get_parser_with_refdefs(contents, &refdefs, options) {
+            match event {
+                Event::Start(Tag::Link { dest_url, .. }) => {
+                    if let Some(name) = crate_name_from_url(&dest_url) {
+                        add(&name, rel_path);
+                    }
+                }
+                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) if is_rust(&info) => {
+                    code = Some(String::new());
+                }
+                Event::Text(t) => {
+                    if let Some(c) = code.as_mut() {
+                        c.push_str(&t);
+                    }
+                }
+                Event::End(TagEnd::CodeBlock) => {
+                    if let Some(c) = code.take() {
+                        for name in crates_used_in_code(&c) {
+                            add(&name, rel_path);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if let Some(code_dir_path) = code_dir_path {
+        for entry in walkdir::WalkDir::new(code_dir_path)
+            .into_iter()
+            .filter_entry(|e| {
+                e.depth() == 0
+                    || !(e.file_name() == "target"
+                        || e.file_name().to_string_lossy().starts_with('.'))
+            })
+            .flatten()
+        {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "rs") {
+                continue;
+            }
+            match std::fs::read_to_string(path) {
+                Ok(code) => {
+                    let rel_path = path.strip_prefix(code_dir_path).unwrap_or(path);
+                    for name in crates_used_in_code(&code) {
+                        add(&name, rel_path);
+                    }
+                }
+                Err(e) => warn!("Could not read {}: {e}", path.display()),
+            }
+        }
+    }
+    Ok((uses, refdefs.into_iter().collect()))
+}
+
+/// Compare the crates used in a book with the declared dependencies.
+///
+/// uses: see [`find_crate_uses`].
+///
+/// refdefs: lowercased label -> URL.
+///
+/// declared: the dependencies declared in `Cargo.toml`.
+///
+/// local_packages: the packages of the workspace, which the code may use
+/// without declaring them.
+pub(crate) fn audit(
+    uses: &BTreeMap<String, (String, BTreeSet<PathBuf>)>,
+    refdefs: &BTreeMap<String, String>,
+    declared: &[DeclaredDependency],
+    local_packages: &BTreeSet<String>,
+) -> DependencyAudit {
+    let local_packages: BTreeSet<String> = local_packages
+        .iter()
+        .map(|p| normalize_crate_name(p))
+        .collect();
+    let packages: BTreeMap<String, &str> = declared
+        .iter()
+        .map(|d| (normalize_crate_name(&d.package), d.package.as_str()))
+        .collect();
+
+    let undeclared = uses
+        .iter()
+        .filter(|(key, _)| !packages.contains_key(*key) && !local_packages.contains(*key))
+        .map(|(_, (name, paths))| (name.clone(), paths.clone()))
+        .collect();
+
+    let mut unused: Vec<String> = packages
+        .iter()
+        .filter(|(key, _)| !uses.contains_key(*key))
+        .map(|(_, package)| package.to_string())
+        .collect();
+    unused.sort();
+
+    let mut name_mismatches: Vec<CrateNameMismatch> = refdefs
+        .iter()
+        .filter_map(|(label, url)| {
+            let name_in_url = crate_name_from_url(url)?;
+            let package = packages.get(&normalize_crate_name(&name_in_url))?;
+            (name_in_url != *package).then(|| CrateNameMismatch {
+                label: label.clone(),
+                url: url.clone(),
+                name_in_url,
+                package: package.to_string(),
+            })
+        })
+        .collect();
+    name_mismatches.sort();
+
+    DependencyAudit {
+        undeclared,
+        unused,
+        name_mismatches,
+    }
+}
+
+/// Write the audit report in Markdown format.
+///
+/// w: a writer (e.g. a File) to write to.
+pub(crate) fn write_audit<W: Write>(report: &DependencyAudit, w: &mut W) -> Result<()> {
+    writeln!(w, "# Dependency audit\n")?;
+
+    writeln!(w, "## Used in the book but not declared in Cargo.toml\n")?;
+    if report.undeclared.is_empty() {
+        writeln!(w, "None.")?;
+    }
+    for (name, paths) in report.undeclared.iter() {
+        let paths = paths
+            .iter()
+            .map(|p| p.to_string_lossy().replace('\\', "/"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        writeln!(w, "- `{name}`: {paths}")?;
+    }
+
+    writeln!(w, "\n## Declared in Cargo.toml but never mentioned\n")?;
+    if report.unused.is_empty() {
+        writeln!(w, "None.")?;
+    }
+    for package in report.unused.iter() {
+        writeln!(w, "- `{package}`")?;
+    }
+
+    writeln!(
+        w,
+        "\n## Reference definitions whose crate name differs from the package name\n"
+    )?;
+    if report.name_mismatches.is_empty() {
+        writeln!(w, "None.")?;
+    }
+    for m in report.name_mismatches.iter() {
+        writeln!(
+            w,
+            "- `[{}]`: <{}> uses `{}` instead of `{}`",
+            m.label, m.url, m.name_in_url, m.package
+        )?;
+    }
+    Ok(())
+}
+
+/// Returns `true` if the info string of a fenced code block denotes Rust
+/// code. As in mdBook, code blocks without a language are Rust code.
+fn is_rust(info: &str) -> bool {
+    let lang = info.split([',', ' ']).next().unwrap_or_default();
+    lang.is_empty() || lang == "rust"
+}
+
+/// Returns the names of the crates that a piece of Rust code uses,
+/// except modules declared in the code itself.
+///
+/// Crates are found in `use` statements, `extern crate` items,
+/// attributes and fully-qualified paths. A path that starts with a name
+/// imported by a `use` statement (e.g. `fs::read` after `use std::fs;`)
+/// is not a crate.
+fn crates_used_in_code(code: &str) -> BTreeSet<String> {
+    let code = strip_comments_and_strings(code);
+    let modules: BTreeSet<&str> = MOD_REGEX
+        .captures_iter(&code)
+        .filter_map(|c| c.name("name"))
+        .map(|m| m.as_str())
+        .collect();
+    let mut names: BTreeSet<&str> = CRATE_USE_REGEX
+        .captures_iter(&code)
+        .filter_map(|c| c.name("name"))
+        .map(|m| m.as_str())
+        .collect();
+
+    // Names imported by `use` statements, except their first segment
+    let imported: BTreeSet<&str> = USE_STATEMENT_REGEX
+        .find_iter(&code)
+        .flat_map(|m| IDENT_REGEX.find_iter(m.as_str()).skip(2))
+        .map(|m| m.as_str())
+        .collect();
+    names.extend(
+        CRATE_PATH_REGEX
+            .captures_iter(&code)
+            .filter_map(|c| c.name("name"))
+            .map(|m| m.as_str())
+            .filter(|name| !imported.contains(name)),
+    );
+    names
+        .into_iter()
+        .filter(|name| !NOT_CRATES.contains(name) && !modules.contains(name))
+        .map(String::from)
+        .collect()
+}
+
+/// Remove the comments of a piece of Rust code and empty its string
+/// literals, so that neither is mistaken for code. Character literals
+/// are removed as well; lifetimes are kept.
+fn strip_comments_and_strings(code: &str) -> String {
+    let mut out = String::with_capacity(code.len());
+    let mut rest = code;
+    while let Some(c) = rest.chars().next() {
+        let after_ident = out
+            .chars()
+            .next_back()
+            .is_some_and(|p| p.is_alphanumeric() || p == '_');
+        let skip = if rest.starts_with("//") {
+            // Line comment, including doc comments; keep the newline
+            rest.find('\n').unwrap_or(rest.len())
+        } else if rest.starts_with("/*") {
+            // Block comments may be nested
+            let mut depth = 0;
+            let mut i = 0;
+            while i < rest.len() {
+                if rest[i..].starts_with("/*") {
+                    depth += 1;
+                    i += 2;
+                } else if rest[i..].starts_with("*/") {
+                    depth -= 1;
+                    i += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    i += rest[i..].chars().next().map_or(1, char::len_utf8);
+                }
+            }
+            out.push(' ');
+            i
+        } else if let Some(caps) = RAW_STRING_REGEX.captures(rest).filter(|_| !after_ident) {
+            let start = caps.get(0).map_or(0, |m| m.end());
+            let end = format!("\"{}", &caps["hashes"]);
+            out.push_str("\"\"");
+            rest[start..]
+                .find(&end)
+                .map_or(rest.len(), |i| start + i + end.len())
+        } else if c == '"' {
+            let mut chars = rest.char_indices().skip(1);
+            let mut end = rest.len();
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '\\' => {
+                        chars.next();
+                    }
+                    '"' => {
+                        end = i + 1;
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+            out.push_str("\"\"");
+            end
+        } else if c == '\'' {
+            // Character literal e.g. `'"'` or `'\''`; otherwise a lifetime
+            let mut chars = rest[1..].chars();
+            match (chars.next(), chars.next()) {
+                (Some('\\'), _) => rest[3.min(rest.len())..]
+                    .find('\'')
+                    .map_or(1, |i| 3 + i + 1),
+                (Some(ch), Some('\'')) => 1 + ch.len_utf8() + 1,
+                _ => {
+                    out.push(c);
+                    1
+                }
+            }
+        } else {
+            out.push(c);
+            c.len_utf8()
+        };
+        rest = &rest[skip..];
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dependencies::DependencyKind;
+
+    #[test]
+    fn test_crates_used_in_code() {
+        let code = "// use not_a_crate::x;\nuse std::fs;\nuse serde_json::Value;\n# use anyhow;\nextern crate rand;\n\
+                    use ::regex as re;\nmod utils;\nuse utils::helper;\n\
+                    #[tokio::main]\n#[rustfmt::skip]\nfn main() {}\n";
+        let names: Vec<_> = crates_used_in_code(code).into_iter().collect();
+        assert_eq!(
+            names,
+            vec!["anyhow", "rand", "regex", "serde_json", "tokio"]
+        );
+    }
+
+    #[test]
+    fn test_crates_used_by_path() {
+        let code = "use std::fs;
+use std::io::{self, Write};
+mod utils {}
+                    fn main() {
+    let s = serde_json::to_string(&1).unwrap();
+                        let v: Vec<u8> = Vec::new();
+    let m = u8::MAX;
+    fs::read(\"a\");
+                        io::stdout();
+    utils::helper();
+    std::mem::drop(v);
+                        let x = ::rand::random::<u8>();
+    s.parse::<i32>();
+}
+";
+        let names: Vec<_> = crates_used_in_code(code).into_iter().collect();
+        assert_eq!(names, vec!["rand", "serde_json"]);
+    }
+
+    #[test]
+    fn test_comments_and_strings_are_ignored() {
+        let code = "let url = \"https://docs.rs/not_a_crate::x\"; use regex::Regex;
+                    let q = '\"'; use anyhow::Result; // use also_not::x;
+                    /* use not_either::x; /* nested */ log::info!(); */
+                    let r = r#\"use raw_not::x;\"#; fn f<'a>(s: &'a str) { tracing::info!(\"{s}\"); }\n";
+        assert_eq!(
+            strip_comments_and_strings("let url = \"http://a//b\"; x::y(); // c\n'\\''; z"),
+            "let url = \"\"; x::y(); \n; z"
+        );
+        let names: Vec<_> = crates_used_in_code(code).into_iter().collect();
+        assert_eq!(names, vec!["anyhow", "regex", "tracing"]);
+    }
+
+    #[test]
+    fn test_audit() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::write(
+            src.join("a.md"),
+            "See [serde-json][serde-json].\n\n```rust\nuse tokio::fs;\n```\n\n```toml\nuse x::y;\n```\n",
+        )
+        .unwrap();
+        std::fs::write(
+            src.join("refs.md"),
+            "[serde-json]: https://docs.rs/serde-json\n",
+        )
+        .unwrap();
+        let code = dir.path().join("code");
+        std::fs::create_dir_all(code.join("src")).unwrap();
+        std::fs::write(
+            code.join("src").join("main.rs"),
+            "use rand::Rng;\nuse my_book::helper;\n",
+        )
+        .unwrap();
+
+        let (uses, refdefs) =
+            find_crate_uses(&src, Some(&code), &ParserOptions::default()).unwrap();
+        let declared: Vec<DeclaredDependency> = ["serde_json", "tokio", "anyhow"]
+            .into_iter()
+            .map(|p| DeclaredDependency {
+                package: p.to_string(),
+                kind: DependencyKind::Normal,
+                target: None,
+            })
+            .collect();
+        let report = audit(
+            &uses,
+            &refdefs,
+            &declared,
+            &BTreeSet::from(["my-book".to_string()]),
+        );
+        assert_eq!(
+            report.undeclared,
+            BTreeMap::from([(
+                "rand".to_string(),
+                BTreeSet::from([PathBuf::from("src/main.rs")])
+            )])
+        );
+        assert_eq!(report.unused, vec!["anyhow"]);
+        assert_eq!(report.name_mismatches.len(), 1);
+        assert_eq!(report.name_mismatches[0].name_in_url, "serde-json");
+        assert_eq!(report.name_mismatches[0].package, "serde_json");
+
+        let mut out = Vec::new();
+        write_audit(&report, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("- `rand`: src/main.rs\n"));
+        assert!(out.contains(
+            "- `[serde-json]`: <https://docs.rs/serde-json> uses `serde-json` instead of `serde_json`\n"
+        ));
+    }
+}
@@ -1,59 +1,263 @@
-//! Parse `Cargo.toml`
-#![allow(dead_code)]
+//! Parse `Cargo.toml` (and the manifests of the workspace members) to
+//! list the direct dependencies
 
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::path::Path;
+use std::path::PathBuf;
 
-use anyhow::Error;
+use cargo_toml::DepsSet;
 use cargo_toml::Manifest;
 use tracing::debug;
+use tracing::warn;
 
-/// Parse `Cargo.toml` (using the `cargo_toml` crate)
+use super::DependencyKind;
+use crate::Error;
+use crate::Result;
+use crate::fs;
+
+/// A direct dependency declared in a `Cargo.toml` manifest
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct DeclaredDependency {
+    /// Name of the package, after renaming
+    /// e.g. `serde_json` for `json = { package = "serde_json" }`
+    pub(crate) package: String,
+    /// Normal, dev or build dependency
+    pub(crate) kind: DependencyKind,
+    /// Target triple or `cfg(...)` expression, for target-specific
+    /// dependencies
+    pub(crate) target: Option<String>,
+}
+
+/// List the crates.io dependencies declared in `Cargo.toml`.
+///
+/// If the manifest is a workspace root, the dependencies of all
+/// workspace members are listed as well. `workspace = true`
+/// dependencies are resolved against `[workspace.dependencies]`.
+/// Path, git and alternative registry dependencies are skipped.
+///
+/// cargo_toml_dir_path: the path to the directory where Cargo.toml may be
+/// found.
+///
+/// kinds: the kinds of dependencies to list.
+pub(crate) fn declared_dependencies<P: AsRef<Path>>(
+    cargo_toml_dir_path: P,
+    kinds: &[DependencyKind],
+) -> Result<Vec<DeclaredDependency>> {
+    let (manifests, workspace_deps) = read_manifests(cargo_toml_dir_path.as_ref())?;
+
+    // Workspace members are not listed, even if published
+    let member_names = package_names(&manifests);
+
+    let mut deps = BTreeSet::new();
+    for manifest in manifests.iter() {
+        let mut tables: Vec<(DependencyKind, Option<&String>, &DepsSet)> = vec![
+            (DependencyKind::Normal, None, &manifest.dependencies),
+            (DependencyKind::Dev, None, &manifest.dev_dependencies),
+            (DependencyKind::Build, None, &manifest.build_dependencies),
+        ];
+        for (target, t) in manifest.target.iter() {
+            tables.push((DependencyKind::Normal, Some(target), &t.dependencies));
+            tables.push((DependencyKind::Dev, Some(target), &t.dev_dependencies));
+            tables.push((DependencyKind::Build, Some(target), &t.build_dependencies));
+        }
+        for (kind, target, table) in tables {
+            if !kinds.contains(&kind) {
+                continue;
+            }
+            for (key, dep) in table.iter() {
+                let dep = match dep {
+                    cargo_toml::Dependency::Inherited(_) => match workspace_deps.get(key) {
+                        Some(d) => d,
+                        None => {
+                            warn!("Could not resolve the workspace dependency {key}");
+                            continue;
+                        }
+                    },
+                    d => d,
+                };
+                if matches!(dep, cargo_toml::Dependency::Inherited(_)) || !dep.is_crates_io() {
+                    debug!("Skipping {key}: not a crates.io dependency");
+                    continue;
+                }
+                let package = dep.package().unwrap_or(key).to_string();
+                if member_names.contains(&package) {
+                    continue;
+                }
+                deps.insert(DeclaredDependency {
+                    package,
+                    kind,
+                    target: target.cloned(),
+                });
+            }
+        }
+    }
+    Ok(deps.into_iter().collect())
+}
+
+/// Returns the names of the packages defined by `Cargo.toml` and, if it
+/// is a workspace root, by the workspace members.
 ///
-/// cargo_toml_dir_path: the path to the directory where Cargo.toml may be found
-pub(crate) fn parse_cargo_toml<P: AsRef<Path>>(
+/// cargo_toml_dir_path: the path to the directory where Cargo.toml may be
+/// found.
+pub(crate) fn local_package_names<P: AsRef<Path>>(
     cargo_toml_dir_path: P,
-) -> Result<Vec<String>, Error> {
+) -> Result<BTreeSet<String>> {
+    let (manifests, _) = read_manifests(cargo_toml_dir_path.as_ref())?;
+    Ok(package_names(&manifests))
+}
+
+/// Parse `Cargo.toml` and the manifests of the workspace members, if it is
+/// a workspace root.
+///
+/// Returns the manifests that define a package and the
+/// `[workspace.dependencies]` table.
+fn read_manifests(cargo_toml_dir_path: &Path) -> Result<(Vec<Manifest>, DepsSet)> {
     debug!("Parsing Cargo.toml...");
+    // Manifest::from_path calls Manifest::complete_from_path, which
+    // searches for a workspace and fills in inherited data.
+    let root = read_manifest(&cargo_toml_dir_path.join("Cargo.toml"))?;
 
-    // Manifest::from_path calls Manifest::complete_from_path to discover implicit
-    // binaries, etc. It will search for a workspace.
-    let manifest = Manifest::from_path(cargo_toml_dir_path)?;
+    let mut manifests = Vec::new();
+    let workspace_deps = root
+        .workspace
+        .as_ref()
+        .map(|w| w.dependencies.clone())
+        .unwrap_or_default();
+    if let Some(ref workspace) = root.workspace {
+        for member_dir_path in
+            workspace_members(cargo_toml_dir_path, &workspace.members, &workspace.exclude)?
+        {
+            manifests.push(read_manifest(&member_dir_path.join("Cargo.toml"))?);
+        }
+    }
+    if root.package.is_some() {
+        manifests.push(root);
+    }
+    Ok((manifests, workspace_deps))
+}
 
-    if let Some(package) = manifest.package {
-        debug!("Name: {}\n", package.name);
-        debug!(
-            "Description: {}\n",
-            package.description.unwrap_or_default().get()?
+/// Returns the names of the packages of a list of manifests.
+fn package_names(manifests: &[Manifest]) -> BTreeSet<String> {
+    manifests
+        .iter()
+        .filter_map(|m| m.package.as_ref().map(|p| p.name.clone()))
+        .collect()
+}
+
+/// Parse a manifest, completing it from the file system.
+fn read_manifest(cargo_toml_path: &Path) -> Result<Manifest> {
+    Manifest::from_path(cargo_toml_path).map_err(|e| match e {
+        cargo_toml::Error::Io(e) => Error::io(cargo_toml_path, e),
+        e => Error::parse(cargo_toml_path, e),
+    })
+}
+
+/// Returns the directories of the members of a workspace.
+///
+/// `members` and `exclude` are paths relative to the workspace root,
+/// which may contain glob patterns e.g. `crates/*`.
+fn workspace_members(
+    workspace_dir_path: &Path,
+    members: &[String],
+    exclude: &[String],
+) -> Result<Vec<PathBuf>> {
+    let exclude = fs::build_globset(exclude)?;
+    let mut dirs = BTreeMap::new();
+    for member in members {
+        let member = member.trim_end_matches('/');
+        let found: Vec<PathBuf> = if member.contains(['*', '?', '[']) {
+            let pattern = fs::build_globset(&[member.to_string()])?;
+            let depth = Path::new(member).components().count();
+            walkdir::WalkDir::new(workspace_dir_path)
+                .min_depth(depth)
+                .max_depth(depth)
+                .into_iter()
+                .flatten()
+                .filter(|e| e.file_type().is_dir())
+                .filter_map(|e| {
+                    let rel = e.path().strip_prefix(workspace_dir_path).ok()?;
+                    pattern.is_match(rel).then(|| rel.to_path_buf())
+                })
+                .collect()
+        } else {
+            vec![PathBuf::from(member)]
+        };
+        for rel in found {
+            let dir = workspace_dir_path.join(&rel);
+            if exclude.is_match(&rel) || !dir.join("Cargo.toml").is_file() {
+                continue;
+            }
+            dirs.insert(rel, dir);
+        }
+    }
+    Ok(dirs.into_values().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write a small workspace to a temporary directory.
+    fn write_test_workspace(dir: &Path) {
+        let write = |path: &str, contents: &str| {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        };
+        write(
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/skipped\"]\n\n\
+             [workspace.dependencies]\nserde = \"1\"\nlocal = { path = \"local\" }\n",
         );
-        debug!("Authors: {:?}\n", package.authors.get()?);
-        debug!(
-            "Homepage: {}\n",
-            package.homepage.unwrap_or_default().get()?
+        write(
+            "crates/a/Cargo.toml",
+            "[package]\nname = \"a\"\nversion = \"0.1.0\"\n\n\
+             [dependencies]\nserde = { workspace = true }\njson = { package = \"serde_json\", version = \"1\" }\n\
+             b = { path = \"../b\", version = \"0.1\" }\nlocal = { workspace = true }\n\
+             forked = { git = \"https://github.com/x/forked\" }\n\n\
+             [target.'cfg(unix)'.dependencies]\nlibc = \"0.2\"\n\n\
+             [dev-dependencies]\ntempfile = \"3\"\n\n\
+             [build-dependencies]\ncc = \"1\"\n",
         );
-        debug!(
-            "Repository: {}\n",
-            package.repository.unwrap_or_default().get()?
+        write("crates/a/src/lib.rs", "");
+        write(
+            "crates/b/Cargo.toml",
+            "[package]\nname = \"b\"\nversion = \"0.1.0\"\n\n[dependencies]\nanyhow = \"1\"\n",
         );
-        debug!(
-            "Documentation: {}\n",
-            package.documentation.unwrap_or_default().get()?
+        write("crates/b/src/lib.rs", "");
+        write(
+            "crates/skipped/Cargo.toml",
+            "[package]\nname = \"skipped\"\nversion = \"0.1.0\"\n\n[dependencies]\nrand = \"1\"\n",
         );
-        debug!("Keywords: {:?}\n", package.keywords.get()?);
-        debug!("Categories: {:?}\n", package.categories.get()?);
+        write("crates/skipped/src/lib.rs", "");
     }
-    let deps = manifest.dependencies;
-    // The keys in this map are not always crate names, this can be overriden by the
-    // package field, and there may be multiple copies of the same crate.
 
-    // Return the crate names
-    Ok(deps.keys().cloned().collect::<Vec<String>>())
-}
+    #[test]
+    fn test_declared_dependencies() {
+        let dir = tempfile::tempdir().unwrap();
+        write_test_workspace(dir.path());
 
-#[cfg(test)]
-mod tests {
-    // use super::*;
+        let deps = declared_dependencies(dir.path(), &[DependencyKind::Normal]).unwrap();
+        let names: Vec<_> = deps.iter().map(|d| d.package.as_str()).collect();
+        assert_eq!(names, vec!["anyhow", "libc", "serde", "serde_json"]);
+        assert_eq!(
+            deps.iter().find(|d| d.package == "libc").unwrap().target,
+            Some("cfg(unix)".to_string())
+        );
 
-    // #[test]
-    // fn test() {
-    // }
+        let deps = declared_dependencies(
+            dir.path().join("crates").join("a"),
+            &[DependencyKind::Dev, DependencyKind::Build],
+        )
+        .unwrap();
+        let names: Vec<_> = deps.iter().map(|d| (d.package.as_str(), d.kind)).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("cc", DependencyKind::Build),
+                ("tempfile", DependencyKind::Dev)
+            ]
+        );
+    }
 }
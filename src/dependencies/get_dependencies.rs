@@ -1,145 +1,229 @@
 //! Get the book's examples' dependencies
 use std::borrow::Cow;
 use std::collections::BTreeMap;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::BufWriter;
 use std::io::Write;
 use std::path::Path;
-use std::process::Command;
+use std::path::PathBuf;
 
-use anyhow::Result;
-use anyhow::anyhow;
-use serde::Deserialize;
-use tempfile::Builder;
 use tracing::debug;
 use tracing::warn;
 
+use super::DependencyOptions;
+use super::declared_dependencies;
+use crate::Error;
+use crate::Result;
+use crate::registry;
+
 /// Stores a dependency to a crate
-#[derive(Debug, Deserialize)]
+#[derive(Debug)]
 pub(crate) struct Dependency<'a> {
     pub(crate) library_name: Cow<'a, str>,
+    /// Name of the package e.g. `serde-json` for the `serde_json` library
+    pub(crate) package_name: Cow<'a, str>,
     pub(crate) package_repo_url: Option<Cow<'a, str>>,
+    /// Most recent version locked in `Cargo.lock`, if any
+    pub(crate) version: Option<Cow<'a, str>>,
+    /// `package.homepage`, if any
+    pub(crate) homepage: Option<Cow<'a, str>>,
+    /// `package.documentation`, if any
+    pub(crate) documentation: Option<Cow<'a, str>>,
 }
 
-/// Parse `Cargo.toml` and returns the list of dependencies:
+/// Parse `Cargo.toml` (and `Cargo.lock`, if any) and returns the list of
+/// direct dependencies, without invoking `cargo`.
+///
+/// Repository, home page and documentation URLs are read from the
+/// manifests in the local registry
+/// cache (`$CARGO_HOME/registry/src`) and, if
+/// `options.cargo_metadata` is set, from `cargo metadata --offline`.
 ///
-/// Calls cargo tree --depth 1 --edges normal --prefix none --format
-/// {lib},{r} --locked (immediate children, no-dev/build, flat list,
-/// crate name and package repository URL)
+/// cargo_toml_dir_path: Path to the directory containing the Cargo.toml
+/// file.
 ///
-/// dir_path: Path to the directory containing the Cargo.toml file.
-pub(crate) fn get_dependencies<P: AsRef<Path>, Q: AsRef<Path>>(
+/// options: see [`DependencyOptions`].
+pub(crate) fn get_dependencies<P: AsRef<Path>>(
     cargo_toml_dir_path: P,
-    log_file_path: Option<Q>,
+    options: &DependencyOptions,
 ) -> Result<BTreeMap<Cow<'static, str>, Dependency<'static>>> {
-    let output = Command::new("cargo")
-        .args([
-            "tree",
-            "--depth",
-            "1",
-            "--edges",
-            "normal",
-            "--prefix",
-            "none",
-            "--format",
-            "{lib},{r}",
-            "--locked",
-        ])
-        .current_dir(cargo_toml_dir_path)
-        .output()?; // returns if failed to execute Command
+    get_dependencies_with_cargo_home(
+        cargo_toml_dir_path.as_ref(),
+        registry::cargo_home_dir().as_deref(),
+        options,
+    )
+}
 
-    write_log(
-        &output.stdout,
-        &output.stderr,
-        log_file_path.as_ref().map(|p| p.as_ref()),
-    )?;
+/// See [`get_dependencies`].
+///
+/// cargo_home_dir_path: the `cargo` home directory, whose registry
+/// cache holds the manifests of the dependencies, if any.
+fn get_dependencies_with_cargo_home(
+    cargo_toml_dir_path: &Path,
+    cargo_home_dir_path: Option<&Path>,
+    options: &DependencyOptions,
+) -> Result<BTreeMap<Cow<'static, str>, Dependency<'static>>> {
+    let declared = declared_dependencies(cargo_toml_dir_path, &options.kinds)?;
 
-    if !output.status.success() {
-        return Err(anyhow!(
-            "`cargo tree` failed. Status: {}. Output: {}\n {}",
-            output.status,
-            String::from_utf8(output.stdout.clone())?,
-            String::from_utf8(output.stderr)?
-        ));
+    // Locked versions, used to find the packages in the registry cache
+    let mut locked_versions: HashMap<String, Vec<String>> = HashMap::new();
+    match find_cargo_lock(cargo_toml_dir_path) {
+        Some(cargo_lock_path) => {
+            for p in registry::read_cargo_lock(cargo_lock_path)? {
+                locked_versions.entry(p.name).or_default().push(p.version);
+            }
+        }
+        None => debug!("[get_dependencies] No Cargo.lock found."),
     }
 
-    // Useful wrapper that implements Read for Vec<u8>
-    let cursor = std::io::Cursor::new(output.stdout);
-
-    let mut rdr = csv::ReaderBuilder::new()
-        .has_headers(false)
-        .from_reader(cursor);
-
-    // Read cargo tree output
     let mut results: BTreeMap<_, _> = BTreeMap::new();
+    for dep in declared.iter() {
+        let versions = locked_versions
+            .get(&dep.package)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let cached = cargo_home_dir_path
+            .and_then(|home| registry::cached_package(home, &dep.package, versions))
+            .unwrap_or_default();
+        // As `cargo tree --format {lib}` does
+        let library_name: Cow<'static, str> = Cow::Owned(dep.package.replace('-', "_"));
+        results
+            .entry(library_name.clone())
+            .or_insert_with(|| Dependency {
+                library_name,
+                package_name: Cow::Owned(dep.package.clone()),
+                package_repo_url: cached.repository.map(Cow::Owned),
+                version: registry::latest_version(versions).map(|v| Cow::Owned(v.to_string())),
+                homepage: cached.homepage.map(Cow::Owned),
+                documentation: cached.documentation.map(Cow::Owned),
+            });
+    }
 
-    while let Some(res) = rdr.deserialize::<Dependency<'_>>().next() {
-        if let Ok(dep) = res {
-            if dep.library_name != "deps" {
-                results.insert(dep.library_name.clone(), dep);
+    if options.cargo_metadata && results.values().any(|d| d.package_repo_url.is_none()) {
+        let packages = registry::run_cargo_metadata(cargo_toml_dir_path)?;
+        for p in packages {
+            if let Some(dep) = results.get_mut(p.name.replace('-', "_").as_str())
+                && dep.package_repo_url.is_none()
+            {
+                dep.package_repo_url = p.repository.map(Cow::Owned);
+                dep.homepage = dep.homepage.take().or(p.homepage.map(Cow::Owned));
+                dep.documentation = dep.documentation.take().or(p.documentation.map(Cow::Owned));
             }
-        } else {
-            return Err(anyhow!("Failed to parse cargo tree output."));
         }
     }
 
+    if let Some(ref log_file_path) = options.log_file_path {
+        write_log(&declared, &results, log_file_path);
+    }
     Ok(results)
 }
 
-/// Write e.g. stdout / stderr to a file.
-fn write_log(out: &[u8], err: &[u8], log_file_path: Option<&Path>) -> Result<()> {
-    let (file, actual_path) = match log_file_path {
-        Some(path) => match File::create(path) {
-            Ok(f) => (f, path.to_path_buf()),
-            Err(e) => {
-                warn!("Failed to create log file {}: {}", path.display(), e);
-                return Ok(());
-            }
-        },
-        None => match Builder::new()
-            .prefix("mdbook-utils-dependencies-")
-            .suffix(".log")
-            .tempfile()
-        {
-            Ok(tf) => {
-                let actual_path = tf.path().to_path_buf();
-                match tf.keep() {
-                    Ok((f, _p)) => (f, actual_path),
-                    Err(e) => {
-                        warn!("Failed to keep temporary log file: {}", e);
-                        return Ok(());
-                    }
-                }
-            }
-            Err(e) => {
-                warn!("Failed to create temporary log file: {}", e);
-                return Ok(());
-            }
-        },
-    };
+/// Locate `Cargo.lock` in the directory of `Cargo.toml` or in one of its
+/// parents (the workspace root).
+pub(crate) fn find_cargo_lock(cargo_toml_dir_path: &Path) -> Option<PathBuf> {
+    cargo_toml_dir_path
+        .ancestors()
+        .map(|d| d.join("Cargo.lock"))
+        .find(|p| p.is_file())
+}
 
-    let mut buffer = BufWriter::new(file);
-    if let Err(e) = buffer
-        .write_all(out)
-        .and_then(|_| buffer.write_all(err))
-        .and_then(|_| buffer.flush())
-    {
-        warn!(
-            "Failed to write to log file {}: {}",
-            actual_path.display(),
-            e
-        );
-    } else {
-        debug!("Dependencies log written to {}", actual_path.display());
+/// Write the dependencies that were found to a log file.
+/// Failures are logged, not returned.
+fn write_log(
+    declared: &[super::DeclaredDependency],
+    results: &BTreeMap<Cow<'static, str>, Dependency<'static>>,
+    log_file_path: &Path,
+) {
+    let write = || -> Result<()> {
+        let file = File::create(log_file_path).map_err(|e| Error::io(log_file_path, e))?;
+        let mut buffer = BufWriter::new(file);
+        for d in declared {
+            writeln!(
+                buffer,
+                "{},{:?},{}",
+                d.package,
+                d.kind,
+                d.target.as_deref().unwrap_or_default()
+            )?;
+        }
+        writeln!(buffer)?;
+        for d in results.values() {
+            writeln!(
+                buffer,
+                "{},{}",
+                d.library_name,
+                d.package_repo_url.as_deref().unwrap_or_default()
+            )?;
+        }
+        buffer.flush()?;
+        Ok(())
+    };
+    match write() {
+        Ok(()) => debug!("Dependencies log written to {}", log_file_path.display()),
+        Err(e) => warn!("{e:?}"),
     }
-    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    // use super::*;
+    use super::*;
 
-    // #[test]
-    // fn test() {
-    // }
+    #[test]
+    fn test_find_cargo_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let member = dir.path().join("crates").join("a");
+        std::fs::create_dir_all(&member).unwrap();
+        std::fs::write(dir.path().join("Cargo.lock"), "version = 4\n").unwrap();
+        assert_eq!(
+            find_cargo_lock(&member),
+            Some(dir.path().join("Cargo.lock"))
+        );
+    }
+
+    #[test]
+    fn test_cached_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"book\"\nversion = \"0.1.0\"\n\n[dependencies]\nserde-json = \"1\"\nanyhow = \"1\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("Cargo.lock"),
+            "version = 4\n\n[[package]]\nname = \"serde-json\"\nversion = \"1.0.1\"\n",
+        )
+        .unwrap();
+        let home = dir.path().join("cargo_home");
+        let registry = home.join("registry/src/index.crates.io-0");
+        for (version, repo) in [("1.0.1", "json/v1"), ("1.0.2", "json/v2")] {
+            let package_dir = registry.join(format!("serde-json-{version}"));
+            std::fs::create_dir_all(&package_dir).unwrap();
+            std::fs::write(
+                package_dir.join("Cargo.toml"),
+                format!(
+                    "[package]\nname = \"serde-json\"\nrepository = \"https://github.com/{repo}\"\nhomepage = \"https://serde.rs\"\n"
+                ),
+            )
+            .unwrap();
+        }
+
+        let deps = get_dependencies_with_cargo_home(
+            dir.path(),
+            Some(&home),
+            &DependencyOptions::default(),
+        )
+        .unwrap();
+        // The manifest of the locked version is used
+        let serde_json = &deps["serde_json"];
+        assert_eq!(serde_json.package_name, "serde-json");
+        assert_eq!(serde_json.version.as_deref(), Some("1.0.1"));
+        assert_eq!(
+            serde_json.package_repo_url.as_deref(),
+            Some("https://github.com/json/v1")
+        );
+        assert_eq!(serde_json.homepage.as_deref(), Some("https://serde.rs"));
+        // Not in the registry cache
+        assert_eq!(deps["anyhow"].package_repo_url, None);
+    }
 }
@@ -1,5 +1,89 @@
 //! Get the book's examples' dependencies
+mod audit;
 mod cargo;
 mod get_dependencies;
+mod upgrade_versions;
 
+use std::path::PathBuf;
+use std::str::FromStr;
+
+pub use audit::CrateNameMismatch;
+pub use audit::DependencyAudit;
+pub(crate) use audit::*;
+pub(crate) use cargo::*;
 pub(crate) use get_dependencies::*;
+pub use upgrade_versions::VersionUpgrade;
+pub(crate) use upgrade_versions::*;
+
+use crate::parser::ParserOptions;
+
+/// Kind of dependency, as declared in `Cargo.toml`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum DependencyKind {
+    /// `[dependencies]`
+    #[default]
+    Normal,
+    /// `[dev-dependencies]`
+    Dev,
+    /// `[build-dependencies]`
+    Build,
+}
+
+impl FromStr for DependencyKind {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "normal" => Ok(Self::Normal),
+            "dev" => Ok(Self::Dev),
+            "build" => Ok(Self::Build),
+            _ => Err(crate::Error::config(format!(
+                "Unknown dependency kind: {s}. Use `normal`, `dev` or `build`."
+            ))),
+        }
+    }
+}
+
+/// Options for the discovery of the dependencies of the code examples
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependencyOptions {
+    /// Kinds of (direct) dependencies to list
+    pub kinds: Vec<DependencyKind>,
+
+    /// If `true`, run `cargo metadata --offline` to retrieve the
+    /// repository URLs that are not found in the local registry cache
+    pub cargo_metadata: bool,
+
+    /// If `true`, pin the generated docs.rs and crates.io URLs to the
+    /// versions locked in `Cargo.lock` e.g.
+    /// `https://docs.rs/serde/1.0.228/serde/`.
+    /// Cannot be combined with `templates`, which pin their URLs with
+    /// `${version}`.
+    pub pin_versions: bool,
+
+    /// Reference definitions to generate for each dependency.
+    /// `None` for the default docs.rs, crates.io and repository
+    /// reference definitions.
+    pub templates: Option<Vec<crate::generate::RefDefTemplate>>,
+
+    /// Optional path to a log file, where the dependencies that were
+    /// found are listed
+    pub log_file_path: Option<PathBuf>,
+
+    /// Markdown dialect of the book, used to read its existing reference
+    /// definitions
+    pub parser: ParserOptions,
+}
+
+impl Default for DependencyOptions {
+    fn default() -> Self {
+        Self {
+            kinds: vec![DependencyKind::Normal],
+            cargo_metadata: false,
+            pin_versions: false,
+            templates: None,
+            log_file_path: None,
+            parser: ParserOptions::default(),
+        }
+    }
+}
@@ -0,0 +1,256 @@
+//! Upgrade the versions of pinned docs.rs / crates.io URLs
+//! to the versions locked in `Cargo.lock`
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+use rayon::prelude::*;
+use regex::Regex;
+use tracing::info;
+
+use crate::Error;
+use crate::Result;
+use crate::lint::FileFix;
+use crate::lint::apply_file_fixes;
+use crate::registry;
+use crate::registry::normalize_crate_name;
+
+/// Regex to find the crate name and version of a pinned URL e.g.
+/// `https://docs.rs/serde/1.0.200/serde/`,
+/// `https://docs.rs/crate/serde/1.0.200` or
+/// `https://crates.io/crates/serde/1.0.200`
+static PINNED_URL_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"https?://(?:docs\.rs/(?:crate/)?|crates\.io/crates/)(?<crate>[A-Za-z0-9_-]+)/(?<version>\d+\.\d+\.\d+(?:-[0-9A-Za-z.-]+)?(?:\+[0-9A-Za-z.-]+)?)(?:[/)>"'\]#?\s]|$)"#,
+    )
+    .unwrap()
+});
+
+/// A version of a docs.rs or crates.io URL that was (or would be) upgraded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionUpgrade {
+    /// Markdown file containing the URL
+    pub file_path: PathBuf,
+    /// Line number (1-based)
+    pub line: usize,
+    /// Name of the crate, as spelled in the URL
+    pub crate_name: String,
+    /// Version found in the URL
+    pub old_version: String,
+    /// Version locked in `Cargo.lock`
+    pub new_version: String,
+}
+
+/// Within each Markdown file in a source directory, replace the versions
+/// of pinned docs.rs and crates.io URLs that are not locked in
+/// `Cargo.lock` by the most recent locked version of the same crate.
+///
+/// URLs of crates that are not in `Cargo.lock` are left untouched.
+///
+/// markdown_dir_path: path to the source directory containing the Markdown
+/// files.
+///
+/// cargo_lock_path: path to `Cargo.lock`.
+///
+/// dry_run: if `true`, report the changes without modifying the files.
+///
+/// The files are updated all at once, or not at all: see
+/// [`apply_file_fixes`].
+pub(crate) fn upgrade_versions_in_all_markdown_files_in<P1, P2>(
+    markdown_dir_path: P1,
+    cargo_lock_path: P2,
+    dry_run: bool,
+) -> Result<Vec<VersionUpgrade>>
+where
+    P1: AsRef<Path>,
+    P2: AsRef<Path>,
+{
+    let locked = locked_versions(registry::read_cargo_lock(cargo_lock_path)?);
+    let paths = crate::fs::find_markdown_files_in(markdown_dir_path.as_ref())?;
+
+    let results = paths
+        .into_par_iter()
+        .map(|p| -> Result<Option<(FileFix, Vec<VersionUpgrade>)>> {
+            info!("Looking into {p:?}");
+            let buf = fs::read_to_string(&p).map_err(|e| Error::io(&p, e))?;
+            let (new_buf, upgrades) = upgrade_versions(&buf, &locked);
+            if upgrades.is_empty() {
+                return Ok(None);
+            }
+            let upgrades = upgrades
+                .into_iter()
+                .map(
+                    |(line, crate_name, old_version, new_version)| VersionUpgrade {
+                        file_path: p.clone(),
+                        line,
+                        crate_name,
+                        old_version,
+                        new_version,
+                    },
+                )
+                .collect();
+            Ok(Some((FileFix::replace_contents(p, buf, new_buf), upgrades)))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let (fixes, upgrades): (Vec<FileFix>, Vec<Vec<VersionUpgrade>>) =
+        results.into_iter().flatten().unzip();
+    if !dry_run {
+        apply_file_fixes(&fixes)?;
+    }
+    Ok(upgrades.into_iter().flatten().collect())
+}
+
+/// Replace the versions of the pinned docs.rs and crates.io URLs of a
+/// Markdown string that are not locked in `Cargo.lock`, see
+/// [`upgrade_versions_in_all_markdown_files_in`].
+///
+/// Returns the new Markdown and the changes, whose `file_path` is empty.
+///
+/// markdown: the Markdown to upgrade.
+///
+/// cargo_lock: the contents of `Cargo.lock`.
+pub(crate) fn upgrade_versions_in_str(
+    markdown: &str,
+    cargo_lock: &str,
+) -> Result<(String, Vec<VersionUpgrade>)> {
+    let locked = locked_versions(registry::parse_cargo_lock(cargo_lock)?);
+    let (new_markdown, upgrades) = upgrade_versions(markdown, &locked);
+    let upgrades = upgrades
+        .into_iter()
+        .map(
+            |(line, crate_name, old_version, new_version)| VersionUpgrade {
+                file_path: PathBuf::new(),
+                line,
+                crate_name,
+                old_version,
+                new_version,
+            },
+        )
+        .collect();
+    Ok((new_markdown, upgrades))
+}
+
+/// Locked versions of the registry packages, indexed by normalized crate
+/// name, see [`normalize_crate_name`].
+fn locked_versions(packages: Vec<registry::LockedPackage>) -> HashMap<String, Vec<String>> {
+    let mut locked: HashMap<String, Vec<String>> = HashMap::new();
+    for p in packages.into_iter().filter(|p| p.source.is_some()) {
+        locked
+            .entry(normalize_crate_name(&p.name))
+            .or_default()
+            .push(p.version);
+    }
+    locked
+}
+
+/// Replace the outdated versions of the pinned URLs in a string.
+///
+/// Returns the new contents and, for each replacement, the line number,
+/// crate name, old and new versions.
+fn upgrade_versions(
+    contents: &str,
+    locked: &HashMap<String, Vec<String>>,
+) -> (String, Vec<(usize, String, String, String)>) {
+    let mut buf = String::with_capacity(contents.len());
+    let mut upgrades = Vec::new();
+    let mut last = 0;
+    for caps in PINNED_URL_REGEX.captures_iter(contents) {
+        let crate_name = &caps["crate"];
+        let version = caps.name("version").unwrap();
+        let Some(versions) = locked.get(&normalize_crate_name(crate_name)) else {
+            continue;
+        };
+        if versions.iter().any(|v| v == version.as_str()) {
+            continue;
+        }
+        let Some(new_version) = registry::latest_version(versions) else {
+            continue;
+        };
+        buf.push_str(&contents[last..version.start()]);
+        buf.push_str(new_version);
+        last = version.end();
+        let line = contents[..version.start()].matches('\n').count() + 1;
+        upgrades.push((
+            line,
+            crate_name.to_string(),
+            version.as_str().to_string(),
+            new_version.to_string(),
+        ));
+    }
+    buf.push_str(&contents[last..]);
+    (buf, upgrades)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upgrade_versions_in_all_markdown_files_in() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let src_dir = dir.path().join("src");
+        fs::create_dir(&src_dir)?;
+        let cargo_lock_path = dir.path().join("Cargo.lock");
+        fs::write(
+            &cargo_lock_path,
+            r#"version = 4
+
+[[package]]
+name = "serde_json"
+version = "1.0.140"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "tokio"
+version = "1.45.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "my_book"
+version = "0.2.0"
+"#,
+        )?;
+        let md_file = src_dir.join("chapter.md");
+        let contents = "[serde_json]: https://docs.rs/serde_json/1.0.120/serde_json/\n\
+                        [serde_json-crate]: https://crates.io/crates/serde-json/1.0.120\n\
+                        [tokio]: https://docs.rs/tokio/1.45.0/tokio/\n\
+                        [spawn]: https://docs.rs/crate/tokio/1.40.0-rc.1/source/\n\
+                        [my_book]: https://docs.rs/my_book/0.1.0/my_book/\n\
+                        [rand]: https://docs.rs/rand/0.8.5/rand/\n";
+        fs::write(&md_file, contents)?;
+
+        let upgrades = upgrade_versions_in_all_markdown_files_in(&src_dir, &cargo_lock_path, true)?;
+        assert_eq!(upgrades.len(), 3);
+        assert_eq!(fs::read_to_string(&md_file)?, contents);
+
+        let upgrades =
+            upgrade_versions_in_all_markdown_files_in(&src_dir, &cargo_lock_path, false)?;
+        assert_eq!(
+            upgrades[1],
+            VersionUpgrade {
+                file_path: md_file.clone(),
+                line: 2,
+                crate_name: "serde-json".into(),
+                old_version: "1.0.120".into(),
+                new_version: "1.0.140".into(),
+            }
+        );
+        assert_eq!(
+            fs::read_to_string(&md_file)?,
+            "[serde_json]: https://docs.rs/serde_json/1.0.140/serde_json/\n\
+             [serde_json-crate]: https://crates.io/crates/serde-json/1.0.140\n\
+             [tokio]: https://docs.rs/tokio/1.45.0/tokio/\n\
+             [spawn]: https://docs.rs/crate/tokio/1.45.0/source/\n\
+             [my_book]: https://docs.rs/my_book/0.1.0/my_book/\n\
+             [rand]: https://docs.rs/rand/0.8.5/rand/\n"
+        );
+        assert!(
+            upgrade_versions_in_all_markdown_files_in(&src_dir, &cargo_lock_path, false)?
+                .is_empty()
+        );
+        Ok(())
+    }
+}
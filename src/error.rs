@@ -0,0 +1,187 @@
+//! Error type of the library
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Boxed error, used as the source of an [`Error`]
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Result type of the library
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Errors returned by the library.
+///
+/// Match on the variant to tell e.g. a missing source directory
+/// ([`Error::Io`]) from an invalid `book.toml` ([`Error::Parse`]) or a
+/// failed `cargo metadata` ([`Error::Command`]). `Error` implements
+/// [`std::error::Error`], thus `?` converts it into an
+/// [`anyhow::Error`], as the command-line tool does.
+///
+/// ```
+/// use mdbook_utils::Book;
+/// use mdbook_utils::Error;
+///
+/// match Book::load("./does_not_exist/") {
+///     Err(Error::Io { path, source }) => {
+///         assert_eq!(path, std::path::Path::new("./does_not_exist/"));
+///         assert_eq!(source.kind(), std::io::ErrorKind::NotFound);
+///     }
+///     _ => unreachable!(),
+/// }
+/// ```
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// A file or directory does not exist, or could not be read,
+    /// created or written
+    #[error("{}: {source}", path.display())]
+    Io {
+        /// Path of the file or directory, empty if unknown e.g. when
+        /// writing to an `impl Write`
+        path: PathBuf,
+        /// Underlying I/O error
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// A file could not be parsed e.g. `book.toml`, `Cargo.lock` or the
+    /// output of `cargo metadata`
+    #[error("Could not parse {}", path.display())]
+    Parse {
+        /// Path of the file, or of the command whose output could not be
+        /// parsed
+        path: PathBuf,
+        /// Underlying parse error
+        #[source]
+        source: BoxError,
+    },
+
+    /// A path is outside of the directory it should be in e.g. a code
+    /// file that would be written outside of the destination directory
+    #[error(
+        "Path traversal detected: {} is outside of {}",
+        path.display(),
+        base_dir.display()
+    )]
+    PathEscape {
+        /// Offending path
+        path: PathBuf,
+        /// Directory the path should be in
+        base_dir: PathBuf,
+    },
+
+    /// Invalid configuration or option value e.g. an unknown lint rule
+    /// or an invalid glob pattern
+    #[error("{0}")]
+    Config(String),
+
+    /// An external command (e.g. `cargo metadata` or `mdbook build`)
+    /// could not be run, or failed
+    #[error("`{command}` failed: {message}")]
+    Command {
+        /// Command line e.g. `cargo metadata`
+        command: String,
+        /// Error message, or the standard error of the command
+        message: String,
+    },
+
+    /// The regex of a link rule could not be compiled
+    #[error("Invalid regex for rule {name}")]
+    Rule {
+        /// Name of the rule
+        name: String,
+        /// Underlying regex error
+        #[source]
+        source: regex::Error,
+    },
+
+    /// A value could not be written in the format an output requires
+    /// e.g. a date that RFC 3339 cannot represent
+    #[error("Could not format {value}")]
+    Format {
+        /// The value, as written by `Debug`
+        value: String,
+        /// Underlying formatting error
+        #[source]
+        source: BoxError,
+    },
+
+    /// Any other error, with its context
+    #[error(transparent)]
+    Other(BoxError),
+}
+
+impl Error {
+    /// Returns the path of the file or directory the error is about, if
+    /// any
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Io { path, .. } | Self::Parse { path, .. } | Self::PathEscape { path, .. } => {
+                Some(path.as_path()).filter(|p| !p.as_os_str().is_empty())
+            }
+            _ => None,
+        }
+    }
+
+    /// I/O error on a path
+    pub(crate) fn io<P: Into<PathBuf>>(path: P, source: std::io::Error) -> Self {
+        Self::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    /// Parse error of a file
+    pub(crate) fn parse<P, E>(path: P, source: E) -> Self
+    where
+        P: Into<PathBuf>,
+        E: Into<BoxError>,
+    {
+        Self::Parse {
+            path: path.into(),
+            source: source.into(),
+        }
+    }
+
+    /// Set the path of a parse error e.g. the path of the file whose
+    /// contents were parsed, or of an I/O error whose path is unknown
+    /// e.g. the path of the file written to
+    pub(crate) fn in_file<P: Into<PathBuf>>(self, path: P) -> Self {
+        match self {
+            Self::Parse { source, .. } => Self::parse(path, source),
+            Self::Io { path: p, source } if p.as_os_str().is_empty() => Self::io(path, source),
+            err => err,
+        }
+    }
+
+    /// Invalid configuration or option value
+    pub(crate) fn config<S: Into<String>>(message: S) -> Self {
+        Self::Config(message.into())
+    }
+
+    /// Formatting error of a value
+    pub(crate) fn format<T, E>(value: &T, source: E) -> Self
+    where
+        T: std::fmt::Debug,
+        E: Into<BoxError>,
+    {
+        Self::Format {
+            value: format!("{value:?}"),
+            source: source.into(),
+        }
+    }
+
+    /// Failed external command
+    pub(crate) fn command<S1: Into<String>, S2: Into<String>>(command: S1, message: S2) -> Self {
+        Self::Command {
+            command: command.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    /// I/O error on an unknown path e.g. when writing to an `impl Write`
+    fn from(source: std::io::Error) -> Self {
+        Self::io(PathBuf::new(), source)
+    }
+}
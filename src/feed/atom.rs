@@ -0,0 +1,171 @@
+//! Write chapters as an Atom 1.0 feed
+//!
+//! See <https://datatracker.ietf.org/doc/html/rfc4287>.
+
+use std::io::Write;
+
+use quick_xml::events::BytesText;
+use quick_xml::writer::Writer;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+
+use super::Channel;
+use super::ChapterInfo;
+use crate::Error;
+use crate::Result;
+
+/// Write an Atom feed, given feed-level information and a list of
+/// chapters.
+pub(super) fn write_atom<W: Write>(
+    channel: &Channel<'_>,
+    entries: &[ChapterInfo],
+    w: &mut W,
+) -> Result<()> {
+    let feed_updated = format_date(channel.updated)?;
+    // Atom requires a date for each entry: that of the feed by default
+    let entry_dates = entries
+        .iter()
+        .map(|e| {
+            e.updated
+                .map_or_else(|| Ok(feed_updated.clone()), format_date)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let mut writer = Writer::new_with_indent(w, b' ', 2);
+    writer
+        .get_mut()
+        .write_all(b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n")?;
+    writer
+        .create_element("feed")
+        .with_attribute(("xmlns", "http://www.w3.org/2005/Atom"))
+        .write_inner_content(|writer| {
+            writer
+                .create_element("title")
+                .write_text_content(BytesText::new(channel.title))?;
+            if let Some(description) = channel.description {
+                writer
+                    .create_element("subtitle")
+                    .write_text_content(BytesText::new(description))?;
+            }
+            writer
+                .create_element("id")
+                .write_text_content(BytesText::new(channel.site_root.as_str()))?;
+            writer
+                .create_element("link")
+                .with_attribute(("href", channel.site_root.as_str()))
+                .write_empty()?;
+            writer
+                .create_element("link")
+                .with_attribute(("rel", "self"))
+                .with_attribute(("href", channel.feed_url.as_str()))
+                .write_empty()?;
+            writer
+                .create_element("updated")
+                .write_text_content(BytesText::new(&feed_updated))?;
+            writer
+                .create_element("generator")
+                .write_text_content(BytesText::new("mdbook-utils"))?;
+            for (entry, updated) in entries.iter().zip(&entry_dates) {
+                writer
+                    .create_element("entry")
+                    .write_inner_content(|writer| {
+                        writer
+                            .create_element("title")
+                            .write_text_content(BytesText::new(&entry.title))?;
+                        writer
+                            .create_element("id")
+                            .write_text_content(BytesText::new(entry.url.as_str()))?;
+                        writer
+                            .create_element("link")
+                            .with_attribute(("href", entry.url.as_str()))
+                            .write_empty()?;
+                        writer
+                            .create_element("updated")
+                            .write_text_content(BytesText::new(updated))?;
+                        if !entry.summary.is_empty() {
+                            writer
+                                .create_element("summary")
+                                .write_text_content(BytesText::new(&entry.summary))?;
+                        }
+                        for tag in &entry.tags {
+                            writer
+                                .create_element("category")
+                                .with_attribute(("term", tag.as_str()))
+                                .write_empty()?;
+                        }
+                        Ok(())
+                    })?;
+            }
+            Ok(())
+        })?;
+    Ok(())
+}
+
+/// Format a date as RFC 3339, as required by Atom.
+fn format_date(date: OffsetDateTime) -> Result<String> {
+    date.format(&Rfc3339).map_err(|e| Error::format(&date, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+    use url::Url;
+
+    use super::*;
+
+    #[test]
+    fn test_write_atom() {
+        let site_root = Url::parse("https://example.com/book/").unwrap();
+        let feed_url = site_root.join("atom.xml").unwrap();
+        let channel = Channel {
+            title: "Cookbook & co",
+            description: None,
+            site_root: &site_root,
+            feed_url: &feed_url,
+            updated: datetime!(2024-05-01 0:00 UTC),
+        };
+        let entries = vec![ChapterInfo {
+            title: "Intro".into(),
+            path: "intro.md".into(),
+            url: site_root.join("intro.html").unwrap(),
+            summary: "Hello <world>".into(),
+            headings: Vec::new(),
+            updated: None,
+            tags: vec!["cli".into()],
+        }];
+        let mut buf = Vec::new();
+        write_atom(&channel, &entries, &mut buf).unwrap();
+        let xml = String::from_utf8(buf).unwrap();
+        assert!(xml.contains("<title>Cookbook &amp; co</title>"));
+        assert!(xml.contains(r#"<link rel="self" href="https://example.com/book/atom.xml"/>"#));
+        assert!(xml.contains("<id>https://example.com/book/intro.html</id>"));
+        assert!(xml.contains("<updated>2024-05-01T00:00:00Z</updated>"));
+        assert!(xml.contains("<summary>Hello &lt;world&gt;</summary>"));
+        assert!(xml.contains(r#"<category term="cli"/>"#));
+    }
+
+    #[test]
+    fn test_write_atom_invalid_date() {
+        let site_root = Url::parse("https://example.com/book/").unwrap();
+        let channel = Channel {
+            title: "Book",
+            description: None,
+            site_root: &site_root,
+            feed_url: &site_root,
+            updated: datetime!(2024-05-01 0:00 UTC),
+        };
+        // RFC 3339 has no seconds in UTC offsets
+        let entries = vec![ChapterInfo {
+            title: "Intro".into(),
+            path: "intro.md".into(),
+            url: site_root.join("intro.html").unwrap(),
+            summary: String::new(),
+            headings: Vec::new(),
+            updated: Some(datetime!(2024-01-01 0:00 +0:00:30)),
+            tags: Vec::new(),
+        }];
+        let mut buf = Vec::new();
+        let err = write_atom(&channel, &entries, &mut buf).unwrap_err();
+        assert!(matches!(err, crate::Error::Format { .. }));
+        assert!(buf.is_empty());
+    }
+}
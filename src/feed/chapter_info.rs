@@ -0,0 +1,274 @@
+//! Collect the title, summary, headings and date of each chapter
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use pulldown_cmark::Event;
+use pulldown_cmark::Tag;
+use pulldown_cmark::TagEnd;
+use time::OffsetDateTime;
+use tracing::debug;
+use tracing::warn;
+use url::Url;
+
+use super::dates;
+use crate::Result;
+use crate::fs;
+use crate::parser;
+use crate::parser::ParserOptions;
+use crate::sitemap;
+use crate::summary::Chapter;
+
+/// Information about a chapter, used to build feeds and search indices
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ChapterInfo {
+    /// Title of the chapter, from the front matter or SUMMARY.md
+    pub(crate) title: String,
+    /// Path of the Markdown file, relative to the source directory
+    pub(crate) path: String,
+    /// Absolute URL of the rendered chapter
+    pub(crate) url: Url,
+    /// Summary, from the front matter or the first paragraph
+    pub(crate) summary: String,
+    /// Headings found in the chapter
+    pub(crate) headings: Vec<Heading>,
+    /// Date of the last update, if known
+    pub(crate) updated: Option<OffsetDateTime>,
+    /// Tags, from the front matter
+    pub(crate) tags: Vec<String>,
+}
+
+/// A heading within a chapter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Heading {
+    /// Heading level, from 1 to 6
+    pub(crate) level: u8,
+    /// Text of the heading
+    pub(crate) title: String,
+    /// Anchor (HTML id), computed as mdBook does
+    pub(crate) id: String,
+}
+
+/// Read the chapters listed in SUMMARY.md and collect their information.
+///
+/// Draft chapters, chapters matched by the `exclude` globs, and
+/// chapters whose file cannot be read are skipped.
+///
+/// markdown_src_dir_path: path to the source directory.
+///
+/// chapters: the chapters of the book, in SUMMARY.md order.
+///
+/// site_root: the URL of the root of the book.
+///
+/// exclude: glob patterns of chapter paths to skip.
+///
+/// use_git: if true, retrieve dates from `git` when the front matter
+/// does not provide one.
+///
+/// parser_options: the Markdown dialect of the chapters.
+pub(crate) fn collect_chapter_info(
+    markdown_src_dir_path: &Path,
+    chapters: &[Chapter],
+    site_root: &Url,
+    exclude: &[String],
+    use_git: bool,
+    parser_options: &ParserOptions,
+) -> Result<Vec<ChapterInfo>> {
+    let exclude = fs::build_globset(exclude)?;
+    let mut infos = Vec::new();
+    for chapter in chapters {
+        let Some(path) = chapter.path.as_deref() else {
+            continue;
+        };
+        if exclude.is_match(path) {
+            debug!("Excluded: {path}");
+            continue;
+        }
+        let file_path = markdown_src_dir_path.join(path);
+        let markdown = match std::fs::read_to_string(&file_path) {
+            Ok(m) => m,
+            Err(e) => {
+                warn!("Skipping {}: {e}", file_path.display());
+                continue;
+            }
+        };
+        let url = match site_root.join(&sitemap::chapter_html_path(path)) {
+            Ok(u) => u,
+            Err(e) => {
+                warn!("Skipping {path}: invalid URL: {e}");
+                continue;
+            }
+        };
+        let mut info =
+            chapter_info_from_markdown(&chapter.title, path, &markdown, url, parser_options);
+        if info.updated.is_none() {
+            info.updated = if use_git {
+                dates::git_last_commit_date(&file_path)
+            } else {
+                None
+            }
+            .or_else(|| dates::modified_date(&file_path));
+        }
+        infos.push(info);
+    }
+    Ok(infos)
+}
+
+/// Extract the information of a chapter from its Markdown.
+///
+/// The front matter keys `title`, `summary` (or `description`) and
+/// `updated` (or `date`) take precedence over the SUMMARY.md title, the
+/// first paragraph and the `git` history; `tags` (or `keywords`) become
+/// the categories of the feed entry.
+///
+/// title: title of the chapter in SUMMARY.md.
+///
+/// path: path of the chapter, relative to the source directory.
+///
+/// markdown: contents of the chapter.
+///
+/// url: absolute URL of the rendered chapter.
+///
+/// parser_options: the Markdown dialect of the chapter.
+pub(crate) fn chapter_info_from_markdown(
+    title: &str,
+    path: &str,
+    markdown: &str,
+    url: Url,
+    parser_options: &ParserOptions,
+) -> ChapterInfo {
+    let (front_matter, markdown) = parser::split_front_matter(markdown);
+    let front_matter = front_matter.unwrap_or_default();
+
+    let (first_paragraph, headings) = scan_markdown(markdown, parser_options);
+    ChapterInfo {
+        title: front_matter.title().unwrap_or(title).to_string(),
+        path: path.to_string(),
+        url,
+        summary: front_matter
+            .summary()
+            .map_or(first_paragraph, str::to_string),
+        headings,
+        updated: front_matter.date().and_then(dates::parse_date),
+        tags: front_matter.tags().into_iter().map(String::from).collect(),
+    }
+}
+
+/// Returns the plain text of the first paragraph and the list of
+/// headings.
+///
+/// Paragraphs that only contain mdBook directives (e.g.
+/// `{{#include refs.md}}`) are skipped.
+fn scan_markdown(markdown: &str, parser_options: &ParserOptions) -> (String, Vec<Heading>) {
+    let mut first_paragraph: Option<String> = None;
+    let mut paragraph: Option<String> = None;
+    let mut heading: Option<(u8, String)> = None;
+    let mut headings = Vec::new();
+    let mut ids: HashMap<String, usize> = HashMap::new();
+
+    for event in parser::get_parser(markdown, parser_options) {
+        match event {
+            Event::Start(Tag::Paragraph) if first_paragraph.is_none() => {
+                paragraph = Some(String::new());
+            }
+            Event::End(TagEnd::Paragraph) => {
+                if let Some(p) = paragraph.take() {
+                    let p = p.trim();
+                    if !p.is_empty() && !p.starts_with("{{#") {
+                        first_paragraph = Some(p.to_string());
+                    }
+                }
+            }
+            Event::Start(Tag::Heading { level, .. }) => {
+                heading = Some((level as u8, String::new()));
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((level, title)) = heading.take() {
+                    let id = unique_id(&normalize_id(&title), &mut ids);
+                    headings.push(Heading { level, title, id });
+                }
+            }
+            Event::Text(t) | Event::Code(t) => {
+                if let Some((_, h)) = heading.as_mut() {
+                    h.push_str(&t);
+                } else if let Some(p) = paragraph.as_mut() {
+                    p.push_str(&t);
+                }
+            }
+            Event::SoftBreak | Event::HardBreak => {
+                if let Some(p) = paragraph.as_mut() {
+                    p.push(' ');
+                }
+            }
+            _ => {}
+        }
+    }
+    (first_paragraph.unwrap_or_default(), headings)
+}
+
+/// Compute the HTML id of a heading, like mdBook does:
+/// lowercase alphanumeric characters, `-` and `_` are kept,
+/// whitespace is replaced by `-`, and everything else is removed.
+fn normalize_id(title: &str) -> String {
+    title
+        .chars()
+        .filter_map(|c| {
+            if c.is_alphanumeric() || c == '_' || c == '-' {
+                Some(c.to_lowercase().collect::<String>())
+            } else if c.is_whitespace() {
+                Some("-".to_string())
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Make an id unique within a chapter by appending `-1`, `-2`... to
+/// repeated ids, like mdBook does.
+fn unique_id(id: &str, ids: &mut HashMap<String, usize>) -> String {
+    let count = ids.entry(id.to_string()).or_insert(0);
+    let unique = if *count == 0 {
+        id.to_string()
+    } else {
+        format!("{id}-{count}")
+    };
+    *count += 1;
+    unique
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn test_chapter_info_from_markdown() {
+        let md = "# Command Line\n\n{{#include refs.md}}\n\nParse `args`\nwith clap.\n\n## Usage\n\n## Usage\n\nMore text.\n";
+        let url = Url::parse("https://example.com/cli.html").unwrap();
+        let info = chapter_info_from_markdown("CLI", "cli.md", md, url, &ParserOptions::default());
+        assert_eq!(info.title, "CLI");
+        assert_eq!(info.summary, "Parse args with clap.");
+        assert_eq!(info.updated, None);
+        let ids: Vec<_> = info.headings.iter().map(|h| h.id.as_str()).collect();
+        assert_eq!(ids, vec!["command-line", "usage", "usage-1"]);
+        assert_eq!(info.headings[1].level, 2);
+    }
+
+    #[test]
+    fn test_chapter_info_from_front_matter() {
+        let md = "---\ntitle: Args\ndescription: About args\ndate: 2024-05-01\n---\n\nText.\n";
+        let url = Url::parse("https://example.com/cli.html").unwrap();
+        let info = chapter_info_from_markdown("CLI", "cli.md", md, url, &ParserOptions::default());
+        assert_eq!(info.title, "Args");
+        assert_eq!(info.summary, "About args");
+        assert_eq!(info.updated, Some(datetime!(2024-05-01 0:00 UTC)));
+    }
+
+    #[test]
+    fn test_normalize_id() {
+        assert_eq!(normalize_id("Hello, World!"), "hello-world");
+        assert_eq!(normalize_id("`std::fs` API"), "stdfs-api");
+    }
+}
@@ -0,0 +1,73 @@
+//! Retrieve the date a chapter was last updated
+
+use std::path::Path;
+use std::process::Command;
+
+use time::Date;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+use time::macros::format_description;
+use tracing::debug;
+
+/// Parse a date written in a front matter, either as RFC 3339
+/// (e.g. `2024-05-01T10:00:00+02:00`) or as a plain date
+/// (e.g. `2024-05-01`, interpreted as midnight UTC).
+pub(crate) fn parse_date(s: &str) -> Option<OffsetDateTime> {
+    let s = s.trim();
+    OffsetDateTime::parse(s, &Rfc3339).ok().or_else(|| {
+        Date::parse(s, format_description!("[year]-[month]-[day]"))
+            .ok()
+            .map(|d| d.midnight().assume_utc())
+    })
+}
+
+/// Returns the committer date of the last `git` commit that modified a
+/// file, if the file is tracked by `git` and `git` is available.
+///
+/// file_path: path to the file.
+pub(crate) fn git_last_commit_date(file_path: &Path) -> Option<OffsetDateTime> {
+    let dir = file_path.parent()?;
+    let file_name = file_path.file_name()?;
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["log", "-1", "--format=%cI", "--"])
+        .arg(file_name)
+        .output()
+        .inspect_err(|e| debug!("[git_last_commit_date] Could not run git: {e}"))
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    // Empty if the file is not tracked
+    parse_date(std::str::from_utf8(&output.stdout).ok()?)
+}
+
+/// Returns the modification time of a file, if available.
+///
+/// file_path: path to the file.
+pub(crate) fn modified_date(file_path: &Path) -> Option<OffsetDateTime> {
+    let modified = std::fs::metadata(file_path).ok()?.modified().ok()?;
+    Some(OffsetDateTime::from(modified))
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(
+            parse_date("2024-05-01"),
+            Some(datetime!(2024-05-01 0:00 UTC))
+        );
+        assert_eq!(
+            parse_date("2024-05-01T10:00:00+02:00"),
+            Some(datetime!(2024-05-01 10:00 +2))
+        );
+        assert_eq!(parse_date(""), None);
+        assert_eq!(parse_date("May 1st"), None);
+    }
+}
@@ -0,0 +1,122 @@
+//! Write a JSON index of the chapters and their headings
+//!
+//! The index is meant to be consumed by external search engines, e.g.:
+//!
+//! ```json
+//! {
+//!   "chapters": [
+//!     {
+//!       "title": "Introduction",
+//!       "path": "intro.md",
+//!       "url": "https://example.com/mybook/intro.html",
+//!       "summary": "...",
+//!       "updated": "2024-05-01T00:00:00Z",
+//!       "tags": ["cli"],
+//!       "headings": [
+//!         { "level": 2, "title": "Usage", "url": "https://example.com/mybook/intro.html#usage" }
+//!       ]
+//!     }
+//!   ]
+//! }
+//! ```
+
+use std::io::Write;
+
+use serde::Serialize;
+use time::format_description::well_known::Rfc3339;
+
+use super::ChapterInfo;
+use crate::Result;
+
+#[derive(Serialize)]
+struct SearchIndex<'a> {
+    chapters: Vec<IndexEntry<'a>>,
+}
+
+#[derive(Serialize)]
+struct IndexEntry<'a> {
+    title: &'a str,
+    path: &'a str,
+    url: &'a str,
+    summary: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    updated: Option<String>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    tags: &'a [String],
+    headings: Vec<IndexHeading<'a>>,
+}
+
+#[derive(Serialize)]
+struct IndexHeading<'a> {
+    level: u8,
+    title: &'a str,
+    url: String,
+}
+
+/// Write the JSON index of a list of chapters.
+pub(super) fn write_json_index<W: Write>(chapters: &[ChapterInfo], w: &mut W) -> Result<()> {
+    let index = SearchIndex {
+        chapters: chapters
+            .iter()
+            .map(|c| IndexEntry {
+                title: &c.title,
+                path: &c.path,
+                url: c.url.as_str(),
+                summary: &c.summary,
+                updated: c.updated.and_then(|d| d.format(&Rfc3339).ok()),
+                tags: &c.tags,
+                headings: c
+                    .headings
+                    .iter()
+                    .map(|h| {
+                        let mut url = c.url.clone();
+                        url.set_fragment(Some(&h.id));
+                        IndexHeading {
+                            level: h.level,
+                            title: &h.title,
+                            url: url.to_string(),
+                        }
+                    })
+                    .collect(),
+            })
+            .collect(),
+    };
+    serde_json::to_writer_pretty(&mut *w, &index).map_err(std::io::Error::from)?;
+    writeln!(w)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use super::*;
+    use crate::feed::Heading;
+
+    #[test]
+    fn test_write_json_index() {
+        let chapters = vec![ChapterInfo {
+            title: "Intro".into(),
+            path: "intro.md".into(),
+            url: Url::parse("https://example.com/intro.html").unwrap(),
+            summary: "Hello".into(),
+            headings: vec![Heading {
+                level: 2,
+                title: "Usage".into(),
+                id: "usage".into(),
+            }],
+            updated: None,
+            tags: Vec::new(),
+        }];
+        let mut buf = Vec::new();
+        write_json_index(&chapters, &mut buf).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        assert_eq!(json["chapters"][0]["title"], "Intro");
+        assert!(json["chapters"][0].get("updated").is_none());
+        assert!(json["chapters"][0].get("tags").is_none());
+        assert_eq!(
+            json["chapters"][0]["headings"][0]["url"],
+            "https://example.com/intro.html#usage"
+        );
+    }
+}
@@ -0,0 +1,206 @@
+//! Atom / RSS feed and JSON search index generation.
+mod atom;
+mod chapter_info;
+mod dates;
+mod json_index;
+mod rss;
+
+use std::io::Write;
+use std::str::FromStr;
+
+pub(crate) use chapter_info::*;
+pub(crate) use dates::parse_date;
+use time::OffsetDateTime;
+use tracing::info;
+use url::Url;
+
+use crate::Result;
+use crate::parser::ParserOptions;
+
+/// Format of the feed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FeedFormat {
+    /// Atom 1.0 (RFC 4287)
+    #[default]
+    Atom,
+    /// RSS 2.0
+    Rss,
+}
+
+impl FromStr for FeedFormat {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "atom" => Ok(Self::Atom),
+            "rss" => Ok(Self::Rss),
+            _ => Err(crate::Error::config(format!(
+                "Unknown feed format: {s}. Use `atom` or `rss`."
+            ))),
+        }
+    }
+}
+
+/// Options for the generation of the feed and of the JSON search index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedOptions {
+    /// Title of the feed, typically the title of the book
+    pub title: String,
+
+    /// Optional description of the feed
+    pub description: Option<String>,
+
+    /// Atom or RSS
+    pub format: FeedFormat,
+
+    /// Path of the book relative to the base URL's host, as set by
+    /// `output.html.site-url` in `book.toml` e.g. `/mybook/`.
+    pub site_url: Option<String>,
+
+    /// Glob patterns of chapter paths (relative to the source
+    /// directory) to leave out e.g. `*refs.md`.
+    pub exclude: Vec<String>,
+
+    /// Maximum number of (most recently updated) chapters in the feed.
+    /// `None` means no limit. The JSON index always lists all chapters.
+    pub max_entries: Option<usize>,
+
+    /// If `true`, chapters without a date in their front matter are dated
+    /// by their last `git` commit, then by their modification time.
+    /// If `false`, only the modification time is used.
+    pub use_git: bool,
+
+    /// Markdown dialect of the chapters
+    pub parser: ParserOptions,
+}
+
+impl Default for FeedOptions {
+    fn default() -> Self {
+        Self {
+            title: "Book".to_string(),
+            description: None,
+            format: FeedFormat::default(),
+            site_url: None,
+            exclude: vec!["*refs.md".to_string()],
+            max_entries: Some(20),
+            use_git: true,
+            parser: ParserOptions::default(),
+        }
+    }
+}
+
+/// Feed-level information
+#[derive(Debug)]
+pub(crate) struct Channel<'a> {
+    /// Title of the feed
+    pub(crate) title: &'a str,
+    /// Description of the feed
+    pub(crate) description: Option<&'a str>,
+    /// URL of the root of the book
+    pub(crate) site_root: &'a Url,
+    /// URL of the feed itself
+    pub(crate) feed_url: &'a Url,
+    /// Date of the most recent update
+    pub(crate) updated: OffsetDateTime,
+}
+
+/// Write a feed for the most recently updated chapters.
+///
+/// chapters: the chapters of the book, in SUMMARY.md order.
+///
+/// site_root: the URL of the root of the book.
+///
+/// feed_url: the URL of the feed itself.
+///
+/// options: see [`FeedOptions`].
+///
+/// w: a writer (e.g. a File) to write the feed to.
+pub(crate) fn write_feed<W: Write>(
+    chapters: Vec<ChapterInfo>,
+    site_root: &Url,
+    feed_url: &Url,
+    options: &FeedOptions,
+    w: &mut W,
+) -> Result<()> {
+    let entries = most_recent(chapters, options.max_entries);
+    let channel = Channel {
+        title: &options.title,
+        description: options.description.as_deref(),
+        site_root,
+        feed_url,
+        updated: entries
+            .iter()
+            .filter_map(|e| e.updated)
+            .max()
+            .unwrap_or_else(OffsetDateTime::now_utc),
+    };
+    match options.format {
+        FeedFormat::Atom => atom::write_atom(&channel, &entries, w)?,
+        FeedFormat::Rss => rss::write_rss(&channel, &entries, w)?,
+    }
+    info!("Feed created.");
+    Ok(())
+}
+
+/// Write a JSON index of the chapters and their headings, for use by
+/// external search engines.
+///
+/// chapters: the chapters of the book, in SUMMARY.md order.
+///
+/// w: a writer (e.g. a File) to write the index to.
+pub(crate) fn write_search_index<W: Write>(chapters: &[ChapterInfo], w: &mut W) -> Result<()> {
+    json_index::write_json_index(chapters, w)?;
+    info!("JSON search index created.");
+    Ok(())
+}
+
+/// Sort chapters by date, most recent first; chapters without a date go
+/// last. The SUMMARY.md order is kept for chapters with the same date.
+fn most_recent(mut chapters: Vec<ChapterInfo>, max_entries: Option<usize>) -> Vec<ChapterInfo> {
+    chapters.sort_by_key(|c| std::cmp::Reverse(c.updated));
+    if let Some(max) = max_entries {
+        chapters.truncate(max);
+    }
+    chapters
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn chapter(title: &str, updated: Option<OffsetDateTime>) -> ChapterInfo {
+        ChapterInfo {
+            title: title.into(),
+            path: format!("{title}.md"),
+            url: Url::parse(&format!("https://example.com/{title}.html")).unwrap(),
+            summary: String::new(),
+            headings: Vec::new(),
+            updated,
+            tags: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_most_recent() {
+        let chapters = vec![
+            chapter("a", Some(datetime!(2024-01-01 0:00 UTC))),
+            chapter("b", None),
+            chapter("c", Some(datetime!(2024-03-01 0:00 UTC))),
+            chapter("d", Some(datetime!(2024-01-01 0:00 UTC))),
+        ];
+        let titles: Vec<_> = most_recent(chapters, Some(3))
+            .into_iter()
+            .map(|c| c.title)
+            .collect();
+        assert_eq!(titles, vec!["c", "a", "d"]);
+    }
+
+    #[test]
+    fn test_feed_format_from_str() {
+        assert_eq!("RSS".parse::<FeedFormat>().unwrap(), FeedFormat::Rss);
+        assert_eq!("atom".parse::<FeedFormat>().unwrap(), FeedFormat::Atom);
+        assert!("json".parse::<FeedFormat>().is_err());
+    }
+}
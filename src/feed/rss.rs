@@ -0,0 +1,145 @@
+//! Write chapters as an RSS 2.0 feed
+//!
+//! See <https://www.rssboard.org/rss-specification>.
+
+use std::io::Write;
+
+use quick_xml::events::BytesText;
+use quick_xml::writer::Writer;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc2822;
+
+use super::Channel;
+use super::ChapterInfo;
+use crate::Error;
+use crate::Result;
+
+/// Write an RSS feed, given feed-level information and a list of
+/// chapters.
+pub(super) fn write_rss<W: Write>(
+    channel: &Channel<'_>,
+    entries: &[ChapterInfo],
+    w: &mut W,
+) -> Result<()> {
+    let last_build_date = format_date(channel.updated)?;
+    let entry_dates = entries
+        .iter()
+        .map(|e| e.updated.map(format_date).transpose())
+        .collect::<Result<Vec<_>>>()?;
+    let mut writer = Writer::new_with_indent(w, b' ', 2);
+    writer
+        .get_mut()
+        .write_all(b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n")?;
+    writer
+        .create_element("rss")
+        .with_attribute(("version", "2.0"))
+        .with_attribute(("xmlns:atom", "http://www.w3.org/2005/Atom"))
+        .write_inner_content(|writer| {
+            writer
+                .create_element("channel")
+                .write_inner_content(|writer| {
+                    writer
+                        .create_element("title")
+                        .write_text_content(BytesText::new(channel.title))?;
+                    writer
+                        .create_element("link")
+                        .write_text_content(BytesText::new(channel.site_root.as_str()))?;
+                    // `description` is required by RSS 2.0
+                    writer
+                        .create_element("description")
+                        .write_text_content(BytesText::new(
+                            channel.description.unwrap_or(channel.title),
+                        ))?;
+                    writer
+                        .create_element("atom:link")
+                        .with_attribute(("href", channel.feed_url.as_str()))
+                        .with_attribute(("rel", "self"))
+                        .with_attribute(("type", "application/rss+xml"))
+                        .write_empty()?;
+                    writer
+                        .create_element("lastBuildDate")
+                        .write_text_content(BytesText::new(&last_build_date))?;
+                    writer
+                        .create_element("generator")
+                        .write_text_content(BytesText::new("mdbook-utils"))?;
+                    for (entry, date) in entries.iter().zip(&entry_dates) {
+                        writer
+                            .create_element("item")
+                            .write_inner_content(|writer| {
+                                writer
+                                    .create_element("title")
+                                    .write_text_content(BytesText::new(&entry.title))?;
+                                writer
+                                    .create_element("link")
+                                    .write_text_content(BytesText::new(entry.url.as_str()))?;
+                                writer
+                                    .create_element("guid")
+                                    .with_attribute(("isPermaLink", "true"))
+                                    .write_text_content(BytesText::new(entry.url.as_str()))?;
+                                if let Some(date) = date {
+                                    writer
+                                        .create_element("pubDate")
+                                        .write_text_content(BytesText::new(date))?;
+                                }
+                                if !entry.summary.is_empty() {
+                                    writer
+                                        .create_element("description")
+                                        .write_text_content(BytesText::new(&entry.summary))?;
+                                }
+                                for tag in &entry.tags {
+                                    writer
+                                        .create_element("category")
+                                        .write_text_content(BytesText::new(tag))?;
+                                }
+                                Ok(())
+                            })?;
+                    }
+                    Ok(())
+                })?;
+            Ok(())
+        })?;
+    Ok(())
+}
+
+/// Format a date as RFC 2822, as required by RSS.
+fn format_date(date: OffsetDateTime) -> Result<String> {
+    date.format(&Rfc2822).map_err(|e| Error::format(&date, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+    use url::Url;
+
+    use super::*;
+
+    #[test]
+    fn test_write_rss() {
+        let site_root = Url::parse("https://example.com/book/").unwrap();
+        let feed_url = site_root.join("rss.xml").unwrap();
+        let channel = Channel {
+            title: "Cookbook",
+            description: Some("Recipes"),
+            site_root: &site_root,
+            feed_url: &feed_url,
+            updated: datetime!(2024-05-01 0:00 UTC),
+        };
+        let entries = vec![ChapterInfo {
+            title: "Intro".into(),
+            path: "intro.md".into(),
+            url: site_root.join("intro.html").unwrap(),
+            summary: "Hello".into(),
+            headings: Vec::new(),
+            updated: Some(datetime!(2024-04-30 12:00 UTC)),
+            tags: vec!["cli".into()],
+        }];
+        let mut buf = Vec::new();
+        write_rss(&channel, &entries, &mut buf).unwrap();
+        let xml = String::from_utf8(buf).unwrap();
+        assert!(xml.contains("<description>Recipes</description>"));
+        assert!(xml.contains("<lastBuildDate>Wed, 01 May 2024 00:00:00 +0000</lastBuildDate>"));
+        assert!(xml.contains("<link>https://example.com/book/intro.html</link>"));
+        assert!(xml.contains("<pubDate>Tue, 30 Apr 2024 12:00:00 +0000</pubDate>"));
+        assert!(xml.contains("<category>cli</category>"));
+    }
+}
@@ -0,0 +1,156 @@
+//! Normalize the fences and info strings of the code blocks
+
+use pulldown_cmark::CodeBlockKind;
+use pulldown_cmark::Event;
+use pulldown_cmark::Tag;
+
+use super::FenceStyle;
+use super::FormatOptions;
+use crate::lint::TextEdit;
+use crate::parser;
+
+/// Returns the edits of the opening and closing fences of the fenced
+/// code blocks of a Markdown document.
+///
+/// Code blocks in block quotes, and code blocks that are not closed,
+/// are left as written.
+pub(crate) fn format_fences(contents: &str, options: &FormatOptions) -> Vec<TextEdit> {
+    if options.fence == FenceStyle::Keep && !options.info_strings {
+        return Vec::new();
+    }
+    let mut edits = Vec::new();
+    for (event, range) in parser::get_parser(contents, &options.parser).into_offset_iter() {
+        if let Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(_))) = event {
+            edits.extend(format_fence(contents, range.start, range.end, options));
+        }
+    }
+    edits
+}
+
+/// Returns the edits of a fenced code block, given its byte range
+fn format_fence(
+    contents: &str,
+    start: usize,
+    end: usize,
+    options: &FormatOptions,
+) -> Vec<TextEdit> {
+    let line_start = contents[..start].rfind('\n').map_or(0, |i| i + 1);
+    if !contents[line_start..start].trim().is_empty() {
+        return Vec::new();
+    }
+    let block = &contents[line_start..end];
+    let Some(open_end) = block.find('\n') else {
+        return Vec::new();
+    };
+    let open = block[..open_end].trim_end_matches('\r');
+    let open_start = open.len() - open.trim_start().len();
+    let open = open.trim_start();
+    let Some(fence_char) = open.chars().next().filter(|c| matches!(c, '`' | '~')) else {
+        return Vec::new();
+    };
+    let fence_len = open.len() - open.trim_start_matches(fence_char).len();
+    let info = &open[fence_len..];
+
+    // The closing fence is the last line of the block
+    let rest = block[open_end + 1..].trim_end_matches(['\n', '\r']);
+    let close_line_start = rest.rfind('\n').map_or(0, |i| i + 1);
+    let (code, close) = rest.split_at(close_line_start);
+    let close_start = close.len() - close.trim_start().len();
+    let close = close.trim();
+    if close.len() < fence_len || close.chars().any(|c| c != fence_char) {
+        return Vec::new();
+    }
+
+    let mut new_char = match options.fence {
+        FenceStyle::Keep => fence_char,
+        FenceStyle::Backtick => '`',
+        FenceStyle::Tilde => '~',
+    };
+    // A backtick fence cannot have backticks in its info string
+    if new_char == '`' && info.contains('`') {
+        new_char = fence_char;
+    }
+    let new_len = if options.fence == FenceStyle::Keep {
+        fence_len
+    } else {
+        // Longer than any run of the fence character that starts a line
+        // of the code, which would close the block
+        let longest = code
+            .lines()
+            .map(|l| {
+                let l = l.trim_start();
+                l.len() - l.trim_start_matches(new_char).len()
+            })
+            .max()
+            .unwrap_or(0);
+        (longest + 1).max(3)
+    };
+    let fence: String = std::iter::repeat_n(new_char, new_len).collect();
+    let new_info = if options.info_strings {
+        normalize_info_string(info)
+    } else {
+        info.to_string()
+    };
+
+    let open_offset = line_start + open_start;
+    let close_offset = line_start + open_end + 1 + close_line_start + close_start;
+    vec![
+        TextEdit {
+            range: open_offset..open_offset + open.len(),
+            replacement: format!("{fence}{new_info}"),
+        },
+        TextEdit {
+            range: close_offset..close_offset + close.len(),
+            replacement: fence,
+        },
+    ]
+}
+
+/// Normalize an info string: trim it and separate its attributes by
+/// commas e.g. ` rust, editable` becomes `rust,editable`. Info strings
+/// with quotes, braces or `=` are only trimmed.
+fn normalize_info_string(info: &str) -> String {
+    let info = info.trim();
+    if info.contains(['"', '\'', '{', '=']) {
+        return info.to_string();
+    }
+    info.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::format_str;
+    use super::*;
+
+    #[test]
+    fn test_format_fences() {
+        let options = FormatOptions::default();
+        let md = "~~~~ rust  editable\n```\nnested\n```\n~~~~\n\n- item\n\n  ``` text\n  a\n  ```\n\n> ~~~\n> quoted\n> ~~~\n\n    indented\n";
+        assert_eq!(
+            format_str(md, &options),
+            "````rust,editable\n```\nnested\n```\n````\n\n- item\n\n  ```text\n  a\n  ```\n\n> ~~~\n> quoted\n> ~~~\n\n    indented\n"
+        );
+        let options = FormatOptions {
+            fence: FenceStyle::Tilde,
+            info_strings: false,
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format_str("```` rust\ncode\n````\n", &options),
+            "~~~ rust\ncode\n~~~\n"
+        );
+    }
+
+    #[test]
+    fn test_normalize_info_string() {
+        assert_eq!(normalize_info_string(" rust, editable "), "rust,editable");
+        assert_eq!(normalize_info_string("rust ignore"), "rust,ignore");
+        assert_eq!(
+            normalize_info_string("{.rust title=\"a b\"}"),
+            "{.rust title=\"a b\"}"
+        );
+    }
+}
@@ -0,0 +1,424 @@
+//! Turn inline links into reference-style links, or the reverse
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::ops::Range;
+
+use heck::ToKebabCase;
+use pulldown_cmark::BrokenLink;
+use pulldown_cmark::CowStr;
+use pulldown_cmark::Event;
+use pulldown_cmark::LinkType;
+use pulldown_cmark::Parser;
+use pulldown_cmark::Tag;
+use pulldown_cmark::TagEnd;
+
+use super::Book;
+use super::FilePlan;
+use super::FormatOptions;
+use super::LinkStyle;
+use super::RefDefLine;
+use super::RefDefPlacement;
+use super::is_summary;
+use crate::link::DEFAULT_RULES;
+use crate::lint::TextEdit;
+use crate::parser::ParserOptions;
+
+/// Link or image, with the positions needed to rewrite it
+#[derive(Debug, Clone)]
+struct FmtLink {
+    link_type: LinkType,
+    url: String,
+    title: String,
+    /// Reference label, for reference-style links
+    label: String,
+    is_image: bool,
+    /// Byte range of the link in the file
+    span: Range<usize>,
+    /// Byte range of the text (or alt text) of the link
+    text: Range<usize>,
+}
+
+impl FmtLink {
+    fn is_reference(&self) -> bool {
+        matches!(
+            self.link_type,
+            LinkType::Reference
+                | LinkType::ReferenceUnknown
+                | LinkType::Collapsed
+                | LinkType::CollapsedUnknown
+                | LinkType::Shortcut
+                | LinkType::ShortcutUnknown
+        )
+    }
+}
+
+/// Parse the links and images of a file. The references that the file
+/// does not define are resolved with the reference definitions of the
+/// files it includes, then of the whole book.
+fn scan_links(book: &Book<'_>, i: usize, options: &ParserOptions) -> Vec<FmtLink> {
+    let contents = &book.files[i].contents;
+    let mut reachable: HashMap<String, (String, String)> = HashMap::new();
+    for d in book
+        .reachable_refdefs(i)
+        .chain(book.refdefs.values().map(|&(_, d)| d))
+    {
+        reachable
+            .entry(d.label.to_lowercase())
+            .or_insert_with(|| (d.dest.clone(), d.title.clone().unwrap_or_default()));
+    }
+    let parser = Parser::new_with_broken_link_callback(
+        contents,
+        options.to_options(),
+        Some(|link: BrokenLink<'_>| {
+            reachable
+                .get(&link.reference.to_lowercase())
+                .map(|(dest, title)| (CowStr::from(dest.clone()), CowStr::from(title.clone())))
+        }),
+    );
+
+    let mut links: Vec<FmtLink> = Vec::new();
+    // Indices of the links being parsed, innermost last
+    let mut open: Vec<usize> = Vec::new();
+    for (event, range) in parser.into_offset_iter() {
+        let (link_type, dest_url, title, id, is_image) = match event {
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => (link_type, dest_url, title, id, false),
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => (link_type, dest_url, title, id, true),
+            Event::End(TagEnd::Link | TagEnd::Image) => {
+                open.pop();
+                extend_text(&mut links, &open, &range);
+                continue;
+            }
+            _ => {
+                extend_text(&mut links, &open, &range);
+                continue;
+            }
+        };
+        extend_text(&mut links, &open, &range);
+        let text_start = range.start + if is_image { 2 } else { 1 };
+        open.push(links.len());
+        links.push(FmtLink {
+            link_type,
+            url: dest_url.to_string(),
+            title: title.to_string(),
+            label: id.to_string(),
+            is_image,
+            span: range.clone(),
+            text: text_start..text_start,
+        });
+    }
+    links
+        .into_iter()
+        .filter(|l| {
+            contents[l.span.clone()].starts_with(if l.is_image { "![" } else { "[" })
+                && l.text.end <= l.span.end
+        })
+        .collect()
+}
+
+/// Extend the text of the open links to the end of an event
+fn extend_text(links: &mut [FmtLink], open: &[usize], range: &Range<usize>) {
+    for &i in open.iter() {
+        links[i].text.end = links[i].text.end.max(range.end);
+    }
+}
+
+/// Returns `true` if a link points to a website
+fn is_http(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+/// Rewrite the links of all files, according to the link style.
+///
+/// In the reference style, the label of a link is that of an existing
+/// definition of its URL, or is given by the [default
+/// rules][crate::link::RuleSet::defaults], or is the kebab-cased link
+/// text. Links whose label is already defined with another URL are
+/// left as written.
+pub(crate) fn format_links(book: &Book<'_>, options: &FormatOptions, plans: &mut [FilePlan]) {
+    if options.link_style == LinkStyle::Keep {
+        return;
+    }
+    // Labels of the converted links, and of the reference-style links
+    // that are left as written
+    let mut converted: HashSet<String> = HashSet::new();
+    let mut used: HashSet<String> = HashSet::new();
+    // Labels added by the conversion, by file, with their URL
+    let mut added: HashMap<(usize, String), String> = HashMap::new();
+
+    for (i, f) in book.files.iter().enumerate() {
+        let links = scan_links(book, i, &options.parser);
+        if is_summary(f) {
+            used.extend(
+                links
+                    .iter()
+                    .filter(|l| l.is_reference())
+                    .map(|l| l.label.to_lowercase()),
+            );
+            continue;
+        }
+        // Replacement of each link; inner links (e.g. badges) are
+        // rewritten first, then within the text of the outer link
+        let mut replacements: Vec<Option<String>> = vec![None; links.len()];
+        for (k, l) in links.iter().enumerate().rev() {
+            let text = text_with_replacements(&f.contents, l, &links, &replacements);
+            let next_char = f.contents[l.span.end..].chars().next();
+            replacements[k] = match options.link_style {
+                LinkStyle::Reference if l.link_type == LinkType::Inline && is_http(&l.url) => {
+                    reference_label(book, i, l, &text, options, &mut added, plans)
+                        .map(|label| reference_link(l.is_image, &text, &label, next_char))
+                }
+                LinkStyle::Inline if l.is_reference() => {
+                    let link = inline_link(l.is_image, &text, &l.url, &l.title);
+                    if link.is_some() {
+                        converted.insert(l.label.to_lowercase());
+                    }
+                    link
+                }
+                _ => None,
+            };
+            if replacements[k].is_none() && l.is_reference() {
+                used.insert(l.label.to_lowercase());
+            }
+        }
+        // Keep the outermost replacements, which include the inner ones
+        let mut covered = 0;
+        for (l, r) in links.iter().zip(replacements) {
+            if l.span.start < covered {
+                continue;
+            }
+            if let Some(replacement) = r {
+                covered = l.span.end;
+                plans[i].edits.push(TextEdit {
+                    range: l.span.clone(),
+                    replacement,
+                });
+            }
+        }
+    }
+
+    if options.link_style == LinkStyle::Inline {
+        for (i, f) in book.files.iter().enumerate() {
+            for (k, d) in f.refdefs.iter().enumerate() {
+                let key = d.label.to_lowercase();
+                if converted.contains(&key) && !used.contains(&key) {
+                    plans[i].removed.insert(k);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the source of the text of a link, with the replacements of
+/// the links it contains
+fn text_with_replacements(
+    contents: &str,
+    link: &FmtLink,
+    links: &[FmtLink],
+    replacements: &[Option<String>],
+) -> String {
+    let mut text = String::new();
+    let mut last = link.text.start;
+    for (l, r) in links.iter().zip(replacements) {
+        if let Some(r) = r
+            && l.span.start >= last
+            && l.span.end <= link.text.end
+        {
+            text.push_str(&contents[last..l.span.start]);
+            text.push_str(r);
+            last = l.span.end;
+        }
+    }
+    text.push_str(&contents[last..link.text.end]);
+    text
+}
+
+/// Returns the label of an inline link turned into a reference-style
+/// link, and plan the reference definition to add if needed. Returns
+/// `None` if the label is defined with another URL.
+fn reference_label(
+    book: &Book<'_>,
+    i: usize,
+    link: &FmtLink,
+    text: &str,
+    options: &FormatOptions,
+    added: &mut HashMap<(usize, String), String>,
+    plans: &mut [FilePlan],
+) -> Option<String> {
+    let target = match options.refdefs {
+        RefDefPlacement::RefsFile => book.refs_file(i).unwrap_or(i),
+        _ => i,
+    };
+    let same_url = |dest: &str, title: &Option<String>| {
+        dest == link.url && title.as_deref().unwrap_or_default() == link.title
+    };
+    if let Some(d) = book
+        .reachable_refdefs(i)
+        .find(|d| same_url(&d.dest, &d.title))
+    {
+        return Some(d.label.clone());
+    }
+    if let Some(((_, key), _)) = added
+        .iter()
+        .find(|((t, _), url)| *t == target && **url == link.url)
+        .filter(|_| link.title.is_empty())
+    {
+        return Some(key.clone());
+    }
+
+    let label = book
+        .refdefs
+        .values()
+        .filter(|(_, d)| same_url(&d.dest, &d.title))
+        .map(|(_, d)| d.label.clone())
+        .min()
+        .or_else(|| DEFAULT_RULES.label(&link.url))
+        .unwrap_or_else(|| text.to_kebab_case());
+    let key = label.to_lowercase();
+    if label.is_empty()
+        || label.contains(['[', ']'])
+        || book
+            .reachable_refdefs(i)
+            .any(|d| d.label.to_lowercase() == key)
+        || book
+            .refdefs
+            .get(&key)
+            .is_some_and(|(_, d)| !same_url(&d.dest, &d.title))
+        || added
+            .iter()
+            .any(|((_, k), url)| *k == key && *url != link.url)
+    {
+        return None;
+    }
+    if added
+        .insert((target, key.clone()), link.url.clone())
+        .is_none()
+    {
+        plans[target].added.push(RefDefLine {
+            key,
+            dest: link.url.clone(),
+            text: refdef_line(&label, &link.url, &link.title),
+        });
+    }
+    Some(label)
+}
+
+/// Returns a reference-style link e.g. `[text][label]`, or `[label]` if
+/// the text is the label and is not followed by a character that would
+/// make it another link
+fn reference_link(is_image: bool, text: &str, label: &str, next_char: Option<char>) -> String {
+    let bang = if is_image { "!" } else { "" };
+    if text == label && !matches!(next_char, Some('(' | '[' | ':')) {
+        format!("{bang}[{text}]")
+    } else {
+        format!("{bang}[{text}][{label}]")
+    }
+}
+
+/// Returns an inline link e.g. `[text](url "title")`, if the URL and
+/// title can be written inline
+fn inline_link(is_image: bool, text: &str, url: &str, title: &str) -> Option<String> {
+    let bang = if is_image { "!" } else { "" };
+    Some(format!(
+        "{bang}[{text}]({}{})",
+        destination(url)?,
+        quoted_title(title)?
+    ))
+}
+
+/// Returns a reference definition e.g. `[label]: url "title"`
+fn refdef_line(label: &str, url: &str, title: &str) -> String {
+    format!(
+        "[{label}]: {}{}",
+        destination(url).unwrap_or_else(|| url.to_string()),
+        quoted_title(title).unwrap_or_default()
+    )
+}
+
+/// Returns the destination of a link, between `<>` if it contains
+/// spaces or unbalanced parentheses
+fn destination(url: &str) -> Option<String> {
+    if url.contains(['<', '>', '\n']) {
+        return None;
+    }
+    let balanced = url.chars().try_fold(0usize, |depth, c| match c {
+        '(' => Some(depth + 1),
+        ')' => depth.checked_sub(1),
+        _ => Some(depth),
+    }) == Some(0);
+    if url.is_empty() || url.contains(char::is_whitespace) || !balanced {
+        Some(format!("<{url}>"))
+    } else {
+        Some(url.to_string())
+    }
+}
+
+/// Returns the title of a link, with a leading space, if any
+fn quoted_title(title: &str) -> Option<String> {
+    if title.is_empty() {
+        Some(String::new())
+    } else if !title.contains('"') {
+        Some(format!(" \"{title}\""))
+    } else if !title.contains('\'') {
+        Some(format!(" '{title}'"))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::format_str;
+    use super::*;
+
+    #[test]
+    fn test_reference_style() {
+        let options = FormatOptions {
+            link_style: LinkStyle::Reference,
+            ..FormatOptions::default()
+        };
+        let md = "[Example](https://example.com \"Home\") and [local](other.md).\n\n\
+                  [![badge](https://img.shields.io/x.svg)](https://example.com/a)\n\n\
+                  [ex]: https://example.com \"Home\"\n";
+        assert_eq!(
+            format_str(md, &options),
+            "[Example][ex] and [local](other.md).\n\n\
+             [![badge]][example.com-a]\n\n\
+             [badge]: https://img.shields.io/x.svg\n\
+             [ex]: https://example.com \"Home\"\n\
+             [example.com-a]: https://example.com/a\n"
+        );
+    }
+
+    #[test]
+    fn test_inline_style() {
+        let options = FormatOptions {
+            link_style: LinkStyle::Inline,
+            ..FormatOptions::default()
+        };
+        let md = "See [a], [text][b] and ![img][c].\n\n\
+                  [a]: https://a.com\n[b]: <https://b.com/x y> 'The \"B\"'\n[c]: c.png\n[d]: https://d.com\n";
+        assert_eq!(
+            format_str(md, &options),
+            "See [a](https://a.com), [text](<https://b.com/x y> 'The \"B\"') and ![img](c.png).\n\n\
+             [d]: https://d.com\n"
+        );
+    }
+
+    #[test]
+    fn test_destination() {
+        assert_eq!(destination("a(b)").as_deref(), Some("a(b)"));
+        assert_eq!(destination("a)b").as_deref(), Some("<a)b>"));
+        assert_eq!(destination("a<b"), None);
+    }
+}
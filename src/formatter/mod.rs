@@ -0,0 +1,445 @@
+//! Format the Markdown files of a book: link style, placement and order
+//! of the reference definitions, code block fences and info strings.
+//!
+//! The files are not rendered back from the parser events: the events
+//! (and their byte ranges) locate the links, reference definitions and
+//! code blocks to rewrite, and everything else is kept as written. The
+//! changes are expressed as [text edits][crate::lint::TextEdit], like
+//! the fixes of the lint rules.
+mod fences;
+mod links;
+mod refdefs;
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use tracing::debug;
+
+use crate::Result;
+use crate::chapters;
+use crate::fs;
+use crate::lint;
+use crate::lint::FileFix;
+use crate::lint::ScannedFile;
+use crate::lint::ScannedRefDef;
+use crate::lint::TextEdit;
+use crate::parser::ParserOptions;
+
+/// Style of the links to websites
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LinkStyle {
+    /// Leave the links as written
+    #[default]
+    Keep,
+    /// Turn inline links to websites (`[text](https://...)`) into
+    /// reference-style links (`[text][label]`), and add the missing
+    /// reference definitions
+    Reference,
+    /// Turn reference-style links into inline links, and remove the
+    /// reference definitions that are no longer used
+    Inline,
+}
+
+impl FromStr for LinkStyle {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "keep" => Ok(Self::Keep),
+            "reference" | "ref" => Ok(Self::Reference),
+            "inline" => Ok(Self::Inline),
+            _ => Err(crate::Error::config(format!(
+                "Unknown link style: {s}. Use `keep`, `reference` or `inline`."
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for LinkStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Keep => "keep",
+            Self::Reference => "reference",
+            Self::Inline => "inline",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// Placement of the reference definitions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RefDefPlacement {
+    /// Leave the reference definitions where they are. New definitions
+    /// are added at the end of the file.
+    Keep,
+    /// Move the reference definitions to the end of their file
+    #[default]
+    End,
+    /// Move the reference definitions of a chapter into the
+    /// `*refs.md` file it includes (e.g. `{{#include refs.md}}`), or to
+    /// the end of the chapter if it includes none
+    RefsFile,
+}
+
+impl FromStr for RefDefPlacement {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "keep" => Ok(Self::Keep),
+            "end" => Ok(Self::End),
+            "refs-file" | "refs" => Ok(Self::RefsFile),
+            _ => Err(crate::Error::config(format!(
+                "Unknown reference definition placement: {s}. Use `keep`, `end` or `refs-file`."
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for RefDefPlacement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Keep => "keep",
+            Self::End => "end",
+            Self::RefsFile => "refs-file",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// Character of the code block fences
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FenceStyle {
+    /// Leave the fences as written
+    Keep,
+    /// ```` ``` ````
+    #[default]
+    Backtick,
+    /// `~~~`
+    Tilde,
+}
+
+impl FromStr for FenceStyle {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "keep" => Ok(Self::Keep),
+            "backtick" | "`" => Ok(Self::Backtick),
+            "tilde" | "~" => Ok(Self::Tilde),
+            _ => Err(crate::Error::config(format!(
+                "Unknown fence style: {s}. Use `keep`, `backtick` or `tilde`."
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for FenceStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Keep => "keep",
+            Self::Backtick => "backtick",
+            Self::Tilde => "tilde",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// Options of [`crate::format_markdown`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Style of the links to websites
+    pub link_style: LinkStyle,
+    /// Placement of the reference definitions
+    pub refdefs: RefDefPlacement,
+    /// Sort the reference definitions by label (case-insensitive)
+    pub sort_refdefs: bool,
+    /// Character of the code block fences. Fences are as short as the
+    /// code allows, and at least three characters long, unless `Keep`.
+    pub fence: FenceStyle,
+    /// Normalize the info strings of the code blocks: trim them and
+    /// separate their attributes by commas e.g. `rust, editable`
+    /// becomes `rust,editable`
+    pub info_strings: bool,
+    /// Options of the Markdown parser
+    pub parser: ParserOptions,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            link_style: LinkStyle::Keep,
+            refdefs: RefDefPlacement::End,
+            sort_refdefs: true,
+            fence: FenceStyle::Backtick,
+            info_strings: true,
+            parser: ParserOptions::default(),
+        }
+    }
+}
+
+/// Changes made by [`crate::format_markdown`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FormatChanges {
+    /// Edits, by file, sorted by path
+    pub files: Vec<FileFix>,
+}
+
+impl FormatChanges {
+    /// Returns `true` if all files are already formatted
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Returns the number of edits
+    pub fn len(&self) -> usize {
+        self.files.iter().map(|f| f.edits.len()).sum()
+    }
+
+    /// Returns the changes of all files, as a unified diff
+    pub fn diff(&self) -> String {
+        self.files.iter().map(FileFix::diff).collect()
+    }
+
+    /// Write the formatted files.
+    ///
+    /// Nothing is written if a file was modified since it was read.
+    pub fn apply(&self) -> Result<()> {
+        lint::apply_file_fixes(&self.files)
+    }
+}
+
+/// Reference definition to write, moved from another place or new
+#[derive(Debug, Clone)]
+pub(crate) struct RefDefLine {
+    /// Label, lowercased
+    pub(crate) key: String,
+    pub(crate) dest: String,
+    /// Source of the definition e.g. `[label]: https://... "title"`
+    pub(crate) text: String,
+}
+
+/// Changes of a file, before they are turned into text edits
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FilePlan {
+    /// Edits of the links and code blocks
+    pub(crate) edits: Vec<TextEdit>,
+    /// Reference definitions to add
+    pub(crate) added: Vec<RefDefLine>,
+    /// Indices of the reference definitions of the file to remove
+    pub(crate) removed: HashSet<usize>,
+}
+
+/// Files of a book, with the reference definitions and includes that
+/// link them
+pub(crate) struct Book<'a> {
+    pub(crate) files: &'a [ScannedFile],
+    /// Reference definitions of the book by lowercased label; the first
+    /// definition wins
+    pub(crate) refdefs: HashMap<String, (usize, &'a ScannedRefDef)>,
+    /// Indices of the files included by each file
+    pub(crate) includes: Vec<Vec<usize>>,
+}
+
+impl<'a> Book<'a> {
+    fn new(files: &'a [ScannedFile]) -> Self {
+        let by_path: HashMap<&str, usize> = files
+            .iter()
+            .enumerate()
+            .map(|(i, f)| (f.rel_path.as_str(), i))
+            .collect();
+        let includes = files
+            .iter()
+            .map(|f| {
+                f.includes
+                    .iter()
+                    .filter(|inc| !chapters::is_external(&inc.name))
+                    .filter_map(|inc| {
+                        by_path
+                            .get(
+                                chapters::resolve(chapters::parent(&f.rel_path), &inc.name)
+                                    .as_str(),
+                            )
+                            .copied()
+                    })
+                    .collect()
+            })
+            .collect();
+        Self {
+            files,
+            refdefs: lint::book_refdefs(files),
+            includes,
+        }
+    }
+
+    /// Returns the reference definitions a file can use: its own, then
+    /// those of the files it includes
+    pub(crate) fn reachable_refdefs(&self, i: usize) -> impl Iterator<Item = &'a ScannedRefDef> {
+        let files = self.files;
+        std::iter::once(i)
+            .chain(self.includes[i].iter().copied())
+            .flat_map(move |j| files[j].refdefs.iter())
+    }
+
+    /// Returns the `*refs.md` file included by a file, if any
+    pub(crate) fn refs_file(&self, i: usize) -> Option<usize> {
+        self.includes[i]
+            .iter()
+            .copied()
+            .find(|&j| j != i && self.files[j].rel_path.ends_with("refs.md"))
+    }
+}
+
+/// Returns `true` for `SUMMARY.md`, whose links are left as written
+fn is_summary(f: &ScannedFile) -> bool {
+    f.rel_path == "SUMMARY.md"
+}
+
+/// Format the Markdown files of a source directory
+pub(crate) fn format<P: AsRef<Path>>(
+    src_dir_path: P,
+    options: &FormatOptions,
+) -> Result<FormatChanges> {
+    let src_dir_path = fs::check_is_dir(src_dir_path)?;
+    let files = lint::scan_files(&src_dir_path, &options.parser)?;
+    Ok(format_files(&files, options))
+}
+
+/// Format a Markdown document on its own
+pub(crate) fn format_str(markdown: &str, options: &FormatOptions) -> String {
+    let files = [ScannedFile::scan(
+        PathBuf::new(),
+        String::new(),
+        markdown.to_string(),
+        &options.parser,
+    )];
+    match format_files(&files, options).files.pop() {
+        Some(fix) => fix.fixed_contents(),
+        None => markdown.to_string(),
+    }
+}
+
+/// Compute the edits of all files
+fn format_files(files: &[ScannedFile], options: &FormatOptions) -> FormatChanges {
+    let book = Book::new(files);
+    let mut plans: Vec<FilePlan> = vec![FilePlan::default(); files.len()];
+
+    links::format_links(&book, options, &mut plans);
+    for (i, f) in files.iter().enumerate() {
+        if !is_summary(f) {
+            plans[i]
+                .edits
+                .extend(fences::format_fences(&f.contents, options));
+        }
+    }
+    refdefs::place_refdefs(&book, options, &mut plans);
+
+    let mut changes = FormatChanges::default();
+    for (f, plan) in files.iter().zip(plans) {
+        let mut fix = FileFix::new(f.path.clone(), f.contents.clone());
+        for edit in plan.edits {
+            if f.contents[edit.range.clone()] != edit.replacement
+                && !fix.add(shrink(&f.contents, edit))
+            {
+                debug!("Overlapping edit skipped in {}", f.path.display());
+            }
+        }
+        if !fix.edits.is_empty() {
+            changes.files.push(fix);
+        }
+    }
+    changes.files.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+    changes
+}
+
+/// Shrink an edit to the lines it changes, so that the diff only shows
+/// them (e.g. a block of reference definitions where a single line
+/// moved)
+fn shrink(contents: &str, edit: TextEdit) -> TextEdit {
+    let old = &contents[edit.range.clone()];
+    let new = edit.replacement.as_str();
+    let common_lines = |a: &mut dyn Iterator<Item = &str>, b: &mut dyn Iterator<Item = &str>| {
+        a.zip(b)
+            .take_while(|(a, b)| a == b && a.ends_with('\n'))
+            .map(|(a, _)| a.len())
+            .sum::<usize>()
+    };
+    let prefix = common_lines(
+        &mut old.split_inclusive('\n'),
+        &mut new.split_inclusive('\n'),
+    );
+    let (old, new) = (&old[prefix..], &new[prefix..]);
+    let suffix = common_lines(
+        &mut old.split_inclusive('\n').rev(),
+        &mut new.split_inclusive('\n').rev(),
+    );
+    TextEdit {
+        range: edit.range.start + prefix..edit.range.end - suffix,
+        replacement: new[..new.len() - suffix].to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_options_from_str() {
+        assert_eq!(
+            "Reference".parse::<LinkStyle>().unwrap(),
+            LinkStyle::Reference
+        );
+        assert_eq!(
+            "refs-file".parse::<RefDefPlacement>().unwrap(),
+            RefDefPlacement::RefsFile
+        );
+        assert_eq!("~".parse::<FenceStyle>().unwrap(), FenceStyle::Tilde);
+        assert!("other".parse::<LinkStyle>().is_err());
+    }
+
+    #[test]
+    fn test_format_str_is_idempotent() {
+        let md = "# Title\n\n[b]: https://b.com\n\nSee [a][] and [b].\n\n~~~ rust, editable\nfn main() {}\n~~~\n\n[a]: https://a.com\n";
+        let options = FormatOptions::default();
+        let formatted = format_str(md, &options);
+        assert_eq!(
+            formatted,
+            "# Title\n\nSee [a][] and [b].\n\n```rust,editable\nfn main() {}\n```\n\n[a]: https://a.com\n[b]: https://b.com\n"
+        );
+        assert_eq!(format_str(&formatted, &options), formatted);
+    }
+
+    #[test]
+    fn test_format_refs_file() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let src = dir.path().join("src");
+        std::fs::create_dir_all(&src)?;
+        std::fs::write(
+            src.join("a.md"),
+            "# A\n\n[serde](https://docs.rs/serde) and [x][].\n\n[x]: https://x.com\n\n{{#include refs.md}}\n",
+        )?;
+        std::fs::write(src.join("refs.md"), "[z]: https://z.com\n")?;
+        let options = FormatOptions {
+            link_style: LinkStyle::Reference,
+            refdefs: RefDefPlacement::RefsFile,
+            ..FormatOptions::default()
+        };
+        let changes = format(&src, &options)?;
+        assert_eq!(changes.files.len(), 2);
+        changes.apply()?;
+        assert_eq!(
+            std::fs::read_to_string(src.join("a.md"))?,
+            "# A\n\n[serde] and [x][].\n\n{{#include refs.md}}\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(src.join("refs.md"))?,
+            "[serde]: https://docs.rs/serde\n[x]: https://x.com\n[z]: https://z.com\n"
+        );
+        assert!(format(&src, &options)?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,286 @@
+//! Move, sort and deduplicate the reference definitions
+
+use std::collections::HashSet;
+use std::ops::Range;
+
+use super::Book;
+use super::FilePlan;
+use super::FormatOptions;
+use super::RefDefLine;
+use super::RefDefPlacement;
+use super::is_summary;
+use crate::lint;
+use crate::lint::TextEdit;
+
+/// Reference definition written on its own line(s), which can be moved
+#[derive(Debug, Clone)]
+struct MovableRefDef {
+    /// Index among the reference definitions of the file
+    index: usize,
+    /// Byte range of the line(s) of the definition, newline included
+    lines: Range<usize>,
+    line: RefDefLine,
+}
+
+/// Returns the reference definitions of a file that are written on their
+/// own lines, sorted by position. Those written in a block quote or
+/// after other text are left where they are.
+fn movable_refdefs(book: &Book<'_>, i: usize) -> Vec<MovableRefDef> {
+    let f = &book.files[i];
+    let mut refdefs: Vec<MovableRefDef> = f
+        .refdefs
+        .iter()
+        .enumerate()
+        .filter_map(|(index, d)| {
+            let lines = lint::delete_lines(&f.contents, &d.span)?.range;
+            Some(MovableRefDef {
+                index,
+                line: RefDefLine {
+                    key: d.label.to_lowercase(),
+                    dest: d.dest.clone(),
+                    text: f.contents[lines.clone()].trim().to_string(),
+                },
+                lines,
+            })
+        })
+        .collect();
+    refdefs.sort_by_key(|d| d.lines.start);
+    refdefs
+}
+
+/// Place the reference definitions of all files: remove those planned
+/// for removal, move them into the `*refs.md` file or to the end of
+/// their file, sort and deduplicate them, and add the new ones.
+pub(crate) fn place_refdefs(book: &Book<'_>, options: &FormatOptions, plans: &mut [FilePlan]) {
+    let mut movable: Vec<Vec<MovableRefDef>> = (0..book.files.len())
+        .map(|i| movable_refdefs(book, i))
+        .collect();
+
+    if options.refdefs == RefDefPlacement::RefsFile {
+        for i in 0..book.files.len() {
+            let Some(r) = book.refs_file(i) else {
+                continue;
+            };
+            if is_summary(&book.files[i]) || book.refs_file(r).is_some() {
+                continue;
+            }
+            let mut kept = Vec::new();
+            for d in std::mem::take(&mut movable[i]) {
+                if plans[i].removed.contains(&d.index) {
+                    kept.push(d);
+                    continue;
+                }
+                let existing = book.files[r]
+                    .refdefs
+                    .iter()
+                    .map(|e| (e.label.to_lowercase(), e.dest.as_str()))
+                    .chain(
+                        plans[r]
+                            .added
+                            .iter()
+                            .map(|e| (e.key.clone(), e.dest.as_str())),
+                    )
+                    .find(|(key, _)| *key == d.line.key)
+                    .map(|(_, dest)| dest.to_string());
+                match existing {
+                    // Defined with another URL: keep the definition in
+                    // the chapter
+                    Some(dest) if dest != d.line.dest => kept.push(d),
+                    Some(_) => {
+                        plans[i].removed.insert(d.index);
+                        kept.push(d);
+                    }
+                    None => {
+                        plans[i].removed.insert(d.index);
+                        plans[r].added.push(d.line.clone());
+                        kept.push(d);
+                    }
+                }
+            }
+            movable[i] = kept;
+        }
+    }
+
+    for (i, f) in book.files.iter().enumerate() {
+        if is_summary(f) {
+            continue;
+        }
+        let plan = &mut plans[i];
+        let edits = match options.refdefs {
+            RefDefPlacement::Keep => {
+                keep_in_place(&f.contents, &movable[i], plan, options.sort_refdefs)
+            }
+            _ => move_to_end(&f.contents, &movable[i], plan, options.sort_refdefs),
+        };
+        plan.edits.extend(edits);
+    }
+}
+
+/// Remove the repeated definitions of a label (the first one wins), and
+/// optionally sort the definitions by label
+fn dedup_and_sort(lines: &mut Vec<RefDefLine>, sort: bool) {
+    let mut seen = HashSet::new();
+    lines.retain(|l| seen.insert(l.key.clone()));
+    if sort {
+        lines.sort_by(|a, b| a.key.cmp(&b.key));
+    }
+}
+
+/// Returns the edits that delete lines, given by ranges sorted by
+/// position. Consecutive lines are deleted together, with the blank line
+/// that follows them if they are preceded by a blank line, so that no
+/// double blank line is left.
+fn deletions(contents: &str, ranges: impl Iterator<Item = Range<usize>>) -> Vec<TextEdit> {
+    let mut runs: Vec<Range<usize>> = Vec::new();
+    for r in ranges {
+        match runs.last_mut() {
+            Some(last) if last.end == r.start => last.end = r.end,
+            _ => runs.push(r),
+        }
+    }
+    runs.into_iter()
+        .map(|mut range| {
+            let after_blank = contents[..range.start]
+                .strip_suffix('\n')
+                .is_none_or(|before| before.is_empty() || before.ends_with('\n'));
+            if after_blank && contents[range.end..].starts_with('\n') {
+                range.end += 1;
+            }
+            TextEdit {
+                range,
+                replacement: String::new(),
+            }
+        })
+        .collect()
+}
+
+/// Returns the text of a block of reference definitions
+fn block(lines: &[RefDefLine]) -> String {
+    lines.iter().map(|l| format!("{}\n", l.text)).collect()
+}
+
+/// Returns the edits that remove the planned definitions, sort the
+/// consecutive definitions in place and append the new ones
+fn keep_in_place(
+    contents: &str,
+    movable: &[MovableRefDef],
+    plan: &FilePlan,
+    sort: bool,
+) -> Vec<TextEdit> {
+    let (removed, kept): (Vec<&MovableRefDef>, Vec<&MovableRefDef>) = movable
+        .iter()
+        .partition(|d| plan.removed.contains(&d.index));
+    let mut edits = deletions(contents, removed.iter().map(|d| d.lines.clone()));
+    if sort {
+        // Groups of definitions on consecutive lines
+        let mut groups: Vec<Vec<&MovableRefDef>> = Vec::new();
+        for d in kept {
+            match groups.last_mut() {
+                Some(g) if g.last().unwrap().lines.end == d.lines.start => g.push(d),
+                _ => groups.push(vec![d]),
+            }
+        }
+        for g in groups.into_iter().filter(|g| g.len() > 1) {
+            let range = g[0].lines.start..g.last().unwrap().lines.end;
+            let mut lines: Vec<RefDefLine> = g.iter().map(|d| d.line.clone()).collect();
+            lines.sort_by(|a, b| a.key.cmp(&b.key));
+            let mut replacement = block(&lines);
+            if !contents[range.clone()].ends_with('\n') {
+                replacement.pop();
+            }
+            edits.push(TextEdit { range, replacement });
+        }
+    }
+    if !plan.added.is_empty() {
+        let mut added = plan.added.clone();
+        dedup_and_sort(&mut added, sort);
+        let mut edit = lint::append_refdefs(contents, &[]);
+        edit.replacement.push_str(&block(&added));
+        edits.push(edit);
+    }
+    edits
+}
+
+/// Returns the edits that move all definitions to the end of the file,
+/// as a single block
+fn move_to_end(
+    contents: &str,
+    movable: &[MovableRefDef],
+    plan: &FilePlan,
+    sort: bool,
+) -> Vec<TextEdit> {
+    // Definitions already at the end of the file, separated by blank
+    // lines only
+    let mut tail_start = contents.len();
+    let mut in_tail = 0;
+    for d in movable.iter().rev() {
+        if !contents[d.lines.end..tail_start].trim().is_empty() {
+            break;
+        }
+        tail_start = d.lines.start;
+        in_tail += 1;
+    }
+    let (body, tail) = movable.split_at(movable.len() - in_tail);
+
+    let mut edits = deletions(contents, body.iter().map(|d| d.lines.clone()));
+    let mut lines: Vec<RefDefLine> = movable
+        .iter()
+        .filter(|d| !plan.removed.contains(&d.index))
+        .map(|d| d.line.clone())
+        .chain(plan.added.iter().cloned())
+        .collect();
+    dedup_and_sort(&mut lines, sort);
+
+    let body_end = contents[..tail_start].trim_end().len();
+    if tail.is_empty() && lines.is_empty() {
+        return edits;
+    }
+    let separator = match tail.first() {
+        _ if body_end == 0 => String::new(),
+        _ if lines.is_empty() => "\n".to_string(),
+        Some(first) if contents[body_end..first.lines.start].ends_with("\n\n") => {
+            contents[body_end..first.lines.start].to_string()
+        }
+        _ => "\n\n".to_string(),
+    };
+    edits.push(TextEdit {
+        range: body_end..contents.len(),
+        replacement: separator + &block(&lines),
+    });
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::FormatOptions;
+    use super::super::format_str;
+    use super::*;
+
+    #[test]
+    fn test_move_to_end() {
+        let options = FormatOptions::default();
+        let md = "[b]: https://b.com\n# Title\n\n> [q]: https://q.com\n\n[A]: https://a.com \"A\"\n\nText [A] [b] [q].\n";
+        assert_eq!(
+            format_str(md, &options),
+            "# Title\n\n> [q]: https://q.com\n\nText [A] [b] [q].\n\n[A]: https://a.com \"A\"\n[b]: https://b.com\n"
+        );
+        let md = "[b]: https://b.com\n[a]: https://a.com\n";
+        assert_eq!(
+            format_str(md, &options),
+            "[a]: https://a.com\n[b]: https://b.com\n"
+        );
+    }
+
+    #[test]
+    fn test_keep_in_place() {
+        let options = FormatOptions {
+            refdefs: RefDefPlacement::Keep,
+            ..FormatOptions::default()
+        };
+        let md = "[b]: https://b.com\n[a]: https://a.com\n\n# Title\n\n[d]: https://d.com\n\nText [a] [b] [d].\n";
+        assert_eq!(
+            format_str(md, &options),
+            "[a]: https://a.com\n[b]: https://b.com\n\n# Title\n\n[d]: https://d.com\n\nText [a] [b] [d].\n"
+        );
+    }
+}
@@ -1,29 +1,39 @@
 //! Directory handling
+use std::io::ErrorKind;
 use std::path::Path;
 use std::path::PathBuf;
 
-use anyhow::Context;
-use anyhow::Error;
-use anyhow::bail;
 use tracing::info;
 
+use crate::Error;
+use crate::Result;
+
 /// Check if a path is a directory
 ///
-/// Return a PathBuf if it is.
-pub(crate) fn check_is_dir<P>(dir_path: P) -> Result<PathBuf, Error>
+/// Return a PathBuf if it is, or an [`Error::Io`] whose kind is
+/// `NotFound` or `NotADirectory`.
+pub(crate) fn check_is_dir<P>(dir_path: P) -> Result<PathBuf>
 where
     P: AsRef<Path>,
 {
     let dir_path = dir_path.as_ref();
     if !dir_path.is_dir() {
-        bail!("{dir_path:?} should be a folder and exist on disk!");
+        let kind = if dir_path.exists() {
+            ErrorKind::NotADirectory
+        } else {
+            ErrorKind::NotFound
+        };
+        return Err(Error::io(
+            dir_path,
+            std::io::Error::new(kind, "should be a folder and exist on disk!"),
+        ));
     }
     Ok(dir_path.to_path_buf())
 }
 
 /// Create the parent directory(ies) for a given file (that will be
 /// created later), if they don't exist.
-pub(crate) fn create_parent_dir_for<P>(file_path: P) -> Result<(), Error>
+pub(crate) fn create_parent_dir_for<P>(file_path: P) -> Result<()>
 where
     P: AsRef<Path>,
 {
@@ -37,24 +47,22 @@ pub(crate) fn create_parent_dir_for<P>(file_path: P) -> Result<(), Error>
 }
 
 /// Create a directory (including parent directories as needed).
-pub(crate) fn create_dir<P>(dir_path: P) -> Result<(), Error>
+pub(crate) fn create_dir<P>(dir_path: P) -> Result<()>
 where
     P: AsRef<Path>,
 {
     match dir_path.as_ref().try_exists() {
         Ok(false) => {
             std::fs::create_dir_all(dir_path.as_ref())
-                .with_context(|| format!("[create_dir] Failed to create {} or one of its parents. Do you have appropriate permissions?", dir_path.as_ref().display()))?;
+                .map_err(|e| Error::io(dir_path.as_ref(), e))?;
             info!("{} created", dir_path.as_ref().display());
         }
         Ok(true) => {
             // debug: tracing::debug!("{} already exists", dest_dir);
         }
-        Err(_) => {
-            bail!(
-                "{:?}'s existence can neither be confirmed nor denied.",
-                dir_path.as_ref()
-            );
+        Err(e) => {
+            // The existence of the directory can neither be confirmed nor denied.
+            return Err(Error::io(dir_path.as_ref(), e));
         }
     }
     Ok(())
@@ -64,24 +72,28 @@ pub(crate) fn create_dir<P>(dir_path: P) -> Result<(), Error>
 mod tests {
     use std::fs;
 
+    use anyhow::Result;
     use tempfile::tempdir;
 
     use super::*;
 
     #[test]
-    fn test_check_is_dir() -> Result<(), Error> {
+    fn test_check_is_dir() -> Result<()> {
         let dir = tempdir()?;
         let res = check_is_dir(dir.path())?;
         assert_eq!(res, dir.path().canonicalize()?);
 
         let file_path = dir.path().join("file.txt");
         fs::write(&file_path, "test")?;
-        assert!(check_is_dir(&file_path).is_err());
+        assert!(matches!(
+            check_is_dir(&file_path),
+            Err(Error::Io { source, .. }) if source.kind() == ErrorKind::NotADirectory
+        ));
         Ok(())
     }
 
     #[test]
-    fn test_create_parent_dir_for() -> Result<(), Error> {
+    fn test_create_parent_dir_for() -> Result<()> {
         let dir = tempdir()?;
         let file_path = dir.path().join("sub").join("file.txt");
         create_parent_dir_for(&file_path)?;
@@ -90,7 +102,7 @@ fn test_create_parent_dir_for() -> Result<(), Error> {
     }
 
     #[test]
-    fn test_create_dir() -> Result<(), Error> {
+    fn test_create_dir() -> Result<()> {
         let dir = tempdir()?;
         let new_dir = dir.path().join("new_dir");
         create_dir(&new_dir)?;
@@ -3,11 +3,12 @@
 use std::path::Path;
 use std::path::PathBuf;
 
-use anyhow::Result;
 use tracing::warn;
 use walkdir::DirEntry;
 use walkdir::WalkDir;
 
+use crate::Result;
+
 /// Returns `true`` if the directory entry is hidden (starts with a `.`).
 fn is_hidden(entry: &DirEntry) -> bool {
     entry
@@ -0,0 +1,41 @@
+//! Match file paths against glob patterns
+
+use globset::GlobBuilder;
+use globset::GlobSet;
+use globset::GlobSetBuilder;
+
+use crate::Error;
+use crate::Result;
+
+/// Compile a list of glob patterns (e.g. `*refs.md`, `drafts/**`).
+///
+/// `*` also matches path separators, so that `*refs.md` matches
+/// `refs.md` as well as `chapter/refs.md`.
+pub(crate) fn build_globset(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = GlobBuilder::new(pattern)
+            .literal_separator(false)
+            .build()
+            .map_err(|e| Error::config(format!("Invalid glob pattern: {e}")))?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|e| Error::config(format!("Invalid glob patterns: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_globset() {
+        let set = build_globset(&["*refs.md".into(), "drafts/**".into()]).unwrap();
+        assert!(set.is_match("refs.md"));
+        assert!(set.is_match("a/refs.md"));
+        assert!(set.is_match("drafts/b/c.md"));
+        assert!(!set.is_match("intro.md"));
+        assert!(build_globset(&["a[".into()]).is_err());
+    }
+}
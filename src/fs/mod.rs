@@ -2,6 +2,7 @@
 //! directories, read multiple files from a directory, etc.
 mod dir;
 mod find_markdown_files;
+mod globs;
 mod path_validation;
 mod read_files;
 pub mod unused;
@@ -9,5 +10,6 @@
 
 pub(crate) use dir::*;
 pub(crate) use find_markdown_files::*;
+pub(crate) use globs::*;
 pub(crate) use path_validation::*;
 pub(crate) use read_files::*;
@@ -2,34 +2,36 @@
 use std::path::Path;
 use std::path::PathBuf;
 
-use anyhow::Result;
-use anyhow::bail;
+use crate::Error;
+use crate::Result;
 
 /// Returns the canonicalized path if it is within the canonicalized base_dir.
-/// Otherwise returns an error.
+/// Otherwise returns an [`Error::PathEscape`].
 ///
 /// This function handles non-existent files by validating their parent
 /// directory. Dangling symlinks are rejected for security.
 pub(crate) fn is_path_within(base_dir: &Path, path: &Path) -> Result<PathBuf> {
-    let base_dir_canon = base_dir.canonicalize()?;
+    let base_dir_canon = base_dir
+        .canonicalize()
+        .map_err(|e| Error::io(base_dir, e))?;
+    let escape = |path: &Path| Error::PathEscape {
+        path: path.to_path_buf(),
+        base_dir: base_dir_canon.clone(),
+    };
 
     match path.canonicalize() {
         Ok(path_canon) => {
             if path_canon.starts_with(&base_dir_canon) {
                 Ok(path_canon)
             } else {
-                bail!(
-                    "Path traversal detected: {:?} is outside of {:?}",
-                    path_canon,
-                    base_dir_canon
-                );
+                Err(escape(&path_canon))
             }
         }
         Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
             // If the path doesn't exist, it might be a dangling symlink or a new file.
             // Check if it's a dangling symlink.
             if path.symlink_metadata().is_ok() {
-                bail!("Path traversal detected: {:?} is a dangling symlink", path);
+                return Err(escape(path));
             }
 
             // Find the first existing ancestor
@@ -51,20 +53,13 @@ pub(crate) fn is_path_within(base_dir: &Path, path: &Path) -> Result<PathBuf> {
                         // but here we are just validating safety.
                         return Ok(path.to_path_buf());
                     } else {
-                        bail!(
-                            "Path traversal detected: ancestor {:?} is outside of {:?}",
-                            ancestor_canon,
-                            base_dir_canon
-                        );
+                        return Err(escape(path));
                     }
                 }
             }
-            bail!(
-                "Path traversal detected: {:?} has no existing ancestor within base directory",
-                path
-            );
+            Err(escape(path))
         }
-        Err(e) => Err(e.into()),
+        Err(e) => Err(Error::io(path, e)),
     }
 }
 
@@ -72,6 +67,7 @@ pub(crate) fn is_path_within(base_dir: &Path, path: &Path) -> Result<PathBuf> {
 mod tests {
     use std::fs;
 
+    use anyhow::Result;
     use tempfile::tempdir;
 
     use super::*;
@@ -92,7 +88,10 @@ fn test_is_path_within() -> Result<()> {
         assert!(is_path_within(&base_dir, &safe_file).is_ok());
 
         // Negative case: existing file outside
-        assert!(is_path_within(&base_dir, &unsafe_file).is_err());
+        assert!(matches!(
+            is_path_within(&base_dir, &unsafe_file),
+            Err(Error::PathEscape { .. })
+        ));
 
         // Positive case: non-existent file in safe dir
         let non_existent_safe = base_dir.join("new.txt");
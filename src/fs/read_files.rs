@@ -4,11 +4,11 @@
 use std::fs::File;
 use std::io;
 use std::io::BufRead;
-use std::io::Read;
 use std::path::Path;
 
-use anyhow::Context;
-use anyhow::Result;
+use crate::Error;
+use crate::Result;
+use crate::parser;
 
 // // Read a single file to String
 // pub(crate) fn read_to_string<P: AsRef<Path>>(path: P) -> String {
@@ -21,8 +21,12 @@
 // }
 
 /// Read all Markdown files in a directory into one big string.
+///
+/// options: the Markdown dialect, which tells whether the front matter
+/// of the files is skipped.
 pub(crate) fn read_to_string_all_markdown_files_in<'a, P>(
     markdown_root_dir_path: P,
+    options: &parser::ParserOptions,
 ) -> Result<Cow<'a, str>>
 where
     P: AsRef<Path>,
@@ -33,18 +37,14 @@ pub(crate) fn read_to_string_all_markdown_files_in<'a, P>(
     // Read all .md files into one big String
     let mut all_markdown = String::with_capacity(1024 * 1024);
     for p in paths {
-        let mut file = File::open(p.as_path()).with_context(|| {
-            format!(
-                "[read_to_string_all_markdown_files_in] Could not open {}. Does the file exist?",
-                p.display()
-            )
-        })?;
-        file.read_to_string(&mut all_markdown).with_context(|| {
-            format!(
-                "[read_to_string_all_markdown_files_in] Could not read {}. Does the file exist?",
-                p.display()
-            )
-        })?;
+        let contents = std::fs::read_to_string(&p).map_err(|e| Error::io(&p, e))?;
+        // The parser recognizes front matter at the top of the document
+        // only, not at the top of each file
+        all_markdown.push_str(parser::skip_front_matter(&contents, options));
+        // Separate the files by a blank line, so that e.g. reference
+        // definitions at the top of a file do not continue the last
+        // paragraph of the previous file
+        all_markdown.push_str("\n\n");
     }
 
     Ok(Cow::from(all_markdown))
@@ -65,16 +65,11 @@ pub(crate) fn read_lines<P>(file_path: P) -> Result<Vec<Cow<'static, str>>>
 where
     P: AsRef<Path>,
 {
-    let file = File::open(file_path.as_ref()).with_context(|| {
-        format!(
-            "[read_lines] Could not open {}. Does the file exist?",
-            file_path.as_ref().display()
-        )
-    })?;
+    let file = File::open(file_path.as_ref()).map_err(|e| Error::io(file_path.as_ref(), e))?;
     // Returns an Iterator to the Reader of the lines of the file.
     Ok(io::BufReader::new(file)
         .lines()
-        .map_while(Result::ok)
+        .map_while(io::Result::ok)
         .map(Cow::from)
         .collect())
 }
@@ -5,10 +5,8 @@
 use std::path::PathBuf;
 use std::sync::LazyLock;
 
-use anyhow::Context;
-use anyhow::Result;
-use anyhow::bail;
-
+use crate::Error;
+use crate::Result;
 use crate::fs;
 use crate::parser;
 
@@ -24,14 +22,15 @@ pub fn identify_files_not_in_summary<P: AsRef<Path> + std::fmt::Debug>(
 
     let summary_path = markdown_src_dir_path.join("SUMMARY.md");
     if !summary_path.exists() {
-        bail!(
+        return Err(Error::config(format!(
             "SUMMARY.md not found in {}",
             markdown_src_dir_path.display()
-        );
+        )));
     }
 
-    let summary_content = std::fs::read_to_string(&summary_path)?;
-    let mut parser = parser::get_parser(&summary_content);
+    let summary_content =
+        std::fs::read_to_string(&summary_path).map_err(|e| Error::io(&summary_path, e))?;
+    let mut parser = parser::get_parser(&summary_content, &parser::ParserOptions::default());
     let links = parser::extract_links(&mut parser);
 
     let mut files_in_summary = std::collections::HashSet::new();
@@ -49,11 +48,11 @@ pub fn identify_files_not_in_summary<P: AsRef<Path> + std::fmt::Debug>(
 
     let mut missing = Vec::new();
     for f in all_files {
-        if let Ok(canon) = f.canonicalize() {
-            let file_name = f.file_name().context("Failed to get file name")?;
-            if !files_in_summary.contains(&canon) && file_name.to_string_lossy() != "SUMMARY.md" {
-                missing.push(f);
-            }
+        if let Ok(canon) = f.canonicalize()
+            && !files_in_summary.contains(&canon)
+            && f.file_name() != Some("SUMMARY.md".as_ref())
+        {
+            missing.push(f);
         }
     }
 
@@ -96,7 +95,7 @@ pub fn identify_unused_rs_examples<
         LazyLock::new(|| regex::Regex::new(r"(?P<path>[a-zA-Z0-9_.\-\/]+\.rs)").unwrap());
 
     for md_file in md_files {
-        let content = std::fs::read_to_string(&md_file)?;
+        let content = std::fs::read_to_string(&md_file).map_err(|e| Error::io(&md_file, e))?;
         for cap in RE.captures_iter(&content) {
             let rel_path = Path::new(&cap["path"]);
             if let Some(parent) = md_file.parent() {
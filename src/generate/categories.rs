@@ -0,0 +1,305 @@
+//! Generate a page of crates.io categories, in Markdown format, listing
+//! the crates of each category and the chapters that cover it.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use super::escape_link_text;
+use crate::Result;
+use crate::link::DEFAULT_RULES;
+use crate::parser::ParserOptions;
+use crate::registry::Category;
+use crate::registry::CrateRegistry;
+use crate::registry::parent_category;
+
+/// Options for the generation of the categories page
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CategoryIndexOptions {
+    /// Path to a directory containing `Cargo.toml`, in which
+    /// `cargo metadata --offline` is run to retrieve the categories of
+    /// the crates
+    pub cargo_metadata_dir_path: Option<PathBuf>,
+
+    /// Path to an extracted crates.io database dump
+    /// (<https://static.crates.io/db-dump.tar.gz>), used to retrieve
+    /// the categories of the crates and up-to-date category descriptions
+    pub db_dump_dir_path: Option<PathBuf>,
+
+    /// Path to a TOML file that assigns categories to crates and / or
+    /// defines additional categories. It takes precedence over the
+    /// other sources.
+    pub mapping_path: Option<PathBuf>,
+
+    /// Markdown dialect of the book
+    pub parser: ParserOptions,
+}
+
+/// A category, its crates and the chapters that cover it
+#[derive(Debug)]
+pub(crate) struct CategoryIndexEntry<'a> {
+    /// Slug e.g. `web-programming::websocket`
+    pub(crate) slug: String,
+    /// Name and description, if known
+    pub(crate) category: Option<&'a Category>,
+    /// Crates of the category that the book mentions
+    pub(crate) crates: Vec<String>,
+    /// Chapters that link to the category or mention one of its crates:
+    /// (title, relative link)
+    pub(crate) chapters: Vec<(String, String)>,
+}
+
+/// Extract a category slug from a crates.io category URL
+/// e.g. `https://crates.io/categories/web-programming::websocket`.
+pub(crate) fn category_slug_from_url(url: &str) -> Option<String> {
+    let url = url::Url::parse(url).ok()?;
+    if !matches!(url.host_str()?, "crates.io" | "www.crates.io") {
+        return None;
+    }
+    let mut segments = url.path_segments()?.filter(|s| !s.is_empty());
+    if segments.next()? != "categories" {
+        return None;
+    }
+    let slug = segments.next()?;
+    slug.chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == ':')
+        .then(|| slug.to_string())
+}
+
+/// Returns the reference label of a category, as created by the
+/// `category` link rule e.g. `cat-web-programming::websocket`.
+pub(crate) fn category_label(slug: &str) -> String {
+    DEFAULT_RULES["category"]
+        .label(&category_url(slug))
+        .unwrap_or_else(|| format!("cat-{slug}"))
+}
+
+/// Returns the crates.io URL of a category.
+fn category_url(slug: &str) -> String {
+    format!("https://crates.io/categories/{slug}")
+}
+
+/// Build the entries of the categories page.
+///
+/// Parent categories of the listed categories are always included, so
+/// that subcategories can be nested under them.
+///
+/// crate_mentions: crate name -> paths of the chapters that mention it,
+/// relative to the source directory.
+///
+/// category_mentions: category slug -> paths of the chapters that link
+/// to it.
+///
+/// registry: crate and category metadata.
+///
+/// chapter_link: returns the title and link of a chapter, given its path.
+pub(crate) fn category_index_entries<'a, F>(
+    crate_mentions: &BTreeMap<String, BTreeSet<PathBuf>>,
+    category_mentions: &BTreeMap<String, BTreeSet<PathBuf>>,
+    registry: &'a CrateRegistry,
+    chapter_link: F,
+) -> Vec<CategoryIndexEntry<'a>>
+where
+    F: Fn(&Path) -> (String, String),
+{
+    let mut index: BTreeMap<String, (BTreeSet<String>, BTreeSet<PathBuf>)> = BTreeMap::new();
+    for (name, paths) in crate_mentions {
+        let Some(info) = registry.get(name) else {
+            continue;
+        };
+        for slug in info.categories.iter() {
+            let (crates, chapters) = index.entry(slug.clone()).or_default();
+            crates.insert(name.clone());
+            chapters.extend(paths.iter().cloned());
+        }
+    }
+    for (slug, paths) in category_mentions {
+        index
+            .entry(slug.clone())
+            .or_default()
+            .1
+            .extend(paths.iter().cloned());
+    }
+    let slugs: Vec<String> = index.keys().cloned().collect();
+    for slug in slugs {
+        let mut slug = slug.as_str();
+        while let Some(parent) = parent_category(slug) {
+            index.entry(parent.to_string()).or_default();
+            slug = parent;
+        }
+    }
+
+    let mut entries: Vec<CategoryIndexEntry<'a>> = index
+        .into_iter()
+        .map(|(slug, (crates, chapters))| CategoryIndexEntry {
+            category: registry.category(&slug),
+            slug,
+            crates: crates.into_iter().collect(),
+            chapters: chapters.iter().map(|p| chapter_link(p)).collect(),
+        })
+        .collect();
+    // Sort segment by segment, so that subcategories follow their parent
+    entries.sort_by(|a, b| a.slug.split("::").cmp(b.slug.split("::")));
+    entries
+}
+
+/// Write the categories page, followed by the `cat-*` reference
+/// definitions of the categories.
+///
+/// entries: the categories to list, sorted so that subcategories follow
+/// their parent.
+///
+/// w: a writer (e.g. a File) to write to.
+pub(crate) fn write_category_index<W: Write>(
+    entries: &[CategoryIndexEntry<'_>],
+    w: &mut W,
+) -> Result<()> {
+    writeln!(w, "# Categories\n")?;
+    if entries.is_empty() {
+        return Ok(());
+    }
+    for e in entries {
+        let depth = e.slug.split("::").count();
+        let hashes = "#".repeat((depth + 1).min(6));
+        // Subcategory names are prefixed by their parent's e.g.
+        // `Web programming::WebSocket`
+        let full_name = e.category.map_or(e.slug.as_str(), |c| c.name.as_str());
+        let name = full_name.rsplit("::").next().unwrap_or(full_name);
+        writeln!(w, "{hashes} [{name}][{}]\n", category_label(&e.slug))?;
+        if let Some(description) = e.category.map(|c| c.description.trim())
+            && !description.is_empty()
+        {
+            writeln!(w, "{description}\n")?;
+        }
+        if !e.crates.is_empty() {
+            let crates = e
+                .crates
+                .iter()
+                .map(|c| format!("[{c}](https://crates.io/crates/{c})"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            writeln!(w, "- Crates: {crates}")?;
+        }
+        if !e.chapters.is_empty() {
+            let chapters = e
+                .chapters
+                .iter()
+                .map(|(title, link)| {
+                    format!(
+                        "[{}]({})",
+                        escape_link_text(title),
+                        link.replace(' ', "%20")
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            writeln!(w, "- Chapters: {chapters}")?;
+        }
+        if !e.crates.is_empty() || !e.chapters.is_empty() {
+            writeln!(w)?;
+        }
+    }
+    for e in entries {
+        writeln!(
+            w,
+            "[{}]: {}",
+            category_label(&e.slug),
+            category_url(&e.slug)
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::CategoryMapping;
+    use crate::registry::bundled_categories;
+
+    #[test]
+    fn test_category_slug_from_url() {
+        assert_eq!(
+            category_slug_from_url("https://crates.io/categories/web-programming::websocket/"),
+            Some("web-programming::websocket".into())
+        );
+        assert_eq!(
+            category_slug_from_url("https://crates.io/categories/parsing?sort=recent-updates"),
+            Some("parsing".into())
+        );
+        assert_eq!(
+            category_slug_from_url("https://crates.io/categories/"),
+            None
+        );
+        assert_eq!(
+            category_slug_from_url("https://crates.io/crates/serde"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_category_label() {
+        assert_eq!(
+            category_label("web-programming::websocket"),
+            "cat-web-programming::websocket"
+        );
+    }
+
+    #[test]
+    fn test_write_category_index() {
+        let mut registry = CrateRegistry::new();
+        registry.add_category_mapping(&bundled_categories().unwrap());
+        registry.add_category_mapping(&CategoryMapping {
+            crates: BTreeMap::from([(
+                "tungstenite".to_string(),
+                vec!["web-programming::websocket".to_string()],
+            )]),
+            ..CategoryMapping::default()
+        });
+        let crate_mentions = BTreeMap::from([
+            (
+                "tungstenite".to_string(),
+                BTreeSet::from([PathBuf::from("ws.md")]),
+            ),
+            (
+                "unknown".to_string(),
+                BTreeSet::from([PathBuf::from("ws.md")]),
+            ),
+        ]);
+        let category_mentions = BTreeMap::from([(
+            "asynchronous".to_string(),
+            BTreeSet::from([PathBuf::from("async.md")]),
+        )]);
+        let entries = category_index_entries(&crate_mentions, &category_mentions, &registry, |p| {
+            let s = p.display().to_string();
+            let title = if s == "ws.md" {
+                "[ws]".to_string()
+            } else {
+                s.clone()
+            };
+            (title, s)
+        });
+        let slugs: Vec<_> = entries.iter().map(|e| e.slug.as_str()).collect();
+        assert_eq!(
+            slugs,
+            vec![
+                "asynchronous",
+                "web-programming",
+                "web-programming::websocket"
+            ]
+        );
+
+        let mut out = Vec::new();
+        write_category_index(&entries, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("# Categories\n\n## [Asynchronous][cat-asynchronous]\n\n"));
+        assert!(out.contains("- Chapters: [async.md](async.md)\n"));
+        assert!(out.contains(
+            "### [WebSocket][cat-web-programming::websocket]\n\nCrates to communicate over the WebSocket protocol.\n\n- Crates: [tungstenite](https://crates.io/crates/tungstenite)\n- Chapters: [\\[ws\\]](ws.md)\n"
+        ));
+        assert!(out.ends_with(
+            "[cat-web-programming::websocket]: https://crates.io/categories/web-programming::websocket\n"
+        ));
+    }
+}
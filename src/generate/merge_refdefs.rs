@@ -4,10 +4,7 @@
 use crate::link::Link;
 
 /// Append, sort and dedupe reference definitions.
-pub(crate) fn merge_links<'a>(
-    existing_links: Vec<Link<'a>>,
-    new_links: &mut Vec<Link<'a>>,
-) -> Vec<Link<'a>> {
+pub(crate) fn merge_links(existing_links: Vec<Link>, new_links: &mut Vec<Link>) -> Vec<Link> {
     let mut buf = existing_links.clone();
     buf.append(new_links);
 
@@ -20,34 +17,21 @@ pub(crate) fn merge_links<'a>(
 
 #[cfg(test)]
 mod tests {
-    use pulldown_cmark::LinkType;
-
     use super::*;
     use crate::link::LinkBuilder;
+    use crate::link::LinkKind;
 
     #[test]
     fn test_merge_links() {
-        let link1 = LinkBuilder::from_type_url_title(
-            LinkType::Shortcut,
-            "url1".into(),
-            "title1".into(),
-            "label1".into(),
-        )
-        .build();
-        let link2 = LinkBuilder::from_type_url_title(
-            LinkType::Shortcut,
-            "url2".into(),
-            "title2".into(),
-            "label2".into(),
-        )
-        .build();
-        let link3 = LinkBuilder::from_type_url_title(
-            LinkType::Shortcut,
-            "url3".into(),
-            "title3".into(),
-            "label3".into(),
-        )
-        .build();
+        let link1 =
+            LinkBuilder::from_type_url_title(LinkKind::Shortcut, "url1", "title1", "label1")
+                .build();
+        let link2 =
+            LinkBuilder::from_type_url_title(LinkKind::Shortcut, "url2", "title2", "label2")
+                .build();
+        let link3 =
+            LinkBuilder::from_type_url_title(LinkKind::Shortcut, "url3", "title3", "label3")
+                .build();
         let link1_dup = link1.clone();
 
         let existing = vec![link3.clone(), link1.clone()];
@@ -1,8 +1,18 @@
 //! Functions that generate reference definitions
-//! e.g. from code dependencies and links.
-pub mod crates;
+//! e.g. from code dependencies and links,
+//! and index pages e.g. of crates.
+mod categories;
+mod crates;
 mod merge_refdefs;
+mod refdef_templates;
 mod refdefs_from_dependencies;
 
+pub use categories::CategoryIndexOptions;
+pub(crate) use categories::*;
+pub use crates::CrateIndexOptions;
+pub use crates::CrateSort;
+pub(crate) use crates::*;
 pub(crate) use merge_refdefs::*;
+pub use refdef_templates::RefDefTemplate;
+pub(crate) use refdef_templates::*;
 pub(crate) use refdefs_from_dependencies::*;
@@ -0,0 +1,164 @@
+//! Templates of the reference definitions generated for each dependency
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::Error;
+use crate::Result;
+use crate::link::expand_pattern;
+
+/// A reference definition to generate for each dependency.
+///
+/// Patterns use the same `${name}` substitution syntax as the link rules.
+/// Available variables:
+///
+/// - `crate`: the library name e.g. `serde_json`,
+/// - `package`: the package name e.g. `serde-json`,
+/// - `version`: the most recent version locked in `Cargo.lock`,
+/// - `repository`, `homepage`, `documentation`: the URLs of the package
+///   manifest.
+///
+/// A template that refers to a variable without value for a given
+/// dependency (e.g. no `homepage`) is skipped for that dependency.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct RefDefTemplate {
+    /// Label pattern e.g. `${crate}-lib-rs`
+    #[serde(rename = "label")]
+    pub label_pattern: String,
+
+    /// URL pattern e.g. `https://lib.rs/crates/${package}`
+    #[serde(rename = "url")]
+    pub url_pattern: String,
+
+    /// Optional badge URL pattern. If present, a `<label>-badge`
+    /// reference definition is generated as well.
+    #[serde(rename = "badge", default)]
+    pub badge_url_pattern: Option<String>,
+}
+
+impl RefDefTemplate {
+    /// Create a template without badge
+    pub fn new(label_pattern: &str, url_pattern: &str) -> Self {
+        Self {
+            label_pattern: label_pattern.to_string(),
+            url_pattern: url_pattern.to_string(),
+            badge_url_pattern: None,
+        }
+    }
+
+    /// The default templates: docs.rs (`${crate}`), crates.io
+    /// (`${crate}-crate`) and repository (`${crate}-github`) reference
+    /// definitions.
+    ///
+    /// pin_versions: if `true`, the docs.rs and crates.io URLs include
+    /// `${version}`.
+    pub fn defaults(pin_versions: bool) -> Vec<Self> {
+        let (docs_rs_url, crates_io_url) = if pin_versions {
+            (
+                "https://docs.rs/${crate}/${version}/${crate}/",
+                "https://crates.io/crates/${crate}/${version}",
+            )
+        } else {
+            (
+                "https://docs.rs/${crate}",
+                "https://crates.io/crates/${crate}",
+            )
+        };
+        vec![
+            // [arrow-rs]: https://docs.rs/arrow/
+            Self::new("${crate}", docs_rs_url),
+            // [config-crate]: https://crates.io/crates/config/
+            Self::new("${crate}-crate", crates_io_url),
+            // [arrow-rs-github]: https://github.com/apache/arrow-rs/
+            Self::new("${crate}-github", "${repository}"),
+        ]
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RefDefTemplateFile {
+    #[serde(default)]
+    templates: Vec<RefDefTemplate>,
+}
+
+/// Parse a TOML list of templates:
+///
+/// ```toml
+/// [[templates]]
+/// label = "${crate}-lib-rs"
+/// url = "https://lib.rs/crates/${package}"
+/// badge = "https://img.shields.io/badge/lib.rs-${crate}-orange"
+/// ```
+pub(crate) fn parse_refdef_templates(contents: &str) -> Result<Vec<RefDefTemplate>> {
+    let file: RefDefTemplateFile =
+        toml::from_str(contents).map_err(|e| Error::parse("templates.toml", e))?;
+    Ok(file.templates)
+}
+
+/// Read and parse a TOML file of templates.
+///
+/// templates_file_path: path to the TOML file.
+pub(crate) fn read_refdef_templates<P: AsRef<Path>>(
+    templates_file_path: P,
+) -> Result<Vec<RefDefTemplate>> {
+    let templates_file_path = templates_file_path.as_ref();
+    let contents = std::fs::read_to_string(templates_file_path)
+        .map_err(|e| Error::io(templates_file_path, e))?;
+    parse_refdef_templates(&contents).map_err(|e| e.in_file(templates_file_path))
+}
+
+/// Replace `$name` and `${name}` by the value of the variable `name`,
+/// and `$$` by `$`, as the link rules do (see
+/// `regex::Captures::expand`).
+///
+/// Returns `None` if a variable has no value, whereas a capture group
+/// that did not match expands to "" in a link rule.
+pub(crate) fn expand(pattern: &str, vars: &HashMap<&str, &str>) -> Option<String> {
+    expand_pattern(pattern, |name| vars.get(name).copied())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        let vars = HashMap::from([("crate", "serde_json"), ("version", "1.0.140")]);
+        assert_eq!(
+            expand("https://docs.rs/${crate}/${version}/$crate/", &vars).as_deref(),
+            Some("https://docs.rs/serde_json/1.0.140/serde_json/")
+        );
+        assert_eq!(
+            expand("$$${crate}-$ x", &vars).as_deref(),
+            Some("$serde_json-$ x")
+        );
+        // As in a link rule, `${` without closing brace is kept as is
+        assert_eq!(expand("${crate", &vars).as_deref(), Some("${crate"));
+        assert_eq!(expand("${homepage}", &vars), None);
+    }
+
+    #[test]
+    fn test_parse_refdef_templates() {
+        let templates = parse_refdef_templates(
+            r#"
+[[templates]]
+label = "${crate}-lib-rs"
+url = "https://lib.rs/crates/${package}"
+
+[[templates]]
+label = "${crate}-homepage"
+url = "${homepage}"
+badge = "https://img.shields.io/badge/${crate}-homepage-blue"
+"#,
+        )
+        .unwrap();
+        assert_eq!(templates.len(), 2);
+        assert_eq!(
+            templates[0],
+            RefDefTemplate::new("${crate}-lib-rs", "https://lib.rs/crates/${package}")
+        );
+        assert!(templates[1].badge_url_pattern.is_some());
+    }
+}
@@ -2,74 +2,163 @@
 
 use std::borrow::Cow;
 use std::collections::BTreeMap;
+use std::collections::HashMap;
 
+use tracing::debug;
 use tracing::info;
 
+use super::RefDefTemplate;
+use super::expand;
 use crate::dependencies::Dependency;
 use crate::link::Link;
 
 /// Create reference definitions from dependencies
 ///
 /// dependencies: sorted map of dependencies
+///
+/// templates: the reference definitions to create for each dependency.
 pub(crate) fn generate_refdefs_from(
     dependencies: BTreeMap<Cow<'_, str>, Dependency<'_>>,
-) -> Vec<Link<'static>> {
+    templates: &[RefDefTemplate],
+) -> Vec<Link> {
     let mut buf = Vec::new();
     for (_, dep) in dependencies {
         info!("{dep:?}");
-        buf.push(generate_refdefs_for_one_library(
-            &dep.library_name,
-            dep.package_repo_url.as_deref(),
-        ));
+        buf.push(generate_refdefs_for_one_library(&dep, templates));
     }
     buf.into_iter().flatten().collect::<Vec<_>>()
 }
 
 /// Create, for a given crate, multiple reference definitions for
 /// common websites such as docs.rs, crates.io, github,
-/// and the associated badge URLs
+/// and the associated badge URLs, following a set of templates.
 fn generate_refdefs_for_one_library(
-    library_name: &str,
-    package_repo_url: Option<&str>,
-) -> Vec<Link<'static>> {
-    let mut buf = Vec::new();
-
-    // [arrow-rs]: https://docs.rs/arrow/
-    let docs_rs_url = format!("https://docs.rs/{}", library_name);
-    buf.push(
-        crate::link::LinkBuilder::default()
-            .set_label(library_name.to_string().into())
-            .set_url(docs_rs_url.into())
-            .build(),
-    );
-
-    // [config-crate]: https://crates.io/crates/config/
-    let crates_io_url = format!("https://crates.io/crates/{}", library_name);
-    buf.push(
-        crate::link::LinkBuilder::default()
-            .set_label(format!("{}-crate", library_name).into())
-            .set_url(crates_io_url.into())
-            .build(),
-    );
+    dep: &Dependency<'_>,
+    templates: &[RefDefTemplate],
+) -> Vec<Link> {
+    let mut vars: HashMap<&str, &str> = HashMap::from([
+        ("crate", dep.library_name.as_ref()),
+        ("package", dep.package_name.as_ref()),
+    ]);
+    for (name, value) in [
+        ("version", &dep.version),
+        ("repository", &dep.package_repo_url),
+        ("homepage", &dep.homepage),
+        ("documentation", &dep.documentation),
+    ] {
+        if let Some(value) = value {
+            vars.insert(name, value);
+        }
+    }
 
-    if let Some(url) = package_repo_url {
-        // [arrow-rs-github]: https://github.com/apache/arrow-rs/
+    let mut buf = Vec::new();
+    for t in templates {
+        let (Some(label), Some(url)) = (
+            expand(&t.label_pattern, &vars),
+            expand(&t.url_pattern, &vars),
+        ) else {
+            debug!(
+                "Skipping the `{}` template for {}: missing value.",
+                t.label_pattern, dep.library_name
+            );
+            continue;
+        };
+        if let Some(badge_url) = t
+            .badge_url_pattern
+            .as_deref()
+            .and_then(|p| expand(p, &vars))
+        {
+            buf.push(
+                crate::link::LinkBuilder::default()
+                    .set_label(format!("{label}-badge"))
+                    .set_url(badge_url)
+                    .build(),
+            );
+        }
         buf.push(
             crate::link::LinkBuilder::default()
-                .set_label(format!("{}-github", library_name).into())
-                .set_url(url.to_string().into())
+                .set_label(label)
+                .set_url(url)
                 .build(),
         );
     }
-
     buf
 }
 
 #[cfg(test)]
 mod tests {
-    // use super::*;
+    use super::*;
+
+    fn serde_json() -> Dependency<'static> {
+        Dependency {
+            library_name: "serde_json".into(),
+            package_name: "serde_json".into(),
+            package_repo_url: Some("https://github.com/serde-rs/json".into()),
+            version: Some("1.0.140".into()),
+            homepage: None,
+            documentation: None,
+        }
+    }
 
-    // #[test]
-    // fn test() {
-    // }
+    fn to_strings(links: Vec<Link>) -> Vec<String> {
+        links
+            .iter()
+            .map(|l| {
+                format!(
+                    "{}: {}",
+                    l.label.as_deref().unwrap_or_default(),
+                    l.get_url()
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_generate_refdefs_for_one_library() {
+        let dep = serde_json();
+        assert_eq!(
+            to_strings(generate_refdefs_for_one_library(
+                &dep,
+                &RefDefTemplate::defaults(false)
+            )),
+            vec![
+                "serde_json: https://docs.rs/serde_json",
+                "serde_json-crate: https://crates.io/crates/serde_json",
+                "serde_json-github: https://github.com/serde-rs/json"
+            ]
+        );
+        assert_eq!(
+            to_strings(generate_refdefs_for_one_library(
+                &dep,
+                &RefDefTemplate::defaults(true)
+            )),
+            vec![
+                "serde_json: https://docs.rs/serde_json/1.0.140/serde_json/",
+                "serde_json-crate: https://crates.io/crates/serde_json/1.0.140",
+                "serde_json-github: https://github.com/serde-rs/json"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate_refdefs_with_templates() {
+        let templates = vec![
+            RefDefTemplate::new("${crate}-lib-rs", "https://lib.rs/crates/${package}"),
+            RefDefTemplate::new("${crate}-homepage", "${homepage}"),
+            RefDefTemplate {
+                badge_url_pattern: Some(
+                    "https://badge-cache.kominick.com/crates/v/${crate}.svg?label=${crate}".into(),
+                ),
+                ..RefDefTemplate::new("${crate}-docs", "https://docs.rs/${crate}")
+            },
+        ];
+        assert_eq!(
+            to_strings(generate_refdefs_for_one_library(&serde_json(), &templates)),
+            vec![
+                "serde_json-lib-rs: https://lib.rs/crates/serde_json",
+                "serde_json-docs-badge: https://badge-cache.kominick.com/crates/v/serde_json.svg?label=serde_json",
+                "serde_json-docs: https://docs.rs/serde_json",
+            ]
+        );
+    }
 }
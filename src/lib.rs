@@ -23,30 +23,35 @@
 // #![doc(html_logo_url = "https://example.com/logo.jpg")]
 
 pub mod api;
+mod book;
+mod book_toml;
 mod build_book;
+mod cache;
+mod chapters;
 mod dependencies;
+mod error;
+mod feed;
+mod formatter;
 mod fs;
 mod generate;
-mod link;
+pub mod link;
+mod lint;
 /// Markdown manipulation modules
 pub mod markdown;
 mod parser;
+mod registry;
+mod render;
 mod sitemap;
+mod summary;
 /// Example Markdown for testing
 pub mod test_markdown;
 mod write_from_parser;
 
 use std::fs::File;
-use std::io::BufWriter;
-use std::io::Write;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::LazyLock;
 
-use anyhow::Context;
-use anyhow::Result;
-use anyhow::bail;
-use pulldown_cmark::LinkType;
 use pulldown_cmark::Parser;
 
 /// Helper function:
@@ -54,7 +59,8 @@
 /// Checks if the source directory exists,
 /// create the destination directory if it doesn't exist,
 /// create the destination file,
-/// parse all the Markdown files in the source directory,
+/// parse all the Markdown files in the source directory (with the default
+/// parser options),
 /// and invoke a closure that uses the parser to write to the file.
 fn helper<P1, P2, F>(src_dir_path: P1, dest_file_path: P2, func: F) -> Result<()>
 where
@@ -66,431 +72,111 @@ fn helper<P1, P2, F>(src_dir_path: P1, dest_file_path: P2, func: F) -> Result<()
 
     fs::create_parent_dir_for(dest_file_path.as_ref())?;
 
-    let mut f = File::create(dest_file_path.as_ref()).with_context(|| {
-        format!(
-            "[helper] Could not create file {}",
-            dest_file_path.as_ref().display()
-        )
-    })?;
+    let mut f =
+        File::create(dest_file_path.as_ref()).map_err(|e| Error::io(dest_file_path.as_ref(), e))?;
 
-    let all_markdown = fs::read_to_string_all_markdown_files_in(src_dir_path)?;
-    let mut parser = parser::get_parser(all_markdown.as_ref());
+    let options = parser::ParserOptions::default();
+    let all_markdown = fs::read_to_string_all_markdown_files_in(src_dir_path, &options)?;
+    let mut parser = parser::get_parser(all_markdown.as_ref(), &options);
 
     func(&mut parser, &mut f)?;
     Ok(())
 }
 
-// Public Functions
-
-// DEBUG
-
-/// Parse Markdown from all .md files in a given source directory and
-/// write all raw events to a file for debugging purposes.
-///
-/// src_dir_path: path to the source directory.
-///
-/// dest_file_path: path to the file to create and write into.
-pub fn debug_parse_to<P1, P2>(src_dir_path: P1, dest_file_path: P2) -> Result<()>
-where
-    P1: AsRef<Path>,
-    P2: AsRef<Path>,
-{
-    helper(
-        src_dir_path,
-        dest_file_path,
-        write_from_parser::write_raw_to,
-    )?;
-    Ok(())
-}
-
-/// Test function that uses fake Markdown and writes events to
-/// `./book/temp/test.log`.
-pub fn test() -> Result<()> {
-    fs::create_dir("./book/temp/")?;
-
-    let dest_file_path = "./book/temp/test.log";
-    let mut f = BufWriter::new(File::create(dest_file_path).context(
-        "[test] Failed to create the destination file. Does the full directory path exist?",
-    )?);
-
-    let test_markdown = test_markdown::get_test_markdown();
-    let mut parser = parser::get_parser(test_markdown.as_ref());
-    write_from_parser::write_raw_to(&mut parser, &mut f)?;
-    f.flush()
-        .context("Not all bytes could be written due to I/O errors or EOF being reached.")?;
-    Ok(())
-}
-
-// REFERENCE DEFINITIONS
-
-/// Parse Markdown from all .md files in a given source directory
-/// and write reference definitions found therein to a file.
-///
-/// src_dir_path: path to the source directory.
-///
-/// dest_file_path: path to the file to create and write into.
-pub fn write_refdefs_to<P1, P2>(src_dir_path: P1, dest_file_path: P2) -> Result<()>
-where
-    P1: AsRef<Path>,
-    P2: AsRef<Path>,
-{
-    helper(
-        src_dir_path,
-        dest_file_path,
-        write_from_parser::write_refdefs_to,
-    )?;
-    Ok(())
-}
-
-/// Parse Markdown from all .md files in a given source directory,
-/// extract existing reference definitions,
-/// identify URLs that are GitHub repos,
-/// create badge URLs for these links,
-/// and write to a file.
-///
-/// src_dir_path: path to the source directory.
-///
-/// dest_file_path: path to the file to create and write into.
-pub fn generate_badges<P1, P2>(src_dir_path: P1, dest_file_path: P2) -> Result<()>
-where
-    P1: AsRef<Path>,
-    P2: AsRef<Path>,
-{
-    helper(
-        src_dir_path,
-        dest_file_path,
-        write_from_parser::write_github_repo_badge_refdefs,
-    )?;
-    Ok(())
-}
-
-// LINKS
-
-/// Parse Markdown from all .md files in a given source directory,
-/// write all inline links and autolinks (i.e., not written as
-/// reference-style links) found therein to a file.
-///
-/// src_dir_path: path to the source directory.
-///
-/// dest_file_path: path to the file to create and write into.
-pub fn write_inline_links<P1, P2>(src_dir_path: P1, dest_file_path: P2) -> Result<()>
-where
-    P1: AsRef<Path>,
-    P2: AsRef<Path>,
-{
-    helper(src_dir_path, dest_file_path, |parser, f| {
-        let links: Vec<link::Link<'_>> = parser::extract_links(parser);
-        let links: Vec<_> = links
-            .into_iter()
-            .filter(|l| {
-                [LinkType::Inline, LinkType::Autolink]
-                    .iter()
-                    .any(|&x| l.get_link_type().unwrap() == x)
-            })
-            .filter(|l| {
-                let url = l.get_url();
-                url.starts_with("http")
-            })
-            .collect();
-        link::write_reference_style_links_to(links, f)?;
-        Ok(())
-    })?;
-
-    Ok(())
-}
-
-/// Parse Markdown from all .md files in a given source directory,
-/// write all links found therein to a file.
-///
-/// src_dir_path: path to the source directory.
-///
-/// dest_file_path: path to the file to create and write into.
-pub fn write_all_links<P1, P2>(src_dir_path: P1, dest_file_path: P2) -> Result<()>
-where
-    P1: AsRef<Path>,
-    P2: AsRef<Path>,
-{
-    helper(src_dir_path, dest_file_path, |parser, f| {
-        let links: Vec<link::Link<'_>> = parser::extract_links(parser);
-        let links: Vec<_> = links
-            .into_iter()
-            .filter(|l| {
-                let url = l.get_url();
-                url.starts_with("http")
-            })
-            .collect();
-        link::write_reference_style_links_to(links, f)?;
-        Ok(())
-    })?;
-
-    Ok(())
-}
-
-/// Parse Markdown from all .md files in a given source directory,
-/// write duplicated links found therein to a file.
-///
-/// src_dir_path: path to the source directory.
-///
-/// dest_file_path: path to the file to create and write into.
-pub fn write_duplicate_links<P1, P2>(src_dir_path: P1, dest_file_path: P2) -> Result<()>
-where
-    P1: AsRef<Path>,
-    P2: AsRef<Path>,
-{
-    helper(src_dir_path, dest_file_path, |parser, f| {
-        let links: Vec<link::Link<'_>> = parser::extract_links(parser);
-        let links: Vec<_> = links
-            .into_iter()
-            .filter(|l| {
-                let url = l.get_url();
-                url.starts_with("http")
-            })
-            .collect();
-        let mut counts = std::collections::HashMap::new();
-        for l in &links {
-            *counts.entry(l.clone()).or_insert(0) += 1;
-        }
-        let duplicates: Vec<_> = links.into_iter().filter(|l| counts[l] > 1).collect();
-        link::write_duplicate_links_to(duplicates, f)?;
-        Ok(())
-    })?;
-
-    Ok(())
-}
-
-/// Parse Markdown from all .md files in a given source directory,
-/// write broken links found therein to a file.
-///
-/// src_dir_path: path to the source directory.
-///
-/// dest_file_path: path to the file to create and write into.
-pub fn write_broken_links<P1, P2>(src_dir_path: P1, dest_file_path: P2) -> Result<()>
-where
-    P1: AsRef<Path>,
-    P2: AsRef<Path>,
-{
-    let src_dir_path = fs::check_is_dir(src_dir_path)?;
-    fs::create_parent_dir_for(dest_file_path.as_ref())?;
-
-    let mut f = File::create(dest_file_path.as_ref()).with_context(|| {
-        format!(
-            "[write_broken_links] Could not create file {}",
-            dest_file_path.as_ref().display()
-        )
-    })?;
-
-    let all_markdown = fs::read_to_string_all_markdown_files_in(src_dir_path)?;
-    let handler = parser::Handler::new();
-    let parser = parser::get_parser_with_broken_links_handler(&all_markdown, handler.clone());
-
-    // We need to consume the parser to trigger the callbacks
-    for _ in parser {}
-
-    let broken_links = handler.broken_links.lock().unwrap().clone();
-
-    link::write_broken_links_to(broken_links, &mut f)?;
-
-    Ok(())
-}
-
-// GENERATE REF DEFS FROM DEPENDENCIES
-
-/// Given a Cargo.toml path,
-/// generate reference definitions from code dependencies
-/// and write them to a file.
-///
-/// cargo_toml_dir_path: path to the directory containing `Cargo.toml`.
-///
-/// markdown_dir_path: path to the directory containing Markdown files.
-///
-/// refdef_dest_file_path: path to the file to create and
-/// write into.
-///
-/// log_file_path: optional path to a log file where the output of `cargo tree`
-/// will be written.
-pub fn generate_refdefs_to<P1, P2, P3, P4>(
-    cargo_toml_dir_path: P1,
-    markdown_dir_path: P2,
-    refdef_dest_file_path: P3,
-    log_file_path: Option<P4>,
-) -> Result<()>
-where
-    P1: AsRef<Path>,
-    P2: AsRef<Path>,
-    P3: AsRef<Path>,
-    P4: AsRef<Path>,
-{
-    // Generate ref defs from dependencies
-    let deps = dependencies::get_dependencies(&cargo_toml_dir_path, log_file_path)?;
-    // for (_, d) in &deps {
-    //     tracing::info!("{d:?}");
-    // }
-    let mut new_links = generate::generate_refdefs_from(deps);
-
-    // TODO can we read just the *-refs.md files?
-    helper(
-        markdown_dir_path,
-        refdef_dest_file_path,
-        move |parser, f| {
-            // Read existing ref defs
-            let existing_links: Vec<link::Link<'_>> = parser::extract_links(parser);
-            let existing_links_static: Vec<link::Link<'static>> =
-                existing_links.into_iter().map(|l| l.to_static()).collect();
-
-            let links = generate::merge_links(existing_links_static, &mut new_links);
-            link::write_refdefs_to(links, f)?;
-            Ok(())
-        },
-    )?;
-    Ok(())
-}
-
-// SITEMAP
-
-/// Create a sitemap.xml file from the list of Markdown files in a
-/// source directory.
-///
-/// src_dir_path: path to the source directory.
-///
-/// domain: base URL e.g. <https://john-cd.com/rust_howto/>.
-///
-/// dest_file_path: the path to the destination file e.g.
-/// book/html/sitemap.xml.
-pub fn generate_sitemap<P1, P2>(
-    markdown_src_dir_path: P1,
-    base_url: url::Url,
-    sitemap_dest_file_path: P2,
-    map_index: Option<(String, String)>,
-) -> Result<()>
-where
-    P1: AsRef<Path>,
-    P2: AsRef<Path>,
-{
-    // Returns an error whether the base URL is a 'cannot-be-a-base' URL,
-    // meaning that parsing a relative URL string with this URL
-    // as the base will return an error.
-    if base_url.cannot_be_a_base() {
-        bail!("Invalid URL - cannot be a base: {base_url}");
-    }
-
-    // Verify source path.
-    let markdown_src_dir_path = fs::check_is_dir(markdown_src_dir_path)?;
-
-    // Create the parent folders of the destination file, if needed.
-    fs::create_parent_dir_for(sitemap_dest_file_path.as_ref())?;
-
-    // Create the `sitemap.xml` file.
-    // `File::create` will create a file if it does not exist,
-    // and will truncate it if it does.
-    let mut f = File::create(sitemap_dest_file_path.as_ref()).with_context(|| {
-        format!(
-            "Failed to create the sitemap file {}. The full directory path may not exist or required permissions may be missing.",
-            sitemap_dest_file_path.as_ref().display()
-        )
-    })?;
-
-    let summary_md_path = markdown_src_dir_path.join("SUMMARY.md");
-    tracing::debug!("SUMMARY.md path: {}", summary_md_path.display());
-    let markdown = std::fs::read_to_string(&summary_md_path).with_context(|| {
-        format!(
-            "[generate_sitemap] Could not read {}. Does the file exist?",
-            summary_md_path.display()
-        )
-    })?;
-    let mut parser = parser::get_parser(markdown.as_str());
-    let links: Vec<link::Link<'_>> = parser::extract_links(&mut parser);
-
-    sitemap::generate_sitemap(links, base_url, &mut f, map_index)?;
-
-    Ok(())
-}
-
-// MARKDOWN GENERATION
-
-/// Generate a listing of crates.io categories
-/// and write to a Markdown file.
-pub fn generate_categories<P1: AsRef<Path>, P2: AsRef<Path>>(
-    src_dir_path: P1,
-    dest_file_path: P2,
-) -> Result<()> {
-    fs::create_parent_dir_for(dest_file_path.as_ref())?;
-    let mut f = File::create(dest_file_path).context("Failed to create categories file.")?;
-    writeln!(f, "# Categories\n")?;
-
-    let src_dir_path = fs::check_is_dir(src_dir_path)?;
-    let all_markdown = fs::read_to_string_all_markdown_files_in(src_dir_path)?;
-    let mut parser = parser::get_parser(all_markdown.as_ref());
-    let links = parser::extract_links(&mut parser);
-
-    let mut categories = std::collections::BTreeSet::new();
-    for l in links {
-        let url = l.get_url();
-        if url.contains("crates.io/categories/") {
-            let mut path = url.split('?').next().unwrap_or("");
-            if path.ends_with('/') {
-                path = &path[..path.len() - 1];
-            }
-            if let Some(name) = path.split('/').next_back()
-                && !name.is_empty()
-                && name != "categories"
-                && name
-                    .chars()
-                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
-            {
-                categories.insert(name.to_string());
-            }
-        }
-    }
-
-    for c in categories {
-        writeln!(f, "- [{c}](https://crates.io/categories/{c})")?;
-    }
-
-    Ok(())
-}
-
-/// Generate a crate index and write to a Markdown file.
-pub fn generate_crates<P1: AsRef<Path>, P2: AsRef<Path>>(
-    src_dir_path: P1,
-    dest_file_path: P2,
-) -> Result<()> {
-    use std::io::Write;
-
-    fs::create_parent_dir_for(dest_file_path.as_ref())?;
-    let mut f = File::create(dest_file_path).context("Failed to create crates file.")?;
-    writeln!(f, "# Crates\n")?;
-
-    let src_dir_path = fs::check_is_dir(src_dir_path)?;
-    let all_markdown = fs::read_to_string_all_markdown_files_in(src_dir_path)?;
-    let mut parser = parser::get_parser(all_markdown.as_ref());
-    let links = parser::extract_links(&mut parser);
-
-    let mut crates = std::collections::BTreeSet::new();
-    for l in links {
-        let url = l.get_url();
-        if url.contains("crates.io/crates/") {
-            let mut path = url.split('?').next().unwrap_or("");
-            if path.ends_with('/') {
-                path = &path[..path.len() - 1];
-            }
-            if let Some(name) = path.split('/').next_back()
-                && !name.is_empty()
-                && name != "crates"
-                && name
-                    .chars()
-                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
-            {
-                crates.insert(name.to_string());
-            }
-        }
-    }
-
-    for c in crates {
-        writeln!(f, "- [{c}](https://crates.io/crates/{c})")?;
-    }
-
-    Ok(())
-}
+pub use api::Book;
+pub use api::BookTable;
+pub use api::BookToml;
+pub use api::BrokenRedirect;
+pub use api::BrokenRef;
+pub use api::BuildTable;
+pub use api::CacheStats;
+pub use api::CategoryIndexOptions;
+pub use api::ChapterMetadata;
+pub use api::ChapterMove;
+pub use api::CodeBlockInfo;
+pub use api::CrateIndexOptions;
+pub use api::CrateNameMismatch;
+pub use api::CrateSort;
+pub use api::DEFAULT_HTML_TEMPLATE;
+pub use api::DependencyAudit;
+pub use api::DependencyKind;
+pub use api::DependencyOptions;
+pub use api::EventFormat;
+pub use api::FeedFormat;
+pub use api::FeedOptions;
+pub use api::FenceStyle;
+pub use api::FileFix;
+pub use api::FormatChanges;
+pub use api::FormatOptions;
+pub use api::HtmlOutput;
+pub use api::ImageInfo;
+pub use api::IncludeInfo;
+pub use api::LinkInfo;
+pub use api::LinkKind;
+pub use api::LinkStyle;
+pub use api::LintFinding;
+pub use api::LintFixes;
+pub use api::LintOptions;
+pub use api::LintReport;
+pub use api::LintRule;
+pub use api::MarkdownCache;
+pub use api::MarkdownExtension;
+pub use api::MarkdownPreset;
+pub use api::MetadataValue;
+pub use api::OutputTable;
+pub use api::ParserOptions;
+pub use api::PreprocessorTable;
+pub use api::RefDefInfo;
+pub use api::RefDefPlacement;
+pub use api::RefDefTemplate;
+pub use api::RenderOptions;
+pub use api::RustTable;
+pub use api::Severity;
+pub use api::SitemapOptions;
+pub use api::SummaryChapter;
+pub use api::TextEdit;
+pub use api::VersionUpgrade;
+pub use api::audit_dependencies;
+pub use api::audit_dependencies_to_writer;
+pub use api::check_redirects;
+pub use api::debug_parse_files_to;
+pub use api::debug_parse_markdown;
+pub use api::debug_parse_markdown_as;
+pub use api::debug_parse_to;
+pub use api::format_markdown;
+pub use api::format_markdown_str;
+pub use api::generate_badges;
+pub use api::generate_categories;
+pub use api::generate_categories_to_writer;
+pub use api::generate_crates;
+pub use api::generate_crates_to_writer;
+pub use api::generate_feed;
+pub use api::generate_feed_and_search_index;
+pub use api::generate_feed_to_writer;
+pub use api::generate_refdefs_to;
+pub use api::generate_refdefs_to_writer;
+pub use api::generate_search_index;
+pub use api::generate_search_index_to_writer;
+pub use api::generate_sitemap;
+pub use api::generate_summary;
+pub use api::generate_summary_to_writer;
+pub use api::lint;
+pub use api::move_chapter;
+pub use api::read_refdef_templates;
+pub use api::render_chapter;
+pub use api::render_chapter_to;
+pub use api::render_markdown_str;
+pub use api::test;
+pub use api::upgrade_versions;
+pub use api::upgrade_versions_in_str;
+pub use api::write_all_links;
+pub use api::write_broken_links;
+pub use api::write_duplicate_links;
+pub use api::write_inline_links;
+pub use api::write_refdefs_to;
+pub use api::write_sitemap;
+pub use error::Error;
+pub use error::Result;
 
 /// Identify .md files not in SUMMARY.md
 // TODO: Handle nested directories more accurately in SUMMARY.md link parsing.
@@ -502,14 +188,21 @@ pub fn identify_files_not_in_summary<P: AsRef<Path>>(
 
     let summary_path = markdown_src_dir_path.join("SUMMARY.md");
     if !summary_path.exists() {
-        bail!(
-            "SUMMARY.md not found in {}",
-            markdown_src_dir_path.display()
-        );
+        return Err(Error::io(
+            &summary_path,
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!(
+                    "SUMMARY.md not found in {}",
+                    markdown_src_dir_path.display()
+                ),
+            ),
+        ));
     }
 
-    let summary_content = std::fs::read_to_string(&summary_path)?;
-    let mut parser = parser::get_parser(&summary_content);
+    let summary_content =
+        std::fs::read_to_string(&summary_path).map_err(|e| Error::io(&summary_path, e))?;
+    let mut parser = parser::get_parser(&summary_content, &parser::ParserOptions::default());
     let links = parser::extract_links(&mut parser);
 
     let mut files_in_summary = std::collections::HashSet::new();
@@ -569,7 +262,7 @@ pub fn identify_unused_rs_examples<P1: AsRef<Path>, P2: AsRef<Path>>(
         LazyLock::new(|| regex::Regex::new(r"(?P<path>[a-zA-Z0-9_.\-\/]+\.rs)").unwrap());
 
     for md_file in md_files {
-        let content = std::fs::read_to_string(&md_file)?;
+        let content = std::fs::read_to_string(&md_file).map_err(|e| Error::io(&md_file, e))?;
         for cap in RE.captures_iter(&content) {
             let rel_path = Path::new(&cap["path"]);
             let abs_path = md_file.parent().unwrap().join(rel_path);
@@ -594,6 +287,7 @@ mod tests {
     use std::fs;
     use std::io::Write;
 
+    use anyhow::Result;
     use tempfile::tempdir;
 
     use super::*;
@@ -620,28 +314,26 @@ fn test_generate_categories() -> Result<()> {
 
         let dest_file_path = temp_dir.path().join("categories.md");
 
-        generate_categories(&src_dir, &dest_file_path)?;
+        generate_categories(&src_dir, &dest_file_path, CategoryIndexOptions::default())?;
 
         let content = fs::read_to_string(&dest_file_path)?;
 
-        assert!(content.contains("# Categories"));
         assert!(
             content
-                .contains("- [development-tools](https://crates.io/categories/development-tools)")
+                .starts_with("# Categories\n\n## [Development tools][cat-development-tools]\n\n")
         );
-        assert!(content.contains("- [parsing](https://crates.io/categories/parsing)"));
-        assert!(!content.contains("- [serde]"));
+        assert!(content.contains("## [Parsing tools][cat-parsing]\n\n"));
+        assert!(content.contains("- Chapters: [test](src/test.md)\n"));
+        assert!(!content.contains("serde"));
 
         // Ensure they are sorted and deduplicated
-        let lines: Vec<&str> = content.lines().filter(|l| l.starts_with("-")).collect();
-        assert_eq!(lines.len(), 2);
+        let lines: Vec<&str> = content.lines().filter(|l| l.starts_with("[cat-")).collect();
         assert_eq!(
-            lines[0],
-            "- [development-tools](https://crates.io/categories/development-tools)"
-        );
-        assert_eq!(
-            lines[1],
-            "- [parsing](https://crates.io/categories/parsing)"
+            lines,
+            vec![
+                "[cat-development-tools]: https://crates.io/categories/development-tools",
+                "[cat-parsing]: https://crates.io/categories/parsing",
+            ]
         );
 
         Ok(())
@@ -672,20 +364,35 @@ fn test_generate_crates() -> Result<()> {
 
         let dest_file_path = temp_dir.path().join("crates.md");
 
-        generate_crates(&src_dir, &dest_file_path)?;
+        let cargo_lock_path = temp_dir.path().join("Cargo.lock");
+        fs::write(
+            &cargo_lock_path,
+            "[[package]]\nname = \"serde\"\nversion = \"1.0.200\"\n",
+        )?;
+        let options = CrateIndexOptions {
+            cargo_lock_path: Some(cargo_lock_path),
+            badges: false,
+            ..Default::default()
+        };
+
+        generate_crates(&src_dir, &dest_file_path, options)?;
 
         let content = fs::read_to_string(&dest_file_path)?;
 
         assert!(content.contains("# Crates"));
-        assert!(content.contains("- [anyhow](https://crates.io/crates/anyhow)"));
-        assert!(content.contains("- [serde](https://crates.io/crates/serde)"));
-        assert!(!content.contains("- [parsing]"));
+        assert!(!content.contains("[parsing]"));
 
         // Ensure they are sorted and deduplicated
-        let lines: Vec<&str> = content.lines().filter(|l| l.starts_with("-")).collect();
+        let lines: Vec<&str> = content.lines().filter(|l| l.starts_with("| [")).collect();
         assert_eq!(lines.len(), 2);
-        assert_eq!(lines[0], "- [anyhow](https://crates.io/crates/anyhow)");
-        assert_eq!(lines[1], "- [serde](https://crates.io/crates/serde)");
+        assert_eq!(
+            lines[0],
+            "| [anyhow](https://crates.io/crates/anyhow) |  |  |  | [test](src/test.md) |"
+        );
+        assert_eq!(
+            lines[1],
+            "| [serde](https://crates.io/crates/serde) | 1.0.200 |  |  | [test](src/test.md) |"
+        );
         Ok(())
     }
 
@@ -702,7 +409,7 @@ fn test_generate_categories_injection() -> Result<()> {
         )?;
 
         let dest_file = dir.path().join("categories.md");
-        generate_categories(&src_dir, &dest_file)?;
+        generate_categories(&src_dir, &dest_file, CategoryIndexOptions::default())?;
 
         let content = fs::read_to_string(&dest_file)?;
         let expected = "# Categories\n\n";
@@ -724,7 +431,7 @@ fn test_generate_crates_injection() -> Result<()> {
         )?;
 
         let dest_file = dir.path().join("crates.md");
-        generate_crates(&src_dir, &dest_file)?;
+        generate_crates(&src_dir, &dest_file, CrateIndexOptions::default())?;
 
         let content = std::fs::read_to_string(&dest_file)?;
         let expected = "# Crates\n\n";
@@ -887,12 +594,22 @@ fn test_generate_categories_happy_path() -> Result<()> {
         )?;
 
         let dest_file = dir.path().join("categories.md");
-        generate_categories(&src_dir, &dest_file)?;
+        generate_categories(&src_dir, &dest_file, CategoryIndexOptions::default())?;
 
         let content = fs::read_to_string(&dest_file)?;
-        let expected = "# Categories\n\n- [cat1](https://crates.io/categories/cat1)\n- [cat2](https://crates.io/categories/cat2)\n- [cat3](https://crates.io/categories/cat3)\n";
+        let expected = "# Categories\n\n\
+            ## [cat1][cat-cat1]\n\n- Chapters: [1](src/1.md), [2](src/2.md)\n\n\
+            ## [cat2][cat-cat2]\n\n- Chapters: [1](src/1.md)\n\n\
+            ## [cat3][cat-cat3]\n\n- Chapters: [1](src/1.md)\n\n\
+            [cat-cat1]: https://crates.io/categories/cat1\n\
+            [cat-cat2]: https://crates.io/categories/cat2\n\
+            [cat-cat3]: https://crates.io/categories/cat3\n";
         assert_eq!(content, expected);
 
+        // To a writer, with links relative to the source directory
+        let mut buf = Vec::new();
+        generate_categories_to_writer(&src_dir, CategoryIndexOptions::default(), &mut buf)?;
+        assert_eq!(String::from_utf8(buf)?, expected.replace("](src/", "]("));
         Ok(())
     }
 
@@ -902,7 +619,7 @@ fn test_generate_categories_invalid_dir() {
         let src_dir = dir.path().join("non_existent_src");
         let dest_file = dir.path().join("categories.md");
 
-        let result = generate_categories(&src_dir, &dest_file);
+        let result = generate_categories(&src_dir, &dest_file, CategoryIndexOptions::default());
         assert!(result.is_err());
     }
 
@@ -919,7 +636,7 @@ fn test_generate_categories_edge_cases() -> Result<()> {
         )?;
 
         let dest_file = dir.path().join("categories.md");
-        generate_categories(&src_dir, &dest_file)?;
+        generate_categories(&src_dir, &dest_file, CategoryIndexOptions::default())?;
 
         let content = fs::read_to_string(&dest_file)?;
         let expected = "# Categories\n\n";
@@ -937,13 +654,11 @@ fn test_generate_sitemap_invalid_base_url() {
         fs::create_dir(&markdown_src_dir_path).unwrap();
         let base_url = Url::parse("mailto:test@example.com").unwrap();
         let sitemap_dest_file_path = dir.path().join("sitemap.xml");
-        let map_index = None;
-
         let result = generate_sitemap(
             markdown_src_dir_path,
             base_url,
             sitemap_dest_file_path,
-            map_index,
+            SitemapOptions::default(),
         );
 
         assert!(result.is_err());
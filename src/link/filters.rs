@@ -0,0 +1,40 @@
+//! Select links among those extracted from the Markdown
+
+use std::collections::HashMap;
+
+use super::Link;
+use super::LinkKind;
+
+/// Keep the links to websites i.e. whose URL starts with `http`
+pub(crate) fn http_links(links: Vec<Link>) -> Vec<Link> {
+    links
+        .into_iter()
+        .filter(|l| {
+            let url = l.get_url();
+            url.starts_with("http")
+        })
+        .collect()
+}
+
+/// Keep the links to websites that are inline links or autolinks (i.e.,
+/// not written as reference-style links)
+pub(crate) fn inline_links(links: Vec<Link>) -> Vec<Link> {
+    http_links(links)
+        .into_iter()
+        .filter(|l| {
+            [LinkKind::Inline, LinkKind::Autolink]
+                .iter()
+                .any(|&x| l.get_link_type() == Some(x))
+        })
+        .collect()
+}
+
+/// Keep the links to websites that appear more than once
+pub(crate) fn duplicate_links(links: Vec<Link>) -> Vec<Link> {
+    let links = http_links(links);
+    let mut counts = HashMap::new();
+    for l in &links {
+        *counts.entry(l.clone()).or_insert(0) += 1;
+    }
+    links.into_iter().filter(|l| counts[l] > 1).collect()
+}
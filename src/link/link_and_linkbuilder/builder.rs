@@ -1,101 +1,132 @@
-use std::borrow::Cow;
-
 use pulldown_cmark::LinkType;
 
+use super::LinkKind;
 use super::link::Link;
 
 /// Link builder that progressively construct a [Link]
-/// from pieces of information
+/// from pieces of information.
+///
+/// Empty strings are ignored: they do not set or clear a field.
 #[derive(Debug, Default)]
-pub(crate) struct LinkBuilder<'a> {
-    link: Link<'a>,
+pub struct LinkBuilder {
+    link: Link,
+}
+
+/// Returns `Some` if the string is not empty
+fn non_empty(s: impl Into<String>) -> Option<String> {
+    Some(s.into()).filter(|s| !s.is_empty())
 }
 
-impl<'a> LinkBuilder<'a> {
-    pub(crate) fn from_type_url_title(
+impl LinkBuilder {
+    /// Start building a link of a given kind, with a URL, a title and
+    /// a reference label
+    pub fn from_type_url_title(
+        link_kind: LinkKind,
+        url: impl Into<String>,
+        title: impl Into<String>,
+        label: impl Into<String>,
+    ) -> Self {
+        Self::from_parsed(link_kind.to_link_type(), url, title, label)
+    }
+
+    /// Start building a link found by the Markdown parser, whose type
+    /// tells whether its reference is defined
+    pub(crate) fn from_parsed(
         link_type: LinkType,
-        url: Cow<'a, str>,
-        title: Cow<'a, str>,
-        label: Cow<'a, str>,
+        url: impl Into<String>,
+        title: impl Into<String>,
+        label: impl Into<String>,
     ) -> Self {
         Self {
             link: Link {
                 link_type: Some(link_type),
-                url: if !url.is_empty() { Some(url) } else { None },
-                title: if !title.is_empty() { Some(title) } else { None },
-                label: if !label.is_empty() { Some(label) } else { None },
+                url: non_empty(url),
+                title: non_empty(title),
+                label: non_empty(label),
                 ..Link::default()
             },
         }
     }
 
-    pub(crate) fn set_url(mut self, url: Cow<'a, str>) -> Self {
-        if !url.is_empty() {
+    /// Set the kind of link
+    pub fn set_link_type(mut self, link_kind: LinkKind) -> Self {
+        self.link.link_type = Some(link_kind.to_link_type());
+        self
+    }
+
+    /// Set the URL
+    pub fn set_url(mut self, url: impl Into<String>) -> Self {
+        if let Some(url) = non_empty(url) {
             self.link.url = Some(url);
         }
         self
     }
 
-    pub(crate) fn add_text(mut self, text: Cow<'a, str>) -> Self {
-        if !text.is_empty() {
-            self.link.text = Some(format!("{}{text}", self.link.text.unwrap_or_default()).into());
+    /// Set the title
+    pub fn set_title(mut self, title: impl Into<String>) -> Self {
+        if let Some(title) = non_empty(title) {
+            self.link.title = Some(title);
+        }
+        self
+    }
+
+    /// Append to the text of the link
+    pub fn add_text(mut self, text: impl Into<String>) -> Self {
+        if let Some(text) = non_empty(text) {
+            self.link.text = Some(self.link.text.unwrap_or_default() + &text);
         }
         self
     }
 
-    pub(crate) fn set_label(mut self, label: Cow<'a, str>) -> Self {
-        if !label.is_empty() {
+    /// Set the reference label
+    pub fn set_label(mut self, label: impl Into<String>) -> Self {
+        if let Some(label) = non_empty(label) {
             self.link.label = Some(label);
         }
         self
     }
 
-    pub(crate) fn set_image(
-        self,
-        image_link_type: LinkType,
-        image_url: Cow<'a, str>,
-        image_title: Cow<'a, str>,
-        image_label: Cow<'a, str>,
+    /// Set the kind, URL, title and reference label of the image within
+    /// the link e.g. a badge
+    pub fn set_image(
+        mut self,
+        image_link_kind: LinkKind,
+        image_url: impl Into<String>,
+        image_title: impl Into<String>,
+        image_label: impl Into<String>,
     ) -> Self {
-        Self {
-            link: Link {
-                image_link_type: Some(image_link_type),
-                image_url: if !image_url.is_empty() {
-                    Some(image_url)
-                } else {
-                    self.link.image_url
-                },
-                image_title: if !image_title.is_empty() {
-                    Some(image_title)
-                } else {
-                    self.link.image_title
-                },
-                image_label: if !image_label.is_empty() {
-                    Some(image_label)
-                } else {
-                    self.link.image_label
-                },
-                ..self.link
-            },
+        self.link.image_link_type = Some(image_link_kind.to_link_type());
+        if let Some(image_url) = non_empty(image_url) {
+            self.link.image_url = Some(image_url);
+        }
+        if let Some(image_title) = non_empty(image_title) {
+            self.link.image_title = Some(image_title);
         }
+        if let Some(image_label) = non_empty(image_label) {
+            self.link.image_label = Some(image_label);
+        }
+        self
     }
 
-    pub(crate) fn set_image_url(mut self, image_url: Cow<'a, str>) -> Self {
-        if !image_url.is_empty() {
+    /// Set the URL of the image within the link e.g. a badge
+    pub fn set_image_url(mut self, image_url: impl Into<String>) -> Self {
+        if let Some(image_url) = non_empty(image_url) {
             self.link.image_url = Some(image_url);
         }
         self
     }
 
-    pub(crate) fn add_image_alt_text(mut self, image_alt_text: Cow<'a, str>) -> Self {
-        if !image_alt_text.is_empty() {
+    /// Append to the alternative text of the image within the link
+    pub fn add_image_alt_text(mut self, image_alt_text: impl Into<String>) -> Self {
+        if let Some(image_alt_text) = non_empty(image_alt_text) {
             self.link.image_alt_text =
-                Some(self.link.image_alt_text.unwrap_or_default() + image_alt_text)
+                Some(self.link.image_alt_text.unwrap_or_default() + &image_alt_text);
         }
         self
     }
 
-    pub(crate) fn build(self) -> Link<'a> {
+    /// Returns the link
+    pub fn build(self) -> Link {
         self.link
     }
 }
@@ -110,27 +141,15 @@ fn test_link_equality_and_hash() {
         use std::hash::Hash;
         use std::hash::Hasher;
 
-        let link1 = LinkBuilder::from_type_url_title(
-            LinkType::Shortcut,
-            "url1".into(),
-            "title1".into(),
-            "label1".into(),
-        )
-        .build();
-        let link2 = LinkBuilder::from_type_url_title(
-            LinkType::Shortcut,
-            "url1".into(),
-            "title1".into(),
-            "label1".into(),
-        )
-        .build();
-        let link3 = LinkBuilder::from_type_url_title(
-            LinkType::Shortcut,
-            "url2".into(),
-            "title1".into(),
-            "label1".into(),
-        )
-        .build();
+        let link1 =
+            LinkBuilder::from_type_url_title(LinkKind::Shortcut, "url1", "title1", "label1")
+                .build();
+        let link2 =
+            LinkBuilder::from_type_url_title(LinkKind::Shortcut, "url1", "title1", "label1")
+                .build();
+        let link3 =
+            LinkBuilder::from_type_url_title(LinkKind::Shortcut, "url2", "title1", "label1")
+                .build();
 
         assert_eq!(link1, link2);
         assert_ne!(link1, link3);
@@ -148,27 +167,12 @@ fn test_link_equality_and_hash() {
 
     #[test]
     fn test_link_ordering() {
-        let link_a = LinkBuilder::from_type_url_title(
-            LinkType::Shortcut,
-            "url".into(),
-            "title".into(),
-            "a".into(),
-        )
-        .build();
-        let link_b = LinkBuilder::from_type_url_title(
-            LinkType::Shortcut,
-            "url".into(),
-            "title".into(),
-            "b".into(),
-        )
-        .build();
-        let link_a_url2 = LinkBuilder::from_type_url_title(
-            LinkType::Shortcut,
-            "url2".into(),
-            "title".into(),
-            "a".into(),
-        )
-        .build();
+        let link_a =
+            LinkBuilder::from_type_url_title(LinkKind::Shortcut, "url", "title", "a").build();
+        let link_b =
+            LinkBuilder::from_type_url_title(LinkKind::Shortcut, "url", "title", "b").build();
+        let link_a_url2 =
+            LinkBuilder::from_type_url_title(LinkKind::Shortcut, "url2", "title", "a").build();
 
         assert!(link_a < link_b);
         assert!(link_a < link_a_url2);
@@ -176,22 +180,12 @@ fn test_link_ordering() {
 
     #[test]
     fn test_to_reference_definition() {
-        let link = LinkBuilder::from_type_url_title(
-            LinkType::Shortcut,
-            "url".into(),
-            "title".into(),
-            "label".into(),
-        )
-        .build();
+        let link =
+            LinkBuilder::from_type_url_title(LinkKind::Shortcut, "url", "title", "label").build();
         assert_eq!(link.to_reference_definition(), "[label]: url \"title\"");
 
-        let link_no_title = LinkBuilder::from_type_url_title(
-            LinkType::Shortcut,
-            "url".into(),
-            "".into(),
-            "label".into(),
-        )
-        .build();
+        let link_no_title =
+            LinkBuilder::from_type_url_title(LinkKind::Shortcut, "url", "", "label").build();
         assert_eq!(link_no_title.to_reference_definition(), "[label]: url");
     }
 }
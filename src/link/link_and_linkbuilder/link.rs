@@ -5,69 +5,90 @@
 
 use heck::ToKebabCase;
 use pulldown_cmark::LinkType;
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::LinkKind;
 
 /// `Link` is a structure that collects all necessary information to
 /// write Markdown (inline or reference-style) links and reference
 /// definitions, including badges.
-#[derive(Debug, Default, Clone)]
-pub(crate) struct Link<'a> {
+///
+/// Create one with a [`LinkBuilder`](super::LinkBuilder), or with
+/// [`RuleSet::link_for`](crate::link::RuleSet::link_for), then render it
+/// e.g.:
+///
+/// ```
+/// use mdbook_utils::link::LinkBuilder;
+///
+/// let link = LinkBuilder::default()
+///     .add_text("serde")
+///     .set_label("serde")
+///     .set_url("https://docs.rs/serde")
+///     .set_image_url("https://img.shields.io/crates/v/serde")
+///     .build();
+/// assert_eq!(link.to_inline_link(), "[serde]( https://docs.rs/serde )");
+/// assert_eq!(link.to_reference_link(), "[serde]");
+/// assert_eq!(
+///     link.to_reference_definition(),
+///     "[serde]: https://docs.rs/serde"
+/// );
+/// assert_eq!(link.to_link_with_badge(), "[![serde][serde-badge]][serde]");
+/// assert_eq!(
+///     link.to_badge_reference_definition(),
+///     "[serde-badge]: https://img.shields.io/crates/v/serde"
+/// );
+/// ```
+///
+/// Two links are equal if they have the same label, URL and title.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Link {
+    #[serde(with = "serde_link_type")]
     pub(crate) link_type: Option<LinkType>,
-    pub(crate) text: Option<Cow<'a, str>>,  // [text](...)
-    pub(crate) label: Option<Cow<'a, str>>, // [...][label] and [label]: ...
-    pub(crate) url: Option<Cow<'a, str>>,   // [...]: url or [...](url) or <url>
+    pub(crate) text: Option<String>,  // [text](...)
+    pub(crate) label: Option<String>, // [...][label] and [label]: ...
+    pub(crate) url: Option<String>,   // [...]: url or [...](url) or <url>
     // parsed_url: Option<Url>, Url::parse( )?
-    pub(crate) title: Option<Cow<'a, str>>, // [...]: url "title" or [...](url "title")
+    pub(crate) title: Option<String>, // [...]: url "title" or [...](url "title")
 
     // [![image_alt_text][image_label]][...]
     // [image_label]: image_url "image_title"
     #[allow(dead_code)]
+    #[serde(with = "serde_link_type")]
     pub(crate) image_link_type: Option<LinkType>,
-    pub(crate) image_alt_text: Option<Cow<'a, str>>,
-    pub(crate) image_label: Option<Cow<'a, str>>,
-    pub(crate) image_url: Option<Cow<'a, str>>,
-    pub(crate) image_title: Option<Cow<'a, str>>,
+    pub(crate) image_alt_text: Option<String>,
+    pub(crate) image_label: Option<String>,
+    pub(crate) image_url: Option<String>,
+    pub(crate) image_title: Option<String>,
 }
 
-impl<'a> Link<'a> {
-    pub(crate) fn to_static(&self) -> Link<'static> {
-        Link {
-            link_type: self.link_type,
-            text: self.text.as_ref().map(|c| Cow::Owned(c.to_string())),
-            label: self.label.as_ref().map(|c| Cow::Owned(c.to_string())),
-            url: self.url.as_ref().map(|c| Cow::Owned(c.to_string())),
-            title: self.title.as_ref().map(|c| Cow::Owned(c.to_string())),
-            image_link_type: self.image_link_type,
-            image_alt_text: self
-                .image_alt_text
-                .as_ref()
-                .map(|c| Cow::Owned(c.to_string())),
-            image_label: self.image_label.as_ref().map(|c| Cow::Owned(c.to_string())),
-            image_url: self.image_url.as_ref().map(|c| Cow::Owned(c.to_string())),
-            image_title: self.image_title.as_ref().map(|c| Cow::Owned(c.to_string())),
-        }
+impl Link {
+    // Getters
+
+    /// Returns the kind of link, if known
+    pub fn get_link_type(&self) -> Option<LinkKind> {
+        self.link_type.map(LinkKind::from)
     }
 
-    // Methods that write Markdown directly
+    /// Returns the link's text, or an empty string
+    pub fn get_text(&self) -> &str {
+        self.text.as_deref().unwrap_or_default()
+    }
 
-    /// Returns the link type
-    pub(crate) fn get_link_type(&self) -> Option<LinkType> {
-        self.link_type
+    /// Returns the link's url, or an empty string
+    pub fn get_url(&self) -> &str {
+        self.url.as_deref().unwrap_or_default()
     }
 
-    /// Return the link's text
-    fn get_text(&self) -> Cow<'_, str> {
-        self.text
-            .as_deref()
-            .map(Cow::Borrowed)
-            .unwrap_or(Cow::Borrowed(""))
+    /// Returns the link's title, if any
+    pub fn get_title(&self) -> Option<&str> {
+        self.title.as_deref()
     }
 
-    /// Returns the link's url
-    pub(crate) fn get_url(&self) -> Cow<'_, str> {
-        self.url
-            .as_deref()
-            .map(Cow::Borrowed)
-            .unwrap_or(Cow::Borrowed(""))
+    /// Returns the url of the image within the link (e.g. a badge), if
+    /// any
+    pub fn get_image_url(&self) -> Option<&str> {
+        self.image_url.as_deref()
     }
 
     /// Returns the link's url (and title if present)
@@ -85,7 +106,7 @@ fn get_url_and_title(&self) -> Cow<'_, str> {
 
     /// Returns the link's reference label, if it exists, or the
     /// kebab-cased link's text
-    fn get_label(&self) -> Cow<'_, str> {
+    pub fn get_label(&self) -> Cow<'_, str> {
         if let Some(label) = &self.label {
             Cow::Borrowed(label.as_ref())
         } else if let Some(txt) = &self.text {
@@ -95,97 +116,150 @@ fn get_label(&self) -> Cow<'_, str> {
         }
     }
 
+    // Methods that write Markdown directly
+
     /// Return a Markdown inline link:
     /// [text](url) or [text](url "title")
-    pub(crate) fn to_inline_link(&self) -> Cow<'_, str> {
-        format!("[{}]( {} )", self.get_text(), self.get_url_and_title()).into()
+    pub fn to_inline_link(&self) -> String {
+        format!("[{}]( {} )", self.get_text(), self.get_url_and_title())
     }
 
     /// Return a reference-style Markdown link:
     /// \[text\]\[label\] or \[text/label\]
-    pub(crate) fn to_reference_link(&self) -> Cow<'_, str> {
-        let txt: String = self.get_text().into();
-        let label: String = self.get_label().into();
+    pub fn to_reference_link(&self) -> String {
+        let txt = self.get_text();
+        let label = self.get_label();
         if txt == label {
-            format!("[{txt}]").into()
+            format!("[{txt}]")
         } else {
-            format!("[{txt}][{label}]").into()
+            format!("[{txt}][{label}]")
         }
     }
 
     /// Return a Markdown reference definition:
     /// \[label\]: url or \[label\]: url "title"
-    pub(crate) fn to_reference_definition(&self) -> Cow<'_, str> {
-        format!("[{}]: {}", self.get_label(), self.get_url_and_title()).into()
+    pub fn to_reference_definition(&self) -> String {
+        format!("[{}]: {}", self.get_label(), self.get_url_and_title())
     }
 
     // BADGES / IMAGES
 
     /// Return the badge alt text, if it exists, or the badge's label
     /// or the link's label
-    fn get_badge_alt_text(&self) -> Cow<'a, str> {
+    fn get_badge_alt_text(&self) -> Cow<'_, str> {
         if let Some(alt_txt) = &self.image_alt_text {
-            alt_txt.clone()
+            Cow::Borrowed(alt_txt)
         } else if let Some(img_lbl) = &self.image_label {
-            img_lbl.clone()
+            Cow::Borrowed(img_lbl)
         } else if let Some(lbl) = &self.label {
-            lbl.clone()
+            Cow::Borrowed(lbl)
         } else {
-            "".into()
+            Cow::Borrowed("")
         }
     }
 
     /// Return the label for the badge reference
     /// e.g. image_label or \<label\>-badge
-    fn get_badge_label(&self) -> Cow<'a, str> {
+    fn get_badge_label(&self) -> Cow<'_, str> {
         if let Some(ref img_lbl) = self.image_label {
-            img_lbl.clone()
+            Cow::Borrowed(img_lbl)
         } else if let Some(ref lbl) = self.label {
             format!("{lbl}-badge").into()
         } else if let Some(ref alt_txt) = self.image_alt_text {
-            alt_txt.clone()
+            Cow::Borrowed(alt_txt)
         } else {
             "badge".into()
         }
     }
 
     /// Return the badge's url and title (if present)
-    fn get_badge_url_and_title(&self) -> Cow<'a, str> {
+    fn get_badge_url_and_title(&self) -> Cow<'_, str> {
         if let Some(ref u) = self.image_url {
             if let Some(ref t) = self.image_title {
                 format!("{u} \"{t}\"").into()
             } else {
-                u.clone()
+                Cow::Borrowed(u)
             }
         } else {
-            Cow::from(String::new())
+            Cow::Borrowed("")
         }
     }
 
     /// Return a badge image that is clickable:
     /// \[ !\[ alt-text \]\[ badge-label \] \]\[ label \]
-    pub(crate) fn to_link_with_badge(&self) -> Cow<'a, str> {
+    pub fn to_link_with_badge(&self) -> String {
         format!(
             "[![{}][{}]][{}]",
             self.get_badge_alt_text(),
             self.get_badge_label(),
             self.get_label()
         )
-        .into()
     }
 
     /// Return the reference definition for a badge image: [badge-label]: https://badge-image-url...  "image_title"
-    pub(crate) fn to_badge_reference_definition(&self) -> Cow<'a, str> {
+    pub fn to_badge_reference_definition(&self) -> String {
         format!(
             "[{}]: {}",
             self.get_badge_label(),
             self.get_badge_url_and_title()
         )
-        .into()
     }
 }
 
-impl PartialOrd for Link<'_> {
+/// (De)serialize a [`LinkType`], which does not implement `serde`'s
+/// traits, by name e.g. `Inline` or `ShortcutUnknown`
+mod serde_link_type {
+    use pulldown_cmark::LinkType;
+    use serde::Deserialize;
+    use serde::Deserializer;
+    use serde::Serialize;
+    use serde::Serializer;
+    use serde::de::Error;
+
+    /// All link types, by name
+    const LINK_TYPES: [(&str, LinkType); 11] = [
+        ("Inline", LinkType::Inline),
+        ("Reference", LinkType::Reference),
+        ("ReferenceUnknown", LinkType::ReferenceUnknown),
+        ("Collapsed", LinkType::Collapsed),
+        ("CollapsedUnknown", LinkType::CollapsedUnknown),
+        ("Shortcut", LinkType::Shortcut),
+        ("ShortcutUnknown", LinkType::ShortcutUnknown),
+        ("Autolink", LinkType::Autolink),
+        ("Email", LinkType::Email),
+        ("WikiLink", LinkType::WikiLink { has_pothole: false }),
+        ("WikiLinkPothole", LinkType::WikiLink { has_pothole: true }),
+    ];
+
+    pub(super) fn serialize<S: Serializer>(
+        link_type: &Option<LinkType>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let name = link_type.and_then(|lt| {
+            LINK_TYPES
+                .iter()
+                .find(|(_, t)| *t == lt)
+                .map(|(name, _)| *name)
+        });
+        name.serialize(serializer)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<LinkType>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|name| {
+                LINK_TYPES
+                    .iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, t)| *t)
+                    .ok_or_else(|| D::Error::custom(format!("unknown link type: {name}")))
+            })
+            .transpose()
+    }
+}
+
+impl PartialOrd for Link {
     /// PartialOrd implementation for Link
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         // The type is `Ord``,
@@ -194,7 +268,7 @@ fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
     }
 }
 
-impl Ord for Link<'_> {
+impl Ord for Link {
     /// Ord implementation for Link
     fn cmp(&self, other: &Self) -> Ordering {
         self.label
@@ -204,7 +278,7 @@ fn cmp(&self, other: &Self) -> Ordering {
     }
 }
 
-impl PartialEq for Link<'_> {
+impl PartialEq for Link {
     /// PartialEq implementation for Link
     fn eq(&self, other: &Self) -> bool {
         (self.label == other.label) && (self.url == other.url) && (self.title == other.title)
@@ -212,9 +286,9 @@ fn eq(&self, other: &Self) -> bool {
 }
 
 /// Eq implementation for Link
-impl Eq for Link<'_> {}
+impl Eq for Link {}
 
-impl Hash for Link<'_> {
+impl Hash for Link {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.label.hash(state);
         self.url.hash(state);
@@ -233,27 +307,15 @@ fn test_link_equality_and_hash() {
         use std::hash::Hash;
         use std::hash::Hasher;
 
-        let link1 = LinkBuilder::from_type_url_title(
-            LinkType::Shortcut,
-            "url1".into(),
-            "title1".into(),
-            "label1".into(),
-        )
-        .build();
-        let link2 = LinkBuilder::from_type_url_title(
-            LinkType::Shortcut,
-            "url1".into(),
-            "title1".into(),
-            "label1".into(),
-        )
-        .build();
-        let link3 = LinkBuilder::from_type_url_title(
-            LinkType::Shortcut,
-            "url2".into(),
-            "title1".into(),
-            "label1".into(),
-        )
-        .build();
+        let link1 =
+            LinkBuilder::from_type_url_title(LinkKind::Shortcut, "url1", "title1", "label1")
+                .build();
+        let link2 =
+            LinkBuilder::from_type_url_title(LinkKind::Shortcut, "url1", "title1", "label1")
+                .build();
+        let link3 =
+            LinkBuilder::from_type_url_title(LinkKind::Shortcut, "url2", "title1", "label1")
+                .build();
 
         assert_eq!(link1, link2);
         assert_ne!(link1, link3);
@@ -271,27 +333,12 @@ fn test_link_equality_and_hash() {
 
     #[test]
     fn test_link_ordering() {
-        let link_a = LinkBuilder::from_type_url_title(
-            LinkType::Shortcut,
-            "url".into(),
-            "title".into(),
-            "a".into(),
-        )
-        .build();
-        let link_b = LinkBuilder::from_type_url_title(
-            LinkType::Shortcut,
-            "url".into(),
-            "title".into(),
-            "b".into(),
-        )
-        .build();
-        let link_a_url2 = LinkBuilder::from_type_url_title(
-            LinkType::Shortcut,
-            "url2".into(),
-            "title".into(),
-            "a".into(),
-        )
-        .build();
+        let link_a =
+            LinkBuilder::from_type_url_title(LinkKind::Shortcut, "url", "title", "a").build();
+        let link_b =
+            LinkBuilder::from_type_url_title(LinkKind::Shortcut, "url", "title", "b").build();
+        let link_a_url2 =
+            LinkBuilder::from_type_url_title(LinkKind::Shortcut, "url2", "title", "a").build();
 
         assert!(link_a < link_b);
         assert!(link_a < link_a_url2);
@@ -299,22 +346,12 @@ fn test_link_ordering() {
 
     #[test]
     fn test_to_reference_definition() {
-        let link = LinkBuilder::from_type_url_title(
-            LinkType::Shortcut,
-            "url".into(),
-            "title".into(),
-            "label".into(),
-        )
-        .build();
+        let link =
+            LinkBuilder::from_type_url_title(LinkKind::Shortcut, "url", "title", "label").build();
         assert_eq!(link.to_reference_definition(), "[label]: url \"title\"");
 
-        let link_no_title = LinkBuilder::from_type_url_title(
-            LinkType::Shortcut,
-            "url".into(),
-            "".into(),
-            "label".into(),
-        )
-        .build();
+        let link_no_title =
+            LinkBuilder::from_type_url_title(LinkKind::Shortcut, "url", "", "label").build();
         assert_eq!(link_no_title.to_reference_definition(), "[label]: url");
     }
 }
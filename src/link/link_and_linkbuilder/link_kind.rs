@@ -0,0 +1,74 @@
+use pulldown_cmark::LinkType;
+
+/// Kind of link, as written in the Markdown source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LinkKind {
+    /// `[text](url)`
+    Inline,
+    /// `[text][label]`
+    Reference,
+    /// `[label][]`
+    Collapsed,
+    /// `[label]`
+    Shortcut,
+    /// `<https://example.com>`
+    Autolink,
+    /// `<someone@example.com>`
+    Email,
+    /// `[[page]]`
+    WikiLink,
+}
+
+impl LinkKind {
+    /// Link type of the Markdown parser, for a link whose reference (if
+    /// any) is defined
+    pub(crate) fn to_link_type(self) -> LinkType {
+        match self {
+            Self::Inline => LinkType::Inline,
+            Self::Reference => LinkType::Reference,
+            Self::Collapsed => LinkType::Collapsed,
+            Self::Shortcut => LinkType::Shortcut,
+            Self::Autolink => LinkType::Autolink,
+            Self::Email => LinkType::Email,
+            Self::WikiLink => LinkType::WikiLink { has_pothole: false },
+        }
+    }
+}
+
+impl From<LinkType> for LinkKind {
+    fn from(link_type: LinkType) -> Self {
+        match link_type {
+            LinkType::Inline => Self::Inline,
+            LinkType::Reference | LinkType::ReferenceUnknown => Self::Reference,
+            LinkType::Collapsed | LinkType::CollapsedUnknown => Self::Collapsed,
+            LinkType::Shortcut | LinkType::ShortcutUnknown => Self::Shortcut,
+            LinkType::Autolink => Self::Autolink,
+            LinkType::Email => Self::Email,
+            LinkType::WikiLink { .. } => Self::WikiLink,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link_kind() {
+        for kind in [
+            LinkKind::Inline,
+            LinkKind::Reference,
+            LinkKind::Collapsed,
+            LinkKind::Shortcut,
+            LinkKind::Autolink,
+            LinkKind::Email,
+            LinkKind::WikiLink,
+        ] {
+            assert_eq!(LinkKind::from(kind.to_link_type()), kind);
+        }
+        assert_eq!(
+            LinkKind::from(LinkType::ShortcutUnknown),
+            LinkKind::Shortcut
+        );
+    }
+}
@@ -1,7 +1,9 @@
-//! Internal models for a Markdown [Link] and [LinkBuilder]
+//! Models for a Markdown [Link], its [LinkKind] and [LinkBuilder]
 
 mod builder;
 mod link;
+mod link_kind;
 
-pub(crate) use builder::*;
-pub(crate) use link::*;
+pub use builder::*;
+pub use link::*;
+pub use link_kind::*;
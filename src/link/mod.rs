@@ -1,10 +1,19 @@
 //! Markdown (inline or reference-style) [links][Link], and
-//! Rules to create a reference label and/or a badge URL
-//! from a link URL
+//! [rules][RuleSet] to create a reference label and/or a badge URL
+//! from a link URL.
+//!
+//! Use [`LinkBuilder`] or [`RuleSet::link_for`] to create a [`Link`], then
+//! render it as an inline link, a reference-style link, a reference
+//! definition or a badge.
+mod filters;
 mod link_and_linkbuilder;
 mod rules;
 mod write_to_file;
 
-pub(crate) use link_and_linkbuilder::*;
-pub(crate) use rules::*;
-pub(crate) use write_to_file::*;
+pub(crate) use filters::*;
+#[doc(inline)]
+pub use link_and_linkbuilder::*;
+#[doc(inline)]
+pub use rules::*;
+#[doc(inline)]
+pub use write_to_file::*;
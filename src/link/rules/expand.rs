@@ -0,0 +1,97 @@
+//! Replacement patterns of the link rules and of the reference
+//! definition templates e.g. `crate-${name}`
+
+/// Replace `$name` and `${name}` in a pattern by the value of the
+/// variable `name`, and `$$` by `$`, with the syntax of
+/// `regex::Captures::expand`:
+///
+/// - `$name` takes the longest run of `[_0-9a-zA-Z]` characters as the name, so
+///   `${name}` is needed before such a character,
+/// - a `$` that does not start a reference (e.g. `$-`, or `${` without closing
+///   brace) is kept as is.
+///
+/// lookup: returns the value of a variable, or `None` if it has no
+/// value, in which case the whole expansion returns `None`.
+pub(crate) fn expand_pattern<'a>(
+    pattern: &str,
+    lookup: impl Fn(&str) -> Option<&'a str>,
+) -> Option<String> {
+    let mut buf = String::with_capacity(pattern.len());
+    let mut rest = pattern;
+    while let Some(i) = rest.find('$') {
+        buf.push_str(&rest[..i]);
+        rest = &rest[i..];
+        if let Some(r) = rest.strip_prefix("$$") {
+            buf.push('$');
+            rest = r;
+            continue;
+        }
+        match variable(rest) {
+            Some((name, len)) => {
+                buf.push_str(lookup(name)?);
+                rest = &rest[len..];
+            }
+            None => {
+                buf.push('$');
+                rest = &rest[1..];
+            }
+        }
+    }
+    buf.push_str(rest);
+    Some(buf)
+}
+
+/// Returns the name of the variable that a pattern starts with (`$name`
+/// or `${name}`) and the length of the reference, if any.
+fn variable(s: &str) -> Option<(&str, usize)> {
+    let s = s.strip_prefix('$')?;
+    if let Some(braced) = s.strip_prefix('{') {
+        let end = braced.find('}')?;
+        return Some((&braced[..end], end + 3));
+    }
+    let end = s
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(s.len());
+    (end > 0).then(|| (&s[..end], end + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+
+    use super::*;
+
+    #[test]
+    fn test_expand_pattern_as_regex() {
+        let regex = Regex::new(r"(?<crate>[a-z_]+)-(?<version>[0-9.]+)").unwrap();
+        let caps = regex.captures("serde_json-1.0.140").unwrap();
+        let lookup = |name: &str| {
+            Some(
+                match name.parse::<usize>() {
+                    Ok(i) => caps.get(i),
+                    Err(_) => caps.name(name),
+                }
+                .map_or("", |m| m.as_str()),
+            )
+        };
+        for pattern in [
+            "https://docs.rs/${crate}/${version}/$crate/",
+            "$$${crate}-$ x",
+            "${crate",
+            "$crate_x ${}$-$1 $",
+            "${crate}${",
+            "é$crateé",
+        ] {
+            let mut expected = String::new();
+            caps.expand(pattern, &mut expected);
+            assert_eq!(expand_pattern(pattern, lookup), Some(expected), "{pattern}");
+        }
+    }
+
+    #[test]
+    fn test_expand_pattern_missing_value() {
+        let lookup = |name: &str| (name == "crate").then_some("serde");
+        assert_eq!(expand_pattern("${crate}", lookup).as_deref(), Some("serde"));
+        assert_eq!(expand_pattern("${crate}-${homepage}", lookup), None);
+    }
+}
@@ -2,9 +2,10 @@
 //! from a link URL
 #![allow(clippy::vec_init_then_push)]
 
+mod expand;
 mod regexes;
 mod structs;
 
+pub(crate) use expand::*;
 pub(crate) use regexes::*;
-#[allow(unused_imports)]
-pub(crate) use structs::*;
+pub use structs::*;
@@ -2,283 +2,283 @@
 //! from a link URL
 #![allow(clippy::vec_init_then_push)]
 
-use std::collections::HashMap;
-
 use once_cell::sync::Lazy;
-use regex::Regex;
 
+use super::Rule;
+use super::RuleSet;
+
+/// Definition of a default rule
 #[derive(Debug, Default)]
-pub(crate) struct Rule<'a> {
-    pub(crate) re: &'a str, // Regex pattern to match the url
-    #[allow(dead_code)]
-    pub(crate) label_pattern: &'a str, // regex replacement pattern
-    pub(crate) badge_url_pattern: &'a str, /* optional pattern to build a
+struct RuleDef<'a> {
+    re: &'a str,            // Regex pattern to match the url
+    label_pattern: &'a str, // regex replacement pattern
+    badge_url_pattern: &'a str, /* optional pattern to build a
                              * badge link */
 }
 
-/// Globally compiled regexes for each rule.
-pub(crate) static COMPILED_RULES: Lazy<HashMap<&str, Regex>> = Lazy::new(|| {
-    GLOBAL_RULES
-        .iter()
-        .map(|(name, rule)| {
-            (
-                *name,
-                Regex::new(rule.re).unwrap_or_else(|_| panic!("Invalid regex for rule: {}", name)),
-            )
-        })
-        .collect()
-});
-
-/// All rules that transform a URL to a label or badge URL.
-pub(crate) static GLOBAL_RULES: Lazy<HashMap<&str, Rule<'_>>> = Lazy::new(|| {
-    let mut m = HashMap::new();
+/// All rules that transform a URL to a label or badge URL, in the order
+/// they are tried: specific rules first, generic rules last.
+pub(crate) static DEFAULT_RULES: Lazy<RuleSet> = Lazy::new(|| {
+    let mut m = Vec::new();
 
     // CATEGORIES
     // [cat-websocket-badge]: https://badge-cache.kominick.com/badge/websocket--x.svg?style=social
     // [cat-websocket]: https://crates.io/categories/web-programming::websocket/
     // Optional query
-    m.insert(
+    m.push((
         "category",
-        Rule {
+        RuleDef {
             re: r"https://crates.io/categories/(?<catg>[^/?]+)/?(?:\?\S+)?",
             label_pattern: "cat-${catg}",
             badge_url_pattern: "https://badge-cache.kominick.com/badge/${catg}--x.svg?style=social",
         },
-    );
+    ));
 
     // CRATES
     // [crates-io]: https://crates.io/
-    m.insert(
+    m.push((
         "crates.io",
-        Rule {
+        RuleDef {
             re: r"https://crates.io(?:/crates)?/?",
             label_pattern: "crates-io",
-            ..Rule::default()
+            ..RuleDef::default()
         },
-    );
+    ));
 
     // [smol-badge]: https://badge-cache.kominick.com/crates/v/smol.svg?label=smol
     // [smol-crate]: https://crates.io/crates/smol/
-    m.insert("crate", Rule {
+    m.push(("crate", RuleDef {
         re: r"https://crates.io/crates/(?<crate>[^/?]+)/?",
         label_pattern: "crate-${crate}",
         badge_url_pattern: "https://badge-cache.kominick.com/crates/v/${crate}.svg?label=${crate}",
-    });
+    }));
 
     // DOCS
-    m.insert(
+    m.push((
         "docs.rs",
-        Rule {
+        RuleDef {
             re: r"https://docs.rs/?",
             label_pattern: "docs-rs",
-            ..Rule::default()
+            ..RuleDef::default()
         },
-    );
+    ));
 
     // [sqlx-badge]: https://badge-cache.kominick.com/crates/v/sqlx.svg?label=sqlx
     // [sqlx]: https://docs.rs/sqlx/
     // [actix-web]: https://docs.rs/actix-web/latest/actix_web/
     // [join]: https://docs.rs/rayon/latest/rayon/fn.join.html
     // [spawn-blocking]: https://docs.rs/tokio/latest/tokio/task/fn.spawn_blocking.html
-    m.insert("documentation", Rule {
+    m.push(("documentation", RuleDef {
         re: r"https://docs.rs/(?<crate>[^/]+)(?:/(?:latest|[^/]+))?(?<item>(?:/[^/]+)+)?/?(?:index\.html)?",
         label_pattern: "${crate}${item}",
         badge_url_pattern: "https://badge-cache.kominick.com/crates/v/${crate}.svg?label=${crate}",
-    });
+    }));
 
     // STD DOCS
     // [std]: https://doc.rust-lang.org/std/"
     // [std-badge]: https://badge-cache.kominick.com/badge/std-1.75.0-blue.svg
-    m.insert(
+    m.push((
         "std",
-        Rule {
+        RuleDef {
             re: r"https://doc.rust-lang.org/std/?",
             label_pattern: "std",
             badge_url_pattern: "https://badge-cache.kominick.com/badge/std-1.75.0-blue.svg",
         },
-    );
+    ));
 
     // [std::option::Option]: https://doc.rust-lang.org/std/option/"
     // [std::sync::atomic]: https://doc.rust-lang.org/std/sync/atomic/"
     // [core::cell::OnceCell]: https://doc.rust-lang.org/core/cell/struct.OnceCell.html
-    m.insert(
+    m.push((
         "std item documentation",
-        Rule {
-            re: r"https://doc.rust-lang.org/(?<lib>std|core)/(?<rest>[^/]+(?:/[^/]+)*?)(?:/|\.html)?",
+        RuleDef {
+            re: r"https://doc.rust-lang.org/(?<lib>std|core)/(?<rest>[^?#]+?)(?:/|\.html)?",
             label_pattern: "${lib}-${rest}",
-            ..Rule::default()
+            ..RuleDef::default()
         },
-    );
+    ));
 
     // LIB.RS
     // https://lib.rs/
-    m.insert(
+    m.push((
         "lib.rs",
-        Rule {
+        RuleDef {
             re: r"https://lib.rs/?",
             label_pattern: "lib-rs",
-            ..Rule::default()
+            ..RuleDef::default()
         },
-    );
+    ));
     // [sqlx-librs]: https://lib.rs/crates/sqlx/
-    m.insert(
+    m.push((
         "crate on lib.rs",
-        Rule {
+        RuleDef {
             re: r"https://lib.rs/crates/(?<crate>[^/]+)/?",
             label_pattern: "lib-rs-${crate}",
-            ..Rule::default()
+            ..RuleDef::default()
         },
-    );
+    ));
 
     // GITHUB REPO WIKI
     // https://github.com/cross-rs/cross/wiki/Getting-Started
-    m.insert(
+    m.push((
         "github repo wiki",
-        Rule {
+        RuleDef {
             re: r"https://github.com/(?<owner>\S*?)/(?<repo>\S*?)/wiki/(?:\S+?)",
             label_pattern: "${repo}-wiki",
-            ..Rule::default()
+            ..RuleDef::default()
         },
-    );
+    ));
 
     // [cross-example-toml]: https://github.com/cross-rs/wiki_assets/blob/main/Configuration/Cross.toml
 
     // GITHUB
     // https://github.com/john-cd/rust_howto/blob/main/CONTRIBUTING.md
-    m.insert(
+    m.push((
         "github.com/john-cd",
-        Rule {
+        RuleDef {
             re: r"https://github.com/john-cd/rust_howto/(?:\S+?/)*(?<last>\S*)(?:/|.md)?",
             label_pattern: "rust-howto-${last}",
-            ..Rule::default()
+            ..RuleDef::default()
         },
-    );
+    ));
 
     // [sqlx-github]: https://github.com/launchbadge/sqlx/
     // https://github.com/amar-laksh/workstation/blob/master/src/main.rs
-    m.insert(
+    m.push((
         "github repo",
-        Rule {
+        RuleDef {
             re: r"https://github.com/(?<owner>[^/]+?)/(?<repo>[^/]+)/?.*",
             label_pattern: "${repo}-github",
             badge_url_pattern: "https://img.shields.io/badge/${repo}-steelblue?logo=github",
         },
-    );
+    ));
 
     // GITHUB PAGES
     // [rustup-documentation]: https://rust-lang.github.io/rustup/
     // [rustup-command-examples]: https://rust-lang.github.io/rustup/examples.html
-    m.insert(
+    m.push((
         "github pages",
-        Rule {
+        RuleDef {
             re: r"https://(?<owner>[^.]+)\.github\.io/(?<repo>[^/]+)/?.*",
             label_pattern: "${repo}-github-pages",
             badge_url_pattern: "https://img.shields.io/badge/${repo}-red?logo=githubpages",
         },
-    );
+    ));
 
     //  BOOKS
 
     // RUST BOOK
     // [rust-book-badge]: https://img.shields.io/badge/Rust_Book-blue?logo=mdbook
     // [rust-book]: https://doc.rust-lang.org/book/
-    m.insert(
+    m.push((
         "rust book",
-        Rule {
+        RuleDef {
             re: r"https://doc.rust-lang.org/book/?",
             label_pattern: "rust-book",
             badge_url_pattern: "https://img.shields.io/badge/Rust_Book-blue?logo=mdbook",
         },
-    );
+    ));
 
     // [box-rust-book-badge]: https://img.shields.io/badge/Box-blue?logo=mdbook
     // [box-rust-book]: https://doc.rust-lang.org/book/ch15-01-box.html
-    m.insert(
+    m.push((
         "rust book item",
-        Rule {
+        RuleDef {
             re: r"https://doc.rust-lang.org/book/ch\d{2}-\d{2}-(?<item>[^.]+)\.html",
             label_pattern: "rust-book-${item}",
             badge_url_pattern: "https://img.shields.io/badge/${item}-blue?logo=mdbook",
         },
-    );
+    ));
 
     // RUST REFERENCE
     // [object-safe-reference-badge]: https://img.shields.io/badge/Object_Safe_Traits-green?logo=mdbook
     // [object-safe-reference]: https://doc.rust-lang.org/nightly/reference/items/traits.html#object-safety
     // [attributes-reference]: https://doc.rust-lang.org/reference/attributes.html
     // [conditional-compilation]: https://doc.rust-lang.org/reference/conditional-compilation.html#the-cfg-attribute
-    m.insert("rust reference", Rule {
+    m.push(("rust reference", RuleDef {
         re: r"https://doc.rust-lang.org(?:/nightly)?/reference/(?:(?<chapter>[^/]+)/)?(?<item>[^/]+)\.html(?:#\S+)?",
         label_pattern: "rust-reference-book-${chapter}-${item}",
         badge_url_pattern: "https://img.shields.io/badge/${item}-green?logo=mdbook",
-    });
+    }));
 
     // RUST BY EXAMPLE
     // [rust-by-example-book-badge]: https://img.shields.io/badge/RBE-violet?logo=mdbook
     // [rust-by-example-book]: https://doc.rust-lang.org/rust-by-example/
-    m.insert(
+    m.push((
         "rust by example book",
-        Rule {
+        RuleDef {
             re: r"https://doc.rust-lang.org/rust-by-example/?",
             label_pattern: "rust-by-example",
             badge_url_pattern: "https://img.shields.io/badge/Rust_by_example-violet?logo=mdbook",
         },
-    );
+    ));
 
     // [visibility-rules-rust-by-example-badge]: https://img.shields.io/badge/RBE-Visibility_Rules-violet?logo=mdbook
     // [visibility-rules-rust-by-example]: https://doc.rust-lang.org/rust-by-example/mod/visibility.html
-    m.insert(
+    m.push((
         "rust by example chapter",
-        Rule {
+        RuleDef {
             re: r"https://doc.rust-lang.org/rust-by-example/(?:[^/]+/)*(?<last>[^/.]+)(?:\.html)?",
             label_pattern: "rust-by-example-${last}",
             badge_url_pattern:
                 "https://img.shields.io/badge/Rust_by_example-${last}-violet?logo=mdbook",
         },
-    );
+    ));
 
     // CARGO BOOK
     // [cargo-book]: https://doc.rust-lang.org/cargo/index.html
-    m.insert(
+    m.push((
         "cargo book",
-        Rule {
+        RuleDef {
             re: r"https://doc.rust-lang.org/cargo/(?<rest>[^/.]+)(?:\.html)?",
             label_pattern: "cargo-book-${rest}",
             badge_url_pattern: "https://img.shields.io/badge/Cargo_Book-${rest}-yellow?logo=mdbook",
         },
-    );
+    ));
 
     // GENERIC
-    m.insert(
+    m.push((
         "website",
-        Rule {
+        RuleDef {
             re: r"https?://(?<domain>[^/]+)/?(?:[?#].*)?$",
             label_pattern: "${domain}-website",
-            ..Rule::default()
+            ..RuleDef::default()
         },
-    );
+    ));
 
     // [My terminal became more Rusty Community]: https://dev.to/22mahmoud/my-terminal-became-more-rusty-4g8l
     // [tokio-glossary]: https://tokio.rs/tokio/glossary
     // [tokio-tutorial]: https://tokio.rs/tokio/tutorial
-    m.insert(
+    m.push((
         "website page",
-        Rule {
+        RuleDef {
             re: r"https?://(?<domain>[^/]+)/(?:.*/)?(?<last>[^/?#.]+)(?:\.html|/)?(?:[?#].*)?$",
             label_pattern: "${domain}-${last}",
-            ..Rule::default()
+            ..RuleDef::default()
         },
-    );
-
-    m
+    ));
+
+    m.into_iter()
+        .map(|(name, def)| {
+            let rule = Rule::new(name, def.re, def.label_pattern)
+                .unwrap_or_else(|_| panic!("Invalid regex for rule: {name}"));
+            if def.badge_url_pattern.is_empty() {
+                rule
+            } else {
+                rule.with_badge_url_pattern(def.badge_url_pattern)
+            }
+        })
+        .collect()
 });
 
 #[cfg(test)]
 mod tests {
+    use regex::Regex;
+
     use super::*;
 
     fn get_re(name: &str) -> &'static Regex {
-        COMPILED_RULES.get(name).unwrap()
+        DEFAULT_RULES[name].regex()
     }
 
     #[test]
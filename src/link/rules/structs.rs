@@ -1,9 +1,321 @@
-#[allow(dead_code)] // TODO
-#[derive(Debug, Default)]
-pub(crate) struct Rule<'a> {
-    pub(crate) re: &'a str, // Regex pattern to match the url
-    #[allow(dead_code)]
-    pub(crate) label_pattern: &'a str, // regex replacement pattern
-    pub(crate) badge_url_pattern: &'a str, /* optional pattern to build a
-                             * badge link */
+use std::ops::Index;
+
+use regex::Regex;
+
+use super::DEFAULT_RULES;
+use super::expand_pattern;
+use crate::Error;
+use crate::Result;
+use crate::link::Link;
+use crate::link::LinkBuilder;
+
+/// Rule that creates a reference label and, optionally, a badge URL from
+/// the URL of a link.
+///
+/// A rule applies to a URL if its regex matches the whole URL. The label
+/// and badge URL patterns may refer to the named capture groups of the
+/// regex e.g. `${crate}`.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    name: String,
+    /// Regex pattern, as given
+    pattern: String,
+    /// Regex that matches the whole URL
+    regex: Regex,
+    label_pattern: String,
+    badge_url_pattern: Option<String>,
+}
+
+impl Rule {
+    /// Create a rule.
+    ///
+    /// name: name of the rule e.g. `crate`.
+    ///
+    /// re: regex pattern to match the URL e.g.
+    /// `https://crates.io/crates/(?<crate>[^/?]+)/?`.
+    ///
+    /// label_pattern: replacement pattern that builds the label e.g.
+    /// `crate-${crate}`.
+    pub fn new(name: &str, re: &str, label_pattern: &str) -> Result<Self> {
+        let regex = Regex::new(&format!("^(?:{re})$")).map_err(|source| Error::Rule {
+            name: name.to_string(),
+            source,
+        })?;
+        Ok(Self {
+            name: name.to_string(),
+            pattern: re.to_string(),
+            regex,
+            label_pattern: label_pattern.to_string(),
+            badge_url_pattern: None,
+        })
+    }
+
+    /// Set the replacement pattern that builds the URL of a badge e.g.
+    /// `https://img.shields.io/crates/v/${crate}`.
+    pub fn with_badge_url_pattern(mut self, badge_url_pattern: &str) -> Self {
+        self.badge_url_pattern = Some(badge_url_pattern.to_string());
+        self
+    }
+
+    /// Returns the name of the rule
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the regex pattern, as given
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Returns the regex, which matches the whole URL
+    pub fn regex(&self) -> &Regex {
+        &self.regex
+    }
+
+    /// Returns the replacement pattern of the label
+    pub fn label_pattern(&self) -> &str {
+        &self.label_pattern
+    }
+
+    /// Returns the replacement pattern of the badge URL, if any
+    pub fn badge_url_pattern(&self) -> Option<&str> {
+        self.badge_url_pattern.as_deref()
+    }
+
+    /// Returns `true` if the rule applies to a URL
+    pub fn is_match(&self, url: &str) -> bool {
+        self.regex.is_match(url)
+    }
+
+    /// Returns the label created from a URL, if the rule applies to it
+    pub fn label(&self, url: &str) -> Option<String> {
+        self.expand(url, &self.label_pattern)
+    }
+
+    /// Returns the badge URL created from a URL, if the rule applies to
+    /// it and has a badge URL pattern
+    pub fn badge_url(&self, url: &str) -> Option<String> {
+        self.expand(url, self.badge_url_pattern.as_deref()?)
+    }
+
+    /// Expand a replacement pattern with the capture groups of a URL.
+    /// Groups that did not participate in the match expand to "".
+    fn expand(&self, url: &str, pattern: &str) -> Option<String> {
+        let caps = self.regex.captures(url)?;
+        expand_pattern(pattern, |name| {
+            let group = match name.parse::<usize>() {
+                Ok(i) => caps.get(i),
+                Err(_) => caps.name(name),
+            };
+            Some(group.map_or("", |m| m.as_str()))
+        })
+    }
+}
+
+/// Ordered set of [rules][Rule]. The first rule that applies to a URL
+/// wins.
+///
+/// ```
+/// use mdbook_utils::link::Rule;
+/// use mdbook_utils::link::RuleSet;
+///
+/// # fn main() -> anyhow::Result<()> {
+/// // Custom rules first, then the default rules
+/// let mut rules: RuleSet = [Rule::new(
+///     "tokio docs",
+///     r"https://tokio.rs/tokio/(?<page>[^/]+)",
+///     "tokio-${page}",
+/// )?]
+/// .into_iter()
+/// .collect();
+/// rules.extend(RuleSet::defaults());
+///
+/// assert_eq!(
+///     rules.label("https://tokio.rs/tokio/tutorial").as_deref(),
+///     Some("tokio-tutorial")
+/// );
+/// let link = rules.link_for("https://crates.io/crates/smol").unwrap();
+/// assert_eq!(
+///     link.to_reference_definition(),
+///     "[crate-smol]: https://crates.io/crates/smol"
+/// );
+/// assert_eq!(
+///     link.to_badge_reference_definition(),
+///     "[crate-smol-badge]: https://badge-cache.kominick.com/crates/v/smol.svg?label=smol"
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RuleSet {
+    rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    /// Create an empty rule set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the default rules (crates.io, docs.rs, GitHub, the Rust
+    /// books, websites...)
+    pub fn defaults() -> Self {
+        DEFAULT_RULES.clone()
+    }
+
+    /// Add a rule, after the existing rules. A rule with the same name is
+    /// replaced in place.
+    pub fn add(&mut self, rule: Rule) {
+        match self.rules.iter_mut().find(|r| r.name == rule.name) {
+            Some(r) => *r = rule,
+            None => self.rules.push(rule),
+        }
+    }
+
+    /// Remove a rule by name, and return it
+    pub fn remove(&mut self, name: &str) -> Option<Rule> {
+        let i = self.rules.iter().position(|r| r.name == name)?;
+        Some(self.rules.remove(i))
+    }
+
+    /// Returns a rule by name
+    pub fn get(&self, name: &str) -> Option<&Rule> {
+        self.rules.iter().find(|r| r.name == name)
+    }
+
+    /// Returns the rules, in order
+    pub fn iter(&self) -> impl Iterator<Item = &Rule> {
+        self.rules.iter()
+    }
+
+    /// Returns the number of rules
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Returns `true` if there are no rules
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Returns the first rule that applies to a URL
+    pub fn find(&self, url: &str) -> Option<&Rule> {
+        self.rules.iter().find(|r| r.is_match(url))
+    }
+
+    /// Returns the label created from a URL by the first rule that
+    /// applies to it
+    pub fn label(&self, url: &str) -> Option<String> {
+        self.find(url)?.label(url)
+    }
+
+    /// Returns the badge URL created from a URL by the first rule that
+    /// applies to it
+    pub fn badge_url(&self, url: &str) -> Option<String> {
+        self.find(url)?.badge_url(url)
+    }
+
+    /// Returns a link to a URL, labeled by the first rule that applies to
+    /// it, with a badge if the rule creates one
+    pub fn link_for(&self, url: &str) -> Option<Link> {
+        let rule = self.find(url)?;
+        let mut builder = LinkBuilder::default()
+            .set_label(rule.label(url)?)
+            .set_url(url);
+        if let Some(badge_url) = rule.badge_url(url) {
+            builder = builder.set_image_url(badge_url);
+        }
+        Some(builder.build())
+    }
+}
+
+impl Index<&str> for RuleSet {
+    type Output = Rule;
+
+    /// Returns a rule by name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no rule with that name.
+    fn index(&self, name: &str) -> &Rule {
+        self.get(name)
+            .unwrap_or_else(|| panic!("No rule named {name}"))
+    }
+}
+
+impl FromIterator<Rule> for RuleSet {
+    fn from_iter<I: IntoIterator<Item = Rule>>(iter: I) -> Self {
+        let mut rules = Self::new();
+        rules.extend(iter);
+        rules
+    }
+}
+
+impl Extend<Rule> for RuleSet {
+    /// Add rules after the existing rules, skipping those whose name is
+    /// already in the set. Extending a set of custom rules with
+    /// [`RuleSet::defaults`] therefore lets the custom rules override the
+    /// default rules of the same name.
+    fn extend<I: IntoIterator<Item = Rule>>(&mut self, iter: I) {
+        for rule in iter {
+            if self.get(&rule.name).is_none() {
+                self.rules.push(rule);
+            }
+        }
+    }
+}
+
+impl IntoIterator for RuleSet {
+    type IntoIter = std::vec::IntoIter<Rule>;
+    type Item = Rule;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.rules.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule_set() -> Result<()> {
+        let mut rules = RuleSet::defaults();
+        assert_eq!(
+            rules.find("https://crates.io/crates/smol/").map(Rule::name),
+            Some("crate")
+        );
+        assert_eq!(
+            rules.label("https://crates.io/").as_deref(),
+            Some("crates-io")
+        );
+        assert_eq!(
+            rules
+                .label("https://github.com/john-cd/mdbook-utils")
+                .as_deref(),
+            Some("mdbook-utils-github")
+        );
+        assert_eq!(
+            rules
+                .badge_url("https://github.com/john-cd/mdbook-utils")
+                .as_deref(),
+            Some("https://img.shields.io/badge/mdbook-utils-steelblue?logo=github")
+        );
+        assert_eq!(rules.badge_url("https://lib.rs/"), None);
+        assert!(rules.find("ftp://example.com/").is_none());
+
+        // Replacing and removing rules
+        let len = rules.len();
+        rules.add(Rule::new("crates.io", r"https://crates.io/?", "crates")?);
+        assert_eq!(rules.len(), len);
+        assert_eq!(rules.label("https://crates.io/").as_deref(), Some("crates"));
+        assert!(rules.remove("crates.io").is_some());
+        assert!(rules.get("crates.io").is_none());
+
+        assert!(matches!(
+            Rule::new("invalid", "(", ""),
+            Err(Error::Rule { name, .. }) if name == "invalid"
+        ));
+        Ok(())
+    }
 }
@@ -1,25 +1,21 @@
 //! Write [links][Link] and reference definitions to file(s)
 use std::io::Write;
 
-use anyhow::Context;
-use anyhow::Result;
 use enumflags2::BitFlags;
 use enumflags2::bitflags;
 
 use super::Link;
+use crate::Result;
 
 // PUBLIC FUNCTIONS
 
-/// Write links to a writer (e.g. file)
-pub(crate) fn write_reference_style_links_to<W>(
-    links: Vec<Link<'_>>,
-    link_writer: &mut W,
-) -> Result<()>
+/// Write links to a writer (e.g. file), under a `# Links` heading:
+/// each as a reference-style link followed by its reference definition
+pub fn write_reference_style_links_to<W>(links: Vec<Link>, link_writer: &mut W) -> Result<()>
 where
     W: Write,
 {
-    writeln!(link_writer, "# Links\n")
-        .context("[write_reference_style_links_to] Failed to write links.")?;
+    writeln!(link_writer, "# Links\n")?;
 
     let link_flags = LinkWrite::ReferenceLink | LinkWrite::ReferenceDefinition;
     for l in links.iter() {
@@ -29,13 +25,13 @@ pub(crate) fn write_reference_style_links_to<W>(
     Ok(())
 }
 
-/// Write a reference definition to a writer (e.g. file)
-pub(crate) fn write_refdefs_to<W>(links: Vec<Link<'_>>, refdef_writer: &mut W) -> Result<()>
+/// Write the reference definitions of links to a writer (e.g. file),
+/// under a `# Reference Definitions` heading
+pub fn write_refdefs_to<W>(links: Vec<Link>, refdef_writer: &mut W) -> Result<()>
 where
     W: Write,
 {
-    writeln!(refdef_writer, "# Reference Definitions\n")
-        .context("[write_refdefs_to] Failed to write reference definitions.")?;
+    writeln!(refdef_writer, "# Reference Definitions\n")?;
 
     let refdef_flags = (LinkWrite::ReferenceDefinition).into();
 
@@ -46,13 +42,13 @@ pub(crate) fn write_refdefs_to<W>(links: Vec<Link<'_>>, refdef_writer: &mut W) -
     Ok(())
 }
 
-/// Write duplicate links to a writer
-pub(crate) fn write_duplicate_links_to<W>(links: Vec<Link<'_>>, link_writer: &mut W) -> Result<()>
+/// Write duplicate links to a writer, under a `# Duplicate Links`
+/// heading
+pub fn write_duplicate_links_to<W>(links: Vec<Link>, link_writer: &mut W) -> Result<()>
 where
     W: Write,
 {
-    writeln!(link_writer, "# Duplicate Links\n")
-        .context("[write_duplicate_links_to] Failed to write duplicate links.")?;
+    writeln!(link_writer, "# Duplicate Links\n")?;
 
     let link_flags = LinkWrite::ReferenceLink | LinkWrite::ReferenceDefinition;
     for l in links.iter() {
@@ -70,8 +66,7 @@ pub(crate) fn write_broken_links_to<W>(
 where
     W: Write,
 {
-    writeln!(link_writer, "# Broken Links\n")
-        .context("[write_broken_links_to] Failed to write broken links.")?;
+    writeln!(link_writer, "# Broken Links\n")?;
 
     for (reference, text, link_type) in broken_links {
         writeln!(link_writer, "- Reference: {reference}")?;
@@ -83,7 +78,7 @@ pub(crate) fn write_broken_links_to<W>(
 }
 
 // /// Write reference definitions and links to a writer (e.g. file)
-// pub(crate) fn write_refdefs_and_links_to<W>(links: Vec<Link<'_>>, writer:
+// pub(crate) fn write_refdefs_and_links_to<W>(links: Vec<Link>, writer:
 // &mut W) -> Result<()> where
 //     W: Write,
 // {
@@ -96,10 +91,10 @@ pub(crate) fn write_broken_links_to<W>(
 //     Ok(())
 // }
 
-/// Write a badge-style link and associated reference definitions
-/// to two separate writers
-pub(crate) fn write_badge_refdefs_and_links_to_two<W1, W2>(
-    links: Vec<Link<'_>>,
+/// Write badge-style links and their reference definitions (of the
+/// link and of the badge) to two separate writers
+pub fn write_badge_refdefs_and_links_to_two<W1, W2>(
+    links: Vec<Link>,
     link_writer: &mut W1,
     refdef_writer: &mut W2,
 ) -> Result<()>
@@ -107,8 +102,7 @@ pub(crate) fn write_badge_refdefs_and_links_to_two<W1, W2>(
     W1: Write,
     W2: Write,
 {
-    writeln!(link_writer, "# Links and Reference Definitions\n")
-        .context("[write_badge_refdefs_and_links_to_two] Failed to write refdefs and links.")?;
+    writeln!(link_writer, "# Links and Reference Definitions\n")?;
 
     let link_flags = LinkWrite::LinkWithBadge.into();
     let refdef_flags = LinkWrite::ReferenceDefinition | LinkWrite::BadgeReferenceDefinition;
@@ -121,7 +115,7 @@ pub(crate) fn write_badge_refdefs_and_links_to_two<W1, W2>(
 
 // /// Write a reference definition to a writer (e.g. file)
 // #[inline]
-// fn write_refdef_to<W>(l: &Link<'_>, refdef_writer: &mut W) -> Result<()>
+// fn write_refdef_to<W>(l: &Link, refdef_writer: &mut W) -> Result<()>
 // where
 //     W: Write,
 // {
@@ -133,7 +127,7 @@ pub(crate) fn write_badge_refdefs_and_links_to_two<W1, W2>(
 
 // /// Write a link to a writer (e.g. file)
 // #[inline]
-// fn write_link_to<W>(l: &Link<'_>, link_writer: &mut W) -> Result<()>
+// fn write_link_to<W>(l: &Link, link_writer: &mut W) -> Result<()>
 // where
 //     W: Write,
 // {
@@ -165,7 +159,7 @@ enum LinkWrite {
 ///
 /// w: the writer e.g. a File or Vec\<u8\>
 #[inline]
-fn write<W: Write>(l: &Link<'_>, flags: &BitFlags<LinkWrite>, w: &mut W) -> Result<()> {
+fn write<W: Write>(l: &Link, flags: &BitFlags<LinkWrite>, w: &mut W) -> Result<()> {
     if flags.contains(LinkWrite::InlineLink) {
         writeln!(w, "{}", l.to_inline_link())?;
     }
@@ -0,0 +1,455 @@
+//! Automatic fixes of lint findings, expressed as text edits
+//!
+//! Each fix replaces a byte range of a file. The edits of all fixes are
+//! composed per file (overlapping edits are skipped), can be previewed
+//! as a unified diff, and are applied to all files at once.
+
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::Error;
+use crate::Result;
+use crate::generate;
+use crate::generate::RefDefTemplate;
+
+/// Number of unchanged lines shown around the changes of a diff
+const DIFF_CONTEXT: usize = 3;
+
+/// Replacement of a byte range of a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    /// Byte range of the replaced text; empty for insertions
+    pub range: Range<usize>,
+    /// New text; empty for deletions
+    pub replacement: String,
+}
+
+impl TextEdit {
+    /// Returns `true` if the ranges of two edits overlap. Insertions at
+    /// the same position, or at the boundary of a replaced range, do not
+    /// overlap.
+    fn overlaps(&self, other: &TextEdit) -> bool {
+        self.range.start < other.range.end && other.range.start < self.range.end
+    }
+}
+
+/// Edits of a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileFix {
+    /// Path of the file
+    pub file_path: PathBuf,
+    /// Contents of the file, as linted
+    pub original: String,
+    /// Edits, sorted by position, that do not overlap
+    pub edits: Vec<TextEdit>,
+}
+
+impl FileFix {
+    pub(crate) fn new(file_path: PathBuf, original: String) -> Self {
+        Self {
+            file_path,
+            original,
+            edits: Vec::new(),
+        }
+    }
+
+    /// Returns the fix that replaces the whole contents of a file
+    pub(crate) fn replace_contents(file_path: PathBuf, original: String, contents: String) -> Self {
+        let mut fix = Self::new(file_path, original);
+        fix.edits.push(TextEdit {
+            range: 0..fix.original.len(),
+            replacement: contents,
+        });
+        fix
+    }
+
+    /// Add an edit, unless it is already present. Returns `false` if the
+    /// edit overlaps another edit, in which case it is not added.
+    pub(crate) fn add(&mut self, edit: TextEdit) -> bool {
+        if self.edits.contains(&edit) {
+            return true;
+        }
+        if self.edits.iter().any(|e| e.overlaps(&edit)) {
+            return false;
+        }
+        // Insertions at the same position keep the order of addition
+        let i = self
+            .edits
+            .partition_point(|e| e.range.start <= edit.range.start);
+        self.edits.insert(i, edit);
+        true
+    }
+
+    /// Returns the contents of the file, with the edits applied
+    pub fn fixed_contents(&self) -> String {
+        apply_edits(&self.original, &self.edits)
+    }
+
+    /// Returns the changes of the file, as a unified diff
+    pub fn diff(&self) -> String {
+        let path = self.file_path.display();
+        let mut buf = format!("--- {path}\n+++ {path}\n");
+        let lines: Vec<&str> = self.original.split_inclusive('\n').collect();
+        let line_starts: Vec<usize> = std::iter::once(0)
+            .chain(self.original.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        let line_of = |offset: usize| line_starts.partition_point(|&s| s <= offset) - 1;
+        let line_start = |l: usize| line_starts.get(l).copied().unwrap_or(self.original.len());
+
+        // Group the edits that touch the same lines
+        let mut blocks: Vec<(usize, usize, Vec<&TextEdit>)> = Vec::new();
+        for e in self.edits.iter() {
+            let first = line_of(e.range.start);
+            let last = line_of(e.range.end.max(e.range.start + 1) - 1).max(first);
+            match blocks.last_mut() {
+                Some((_, block_last, edits)) if first <= *block_last => {
+                    *block_last = last.max(*block_last);
+                    edits.push(e);
+                }
+                _ => blocks.push((first, last, vec![e])),
+            }
+        }
+
+        // Group the blocks whose context lines overlap into hunks
+        let mut hunks: Vec<Vec<(usize, usize, Vec<&TextEdit>)>> = Vec::new();
+        for block in blocks {
+            match hunks.last_mut().and_then(|h| h.last()) {
+                Some(&(_, last, _)) if block.0 <= last + 1 + 2 * DIFF_CONTEXT => {
+                    hunks.last_mut().unwrap().push(block)
+                }
+                _ => hunks.push(vec![block]),
+            }
+        }
+
+        let mut delta: isize = 0;
+        for hunk in hunks {
+            let start = hunk[0].0.saturating_sub(DIFF_CONTEXT);
+            let end = (hunk.last().unwrap().1 + 1 + DIFF_CONTEXT).min(lines.len());
+            let mut body = String::new();
+            let (mut old_len, mut new_len) = (0, 0);
+            let context = |body: &mut String, from: usize, to: usize| {
+                for line in lines.iter().take(to).skip(from) {
+                    push_line(body, ' ', line);
+                }
+                to.saturating_sub(from)
+            };
+            let mut l = start;
+            for (first, last, edits) in hunk.iter() {
+                let n = context(&mut body, l, *first);
+                old_len += n;
+                new_len += n;
+                let block_start = line_start(*first);
+                let old = &self.original[block_start..line_start(last + 1)];
+                let edits: Vec<TextEdit> = edits
+                    .iter()
+                    .map(|e| TextEdit {
+                        range: e.range.start - block_start..e.range.end - block_start,
+                        replacement: e.replacement.clone(),
+                    })
+                    .collect();
+                let new = apply_edits(old, &edits);
+                for line in old.split_inclusive('\n') {
+                    push_line(&mut body, '-', line);
+                    old_len += 1;
+                }
+                for line in new.split_inclusive('\n') {
+                    push_line(&mut body, '+', line);
+                    new_len += 1;
+                }
+                l = last + 1;
+            }
+            let n = context(&mut body, l.min(end), end);
+            old_len += n;
+            new_len += n;
+
+            let old_start = if old_len == 0 { start } else { start + 1 };
+            let new_start = (start as isize + delta) as usize + usize::from(new_len > 0);
+            buf.push_str(&format!(
+                "@@ -{old_start},{old_len} +{new_start},{new_len} @@\n{body}"
+            ));
+            delta += new_len as isize - old_len as isize;
+        }
+        buf
+    }
+}
+
+/// Append a line to a diff, with its prefix (` `, `-` or `+`)
+fn push_line(buf: &mut String, prefix: char, line: &str) {
+    buf.push(prefix);
+    buf.push_str(line);
+    if !line.ends_with('\n') {
+        buf.push('\n');
+    }
+}
+
+/// Apply edits, sorted by position and without overlap, to a text
+fn apply_edits(text: &str, edits: &[TextEdit]) -> String {
+    let mut buf = String::with_capacity(text.len());
+    let mut last = 0;
+    for e in edits.iter() {
+        buf.push_str(&text[last..e.range.start]);
+        buf.push_str(&e.replacement);
+        last = e.range.end;
+    }
+    buf.push_str(&text[last..]);
+    buf
+}
+
+/// Fixes of the findings of [`crate::lint`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LintFixes {
+    /// Edits, by file, sorted by path
+    pub files: Vec<FileFix>,
+    /// Number of edits skipped because they overlap another edit. Run
+    /// the fixes again to apply them.
+    pub skipped: usize,
+}
+
+impl LintFixes {
+    /// Returns `true` if there is nothing to fix
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Returns the number of edits
+    pub fn len(&self) -> usize {
+        self.files.iter().map(|f| f.edits.len()).sum()
+    }
+
+    /// Returns the changes of all files, as a unified diff
+    pub fn diff(&self) -> String {
+        self.files.iter().map(FileFix::diff).collect()
+    }
+
+    /// Apply the edits to all files.
+    ///
+    /// Nothing is written if a file was modified since it was linted.
+    /// The fixed files are written next to the originals first, then
+    /// renamed over them, so that a failure does not leave a file half
+    /// written.
+    pub fn apply(&self) -> Result<()> {
+        apply_file_fixes(&self.files)
+    }
+}
+
+/// Apply the edits of several files, all at once.
+///
+/// Nothing is written if a file was modified since it was read. The
+/// fixed files are written next to the originals first. Each original
+/// is then set aside as a backup and replaced by its fixed file; if a
+/// replacement fails, the files already replaced are restored from
+/// their backups, so that the files are either all fixed or all left
+/// as they were. No temporary file is left behind.
+pub(crate) fn apply_file_fixes(files: &[FileFix]) -> Result<()> {
+    for f in files.iter() {
+        let current =
+            std::fs::read_to_string(&f.file_path).map_err(|e| Error::io(&f.file_path, e))?;
+        if current != f.original {
+            return Err(Error::io(
+                &f.file_path,
+                std::io::Error::other("modified since it was read. Run the command again."),
+            ));
+        }
+    }
+    let mut tmp_paths: Vec<PathBuf> = Vec::with_capacity(files.len());
+    for f in files.iter() {
+        let tmp_path = side_path(&f.file_path, "fix.tmp");
+        if let Err(e) = std::fs::write(&tmp_path, f.fixed_contents()) {
+            remove_all(tmp_paths.iter().chain(std::iter::once(&tmp_path)));
+            return Err(Error::io(tmp_path, e));
+        }
+        tmp_paths.push(tmp_path);
+    }
+    // Original path and backup of each replaced file
+    let mut replaced: Vec<(&Path, PathBuf)> = Vec::with_capacity(files.len());
+    for (f, tmp_path) in files.iter().zip(tmp_paths.iter()) {
+        let backup_path = side_path(&f.file_path, "fix.bak");
+        let result = std::fs::rename(&f.file_path, &backup_path).and_then(|()| {
+            std::fs::rename(tmp_path, &f.file_path).inspect_err(|_| {
+                let _ = std::fs::rename(&backup_path, &f.file_path);
+            })
+        });
+        if let Err(e) = result {
+            for (path, backup_path) in replaced.iter().rev() {
+                let _ = std::fs::rename(backup_path, path);
+            }
+            remove_all(tmp_paths.iter());
+            return Err(Error::io(&f.file_path, e));
+        }
+        replaced.push((&f.file_path, backup_path));
+    }
+    remove_all(replaced.iter().map(|(_, backup_path)| backup_path));
+    Ok(())
+}
+
+/// Returns the path of a file written next to a fixed file e.g.
+/// `dir/.intro.md.fix.tmp` for the fixed contents, before it replaces
+/// the original, or `dir/.intro.md.fix.bak` for the original
+pub(crate) fn side_path(file_path: &Path, extension: &str) -> PathBuf {
+    let file_name = file_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    file_path.with_file_name(format!(".{file_name}.{extension}"))
+}
+
+/// Remove files, ignoring those that no longer exist
+fn remove_all<'a>(paths: impl Iterator<Item = &'a PathBuf>) {
+    for p in paths {
+        let _ = std::fs::remove_file(p);
+    }
+}
+
+/// Returns the edit that deletes the lines of a range, if nothing else
+/// is written on them, e.g. a reference definition
+pub(crate) fn delete_lines(contents: &str, range: &Range<usize>) -> Option<TextEdit> {
+    let start = contents[..range.start].rfind('\n').map_or(0, |i| i + 1);
+    let end = contents[range.end..]
+        .find('\n')
+        .map_or(contents.len(), |i| range.end + i + 1);
+    if !contents[start..range.start].trim().is_empty()
+        || !contents[range.end..end].trim().is_empty()
+    {
+        return None;
+    }
+    Some(TextEdit {
+        range: start..end,
+        replacement: String::new(),
+    })
+}
+
+/// Returns the edit that appends reference definitions at the end of a
+/// file, after a blank line unless the file already ends with reference
+/// definitions
+pub(crate) fn append_refdefs(contents: &str, refdefs: &[(String, String)]) -> TextEdit {
+    let trimmed = contents.trim_end();
+    let last_line = trimmed.rsplit('\n').next().unwrap_or_default();
+    let mut buf = String::new();
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        buf.push('\n');
+    }
+    let ends_with_refdef = last_line.starts_with('[') && last_line.contains("]: ");
+    if !trimmed.is_empty() && !ends_with_refdef && !contents.ends_with("\n\n") {
+        buf.push('\n');
+    }
+    for (label, url) in refdefs.iter() {
+        buf.push_str(&format!("[{label}]: {url}\n"));
+    }
+    TextEdit {
+        range: contents.len()..contents.len(),
+        replacement: buf,
+    }
+}
+
+/// Returns the URL of an undefined reference, if its label follows one
+/// of the templates whose URL only depends on the crate name e.g.
+/// `serde-crate` for `${crate}-crate`.
+///
+/// Templates whose label is the crate name alone (e.g. `${crate}`) are
+/// not used, since any label would match them.
+pub(crate) fn known_refdef_url(label: &str, templates: &[RefDefTemplate]) -> Option<String> {
+    templates.iter().find_map(|t| {
+        let (prefix, suffix) = ["${crate}", "${package}"]
+            .iter()
+            .find_map(|var| t.label_pattern.split_once(var))?;
+        if prefix.is_empty() && suffix.is_empty() {
+            return None;
+        }
+        let name = label.strip_prefix(prefix)?.strip_suffix(suffix)?;
+        if name.is_empty()
+            || !name.starts_with(|c: char| c.is_ascii_alphabetic())
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return None;
+        }
+        let vars = HashMap::from([("crate", name), ("package", name)]);
+        generate::expand(&t.url_pattern, &vars)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_fix() {
+        let original = "one\ntwo\nthree\n\n[x]: https://x.com\n";
+        let mut fix = FileFix::new(PathBuf::from("a.md"), original.to_string());
+        let delete = delete_lines(original, &(15..33)).unwrap();
+        assert_eq!(delete.range, 15..34);
+        assert!(fix.add(delete.clone()));
+        assert!(fix.add(delete));
+        assert!(fix.add(TextEdit {
+            range: 4..7,
+            replacement: "2".into(),
+        }));
+        assert!(!fix.add(TextEdit {
+            range: 5..6,
+            replacement: "w".into(),
+        }));
+        assert!(fix.add(append_refdefs(
+            original,
+            &[("y".into(), "https://y.com".into())]
+        )));
+        assert_eq!(fix.edits.len(), 3);
+        assert_eq!(
+            fix.fixed_contents(),
+            "one\n2\nthree\n\n[y]: https://y.com\n"
+        );
+        assert_eq!(
+            fix.diff(),
+            "--- a.md\n+++ a.md\n@@ -1,5 +1,5 @@\n one\n-two\n+2\n three\n \n\
+             -[x]: https://x.com\n+[y]: https://y.com\n"
+        );
+    }
+
+    #[test]
+    fn test_apply_file_fixes_rolls_back() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let fix = |name: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, "old\n").unwrap();
+            let mut fix = FileFix::new(path, "old\n".to_string());
+            fix.add(TextEdit {
+                range: 0..3,
+                replacement: "new".into(),
+            });
+            fix
+        };
+        let files = [fix("a.md"), fix("b.md")];
+        // The original of b.md cannot be set aside
+        std::fs::create_dir_all(dir.path().join(".b.md.fix.bak").join("x"))?;
+
+        assert!(apply_file_fixes(&files).is_err());
+        for name in ["a.md", "b.md"] {
+            assert_eq!(std::fs::read_to_string(dir.path().join(name))?, "old\n");
+        }
+        let mut names: Vec<_> = std::fs::read_dir(dir.path())?
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec![".b.md.fix.bak", "a.md", "b.md"]);
+
+        std::fs::remove_dir_all(dir.path().join(".b.md.fix.bak"))?;
+        apply_file_fixes(&files)?;
+        assert_eq!(std::fs::read_to_string(dir.path().join("b.md"))?, "new\n");
+        assert_eq!(std::fs::read_dir(dir.path())?.count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_known_refdef_url() {
+        let templates = RefDefTemplate::defaults(false);
+        assert_eq!(
+            known_refdef_url("serde-crate", &templates).as_deref(),
+            Some("https://crates.io/crates/serde")
+        );
+        assert_eq!(known_refdef_url("serde", &templates), None);
+        assert_eq!(known_refdef_url("serde-github", &templates), None);
+        assert_eq!(known_refdef_url("a b-crate", &templates), None);
+    }
+}
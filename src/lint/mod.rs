@@ -0,0 +1,828 @@
+//! Check the Markdown files of a book in a single pass: undefined
+//! references, duplicate links, files missing from `SUMMARY.md`, unused
+//! code examples and reference definitions, conflicting reference
+//! definitions, links to missing files, label casing and bare URLs.
+//!
+//! Each finding carries a stable rule ID (e.g. `MU001`) and a
+//! configurable severity. Findings may be suppressed by
+//! `<!-- mdbook-utils: allow(MU001) -->` (same or next line) and
+//! `<!-- mdbook-utils: allow-file(MU001) -->` (whole file) comments.
+//!
+//! Some findings come with text edits that fix them, see [`LintFixes`].
+mod fix;
+mod rules;
+mod scan;
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::Path;
+use std::path::PathBuf;
+
+pub use fix::FileFix;
+pub use fix::LintFixes;
+pub use fix::TextEdit;
+pub(crate) use fix::*;
+use pulldown_cmark::LinkType;
+use rayon::prelude::*;
+pub use rules::LintFinding;
+pub use rules::LintRule;
+pub use rules::Severity;
+pub(crate) use scan::*;
+use tracing::debug;
+
+use crate::Error;
+use crate::Result;
+use crate::chapters;
+use crate::fs;
+use crate::generate::RefDefTemplate;
+use crate::parser::ParserOptions;
+use crate::sitemap;
+use crate::summary;
+
+/// Options of [`crate::lint`]
+#[derive(Debug, Clone, Default)]
+pub struct LintOptions {
+    /// Directory of the code examples (`.rs` files) included by the
+    /// Markdown files. The `unused-rs-example` rule is not checked if
+    /// `None`.
+    pub code_dir_path: Option<PathBuf>,
+
+    /// Severity of the rules whose default severity is overridden
+    pub severities: BTreeMap<LintRule, Severity>,
+
+    /// Templates of the reference definitions added for the undefined
+    /// references that follow them e.g. `[serde-crate]` (see
+    /// [`RefDefTemplate`]). `RefDefTemplate::defaults(false)` if `None`.
+    pub templates: Option<Vec<RefDefTemplate>>,
+
+    /// `[output.html.redirect]` table of `book.toml`, used to fix the
+    /// links to moved chapters
+    pub redirects: BTreeMap<String, String>,
+
+    /// Options of the Markdown parser
+    pub parser: ParserOptions,
+}
+
+impl LintOptions {
+    /// Returns the severity of a rule
+    pub fn severity(&self, rule: LintRule) -> Severity {
+        self.severities
+            .get(&rule)
+            .copied()
+            .unwrap_or_else(|| rule.default_severity())
+    }
+}
+
+/// Result of [`crate::lint`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LintReport {
+    /// Findings that are not suppressed, sorted by file and line
+    pub findings: Vec<LintFinding>,
+    /// Number of findings suppressed by `allow` comments
+    pub suppressed: usize,
+    /// Fixes of the findings that can be fixed automatically
+    pub fixes: LintFixes,
+}
+
+impl LintReport {
+    /// Returns the number of findings whose severity is `error`
+    pub fn errors(&self) -> usize {
+        self.count(Severity::Error)
+    }
+
+    /// Returns the number of findings whose severity is `warning`
+    pub fn warnings(&self) -> usize {
+        self.count(Severity::Warning)
+    }
+
+    fn count(&self, severity: Severity) -> usize {
+        self.findings
+            .iter()
+            .filter(|f| f.severity == severity)
+            .count()
+    }
+}
+
+/// Finding, before severities and suppressions are applied
+struct RawFinding {
+    /// Index of the Markdown file in the book, or `None` for files that
+    /// are not Markdown e.g. `.rs` examples
+    file: Option<usize>,
+    file_path: PathBuf,
+    line: Option<usize>,
+    rule: LintRule,
+    message: String,
+    /// Edits that fix the finding, by index of the Markdown file
+    edits: Vec<(usize, TextEdit)>,
+}
+
+impl RawFinding {
+    /// Add an edit of a Markdown file that fixes the finding, if any
+    fn with_edit(mut self, file: usize, edit: Option<TextEdit>) -> Self {
+        self.edits.extend(edit.map(|e| (file, e)));
+        self
+    }
+}
+
+/// Parse all Markdown files of a source directory once and run the lint
+/// rules whose severity is not `off`.
+pub(crate) fn lint<P: AsRef<Path>>(src_dir_path: P, options: &LintOptions) -> Result<LintReport> {
+    let src_dir_path = fs::check_is_dir(src_dir_path)?;
+    let files = scan_files(&src_dir_path, &options.parser)?;
+
+    let enabled = |rule: LintRule| options.severity(rule) != Severity::Off;
+    let mut raw = Vec::new();
+    if enabled(LintRule::UndefinedReference) {
+        let templates = options
+            .templates
+            .clone()
+            .unwrap_or_else(|| RefDefTemplate::defaults(false));
+        raw.extend(undefined_references(&files, &templates));
+    }
+    if enabled(LintRule::DuplicateLink) {
+        raw.extend(duplicate_links(&files));
+    }
+    if enabled(LintRule::FileNotInSummary) {
+        raw.extend(files_not_in_summary(&src_dir_path, &files)?);
+    }
+    if enabled(LintRule::UnusedRsExample)
+        && let Some(ref code_dir_path) = options.code_dir_path
+    {
+        raw.extend(unused_rs_examples(code_dir_path, &files)?);
+    }
+    if enabled(LintRule::UnusedRefDef) {
+        raw.extend(unused_refdefs(&files));
+    }
+    if enabled(LintRule::DuplicateRefDef) || enabled(LintRule::ConflictingRefDef) {
+        raw.extend(repeated_refdefs(&files));
+    }
+    if enabled(LintRule::MissingLocalFile) {
+        raw.extend(missing_local_files(
+            &src_dir_path,
+            &files,
+            &options.redirects,
+        ));
+    }
+    if enabled(LintRule::LabelCase) {
+        raw.extend(label_cases(&files));
+    }
+    if enabled(LintRule::BareUrl) {
+        raw.extend(bare_urls(&files));
+    }
+
+    let mut report = LintReport::default();
+    let mut fixes: BTreeMap<usize, FileFix> = BTreeMap::new();
+    for f in raw {
+        let severity = options.severity(f.rule);
+        if severity == Severity::Off {
+            continue;
+        }
+        if f.file
+            .is_some_and(|i| files[i].suppressions.allows(f.rule, f.line))
+        {
+            report.suppressed += 1;
+            continue;
+        }
+        for (i, edit) in f.edits.iter().cloned() {
+            let fix = fixes
+                .entry(i)
+                .or_insert_with(|| FileFix::new(files[i].path.clone(), files[i].contents.clone()));
+            if !fix.add(edit) {
+                report.fixes.skipped += 1;
+            }
+        }
+        report.findings.push(LintFinding {
+            file_path: f.file_path,
+            line: f.line,
+            rule: f.rule,
+            severity,
+            message: f.message,
+            fixable: !f.edits.is_empty(),
+        });
+    }
+    report.findings.sort();
+    report.fixes.files = fixes.into_values().collect();
+    report
+        .fixes
+        .files
+        .sort_by(|a, b| a.file_path.cmp(&b.file_path));
+    Ok(report)
+}
+
+/// Read and parse all Markdown files of a source directory, in parallel.
+pub(crate) fn scan_files(src_dir_path: &Path, options: &ParserOptions) -> Result<Vec<ScannedFile>> {
+    fs::find_markdown_files_in(src_dir_path)?
+        .into_par_iter()
+        .map(|p| {
+            let contents = std::fs::read_to_string(&p).map_err(|e| Error::io(&p, e))?;
+            let rel_path = p
+                .strip_prefix(src_dir_path)
+                .unwrap_or(&p)
+                .to_string_lossy()
+                .replace('\\', "/");
+            Ok(ScannedFile::scan(p, rel_path, contents, options))
+        })
+        .collect()
+}
+
+/// Returns a finding for a line of a Markdown file
+fn finding(
+    files: &[ScannedFile],
+    i: usize,
+    line: usize,
+    rule: LintRule,
+    message: String,
+) -> RawFinding {
+    RawFinding {
+        file: Some(i),
+        file_path: files[i].path.clone(),
+        line: Some(line),
+        rule,
+        message,
+        edits: Vec::new(),
+    }
+}
+
+/// Returns the reference definitions of the book by lowercased label:
+/// the first definition wins, as in a single Markdown document.
+pub(crate) fn book_refdefs(files: &[ScannedFile]) -> HashMap<String, (usize, &ScannedRefDef)> {
+    let mut refdefs = HashMap::new();
+    for (i, f) in files.iter().enumerate() {
+        for d in f.refdefs.iter() {
+            refdefs.entry(d.label.to_lowercase()).or_insert((i, d));
+        }
+    }
+    refdefs
+}
+
+/// MU001: references defined nowhere in the book.
+///
+/// The references that follow a template (e.g. `[serde-crate]`) are
+/// fixed by adding their definitions at the end of the file.
+fn undefined_references(files: &[ScannedFile], templates: &[RefDefTemplate]) -> Vec<RawFinding> {
+    let refdefs = book_refdefs(files);
+    let mut raw = Vec::new();
+    for (i, f) in files.iter().enumerate() {
+        let undefined: Vec<&ScannedRef> = f
+            .unresolved
+            .iter()
+            .filter(|r| !refdefs.contains_key(&r.name.to_lowercase()))
+            .collect();
+        let mut known: BTreeMap<String, (String, String)> = BTreeMap::new();
+        for r in undefined.iter() {
+            if let Some(url) = known_refdef_url(&r.name, templates) {
+                known
+                    .entry(r.name.to_lowercase())
+                    .or_insert((r.name.clone(), url));
+            }
+        }
+        // A single edit adds all the definitions of the file
+        let edit = (!known.is_empty())
+            .then(|| append_refdefs(&f.contents, &known.values().cloned().collect::<Vec<_>>()));
+        for r in undefined {
+            raw.push(
+                finding(
+                    files,
+                    i,
+                    r.line,
+                    LintRule::UndefinedReference,
+                    format!("`[{}]` is not defined", r.name),
+                )
+                .with_edit(
+                    i,
+                    edit.clone()
+                        .filter(|_| known.contains_key(&r.name.to_lowercase())),
+                ),
+            );
+        }
+    }
+    raw
+}
+
+/// MU002: URLs written inline more than once
+fn duplicate_links(files: &[ScannedFile]) -> Vec<RawFinding> {
+    let mut first: HashMap<&str, (usize, usize)> = HashMap::new();
+    let mut raw = Vec::new();
+    for (i, f) in files.iter().enumerate() {
+        for l in f.links.iter().filter(|l| {
+            !l.is_image
+                && matches!(l.link_type, LinkType::Inline | LinkType::Autolink)
+                && l.url.starts_with("http")
+        }) {
+            match first.get(l.url.as_str()) {
+                Some(&(j, line)) => raw.push(finding(
+                    files,
+                    i,
+                    l.line,
+                    LintRule::DuplicateLink,
+                    format!(
+                        "`{}` is already linked at {}:{line}; consider a reference-style link",
+                        l.url, files[j].rel_path
+                    ),
+                )),
+                None => {
+                    first.insert(&l.url, (i, l.line));
+                }
+            }
+        }
+    }
+    raw
+}
+
+/// Returns the paths, relative to the source directory, of the files
+/// included by a Markdown file
+fn included_paths(f: &ScannedFile) -> impl Iterator<Item = String> + '_ {
+    f.includes
+        .iter()
+        .filter(|inc| !chapters::is_external(&inc.name))
+        .map(|inc| chapters::resolve(chapters::parent(&f.rel_path), &inc.name))
+}
+
+/// MU003: Markdown files neither listed in `SUMMARY.md` nor included
+fn files_not_in_summary(src_dir_path: &Path, files: &[ScannedFile]) -> Result<Vec<RawFinding>> {
+    if !src_dir_path.join("SUMMARY.md").is_file() {
+        debug!("SUMMARY.md not found in {}", src_dir_path.display());
+        return Ok(Vec::new());
+    }
+    let mut used: HashSet<String> = summary::read_summary(src_dir_path)?
+        .into_iter()
+        .filter_map(|c| c.path)
+        .collect();
+    used.extend(files.iter().flat_map(included_paths));
+    Ok(files
+        .iter()
+        .enumerate()
+        .filter(|(_, f)| f.rel_path != "SUMMARY.md" && !used.contains(&f.rel_path))
+        .map(|(i, f)| RawFinding {
+            file: Some(i),
+            file_path: f.path.clone(),
+            line: None,
+            rule: LintRule::FileNotInSummary,
+            message: "not listed in SUMMARY.md nor included by another file".into(),
+            edits: Vec::new(),
+        })
+        .collect())
+}
+
+/// MU004: `.rs` files of the code directory that are not included
+fn unused_rs_examples(code_dir_path: &Path, files: &[ScannedFile]) -> Result<Vec<RawFinding>> {
+    let code_dir_path = fs::check_is_dir(code_dir_path)?;
+    let used: HashSet<PathBuf> = files
+        .iter()
+        .flat_map(|f| {
+            let dir = f.path.parent().unwrap_or(Path::new(""));
+            f.includes
+                .iter()
+                .filter_map(move |inc| dir.join(&inc.name).canonicalize().ok())
+        })
+        .collect();
+    let mut raw = Vec::new();
+    for entry in walkdir::WalkDir::new(&code_dir_path)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && e.path().extension().is_some_and(|ext| ext == "rs"))
+    {
+        if entry
+            .path()
+            .canonicalize()
+            .is_ok_and(|canon| !used.contains(&canon))
+        {
+            raw.push(RawFinding {
+                file: None,
+                file_path: entry.into_path(),
+                line: None,
+                rule: LintRule::UnusedRsExample,
+                message: "not included by any Markdown file".into(),
+                edits: Vec::new(),
+            });
+        }
+    }
+    Ok(raw)
+}
+
+/// MU005: reference definitions that no link or image uses
+fn unused_refdefs(files: &[ScannedFile]) -> Vec<RawFinding> {
+    let used: HashSet<String> = files
+        .iter()
+        .flat_map(|f| {
+            f.links
+                .iter()
+                .filter(|l| !l.label.is_empty())
+                .map(|l| l.label.to_lowercase())
+                .chain(f.unresolved.iter().map(|r| r.name.to_lowercase()))
+        })
+        .collect();
+    let mut raw = Vec::new();
+    for (i, f) in files.iter().enumerate() {
+        for d in f.refdefs.iter() {
+            if !used.contains(&d.label.to_lowercase()) {
+                raw.push(
+                    finding(
+                        files,
+                        i,
+                        d.line,
+                        LintRule::UnusedRefDef,
+                        format!("`[{}]` is never used", d.label),
+                    )
+                    .with_edit(i, delete_lines(&f.contents, &d.span)),
+                );
+            }
+        }
+    }
+    raw
+}
+
+/// Returns `true` if a link or image of a Markdown file uses a label
+fn uses_label(f: &ScannedFile, label: &str) -> bool {
+    let label = label.to_lowercase();
+    f.links
+        .iter()
+        .any(|l| !l.label.is_empty() && l.label.to_lowercase() == label)
+        || f.unresolved.iter().any(|r| r.name.to_lowercase() == label)
+}
+
+/// MU006 and MU007: labels defined in several files. The repeated
+/// definitions with the same URL are fixed by removing them, unless the
+/// file uses them without including the file of the first definition:
+/// mdBook renders each chapter on its own, so its links would break.
+fn repeated_refdefs(files: &[ScannedFile]) -> Vec<RawFinding> {
+    let mut first: HashMap<String, (usize, &ScannedRefDef)> = HashMap::new();
+    let mut raw = Vec::new();
+    for (i, f) in files.iter().enumerate() {
+        for d in f.refdefs.iter() {
+            let Some(&(j, first_def)) = first.get(&d.label.to_lowercase()) else {
+                first.insert(d.label.to_lowercase(), (i, d));
+                continue;
+            };
+            let location = format!("{}:{}", files[j].rel_path, first_def.line);
+            raw.push(if first_def.dest == d.dest {
+                let removable = j == i
+                    || included_paths(f).any(|p| p == files[j].rel_path)
+                    || !uses_label(f, &d.label);
+                finding(
+                    files,
+                    i,
+                    d.line,
+                    LintRule::DuplicateRefDef,
+                    format!("`[{}]` is already defined at {location}", d.label),
+                )
+                .with_edit(
+                    i,
+                    delete_lines(&f.contents, &d.span).filter(|_| removable),
+                )
+            } else {
+                finding(
+                    files,
+                    i,
+                    d.line,
+                    LintRule::ConflictingRefDef,
+                    format!(
+                        "`[{}]` is defined at {location} with another URL (`{}`), which takes precedence",
+                        d.label, first_def.dest
+                    ),
+                )
+            });
+        }
+    }
+    raw
+}
+
+/// Returns `true` if a path, relative to the source directory, exists.
+/// As in mdBook, a link to a `.html` page may point to a `.md` chapter.
+fn local_file_exists(src_dir_path: &Path, path: &str) -> bool {
+    src_dir_path.join(path).exists()
+        || path
+            .strip_suffix(".html")
+            .is_some_and(|p| src_dir_path.join(format!("{p}.md")).exists())
+}
+
+/// MU008: relative links, images and includes to missing files.
+///
+/// The links to chapters that moved, according to the redirects of
+/// `book.toml`, are fixed, if their destination is written in the same
+/// file.
+fn missing_local_files(
+    src_dir_path: &Path,
+    files: &[ScannedFile],
+    redirects: &BTreeMap<String, String>,
+) -> Vec<RawFinding> {
+    let mut raw = Vec::new();
+    for (i, f) in files.iter().enumerate() {
+        let dir = chapters::parent(&f.rel_path);
+        let dests = f
+            .links
+            .iter()
+            .map(|l| (l.url.as_str(), l.line, Some(l)))
+            .chain(
+                f.includes
+                    .iter()
+                    .map(|inc| (inc.name.as_str(), inc.line, None)),
+            );
+        for (dest, line, link) in dests {
+            let path = &dest[..dest.find(['#', '?']).unwrap_or(dest.len())];
+            if path.is_empty() || chapters::is_external(path) || path.contains(':') {
+                continue;
+            }
+            let target = chapters::resolve(dir, path);
+            if !local_file_exists(src_dir_path, &target) {
+                let edit = link.and_then(|l| {
+                    let new_target = moved_chapter(src_dir_path, &target, redirects)?;
+                    let range = dest_range(f, l)?;
+                    Some(TextEdit {
+                        range,
+                        replacement: chapters::relative_to(&new_target, dir),
+                    })
+                });
+                raw.push(
+                    finding(
+                        files,
+                        i,
+                        line,
+                        LintRule::MissingLocalFile,
+                        format!("`{path}` does not exist"),
+                    )
+                    .with_edit(i, edit),
+                );
+            }
+        }
+    }
+    raw
+}
+
+/// Returns the new path of a chapter (`.md`) or page (`.html`),
+/// relative to the source directory, that was moved and redirected, if
+/// the new chapter exists.
+fn moved_chapter(
+    src_dir_path: &Path,
+    target: &str,
+    redirects: &BTreeMap<String, String>,
+) -> Option<String> {
+    let html = sitemap::chapter_html_path(target);
+    let from = format!("/{html}");
+    let (new_html, _) = chapters::resolve_redirect(&from, redirects.get(&from)?, "/")?;
+    let new_md = format!("{}.md", new_html.strip_suffix(".html")?);
+    if !src_dir_path.join(&new_md).is_file() {
+        return None;
+    }
+    Some(if target.ends_with(".html") {
+        new_html
+    } else {
+        new_md
+    })
+}
+
+/// Returns the byte range of the path of the destination of a link, if
+/// the destination is written in the same file: in the link itself or in
+/// its reference definition.
+fn dest_range(f: &ScannedFile, l: &ScannedLink) -> Option<Range<usize>> {
+    let (start, end) = if l.label.is_empty() {
+        // The destination follows the last `](` of the link
+        let i = f.contents[l.span.clone()].rfind("](")?;
+        (l.span.start + i + 2, l.span.end)
+    } else {
+        let d = f
+            .refdefs
+            .iter()
+            .find(|d| d.label.to_lowercase() == l.label.to_lowercase())?;
+        let i = f.contents[d.span.clone()].find("]:")?;
+        (d.span.start + i + 2, d.span.end)
+    };
+    chapters::find_dest(&f.contents, start, end, &l.url)
+}
+
+/// MU009: `[text][label]` links whose label differs in case from its
+/// definition. They are fixed by using the label of the definition.
+fn label_cases(files: &[ScannedFile]) -> Vec<RawFinding> {
+    let refdefs = book_refdefs(files);
+    let mut raw = Vec::new();
+    for (i, f) in files.iter().enumerate() {
+        let labels = f
+            .links
+            .iter()
+            .filter_map(|l| Some((l.label.as_str(), l.label_span.clone()?, l.line)))
+            .chain(
+                f.unresolved
+                    .iter()
+                    .filter_map(|r| Some((r.name.as_str(), r.label_span.clone()?, r.line))),
+            );
+        for (label, range, line) in labels {
+            let key = label.to_lowercase();
+            // The definition of the same file takes precedence
+            let Some(def) = f
+                .refdefs
+                .iter()
+                .find(|d| d.label.to_lowercase() == key)
+                .or_else(|| refdefs.get(&key).map(|(_, d)| *d))
+            else {
+                continue;
+            };
+            if def.label != label {
+                raw.push(
+                    finding(
+                        files,
+                        i,
+                        line,
+                        LintRule::LabelCase,
+                        format!("`[{label}]` is defined as `[{}]`", def.label),
+                    )
+                    .with_edit(
+                        i,
+                        Some(TextEdit {
+                            range,
+                            replacement: def.label.clone(),
+                        }),
+                    ),
+                );
+            }
+        }
+    }
+    raw
+}
+
+/// MU010: URLs written as plain text. They are fixed by turning them
+/// into autolinks e.g. `<https://example.com>`.
+fn bare_urls(files: &[ScannedFile]) -> Vec<RawFinding> {
+    let mut raw = Vec::new();
+    for (i, f) in files.iter().enumerate() {
+        for u in f.bare_urls.iter() {
+            raw.push(
+                finding(
+                    files,
+                    i,
+                    u.line,
+                    LintRule::BareUrl,
+                    format!("`{}` is not a link; write `<{}>`", u.url, u.url),
+                )
+                .with_edit(
+                    i,
+                    Some(TextEdit {
+                        range: u.span.clone(),
+                        replacement: format!("<{}>", u.url),
+                    }),
+                ),
+            );
+        }
+    }
+    raw
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lint() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let src = dir.path().join("src");
+        let code = dir.path().join("code");
+        std::fs::create_dir(&src)?;
+        std::fs::create_dir(&code)?;
+        std::fs::write(src.join("SUMMARY.md"), "- [A](a.md)\n- [B](b.md)\n")?;
+        std::fs::write(
+            src.join("a.md"),
+            "[x] [y] [a](https://a.com)\n\n{{#include refs.md}}\n\
+             ```rust\n{{#include ../code/used.rs:main}}\n```\n\n[Missing](missing.md) [B](b.html#top)\n",
+        )?;
+        std::fs::write(
+            src.join("b.md"),
+            "[again](https://a.com)\n<!-- mdbook-utils: allow(MU001) -->\n[suppressed]\n\n\
+             [x]: https://other.com\n",
+        )?;
+        std::fs::write(
+            src.join("refs.md"),
+            "[x]: https://x.com\n[unused]: https://u.com\n",
+        )?;
+        std::fs::write(src.join("orphan.md"), "# Orphan\n")?;
+        std::fs::write(code.join("used.rs"), "fn main() {}\n")?;
+        std::fs::write(code.join("unused.rs"), "fn main() {}\n")?;
+
+        let mut options = LintOptions {
+            code_dir_path: Some(code.clone()),
+            ..LintOptions::default()
+        };
+        let report = lint(&src, &options)?;
+        let found: Vec<_> = report
+            .findings
+            .iter()
+            .map(|f| {
+                (
+                    f.file_path
+                        .file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .to_string(),
+                    f.line,
+                    f.rule.id(),
+                )
+            })
+            .collect();
+        let expected: Vec<(String, Option<usize>, &str)> = vec![
+            ("a.md".into(), Some(1), "MU001"),
+            ("a.md".into(), Some(8), "MU008"),
+            ("b.md".into(), Some(1), "MU002"),
+            ("unused.rs".into(), None, "MU004"),
+            ("orphan.md".into(), None, "MU003"),
+            ("refs.md".into(), Some(1), "MU007"),
+            ("refs.md".into(), Some(2), "MU005"),
+        ];
+        let mut expected = expected;
+        expected.sort_by(|a, b| {
+            let path = |name: &str| {
+                if name.ends_with(".rs") {
+                    code.join(name)
+                } else {
+                    src.join(name)
+                }
+            };
+            (path(&a.0), a.1).cmp(&(path(&b.0), b.1))
+        });
+        assert_eq!(found, expected);
+        assert_eq!(report.suppressed, 1);
+        assert_eq!(report.errors(), 3);
+
+        options
+            .severities
+            .insert(LintRule::UndefinedReference, Severity::Off);
+        options
+            .severities
+            .insert(LintRule::ConflictingRefDef, Severity::Warning);
+        let report = lint(&src, &options)?;
+        assert_eq!(report.errors(), 1);
+        assert_eq!(report.suppressed, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_lint_fixes() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let src = dir.path().join("src");
+        std::fs::create_dir_all(src.join("part"))?;
+        std::fs::write(
+            src.join("SUMMARY.md"),
+            "- [A](a.md)\n- [Two](part/two.md)\n",
+        )?;
+        std::fs::write(
+            src.join("a.md"),
+            "# A\n\nSee https://a.com, [one](one.md#x), [two][Two] and [serde-crate].\n\n\
+             [two]: part/two.md\n[unused]: https://u.com\n",
+        )?;
+        std::fs::write(src.join("part/two.md"), "# Two\n\n[two]: part/two.md\n")?;
+        let options = LintOptions {
+            redirects: BTreeMap::from([("/one.html".to_string(), "part/two.html".to_string())]),
+            ..LintOptions::default()
+        };
+        let report = lint(&src, &options)?;
+        assert!(report.findings.iter().all(|f| f.fixable));
+        assert_eq!(report.fixes.files.len(), 2);
+        assert_eq!(report.fixes.skipped, 0);
+        assert_eq!(
+            report.fixes.files[0].fixed_contents(),
+            "# A\n\nSee <https://a.com>, [one](part/two.md#x), [two][two] and [serde-crate].\n\n\
+             [two]: part/two.md\n[serde-crate]: https://crates.io/crates/serde\n"
+        );
+        assert_eq!(report.fixes.files[1].fixed_contents(), "# Two\n\n");
+        assert!(report.fixes.diff().contains("-[unused]: https://u.com\n"));
+
+        report.fixes.apply()?;
+        let report = lint(&src, &options)?;
+        assert_eq!(report.findings, Vec::new());
+        assert!(report.fixes.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicate_refdefs_used_by_their_chapter_are_kept() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let src = dir.path().join("src");
+        std::fs::create_dir(&src)?;
+        std::fs::write(
+            src.join("SUMMARY.md"),
+            "- [A](a.md)\n- [B](b.md)\n- [C](c.md)\n",
+        )?;
+        std::fs::write(src.join("a.md"), "[x]\n\n[x]: https://x.com\n")?;
+        // b.md is rendered on its own: its definition must stay
+        std::fs::write(src.join("b.md"), "[x]\n\n[x]: https://x.com\n")?;
+        // c.md includes a.md, which defines `[x]` first
+        std::fs::write(
+            src.join("c.md"),
+            "{{#include a.md}}\n\n[x]\n\n[x]: https://x.com\n",
+        )?;
+        let report = lint(&src, &LintOptions::default())?;
+        let duplicates: Vec<_> = report
+            .findings
+            .iter()
+            .filter(|f| f.rule == LintRule::DuplicateRefDef)
+            .map(|f| (f.file_path.file_name().unwrap().to_owned(), f.fixable))
+            .collect();
+        assert_eq!(
+            duplicates,
+            vec![("b.md".into(), false), ("c.md".into(), true)]
+        );
+        assert_eq!(report.fixes.files.len(), 1);
+        assert_eq!(report.fixes.files[0].file_path, src.join("c.md"));
+        Ok(())
+    }
+}
@@ -0,0 +1,219 @@
+//! Lint rules, their stable IDs and severities, and findings
+
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// A check performed by [`crate::lint`]
+///
+/// Each rule has a stable ID (e.g. `MU001`) and a name (e.g.
+/// `undefined-reference`); either may be used in configuration files,
+/// on the command line and in `<!-- mdbook-utils: allow(...) -->`
+/// comments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LintRule {
+    /// `MU001`: a reference-style link or image whose reference is not
+    /// defined anywhere in the book
+    UndefinedReference,
+    /// `MU002`: the same URL is written inline more than once; a
+    /// reference-style link would avoid the repetition
+    DuplicateLink,
+    /// `MU003`: a Markdown file that is neither listed in `SUMMARY.md` nor
+    /// included by another file
+    FileNotInSummary,
+    /// `MU004`: a `.rs` file of the code directory that no Markdown file
+    /// includes
+    UnusedRsExample,
+    /// `MU005`: a reference definition that no link or image uses
+    UnusedRefDef,
+    /// `MU006`: a reference definition repeated, with the same URL, in
+    /// another file
+    DuplicateRefDef,
+    /// `MU007`: a label defined in several files with different URLs;
+    /// only the first definition is used
+    ConflictingRefDef,
+    /// `MU008`: a relative link, image or include whose file does not
+    /// exist
+    MissingLocalFile,
+    /// `MU009`: the label of a `[text][label]` link differs in case from
+    /// its reference definition
+    LabelCase,
+    /// `MU010`: a URL written as plain text, which mdBook does not turn
+    /// into a link
+    BareUrl,
+}
+
+impl LintRule {
+    /// All rules, in order of ID
+    pub const ALL: [LintRule; 10] = [
+        Self::UndefinedReference,
+        Self::DuplicateLink,
+        Self::FileNotInSummary,
+        Self::UnusedRsExample,
+        Self::UnusedRefDef,
+        Self::DuplicateRefDef,
+        Self::ConflictingRefDef,
+        Self::MissingLocalFile,
+        Self::LabelCase,
+        Self::BareUrl,
+    ];
+
+    /// Returns the stable ID of the rule e.g. `MU001`
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::UndefinedReference => "MU001",
+            Self::DuplicateLink => "MU002",
+            Self::FileNotInSummary => "MU003",
+            Self::UnusedRsExample => "MU004",
+            Self::UnusedRefDef => "MU005",
+            Self::DuplicateRefDef => "MU006",
+            Self::ConflictingRefDef => "MU007",
+            Self::MissingLocalFile => "MU008",
+            Self::LabelCase => "MU009",
+            Self::BareUrl => "MU010",
+        }
+    }
+
+    /// Returns the name of the rule e.g. `undefined-reference`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::UndefinedReference => "undefined-reference",
+            Self::DuplicateLink => "duplicate-link",
+            Self::FileNotInSummary => "file-not-in-summary",
+            Self::UnusedRsExample => "unused-rs-example",
+            Self::UnusedRefDef => "unused-refdef",
+            Self::DuplicateRefDef => "duplicate-refdef",
+            Self::ConflictingRefDef => "conflicting-refdef",
+            Self::MissingLocalFile => "missing-local-file",
+            Self::LabelCase => "label-case",
+            Self::BareUrl => "bare-url",
+        }
+    }
+
+    /// Returns the severity of the rule, unless configured otherwise
+    pub fn default_severity(self) -> Severity {
+        match self {
+            Self::UndefinedReference | Self::ConflictingRefDef | Self::MissingLocalFile => {
+                Severity::Error
+            }
+            Self::DuplicateLink
+            | Self::FileNotInSummary
+            | Self::UnusedRsExample
+            | Self::UnusedRefDef
+            | Self::DuplicateRefDef
+            | Self::LabelCase
+            | Self::BareUrl => Severity::Warning,
+        }
+    }
+}
+
+impl FromStr for LintRule {
+    type Err = crate::Error;
+
+    /// Parse a rule ID (e.g. `MU001`) or name (e.g.
+    /// `undefined-reference`), ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match Self::ALL
+            .into_iter()
+            .find(|r| r.id().eq_ignore_ascii_case(s) || r.name().eq_ignore_ascii_case(s))
+        {
+            Some(r) => Ok(r),
+            None => Err(crate::Error::config(format!("Unknown lint rule: {s}"))),
+        }
+    }
+}
+
+impl fmt::Display for LintRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.id(), self.name())
+    }
+}
+
+/// Severity of a lint rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The rule is not checked
+    Off,
+    /// Findings are reported
+    Warning,
+    /// Findings are reported and make the lint fail
+    Error,
+}
+
+impl FromStr for Severity {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "off" | "allow" => Ok(Self::Off),
+            "warning" | "warn" => Ok(Self::Warning),
+            "error" | "deny" => Ok(Self::Error),
+            _ => Err(crate::Error::config(format!(
+                "Unknown severity: {s}. Use `error`, `warning` or `off`."
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Off => "off",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// A problem found by [`crate::lint`]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LintFinding {
+    /// File where the problem was found
+    pub file_path: PathBuf,
+    /// Line number (starting at 1), if the problem is not about the
+    /// file as a whole
+    pub line: Option<usize>,
+    /// Rule that found the problem
+    pub rule: LintRule,
+    /// Severity of the rule
+    pub severity: Severity,
+    /// Description of the problem
+    pub message: String,
+    /// `true` if the problem can be fixed automatically, see
+    /// [`crate::LintFixes`]
+    pub fixable: bool,
+}
+
+impl fmt::Display for LintFinding {
+    /// e.g. `src/intro.md:12: error[MU001 undefined-reference]: ...`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.file_path.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{line}")?;
+        }
+        write!(f, ": {}[{}]: {}", self.severity, self.rule, self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lint_rule_from_str() -> anyhow::Result<()> {
+        assert_eq!("MU003".parse::<LintRule>()?, LintRule::FileNotInSummary);
+        assert_eq!("mu003".parse::<LintRule>()?, LintRule::FileNotInSummary);
+        assert_eq!(
+            "undefined-reference".parse::<LintRule>()?,
+            LintRule::UndefinedReference
+        );
+        assert!("MU999".parse::<LintRule>().is_err());
+        for (i, rule) in LintRule::ALL.into_iter().enumerate() {
+            assert_eq!(rule.id(), format!("MU{:03}", i + 1));
+        }
+        assert_eq!("warn".parse::<Severity>()?, Severity::Warning);
+        Ok(())
+    }
+}
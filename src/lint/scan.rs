@@ -0,0 +1,365 @@
+//! Parse a Markdown file once and record, with their positions, the
+//! links, reference definitions, undefined references, includes, bare
+//! URLs and `<!-- mdbook-utils: allow(...) -->` comments that the lint
+//! rules check
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::PathBuf;
+
+use once_cell::sync::Lazy;
+use pulldown_cmark::BrokenLink;
+use pulldown_cmark::CowStr;
+use pulldown_cmark::Event;
+use pulldown_cmark::LinkType;
+use pulldown_cmark::Parser;
+use pulldown_cmark::Tag;
+use pulldown_cmark::TagEnd;
+use pulldown_cmark::TextMergeWithOffset;
+use regex::Regex;
+use tracing::warn;
+
+use super::LintRule;
+use crate::cache::UNRESOLVED_URL;
+use crate::parser;
+use crate::parser::ParserOptions;
+
+/// Regex to find `<!-- mdbook-utils: allow(MU001, unused-refdef) -->`
+/// and `<!-- mdbook-utils: allow-file(...) -->` comments
+static ALLOW_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"<!--\s*mdbook-utils:\s*(?<kind>allow|allow-file)\s*\((?<rules>[^)]*)\)\s*-->")
+        .unwrap()
+});
+
+/// Regex to find the URLs written as plain text
+static BARE_URL_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"https?://[^\s<>\[\]`]+").unwrap());
+
+/// Link or image
+#[derive(Debug, Clone)]
+pub(crate) struct ScannedLink {
+    pub(crate) link_type: LinkType,
+    pub(crate) url: String,
+    /// Reference label, for reference-style links
+    pub(crate) label: String,
+    pub(crate) is_image: bool,
+    /// Byte range of the link in the file
+    pub(crate) span: Range<usize>,
+    /// Byte range of the label of a `[text][label]` link
+    pub(crate) label_span: Option<Range<usize>>,
+    /// Line number, starting at 1
+    pub(crate) line: usize,
+}
+
+/// Reference definition
+#[derive(Debug, Clone)]
+pub(crate) struct ScannedRefDef {
+    pub(crate) label: String,
+    pub(crate) dest: String,
+    pub(crate) title: Option<String>,
+    pub(crate) span: Range<usize>,
+    pub(crate) line: usize,
+}
+
+/// Reference without definition in the file, or include statement
+#[derive(Debug, Clone)]
+pub(crate) struct ScannedRef {
+    /// Reference, or path of the included file
+    pub(crate) name: String,
+    /// Byte range of the label of a `[text][label]` link
+    pub(crate) label_span: Option<Range<usize>>,
+    pub(crate) line: usize,
+}
+
+/// URL written as plain text, rather than as a link
+#[derive(Debug, Clone)]
+pub(crate) struct ScannedBareUrl {
+    pub(crate) url: String,
+    pub(crate) span: Range<usize>,
+    pub(crate) line: usize,
+}
+
+/// Rules disabled by `<!-- mdbook-utils: allow(...) -->` comments
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Suppressions {
+    /// Rules allowed in the whole file, by `allow-file(...)`
+    file: HashSet<LintRule>,
+    /// Rules allowed by `allow(...)` in the line of the comment and the
+    /// next one, by line number
+    lines: HashMap<usize, HashSet<LintRule>>,
+}
+
+impl Suppressions {
+    /// Returns `true` if a rule is allowed at a line, or for the file as
+    /// a whole (`line` is `None`), in which case any `allow(...)`
+    /// comment of the file applies.
+    pub(crate) fn allows(&self, rule: LintRule, line: Option<usize>) -> bool {
+        self.file.contains(&rule)
+            || match line {
+                Some(line) => [line, line.saturating_sub(1)]
+                    .iter()
+                    .any(|l| self.lines.get(l).is_some_and(|r| r.contains(&rule))),
+                None => self.lines.values().any(|r| r.contains(&rule)),
+            }
+    }
+}
+
+/// Markdown file, parsed
+#[derive(Debug, Clone)]
+pub(crate) struct ScannedFile {
+    /// Path of the file
+    pub(crate) path: PathBuf,
+    /// Path relative to the source directory, using `/` as separator
+    pub(crate) rel_path: String,
+    /// Contents of the file
+    pub(crate) contents: String,
+    /// Links and images, except those whose reference is not defined in
+    /// the file
+    pub(crate) links: Vec<ScannedLink>,
+    pub(crate) refdefs: Vec<ScannedRefDef>,
+    /// References not defined in the file
+    pub(crate) unresolved: Vec<ScannedRef>,
+    /// `{{#include ...}}` statements and the like
+    pub(crate) includes: Vec<ScannedRef>,
+    pub(crate) bare_urls: Vec<ScannedBareUrl>,
+    pub(crate) suppressions: Suppressions,
+}
+
+impl ScannedFile {
+    /// Parse the contents of a Markdown file.
+    pub(crate) fn scan(
+        path: PathBuf,
+        rel_path: String,
+        contents: String,
+        options: &ParserOptions,
+    ) -> Self {
+        let line_starts: Vec<usize> = std::iter::once(0)
+            .chain(contents.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        let line_of = |offset: usize| line_starts.partition_point(|&s| s <= offset);
+
+        let mut unresolved = Vec::new();
+        let parser = Parser::new_with_broken_link_callback(
+            &contents,
+            options.to_options(),
+            Some(|link: BrokenLink<'_>| {
+                unresolved.push(ScannedRef {
+                    name: link.reference.to_string(),
+                    label_span: (link.link_type == LinkType::Reference)
+                        .then(|| label_span(&contents, &link.span, &link.reference))
+                        .flatten(),
+                    line: line_of(link.span.start),
+                });
+                Some((CowStr::Borrowed(UNRESOLVED_URL), CowStr::Borrowed("")))
+            }),
+        );
+        let mut refdefs: Vec<ScannedRefDef> = parser
+            .reference_definitions()
+            .iter()
+            .map(|(label, def)| ScannedRefDef {
+                label: label.to_string(),
+                dest: def.dest.to_string(),
+                title: def.title.as_ref().map(|t| t.to_string()),
+                line: line_of(def.span.start),
+                span: def.span.clone(),
+            })
+            .collect();
+        refdefs.sort_by_key(|d| d.span.start);
+
+        let mut links = Vec::new();
+        let mut bare_urls = Vec::new();
+        // Depth of the links, images, code blocks and metadata blocks, in
+        // which URLs are not bare
+        let mut depth = 0usize;
+        for (event, range) in TextMergeWithOffset::new(parser.into_offset_iter()) {
+            let (link_type, dest_url, id, is_image) = match event {
+                Event::Start(Tag::CodeBlock(_) | Tag::MetadataBlock(_)) => {
+                    depth += 1;
+                    continue;
+                }
+                Event::End(
+                    TagEnd::Link | TagEnd::Image | TagEnd::CodeBlock | TagEnd::MetadataBlock(_),
+                ) => {
+                    depth = depth.saturating_sub(1);
+                    continue;
+                }
+                // Text that differs from its source (e.g. escaped) is skipped
+                Event::Text(text) if depth == 0 && contents[range.clone()] == *text => {
+                    for m in BARE_URL_REGEX.find_iter(&text) {
+                        let url = trim_url(m.as_str());
+                        let start = range.start + m.start();
+                        bare_urls.push(ScannedBareUrl {
+                            url: url.to_string(),
+                            span: start..start + url.len(),
+                            line: line_of(start),
+                        });
+                    }
+                    continue;
+                }
+                Event::Start(Tag::Link {
+                    link_type,
+                    dest_url,
+                    id,
+                    ..
+                }) => (link_type, dest_url, id, false),
+                Event::Start(Tag::Image {
+                    link_type,
+                    dest_url,
+                    id,
+                    ..
+                }) => (link_type, dest_url, id, true),
+                _ => continue,
+            };
+            depth += 1;
+            if dest_url.as_ref() != UNRESOLVED_URL {
+                links.push(ScannedLink {
+                    link_type,
+                    url: dest_url.to_string(),
+                    label_span: (link_type == LinkType::Reference)
+                        .then(|| label_span(&contents, &range, &id))
+                        .flatten(),
+                    label: id.to_string(),
+                    is_image,
+                    span: range.clone(),
+                    line: line_of(range.start),
+                });
+            }
+        }
+
+        let includes = parser::find_includes(&contents)
+            .map(|i| ScannedRef {
+                name: i.path.to_string(),
+                label_span: None,
+                line: line_of(i.path_span.start),
+            })
+            .collect();
+
+        let mut suppressions = Suppressions::default();
+        for caps in ALLOW_REGEX.captures_iter(&contents) {
+            let line = line_of(caps.get(0).unwrap().start());
+            for name in caps["rules"].split(',').filter(|s| !s.trim().is_empty()) {
+                match name.parse::<LintRule>() {
+                    Ok(rule) if &caps["kind"] == "allow-file" => {
+                        suppressions.file.insert(rule);
+                    }
+                    Ok(rule) => {
+                        suppressions.lines.entry(line).or_default().insert(rule);
+                    }
+                    Err(e) => warn!("{}:{line}: {e}", path.display()),
+                }
+            }
+        }
+
+        Self {
+            path,
+            rel_path,
+            contents,
+            links,
+            refdefs,
+            unresolved,
+            includes,
+            bare_urls,
+            suppressions,
+        }
+    }
+}
+
+/// Returns the byte range of the label of a `[text][label]` link, if the
+/// source of the link ends with it as written
+fn label_span(contents: &str, span: &Range<usize>, label: &str) -> Option<Range<usize>> {
+    let start = span.end.checked_sub(label.len() + 1)?;
+    (contents.get(span.start..span.end)?.ends_with(']')
+        && contents.get(start.checked_sub(1)?..start)? == "["
+        && contents.get(start..span.end - 1)? == label)
+        .then_some(start..span.end - 1)
+}
+
+/// Remove the trailing punctuation, and any unbalanced closing
+/// parenthesis, of a bare URL e.g. `https://example.com).`
+fn trim_url(url: &str) -> &str {
+    let mut url = url;
+    loop {
+        let trimmed = url.trim_end_matches(['.', ',', ';', ':', '!', '?', '\'', '"', '*', '_']);
+        url = match trimmed.strip_suffix(')') {
+            Some(t) if trimmed.matches('(').count() < trimmed.matches(')').count() => t,
+            _ => trimmed,
+        };
+        if url == trimmed {
+            return url;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan() {
+        let contents = "# Title\n\n[a](https://a.com) [b][x] ![img](img.png)\n\
+                        <!-- mdbook-utils: allow(MU001, bogus) -->\n[undefined]\n\n\
+                        {{#include ../code/main.rs:5}}\n\n[x]: https://x.com\n";
+        let file = ScannedFile::scan(
+            PathBuf::from("src/a.md"),
+            "a.md".into(),
+            contents.to_string(),
+            &ParserOptions::default(),
+        );
+        let links: Vec<_> = file
+            .links
+            .iter()
+            .map(|l| (l.url.as_str(), l.is_image, l.line))
+            .collect();
+        assert_eq!(
+            links,
+            vec![
+                ("https://a.com", false, 3),
+                ("https://x.com", false, 3),
+                ("img.png", true, 3)
+            ]
+        );
+        assert_eq!(file.refdefs[0].line, 9);
+        assert_eq!(file.unresolved[0].name, "undefined");
+        assert_eq!(file.unresolved[0].line, 5);
+        assert_eq!(file.includes[0].name, "../code/main.rs");
+        assert_eq!(file.includes[0].line, 7);
+        assert!(
+            file.suppressions
+                .allows(LintRule::UndefinedReference, Some(5))
+        );
+        assert!(
+            !file
+                .suppressions
+                .allows(LintRule::UndefinedReference, Some(6))
+        );
+        assert!(file.suppressions.allows(LintRule::UndefinedReference, None));
+        assert!(!file.suppressions.allows(LintRule::DuplicateLink, None));
+    }
+
+    #[test]
+    fn test_scan_bare_urls_and_labels() {
+        let contents = "See https://a.com/x_(y)_z. and <https://b.com> [c](https://c.com) \
+                        `https://d.com` [text][Label] [other][elsewhere]\n\n\
+                        ```\nhttps://e.com\n```\n\n[label]: https://l.com\n";
+        let file = ScannedFile::scan(
+            PathBuf::from("src/a.md"),
+            "a.md".into(),
+            contents.to_string(),
+            &ParserOptions::default(),
+        );
+        let urls: Vec<_> = file
+            .bare_urls
+            .iter()
+            .map(|u| (u.url.as_str(), &contents[u.span.clone()]))
+            .collect();
+        assert_eq!(
+            urls,
+            vec![("https://a.com/x_(y)_z", "https://a.com/x_(y)_z")]
+        );
+        let label_span = file.links[2].label_span.clone().unwrap();
+        assert_eq!(&contents[label_span], "Label");
+        let label_span = file.unresolved[0].label_span.clone().unwrap();
+        assert_eq!(&contents[label_span], "elsewhere");
+        assert_eq!(trim_url("https://a.com/(x))."), "https://a.com/(x)");
+    }
+}
@@ -9,6 +9,7 @@
 
 use crate::cli::Cli;
 use crate::cli::Command;
+use crate::cli::GlobalOpts;
 mod cli;
 
 /// Entrypoint of the command-line interface.
@@ -40,10 +41,23 @@ fn main() -> Result<()> {
     } else {
         EnvFilter::new("info")
     };
-    tracing_subscriber::fmt().with_env_filter(env_filter).init();
+    // Logs go to stderr, so that commands can write to stdout (e.g.
+    // `render`)
+    tracing_subscriber::fmt()
+        .with_env_filter(env_filter)
+        .with_writer(std::io::stderr)
+        .init();
 
-    // Retrieves default configuration (from `book.toml`, env. vars,
-    // or hard-coded defaults); also stores global_opts.
+    run_command(cmd, global_opts)
+}
+
+/// Run a command, with the configuration built from the global options.
+///
+/// The interactive mode runs the commands that the user picks through
+/// this function as well.
+pub(crate) fn run_command(cmd: Command, global_opts: GlobalOpts) -> Result<()> {
+    // Retrieves the configuration (from env. vars, `mdbook-utils.toml`,
+    // `book.toml`, or hard-coded defaults); also stores global_opts.
     let config = cli::config::init(global_opts)?;
     debug!("{config:?}");
 
@@ -61,7 +75,12 @@ fn main() -> Result<()> {
             let markdown_src_dir_path = config.markdown_src_dir_path(args.src, "./src/")?;
             let base_url = config.base_url(args.base)?;
             let sitemap_dest_file_path = config.sitemap_file_path(args.dest);
-            let map_index = config.sitemap_map_index(args.map_index);
+            let options = mdbook_utils::SitemapOptions {
+                site_url: config.site_url()?,
+                exclude: config.sitemap_exclude(args.exclude),
+                map_index: config.sitemap_map_index(args.map_index),
+                robots_txt: !args.no_robots_txt,
+            };
 
             println!(
                 "Generating {} from the list of Markdown files in {}...",
@@ -72,22 +91,136 @@ fn main() -> Result<()> {
                 markdown_src_dir_path,
                 base_url,
                 sitemap_dest_file_path,
-                map_index,
+                options,
             )
             .context("[main] Failed to generate the sitemap.")?;
             println!("{}", style("Done.").green());
         }
+        Command::Feed(args) => {
+            let markdown_src_dir_path = config.markdown_src_dir_path(args.src, "./src/")?;
+            let base_url = config.base_url(args.base)?;
+            let format = config.feed_format(args.format)?;
+            let feed_dest_file_path = config.feed_file_path(args.dest, format);
+            let default_options = mdbook_utils::FeedOptions::default();
+            let options = mdbook_utils::FeedOptions {
+                title: config
+                    .feed_title(args.title)
+                    .unwrap_or(default_options.title),
+                format,
+                site_url: config.site_url()?,
+                exclude: config.sitemap_exclude(args.exclude),
+                max_entries: Some(config.feed_max_entries(args.max_entries)),
+                use_git: config.feed_use_git(args.no_git),
+                parser: config.parser_options()?,
+                ..default_options
+            };
+
+            println!(
+                "Generating {} from the chapters listed in {}...",
+                style(feed_dest_file_path.display()).cyan(),
+                style(markdown_src_dir_path.join("SUMMARY.md").display()).cyan(),
+            );
+            if let Some(index_dest_file_path) = args.json_index {
+                println!(
+                    "Generating the JSON index {}...",
+                    style(index_dest_file_path.display()).cyan(),
+                );
+                mdbook_utils::generate_feed_and_search_index(
+                    &markdown_src_dir_path,
+                    base_url,
+                    feed_dest_file_path,
+                    index_dest_file_path,
+                    options,
+                )
+                .context("[main] Failed to generate the feed and the JSON index.")?;
+            } else {
+                mdbook_utils::generate_feed(
+                    &markdown_src_dir_path,
+                    base_url,
+                    feed_dest_file_path,
+                    options,
+                )
+                .context("[main] Failed to generate the feed.")?;
+            }
+            println!("{}", style("Done.").green());
+        }
+        Command::Lint(args) => {
+            cli::lint::run(args, config)?;
+        }
+        Command::Watch(args) => {
+            cli::watch::run(args, config)?;
+        }
+        Command::Config(subcmd) => {
+            cli::config_commands::run(subcmd, config)?;
+        }
+        Command::Interactive => {
+            cli::interactive::run(config)?;
+        }
+        Command::Render(args) => {
+            let markdown_src_dir_path = config.markdown_src_dir_path(args.src, "./src/")?;
+            let template = match (args.template, args.standalone) {
+                (Some(path), _) => Some(std::fs::read_to_string(&path).with_context(|| {
+                    format!("[main] Failed to read the template {}.", path.display())
+                })?),
+                (None, true) => Some(mdbook_utils::DEFAULT_HTML_TEMPLATE.to_string()),
+                (None, false) => None,
+            };
+            let options = mdbook_utils::RenderOptions {
+                resolve_refdefs: !args.no_refdefs,
+                html_links: !args.keep_md_links,
+                template,
+                parser: config.parser_options()?,
+            };
+            match args.dest.file_path {
+                Some(dest_file_path) => {
+                    mdbook_utils::render_chapter_to(
+                        markdown_src_dir_path,
+                        &args.chapter,
+                        &options,
+                        &dest_file_path,
+                    )
+                    .context("[main] Failed to render the chapter.")?;
+                    println!(
+                        "{} {}",
+                        style("Done:").green(),
+                        style(dest_file_path.display()).cyan()
+                    );
+                }
+                None => {
+                    let html = mdbook_utils::render_chapter(
+                        markdown_src_dir_path,
+                        &args.chapter,
+                        &options,
+                    )
+                    .context("[main] Failed to render the chapter.")?;
+                    print!("{html}");
+                }
+            }
+        }
         Command::Debug(args) => {
             let markdown_src_dir_path = config.markdown_src_dir_path(args.src, "./src/")?;
-            let log_dest_path = config.dest_file_path(args.dest, "debug.log");
+            let default_file_name = match args.format {
+                mdbook_utils::EventFormat::Json => "debug.json",
+                _ => "debug.log",
+            };
+            let log_dest_path = config.dest_file_path(args.dest, default_file_name);
             println!(
-                "Parsing Markdown files in {} and writing raw events to {}...",
+                "Parsing Markdown files in {} and writing the events to {}...",
                 style(markdown_src_dir_path.display()).cyan(),
                 style(log_dest_path.display()).cyan()
             );
-            mdbook_utils::debug_parse_to(markdown_src_dir_path, log_dest_path)
-                .context("[main] Failed to generate the debug log.")?;
-            println!("{}", style("Done.").green());
+            let files = mdbook_utils::debug_parse_files_to(
+                markdown_src_dir_path,
+                args.file.as_deref(),
+                args.format,
+                &config.parser_options()?,
+                log_dest_path,
+            )
+            .context("[main] Failed to generate the debug log.")?;
+            println!(
+                "{}",
+                style(format!("Done: {} file(s).", files.len())).green()
+            );
         }
         Command::Test => {
             mdbook_utils::test().context("[main] Failed to generate the test file.")?;
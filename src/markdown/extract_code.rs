@@ -6,7 +6,6 @@
 use std::path::PathBuf;
 use std::sync::LazyLock;
 
-use anyhow::Result;
 use once_cell::sync::Lazy;
 use rand::distr::Alphanumeric;
 use rand::distr::SampleString;
@@ -14,6 +13,9 @@
 use regex::Regex;
 use tracing::info;
 
+use crate::Error;
+use crate::Result;
+
 /// Embedded Rust code extraction from Markdown
 static EXTRACT_REGEX: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"(?s)```rust.*?\n(?<code>.*?)```").unwrap());
@@ -49,14 +51,17 @@ pub fn extract_code_from_all_markdown_files_in<P1, P2>(
     crate::fs::create_dir(code_dest_dir_path.as_ref())?;
 
     // Canonicalize and ensure path is within code_dest_dir_path
-    let code_dest_canon = code_dest_dir_path.as_ref().canonicalize()?;
+    let code_dest_canon = code_dest_dir_path
+        .as_ref()
+        .canonicalize()
+        .map_err(|e| Error::io(code_dest_dir_path.as_ref(), e))?;
 
     // Process each .md file
     markdown_file_paths
         .into_par_iter()
         .try_for_each(|p| -> Result<()> {
             info!("{p:?}");
-            let buf = fs::read_to_string(p.as_path())?;
+            let buf = fs::read_to_string(p.as_path()).map_err(|e| Error::io(&p, e))?;
             let random_string = Alphanumeric.sample_string(&mut rand::rng(), 5);
 
             // debug!("{p:?}: length = {}", buf.len());
@@ -81,12 +86,13 @@ pub fn extract_code_from_all_markdown_files_in<P1, P2>(
                 );
                 let code_path = code_dest_dir_path.as_ref().join(code_filename);
 
-                if crate::fs::is_path_within(&code_dest_canon, &code_path).is_err() {
-                    anyhow::bail!("Path traversal detected: attempt to write file outside destination directory");
-                }
+                // Reject a file written outside of the destination directory
+                crate::fs::is_path_within(&code_dest_canon, &code_path)?;
 
                 info!(" {number}: {code_path:?}\n");
-                File::create(code_path)?.write_all(code.as_bytes())?;
+                File::create(&code_path)
+                    .and_then(|mut f| f.write_all(code.as_bytes()))
+                    .map_err(|e| Error::io(&code_path, e))?;
             }
             Ok(())
         })?;
@@ -132,7 +138,7 @@ pub fn remove_code_from_all_markdown_files_in<P1, P2>(
         .into_par_iter()
         .try_for_each(|p| -> Result<()> {
             info!("{p:?}");
-            let buf = fs::read_to_string(p.as_path())?;
+            let buf = fs::read_to_string(p.as_path()).map_err(|e| Error::io(&p, e))?;
 
             let mut new_txt = String::with_capacity(buf.len());
             let mut last_match = 0;
@@ -164,7 +170,9 @@ pub fn remove_code_from_all_markdown_files_in<P1, P2>(
 
             if counter > 0 {
                 new_txt.push_str(&buf[last_match..]);
-                File::create(p)?.write_all(new_txt.as_bytes())?;
+                File::create(&p)
+                    .and_then(|mut f| f.write_all(new_txt.as_bytes()))
+                    .map_err(|e| Error::io(&p, e))?;
             }
             Ok(())
         })?;
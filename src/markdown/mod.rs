@@ -3,6 +3,7 @@
 pub mod extract_code;
 pub mod remove_includes;
 pub mod replace_include;
+pub mod strip_front_matter;
 
 #[doc(inline)]
 pub use extract_code::*;
@@ -10,3 +11,5 @@
 pub use remove_includes::*;
 #[doc(inline)]
 pub use replace_include::*;
+#[doc(inline)]
+pub use strip_front_matter::*;
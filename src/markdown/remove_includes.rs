@@ -9,12 +9,14 @@
 use std::path::Path;
 use std::sync::Mutex;
 
-use anyhow::Result;
 use once_cell::sync::Lazy;
 use rayon::prelude::*;
 use regex::Regex;
 use tracing::info;
 
+use crate::Error;
+use crate::Result;
+
 /// Regex to find {{#include \<file\>.md}}
 static REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{\{#include.*?\}\}").unwrap());
 
@@ -47,12 +49,14 @@ pub fn remove_includes_in_all_markdown_files_in<P>(
     // Process each .md file
     paths.into_par_iter().try_for_each(|p| -> Result<()> {
         info!("Looking into {p:?}");
-        let buf = fs::read_to_string(p.as_path())?;
+        let buf = fs::read_to_string(p.as_path()).map_err(|e| Error::io(&p, e))?;
         if REGEX.is_match(&buf) {
             let new_txt = REGEX.replace_all(&buf, regex::NoExpand(contents_to_insert));
             if let std::borrow::Cow::Owned(new_txt) = new_txt {
                 // tracing::debug!("modified: {}", p.display());
-                File::create(p.clone())?.write_all(new_txt.as_bytes())?;
+                File::create(&p)
+                    .and_then(|mut f| f.write_all(new_txt.as_bytes()))
+                    .map_err(|e| Error::io(&p, e))?;
                 modified.lock().unwrap().push(p);
             }
         }
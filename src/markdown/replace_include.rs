@@ -4,15 +4,27 @@
 use std::io::Write;
 use std::path::Path;
 
-use anyhow::Result;
-use once_cell::sync::Lazy;
 use rayon::prelude::*;
-use regex::Regex;
 use tracing::info;
 
-/// Regex to find {{#include \<file\>.md}}.
-static INSERT_REGEX: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"\{\{#include (?<filepath>\S+?\.md)\}\}").unwrap());
+use crate::Error;
+use crate::Result;
+use crate::parser;
+use crate::parser::IncludeKind;
+use crate::parser::IncludeStatement;
+
+/// Returns the {{#include \<file\>.md}} statements of a Markdown file,
+/// i.e. those that include a whole Markdown file.
+fn find_md_includes(contents: &str) -> Vec<IncludeStatement<'_>> {
+    parser::find_includes(contents)
+        .filter(|i| {
+            i.kind == IncludeKind::Include
+                && i.selector.is_none()
+                && i.attrs.is_empty()
+                && i.path.ends_with(".md")
+        })
+        .collect()
+}
 
 ///  Within each mdBook-style Markdown file in a source directory,
 /// replace {{#include file.md}} statements by the contents of the
@@ -24,15 +36,15 @@
 ///
 /// markdown_src_dir_path: path to the source directory containing the
 /// Markdown files.
-use anyhow::Context;
-
-/// Process files and replace include macros
 #[tracing::instrument(skip(markdown_src_dir_path))]
 pub fn include_in_all_markdown_files_in<P>(markdown_src_dir_path: P) -> Result<()>
 where
     P: AsRef<Path> + std::marker::Sync,
 {
-    let base_dir = markdown_src_dir_path.as_ref().canonicalize()?;
+    let base_dir = markdown_src_dir_path
+        .as_ref()
+        .canonicalize()
+        .map_err(|e| Error::io(markdown_src_dir_path.as_ref(), e))?;
 
     // Locate the Markdown files with the src directory
     let paths = crate::fs::find_markdown_files_in(markdown_src_dir_path.as_ref())?;
@@ -42,22 +54,19 @@ pub fn include_in_all_markdown_files_in<P>(markdown_src_dir_path: P) -> Result<(
         info!("Looking into {p:?}");
         let parent_dir = p
             .parent()
-            .context("Expected parent directory")?
+            .ok_or_else(|| Error::config(format!("{} has no parent directory", p.display())))?
             .to_string_lossy();
-        let buf = fs::read_to_string(p.as_path())?;
-        if INSERT_REGEX.is_match(&buf) {
+        let buf = fs::read_to_string(p.as_path()).map_err(|e| Error::io(&p, e))?;
+        let includes = find_md_includes(&buf);
+        if !includes.is_empty() {
             let mut new_txt = String::with_capacity(buf.len());
             let mut last_match = 0;
             let mut modified = false;
 
-            for cap in INSERT_REGEX.captures_iter(&buf) {
-                let m = cap.get(0).unwrap();
-                new_txt.push_str(&buf[last_match..m.start()]);
+            for include in includes {
+                new_txt.push_str(&buf[last_match..include.span.start]);
 
-                let rel_file_path = cap
-                    .name("filepath")
-                    .context("Missing filepath capture")?
-                    .as_str();
+                let rel_file_path = include.path;
                 if !rel_file_path.ends_with("refs.md") {
                     let path_file_to_insert = Path::new(parent_dir.as_ref()).join(rel_file_path);
                     let canonicalized_insert = match path_file_to_insert.canonicalize() {
@@ -78,7 +87,7 @@ pub fn include_in_all_markdown_files_in<P>(markdown_src_dir_path: P) -> Result<(
                         markdown_src_dir_path.as_ref(),
                         &path_file_to_insert,
                     ) {
-                        Ok(p) => fs::read_to_string(p)?,
+                        Ok(p) => fs::read_to_string(&p).map_err(|e| Error::io(&p, e))?,
                         Err(e) => {
                             tracing::error!("{e}");
                             continue;
@@ -90,15 +99,17 @@ pub fn include_in_all_markdown_files_in<P>(markdown_src_dir_path: P) -> Result<(
                     modified = true;
                 } else {
                     info!("Ignored");
-                    new_txt.push_str(m.as_str());
+                    new_txt.push_str(&buf[include.span.clone()]);
                 }
-                last_match = m.end();
+                last_match = include.span.end;
             }
             new_txt.push_str(&buf[last_match..]);
 
             if modified {
                 // debug!("{}",  new_txt);
-                File::create(p)?.write_all(new_txt.as_bytes())?;
+                File::create(&p)
+                    .and_then(|mut f| f.write_all(new_txt.as_bytes()))
+                    .map_err(|e| Error::io(&p, e))?;
             }
         }
         Ok(())
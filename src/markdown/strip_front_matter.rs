@@ -0,0 +1,89 @@
+//! Remove the front matter from mdBook-style Markdown sources.
+//!
+//! mdBook does not recognize front matter: its HTML renderer would
+//! display it at the top of the chapter. Run on a copy of the sources
+//! (or on a preprocessed output) before `mdbook build`, once the front
+//! matter has been used e.g. to generate `SUMMARY.md`, the sitemap or the
+//! feed.
+
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use rayon::prelude::*;
+use tracing::info;
+
+use crate::Error;
+use crate::Result;
+use crate::lint;
+use crate::lint::FileFix;
+use crate::lint::TextEdit;
+use crate::parser;
+
+/// Within each Markdown file in a directory, remove the YAML (`---`) or
+/// TOML (`+++`) front matter block at the top of the file, if any.
+///
+/// The files are written all at once, through temporary files, and
+/// nothing is written if a file was modified since it was read.
+///
+/// markdown_dir_path: path to the directory containing the Markdown
+/// files.
+///
+/// Returns the paths of the modified files.
+pub fn strip_front_matter_in_all_markdown_files_in<P>(markdown_dir_path: P) -> Result<Vec<PathBuf>>
+where
+    P: AsRef<Path>,
+{
+    let paths = crate::fs::find_markdown_files_in(markdown_dir_path.as_ref())?;
+
+    let fixes: Vec<FileFix> = paths
+        .into_par_iter()
+        .map(|p| -> Result<Option<FileFix>> {
+            info!("Looking into {p:?}");
+            let buf = fs::read_to_string(p.as_path()).map_err(|e| Error::io(&p, e))?;
+            let (Some(_), rest) = parser::split_front_matter(&buf) else {
+                return Ok(None);
+            };
+            let mut fix = FileFix::new(p, buf.clone());
+            fix.add(TextEdit {
+                range: 0..buf.len() - rest.len(),
+                replacement: String::new(),
+            });
+            Ok(Some(fix))
+        })
+        .filter_map(Result::transpose)
+        .collect::<Result<_>>()?;
+
+    lint::apply_file_fixes(&fixes)?;
+    Ok(fixes.into_iter().map(|f| f.file_path).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_strip_front_matter_in_all_markdown_files_in() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let src_dir = dir.path().join("src");
+        fs::create_dir(&src_dir)?;
+        let yaml = src_dir.join("yaml.md");
+        fs::write(&yaml, "---\ntitle: A\n---\n# A\n")?;
+        let toml = src_dir.join("toml.md");
+        fs::write(&toml, "+++\ntitle = \"B\"\n+++\n# B\n")?;
+        let none = src_dir.join("none.md");
+        fs::write(&none, "# C\n\n---\n\nText\n")?;
+
+        let mut modified = strip_front_matter_in_all_markdown_files_in(&src_dir)?;
+        modified.sort();
+        assert_eq!(modified, vec![toml.clone(), yaml.clone()]);
+        assert_eq!(fs::read_to_string(&yaml)?, "# A\n");
+        assert_eq!(fs::read_to_string(&toml)?, "# B\n");
+        assert_eq!(fs::read_to_string(&none)?, "# C\n\n---\n\nText\n");
+        // No temporary file is left behind
+        assert_eq!(fs::read_dir(&src_dir)?.count(), 3);
+        Ok(())
+    }
+}
@@ -1,8 +1,5 @@
 //! Use a Markdown parser to extract links
-use std::borrow::Cow;
-
 use pulldown_cmark::Event;
-use pulldown_cmark::Parser;
 use pulldown_cmark::Tag;
 use pulldown_cmark::TagEnd;
 use tracing::debug;
@@ -19,13 +16,16 @@ enum Where {
              * End(Tag::Image(..)), within a link */
 }
 
-/// Read from a Markdown parser, extract links from the event stream,
-/// and return said links.
-pub(crate) fn extract_links<'input>(parser: &mut Parser<'input>) -> Vec<Link<'input>> {
-    let mut state: Vec<(Where, LinkBuilder<'input>)> = Vec::new();
-    let mut links: Vec<Link<'input>> = Vec::new();
+/// Read from a Markdown parser (or any stream of events), extract links
+/// from the event stream, and return said links.
+pub(crate) fn extract_links<'input, I>(events: I) -> Vec<Link>
+where
+    I: IntoIterator<Item = Event<'input>>,
+{
+    let mut state: Vec<(Where, LinkBuilder)> = Vec::new();
+    let mut links: Vec<Link> = Vec::new();
 
-    for event in parser {
+    for event in events {
         match event {
             // Start of a link
             Event::Start(Tag::Link {
@@ -37,12 +37,7 @@ pub(crate) fn extract_links<'input>(parser: &mut Parser<'input>) -> Vec<Link<'in
                 debug!("Link: link_type: {link_type:?}, url: {dest_url}, title: {title}, id: {id}");
                 state.push((
                     Where::InLink,
-                    LinkBuilder::from_type_url_title(
-                        link_type,
-                        dest_url.into(),
-                        title.into(),
-                        id.into(),
-                    ),
+                    LinkBuilder::from_parsed(link_type, dest_url, title, id),
                 ));
             }
 
@@ -78,12 +73,7 @@ pub(crate) fn extract_links<'input>(parser: &mut Parser<'input>) -> Vec<Link<'in
                     if whr == Where::InLink {
                         state.push((
                             Where::InImageInLink,
-                            link_builder.set_image(
-                                link_type,
-                                dest_url.into(),
-                                title.into(),
-                                id.into(),
-                            ),
+                            link_builder.set_image(link_type.into(), dest_url, title, id),
                         ));
                     } else {
                         tracing::warn!("Expected InLink state, found {:?}", whr);
@@ -110,7 +100,7 @@ pub(crate) fn extract_links<'input>(parser: &mut Parser<'input>) -> Vec<Link<'in
                 debug!("Event::Text({t:?})");
                 if let Some((whr, link_builder)) = state.pop() {
                     if whr == Where::InImageInLink {
-                        state.push((whr, link_builder.add_image_alt_text(Cow::from(t))));
+                        state.push((whr, link_builder.add_image_alt_text(t)));
                     } else {
                         tracing::warn!("Expected InImageInLink state, found {:?}", whr);
                         state.push((whr, link_builder)); // restore state
@@ -122,7 +112,7 @@ pub(crate) fn extract_links<'input>(parser: &mut Parser<'input>) -> Vec<Link<'in
                 debug!("Event::Text({t:?})");
                 if let Some((whr, link_builder)) = state.pop() {
                     if whr == Where::InLink {
-                        state.push((whr, link_builder.add_text(Cow::from(t))));
+                        state.push((whr, link_builder.add_text(t)));
                     } else {
                         tracing::warn!("Expected InLink state, found {:?}", whr);
                         state.push((whr, link_builder)); // restore state
@@ -134,9 +124,9 @@ pub(crate) fn extract_links<'input>(parser: &mut Parser<'input>) -> Vec<Link<'in
                 debug!("code: {c}");
                 if let Some((whr, link_builder)) = state.pop() {
                     if whr == Where::InImageInLink {
-                        state.push((whr, link_builder.add_image_alt_text(c.into())));
+                        state.push((whr, link_builder.add_image_alt_text(c)));
                     } else if whr == Where::InLink {
-                        state.push((whr, link_builder.add_text(c.into())));
+                        state.push((whr, link_builder.add_text(c)));
                     } else {
                         tracing::warn!("Expected InImageInLink or InLink state, found {:?}", whr);
                         state.push((whr, link_builder)); // restore state
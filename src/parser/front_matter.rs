@@ -0,0 +1,362 @@
+//! Split the (optional) front matter from the Markdown content of a
+//! chapter, and parse it into [`ChapterMetadata`]
+//!
+//! The front matter is a block at the very top of the file: either YAML
+//! `key: value` lines delimited by `---` lines, or TOML delimited by
+//! `+++` lines:
+//!
+//! ```md
+//! ---
+//! date: 2024-05-01
+//! summary: "How to parse command-line arguments"
+//! tags: [cli, parsing]
+//! crates:
+//!   - clap
+//!   - lexopt
+//! ---
+//!
+//! # Chapter title
+//! ```
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use serde::Serialize;
+use tracing::warn;
+
+use super::ParserOptions;
+
+/// Value of a front matter key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MetadataValue {
+    /// Single value e.g. `date: 2024-05-01`. Numbers, booleans and dates
+    /// are kept as written.
+    String(String),
+    /// List e.g. `tags: [cli, parsing]`
+    List(Vec<String>),
+}
+
+/// Metadata of a chapter, from the front matter of its Markdown file.
+///
+/// Keys are lowercased. Nested tables are ignored.
+///
+/// ```
+/// use mdbook_utils::ChapterMetadata;
+///
+/// let md =
+///     "+++\ntitle = \"Parsing\"\ntags = [\"cli\", \"parsing\"]\nweight = 2\n+++\n# Parsing\n";
+/// let metadata = ChapterMetadata::from_markdown(md).unwrap();
+/// assert_eq!(metadata.title(), Some("Parsing"));
+/// assert_eq!(metadata.tags(), vec!["cli", "parsing"]);
+/// assert_eq!(metadata.weight(), Some(2));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChapterMetadata {
+    values: BTreeMap<String, MetadataValue>,
+}
+
+impl ChapterMetadata {
+    /// Parse the front matter of a Markdown document, if any
+    pub fn from_markdown(markdown: &str) -> Option<Self> {
+        split_front_matter(markdown).0
+    }
+
+    /// Returns the value of a key, if any
+    pub fn value(&self, key: &str) -> Option<&MetadataValue> {
+        self.values.get(key)
+    }
+
+    /// Returns the value of a key, if it is a single, non-empty value
+    pub fn get(&self, key: &str) -> Option<&str> {
+        match self.values.get(key)? {
+            MetadataValue::String(s) if !s.is_empty() => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the items of a list, or of a single comma-separated value
+    /// e.g. `tags: cli, parsing`
+    pub fn list(&self, key: &str) -> Vec<&str> {
+        match self.values.get(key) {
+            Some(MetadataValue::List(items)) => items.iter().map(String::as_str).collect(),
+            Some(MetadataValue::String(s)) => s
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns the keys and values, sorted by key
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &MetadataValue)> {
+        self.values.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    /// Returns `true` if there are no keys
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// `title`
+    pub fn title(&self) -> Option<&str> {
+        self.get("title")
+    }
+
+    /// `summary` or `description`
+    pub fn summary(&self) -> Option<&str> {
+        self.get("summary").or_else(|| self.get("description"))
+    }
+
+    /// Date of the last update: `updated` or `date`, as written
+    pub fn date(&self) -> Option<&str> {
+        self.get("updated").or_else(|| self.get("date"))
+    }
+
+    /// `tags` or `keywords`
+    pub fn tags(&self) -> Vec<&str> {
+        match self.list("tags") {
+            tags if tags.is_empty() => self.list("keywords"),
+            tags => tags,
+        }
+    }
+
+    /// `crates`: crates used by the chapter, even if it does not link to
+    /// them
+    pub fn crates(&self) -> Vec<&str> {
+        self.list("crates")
+    }
+
+    /// `weight` or `order`: position of the chapter among its siblings,
+    /// lowest first
+    pub fn weight(&self) -> Option<i64> {
+        self.get("weight")
+            .or_else(|| self.get("order"))
+            .and_then(|w| w.parse().ok())
+    }
+
+    /// `draft: true`
+    pub fn is_draft(&self) -> bool {
+        self.get("draft") == Some("true")
+    }
+}
+
+/// Split the front matter (if any) from the Markdown content.
+///
+/// Keys are lowercased; surrounding quotes are removed from YAML values.
+/// YAML lines that are not `key: value` pairs or list items (e.g.
+/// comments) are ignored.
+///
+/// Returns the front matter, if present, and the rest of the Markdown.
+///
+/// markdown: the contents of a Markdown file.
+pub(crate) fn split_front_matter(markdown: &str) -> (Option<ChapterMetadata>, &str) {
+    match front_matter_block(markdown) {
+        Some((FrontMatterFormat::Yaml, block, rest)) => (Some(parse_yaml(block)), rest),
+        Some((FrontMatterFormat::Toml, block, rest)) => (Some(parse_toml(block)), rest),
+        None => (None, markdown),
+    }
+}
+
+/// Remove the front matter (if any) from the Markdown content, unless
+/// front matter handling is disabled in the parser options.
+///
+/// markdown: the contents of a Markdown file.
+///
+/// options: the Markdown dialect, see [`ParserOptions`].
+pub(crate) fn skip_front_matter<'a>(markdown: &'a str, options: &ParserOptions) -> &'a str {
+    if !options.front_matter {
+        return markdown;
+    }
+    front_matter_block(markdown).map_or(markdown, |(_, _, rest)| rest)
+}
+
+/// Format of a front matter block
+enum FrontMatterFormat {
+    /// Delimited by `---`
+    Yaml,
+    /// Delimited by `+++`
+    Toml,
+}
+
+/// Find the front matter block at the very top of the file, closed by
+/// the same delimiter.
+///
+/// Returns the format, the contents of the block and the rest of the
+/// Markdown.
+fn front_matter_block(markdown: &str) -> Option<(FrontMatterFormat, &str, &str)> {
+    let content = markdown.strip_prefix('\u{feff}').unwrap_or(markdown);
+    let (format, delimiter) = if content.starts_with("---") {
+        (FrontMatterFormat::Yaml, "---")
+    } else if content.starts_with("+++") {
+        (FrontMatterFormat::Toml, "+++")
+    } else {
+        return None;
+    };
+    let block = content[delimiter.len()..]
+        .strip_prefix('\n')
+        .or_else(|| content[delimiter.len()..].strip_prefix("\r\n"))?;
+    let mut offset = 0;
+    for line in block.split_inclusive('\n') {
+        if line.trim_end() == delimiter {
+            return Some((format, &block[..offset], &block[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    // No closing delimiter: this is not front matter (e.g. a horizontal
+    // rule).
+    None
+}
+
+/// Parse YAML `key: value` lines, `key: [a, b]` lists and `- item` lists
+fn parse_yaml(block: &str) -> ChapterMetadata {
+    let mut values = BTreeMap::new();
+    let mut list_key: Option<String> = None;
+    for line in block.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if let Some(item) = trimmed.strip_prefix('-')
+            && let Some(MetadataValue::List(items)) =
+                list_key.as_ref().and_then(|k| values.get_mut(k))
+        {
+            items.push(unquote(item.trim()).to_string());
+            continue;
+        }
+        list_key = None;
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        if key.is_empty() || key.starts_with('#') {
+            continue;
+        }
+        let value = value.trim();
+        let value = if value.is_empty() {
+            // Block list, if followed by `- item` lines
+            list_key = Some(key.clone());
+            MetadataValue::List(Vec::new())
+        } else if let Some(items) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+            MetadataValue::List(
+                items
+                    .split(',')
+                    .map(|i| unquote(i.trim()).to_string())
+                    .filter(|i| !i.is_empty())
+                    .collect(),
+            )
+        } else {
+            MetadataValue::String(unquote(value).to_string())
+        };
+        values.insert(key, value);
+    }
+    // A key without value nor items is an empty value
+    for value in values.values_mut() {
+        if matches!(value, MetadataValue::List(items) if items.is_empty()) {
+            *value = MetadataValue::String(String::new());
+        }
+    }
+    ChapterMetadata { values }
+}
+
+/// Parse TOML; nested tables are ignored
+fn parse_toml(block: &str) -> ChapterMetadata {
+    let table: toml::Table = match toml::from_str(block) {
+        Ok(t) => t,
+        Err(e) => {
+            warn!("Invalid TOML front matter: {e}");
+            return ChapterMetadata::default();
+        }
+    };
+    let scalar = |v: &toml::Value| match v {
+        toml::Value::String(s) => Some(s.clone()),
+        toml::Value::Array(_) | toml::Value::Table(_) => None,
+        v => Some(v.to_string()),
+    };
+    let values = table
+        .iter()
+        .filter_map(|(key, v)| {
+            let value = match v {
+                toml::Value::Array(items) => {
+                    MetadataValue::List(items.iter().filter_map(scalar).collect())
+                }
+                v => MetadataValue::String(scalar(v)?),
+            };
+            Some((key.to_lowercase(), value))
+        })
+        .collect();
+    ChapterMetadata { values }
+}
+
+/// Remove the quotes surrounding a YAML value, if any
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+        .unwrap_or(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_front_matter() {
+        let md = "---\ndate: 2024-05-01\nSummary: \"Hello: world\"\n---\n# Title\n";
+        let (fm, rest) = split_front_matter(md);
+        let fm = fm.unwrap();
+        assert_eq!(fm.get("date"), Some("2024-05-01"));
+        assert_eq!(fm.summary(), Some("Hello: world"));
+        assert_eq!(rest, "# Title\n");
+
+        let md = "# Title\n\n---\n";
+        assert_eq!(split_front_matter(md), (None, md));
+
+        let md = "---\nnot closed\n";
+        assert_eq!(split_front_matter(md), (None, md));
+    }
+
+    #[test]
+    fn test_parse_yaml_lists() {
+        let md = "---\ntags: [cli, 'parsing']\ncrates:\n  - clap\n  - \"lexopt\"\nkeywords: a, b\nempty:\norder: 3\ndraft: true\n---\n";
+        let fm = ChapterMetadata::from_markdown(md).unwrap();
+        assert_eq!(fm.tags(), vec!["cli", "parsing"]);
+        assert_eq!(fm.crates(), vec!["clap", "lexopt"]);
+        assert_eq!(fm.list("keywords"), vec!["a", "b"]);
+        assert_eq!(fm.get("empty"), None);
+        assert_eq!(fm.weight(), Some(3));
+        assert!(fm.is_draft());
+    }
+
+    #[test]
+    fn test_parse_toml() {
+        let md = "+++\ndate = 2024-05-01\nTitle = \"Args\"\ncrates = [\"clap\"]\n[extra]\nx = 1\n+++\n# Title\n";
+        let (fm, rest) = split_front_matter(md);
+        let fm = fm.unwrap();
+        assert_eq!(fm.date(), Some("2024-05-01"));
+        assert_eq!(fm.title(), Some("Args"));
+        assert_eq!(fm.crates(), vec!["clap"]);
+        assert!(fm.value("extra").is_none());
+        assert_eq!(rest, "# Title\n");
+    }
+
+    #[test]
+    fn test_skip_front_matter() {
+        let options = ParserOptions::default();
+        let md = "+++\ndate = 2024-05-01\n+++\n# Title\n";
+        assert_eq!(skip_front_matter(md, &options), "# Title\n");
+        let md = "---\ndate: 2024-05-01\n---\n# Title\n";
+        assert_eq!(skip_front_matter(md, &options), "# Title\n");
+        let md = "---\nnot closed\n";
+        assert_eq!(skip_front_matter(md, &options), md);
+
+        let options = ParserOptions {
+            front_matter: false,
+            ..Default::default()
+        };
+        let md = "+++\ndate = 2024-05-01\n+++\n# Title\n";
+        assert_eq!(skip_front_matter(md, &options), md);
+    }
+}
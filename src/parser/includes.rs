@@ -0,0 +1,114 @@
+//! Find mdBook's `{{#include}}`, `{{#rustdoc_include}}` and
+//! `{{#playground}}` statements.
+//!
+//! Moving chapters, linting, generating `SUMMARY.md` and rendering all
+//! use this module, so that they agree on what an include statement is.
+//!
+//! See the [mdBook documentation](https://rust-lang.github.io/mdBook/format/mdbook.html#including-files).
+use std::ops::Range;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// Regex to find the include statements and their arguments, e.g.
+/// `{{#include ../code/main.rs:5:}}` or `{{#playground main.rs
+/// editable}}`. `\{{#include ...}}` is an escaped statement.
+static INCLUDE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?<escape>\\)?\{\{\s*#(?<kind>include|rustdoc_include|playground)\s+(?<path>[^\s:}]+)(?::(?<selector>[^\s}]*))?(?<attrs>[^}]*?)\s*\}\}",
+    )
+    .unwrap()
+});
+
+/// Kind of include statement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum IncludeKind {
+    /// `{{#include ...}}`
+    Include,
+    /// `{{#rustdoc_include ...}}`
+    RustdocInclude,
+    /// `{{#playground ...}}`
+    Playground,
+}
+
+/// Include statement found in a Markdown document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct IncludeStatement<'a> {
+    pub(crate) kind: IncludeKind,
+    /// `true` for `\{{#include ...}}`, which mdBook leaves as written
+    /// (without the backslash)
+    pub(crate) escaped: bool,
+    /// Byte range of the whole statement, including the backslash of an
+    /// escaped statement
+    pub(crate) span: Range<usize>,
+    /// Path of the included file, relative to the Markdown file, e.g.
+    /// `../code/main.rs`
+    pub(crate) path: &'a str,
+    /// Byte range of the path
+    pub(crate) path_span: Range<usize>,
+    /// Anchor or line range suffix of the path, if any, e.g. `main`,
+    /// `5` or `2:10`
+    pub(crate) selector: Option<&'a str>,
+    /// Other arguments e.g. `editable` in `{{#playground main.rs
+    /// editable}}`
+    pub(crate) attrs: Vec<&'a str>,
+}
+
+/// Returns the include statements of a Markdown document, escaped ones
+/// included, in order.
+pub(crate) fn find_all_includes(contents: &str) -> impl Iterator<Item = IncludeStatement<'_>> {
+    INCLUDE_REGEX.captures_iter(contents).map(|caps| {
+        let kind = match &caps["kind"] {
+            "rustdoc_include" => IncludeKind::RustdocInclude,
+            "playground" => IncludeKind::Playground,
+            _ => IncludeKind::Include,
+        };
+        let path = caps.name("path").unwrap();
+        IncludeStatement {
+            kind,
+            escaped: caps.name("escape").is_some(),
+            span: caps.get(0).unwrap().range(),
+            path: path.as_str(),
+            path_span: path.range(),
+            selector: caps.name("selector").map(|m| m.as_str()),
+            attrs: caps
+                .name("attrs")
+                .map(|m| m.as_str().split_whitespace().collect())
+                .unwrap_or_default(),
+        }
+    })
+}
+
+/// Returns the include statements of a Markdown document that mdBook
+/// expands, i.e. not escaped, in order.
+pub(crate) fn find_includes(contents: &str) -> impl Iterator<Item = IncludeStatement<'_>> {
+    find_all_includes(contents).filter(|i| !i.escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_includes() {
+        let md = "{{#include ../code/main.rs:5:}}\n\\{{#include a.md}}\n\
+                  {{ #playground main.rs editable }}\n{{#rustdoc_include b.rs:main}}\n\
+                  {{#include refs.md}}\n";
+        let all: Vec<_> = find_all_includes(md).collect();
+        assert_eq!(all.len(), 5);
+        assert_eq!(all[0].path, "../code/main.rs");
+        assert_eq!(all[0].selector, Some("5:"));
+        assert_eq!(&md[all[0].path_span.clone()], "../code/main.rs");
+        assert_eq!(&md[all[0].span.clone()], "{{#include ../code/main.rs:5:}}");
+        assert!(all[1].escaped);
+        assert_eq!(&md[all[1].span.clone()], "\\{{#include a.md}}");
+        assert_eq!(all[2].kind, IncludeKind::Playground);
+        assert_eq!(all[2].attrs, vec!["editable"]);
+        assert_eq!(all[2].selector, None);
+        assert_eq!(all[3].kind, IncludeKind::RustdocInclude);
+        assert_eq!(all[3].selector, Some("main"));
+
+        let paths: Vec<_> = find_includes(md).map(|i| i.path).collect();
+        assert_eq!(paths, vec!["../code/main.rs", "main.rs", "b.rs", "refs.md"]);
+    }
+}
@@ -2,14 +2,23 @@
 //! extract reference definitions and links
 //! from said parser
 mod extract_links;
+mod front_matter;
+mod includes;
+mod options;
+
+use std::collections::HashMap;
 
 pub(crate) use extract_links::*;
+pub use front_matter::ChapterMetadata;
+pub use front_matter::MetadataValue;
+pub(crate) use front_matter::skip_front_matter;
+pub(crate) use front_matter::split_front_matter;
+pub(crate) use includes::*;
+pub use options::*;
 use pulldown_cmark::BrokenLink;
 use pulldown_cmark::BrokenLinkCallback;
 use pulldown_cmark::CowStr;
-use pulldown_cmark::Options;
 use pulldown_cmark::Parser;
-use tracing::warn;
 
 // Public Functions
 
@@ -18,86 +27,64 @@
 /// Return a parser with suitable options
 ///
 /// markdown_input: the unprocessed markdown text
-pub(crate) fn get_parser(markdown_input: &str) -> Parser<'_> {
-    Parser::new_ext(markdown_input, get_options())
-}
-
-// Private Functions
-
-/// Return suitable Parser options
-fn get_options() -> Options {
-    // Set up options and parser.
-    // Strikethroughs, etc... are not part of the CommonMark standard
-    // and we therefore must enable them explicitly.
-    let mut options = Options::empty();
-    options.insert(Options::ENABLE_STRIKETHROUGH);
-    options.insert(Options::ENABLE_FOOTNOTES);
-    options.insert(Options::ENABLE_TABLES);
-    options
-}
-
-// BROKEN REFERENCES -----------------------------------
-
-use std::sync::Arc;
-use std::sync::Mutex;
-
-/// Handler for broken references
-#[derive(Debug, Clone, Default)]
-pub(crate) struct Handler {
-    /// List of broken links found
-    pub broken_links: Arc<Mutex<Vec<(String, String, String)>>>,
+///
+/// options: the Markdown dialect, see [`ParserOptions`].
+pub(crate) fn get_parser<'input>(
+    markdown_input: &'input str,
+    options: &ParserOptions,
+) -> Parser<'input> {
+    Parser::new_ext(markdown_input, options.to_options())
 }
 
-impl Handler {
-    pub(crate) fn new() -> Self {
-        Self {
-            broken_links: Arc::new(Mutex::new(Vec::new())),
-        }
-    }
-}
+// REFERENCE DEFINITIONS
 
-/// Implement the trait required by `new_with_broken_link_callback`
-impl<'input> BrokenLinkCallback<'input> for Handler {
-    /// In case the parser encounters any potential links that have a broken
-    /// reference (e.g \[foo\] when there is no \[foo\]:  entry at the bottom)
-    /// the provided callback will be called with the reference name, and
-    /// the returned pair will be used as the link URL and title if it is
-    /// not None.
-    fn handle_broken_link(
-        &mut self,
-        link: BrokenLink<'input>,
-    ) -> Option<(CowStr<'input>, CowStr<'input>)> {
-        warn!(
-            "Issue with the markdown: reference: {}, type: {:?}",
-            link.reference, link.link_type,
-        );
-        if let Ok(mut links) = self.broken_links.lock() {
-            links.push((
-                link.reference.into_string(),
-                "".into(), // We don't have the full input here anymore without 'input
-                format!("{:?}", link.link_type),
-            ));
-        } else {
-            tracing::warn!("Failed to lock broken_links");
-        }
-        None
-    }
+/// Collect the reference definitions of a Markdown document:
+/// lowercased label -> URL.
+///
+/// markdown_input: the unprocessed markdown text
+///
+/// options: the Markdown dialect, see [`ParserOptions`].
+pub(crate) fn collect_reference_definitions(
+    markdown_input: &str,
+    options: &ParserOptions,
+) -> HashMap<String, String> {
+    let mut parser = get_parser(markdown_input, options);
+    // The reference definitions are available once the parser is consumed
+    parser.by_ref().for_each(drop);
+    parser
+        .reference_definitions()
+        .iter()
+        .map(|(label, def)| (label.to_lowercase(), def.dest.to_string()))
+        .collect()
 }
 
-/// Return a parser with suitable options and a broken link handler.
+/// Return a parser that resolves otherwise broken reference-style links
+/// using reference definitions found elsewhere (e.g. in a `refs.md` file
+/// included by mdBook).
 ///
-/// markdown_input: the Markdown contents to parse
-pub(crate) fn get_parser_with_broken_links_handler<'input>(
+/// markdown_input: the unprocessed markdown text
+///
+/// refdefs: lowercased label -> URL, see [`collect_reference_definitions`].
+///
+/// options: the Markdown dialect, see [`ParserOptions`].
+pub(crate) fn get_parser_with_refdefs<'input>(
     markdown_input: &'input str,
-    handler: Handler,
-) -> Parser<'input, Handler> {
-    Parser::<'input, Handler>::new_with_broken_link_callback(
+    refdefs: &'input HashMap<String, String>,
+    options: &ParserOptions,
+) -> Parser<'input, impl BrokenLinkCallback<'input>> {
+    Parser::new_with_broken_link_callback(
         markdown_input,
-        get_options(),
-        Some(handler),
+        options.to_options(),
+        Some(move |link: BrokenLink<'input>| {
+            refdefs
+                .get(&link.reference.to_lowercase())
+                .map(|url| (CowStr::from(url.as_str()), CowStr::from("")))
+        }),
     )
 }
 
+// BROKEN REFERENCES -----------------------------------
+
 // Example using `new_with_broken_link_callback` from https://github.com/raphlinus/pulldown-cmark/blob/1a5e54546b40d79eec8001d4e268b436571a78bb/pulldown-cmark/src/main.rs#L33
 // fn dry_run(text: &str, opts: Options, broken_links: &mut
 // Vec<BrokenLink<'static>>) {     let p =
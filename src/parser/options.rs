@@ -0,0 +1,292 @@
+//! Markdown dialect: the extensions to CommonMark enabled when parsing
+//!
+//! The options are passed to each function that parses Markdown, e.g.
+//! as the `parser` field of [`crate::LintOptions`], or to
+//! [`crate::Book::load_with_options`].
+
+use std::fmt;
+use std::str::FromStr;
+
+use pulldown_cmark::Options;
+
+/// Set of Markdown extensions that [`ParserOptions`] start from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MarkdownPreset {
+    /// CommonMark, plus strikethrough, footnotes and tables
+    #[default]
+    Default,
+    /// The extensions that mdBook enables: tables, footnotes,
+    /// strikethrough, task lists and heading attributes. Add
+    /// [`MarkdownExtension::SmartPunctuation`] if
+    /// `output.html.smart-punctuation` is set in `book.toml`.
+    MdBook,
+    /// CommonMark, without extensions
+    CommonMark,
+    /// All the extensions of [`MarkdownExtension`]
+    All,
+}
+
+impl MarkdownPreset {
+    /// All presets
+    pub const ALL: [MarkdownPreset; 4] = [Self::Default, Self::MdBook, Self::CommonMark, Self::All];
+
+    /// Returns the name of the preset e.g. `mdbook`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::MdBook => "mdbook",
+            Self::CommonMark => "commonmark",
+            Self::All => "all",
+        }
+    }
+
+    /// Returns the extensions of the preset
+    pub fn extensions(self) -> &'static [MarkdownExtension] {
+        use MarkdownExtension::*;
+        match self {
+            Self::Default => &[Strikethrough, Footnotes, Tables],
+            Self::MdBook => &[
+                Tables,
+                Footnotes,
+                Strikethrough,
+                TaskLists,
+                HeadingAttributes,
+            ],
+            Self::CommonMark => &[],
+            Self::All => &MarkdownExtension::ALL,
+        }
+    }
+}
+
+impl FromStr for MarkdownPreset {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match Self::ALL
+            .into_iter()
+            .find(|p| p.name().eq_ignore_ascii_case(s))
+        {
+            Some(p) => Ok(p),
+            None => Err(crate::Error::config(format!(
+                "Unknown Markdown preset: {s}. Use `default`, `mdbook`, `commonmark` or `all`."
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for MarkdownPreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Extension to CommonMark supported by the parser
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MarkdownExtension {
+    /// `| a | b |` tables
+    Tables,
+    /// `[^note]` footnotes
+    Footnotes,
+    /// `~~strikethrough~~`
+    Strikethrough,
+    /// `- [x]` task lists
+    TaskLists,
+    /// Curly quotes, dashes and ellipses
+    SmartPunctuation,
+    /// `# Heading {#id .class}` attributes
+    HeadingAttributes,
+    /// `$inline$` and `$$display$$` math
+    Math,
+    /// GitHub flavored Markdown blockquote alerts e.g. `> [!NOTE]`
+    Gfm,
+    /// Definition lists
+    DefinitionList,
+    /// `^superscript^`
+    Superscript,
+    /// `~subscript~`
+    Subscript,
+    /// `[[wikilinks]]`
+    WikiLinks,
+}
+
+impl MarkdownExtension {
+    /// All extensions
+    pub const ALL: [MarkdownExtension; 12] = [
+        Self::Tables,
+        Self::Footnotes,
+        Self::Strikethrough,
+        Self::TaskLists,
+        Self::SmartPunctuation,
+        Self::HeadingAttributes,
+        Self::Math,
+        Self::Gfm,
+        Self::DefinitionList,
+        Self::Superscript,
+        Self::Subscript,
+        Self::WikiLinks,
+    ];
+
+    /// Returns the name of the extension e.g. `heading-attributes`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Tables => "tables",
+            Self::Footnotes => "footnotes",
+            Self::Strikethrough => "strikethrough",
+            Self::TaskLists => "tasklists",
+            Self::SmartPunctuation => "smart-punctuation",
+            Self::HeadingAttributes => "heading-attributes",
+            Self::Math => "math",
+            Self::Gfm => "gfm",
+            Self::DefinitionList => "definition-list",
+            Self::Superscript => "superscript",
+            Self::Subscript => "subscript",
+            Self::WikiLinks => "wikilinks",
+        }
+    }
+
+    /// Returns the corresponding parser option
+    fn option(self) -> Options {
+        match self {
+            Self::Tables => Options::ENABLE_TABLES,
+            Self::Footnotes => Options::ENABLE_FOOTNOTES,
+            Self::Strikethrough => Options::ENABLE_STRIKETHROUGH,
+            Self::TaskLists => Options::ENABLE_TASKLISTS,
+            Self::SmartPunctuation => Options::ENABLE_SMART_PUNCTUATION,
+            Self::HeadingAttributes => Options::ENABLE_HEADING_ATTRIBUTES,
+            Self::Math => Options::ENABLE_MATH,
+            Self::Gfm => Options::ENABLE_GFM,
+            Self::DefinitionList => Options::ENABLE_DEFINITION_LIST,
+            Self::Superscript => Options::ENABLE_SUPERSCRIPT,
+            Self::Subscript => Options::ENABLE_SUBSCRIPT,
+            Self::WikiLinks => Options::ENABLE_WIKILINKS,
+        }
+    }
+}
+
+impl FromStr for MarkdownExtension {
+    type Err = crate::Error;
+
+    /// Parse the name of an extension (e.g. `heading-attributes`),
+    /// ignoring case; underscores may replace dashes.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().replace('_', "-");
+        match Self::ALL
+            .into_iter()
+            .find(|e| e.name().eq_ignore_ascii_case(&s))
+        {
+            Some(e) => Ok(e),
+            None => Err(crate::Error::config(format!(
+                "Unknown Markdown extension: {s}"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for MarkdownExtension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Options of the Markdown parser: a preset, additional extensions and
+/// front matter handling.
+///
+/// ```
+/// use mdbook_utils::MarkdownExtension;
+/// use mdbook_utils::ParserOptions;
+///
+/// let mut options = ParserOptions::mdbook();
+/// options.extensions.push(MarkdownExtension::SmartPunctuation);
+/// assert!(options.is_enabled(MarkdownExtension::TaskLists));
+///
+/// let lint_options = mdbook_utils::LintOptions {
+///     parser: options,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParserOptions {
+    /// Extensions to start from
+    pub preset: MarkdownPreset,
+    /// Extensions enabled in addition to those of the preset
+    pub extensions: Vec<MarkdownExtension>,
+    /// Recognize a YAML (`---`) or TOML (`+++`) front matter block at the
+    /// top of a file, and skip it: its contents are not parsed as
+    /// Markdown
+    pub front_matter: bool,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            preset: MarkdownPreset::Default,
+            extensions: Vec::new(),
+            front_matter: true,
+        }
+    }
+}
+
+impl ParserOptions {
+    /// Options that match mdBook's
+    pub fn mdbook() -> Self {
+        Self {
+            preset: MarkdownPreset::MdBook,
+            ..Self::default()
+        }
+    }
+
+    /// Returns `true` if an extension is enabled, by the preset or
+    /// explicitly
+    pub fn is_enabled(&self, extension: MarkdownExtension) -> bool {
+        self.preset.extensions().contains(&extension) || self.extensions.contains(&extension)
+    }
+
+    /// Returns the options of the parser
+    pub(crate) fn to_options(&self) -> Options {
+        let mut options = Options::empty();
+        for extension in self.preset.extensions().iter().chain(&self.extensions) {
+            options.insert(extension.option());
+        }
+        if self.front_matter {
+            options.insert(Options::ENABLE_YAML_STYLE_METADATA_BLOCKS);
+            options.insert(Options::ENABLE_PLUSES_DELIMITED_METADATA_BLOCKS);
+        }
+        options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parser_options() {
+        // The default options are those used before options could be set
+        let options = ParserOptions::default().to_options();
+        assert!(options.contains(
+            Options::ENABLE_STRIKETHROUGH | Options::ENABLE_FOOTNOTES | Options::ENABLE_TABLES
+        ));
+        assert!(options.contains(Options::ENABLE_YAML_STYLE_METADATA_BLOCKS));
+        assert!(!options.contains(Options::ENABLE_TASKLISTS));
+
+        let mut mdbook = ParserOptions::mdbook();
+        mdbook.extensions.push("smart_punctuation".parse().unwrap());
+        mdbook.front_matter = false;
+        let options = mdbook.to_options();
+        assert!(options.contains(
+            Options::ENABLE_TASKLISTS
+                | Options::ENABLE_HEADING_ATTRIBUTES
+                | Options::ENABLE_SMART_PUNCTUATION
+        ));
+        assert!(!options.contains(Options::ENABLE_MATH));
+        assert!(!options.contains(Options::ENABLE_YAML_STYLE_METADATA_BLOCKS));
+
+        assert_eq!(
+            "MdBook".parse::<MarkdownPreset>().unwrap(),
+            MarkdownPreset::MdBook
+        );
+        assert!("mdbook2".parse::<MarkdownPreset>().is_err());
+        assert!("latex".parse::<MarkdownExtension>().is_err());
+    }
+}
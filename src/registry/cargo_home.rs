@@ -0,0 +1,130 @@
+//! Read the manifests of the packages that `cargo` downloaded into its
+//! local registry cache (`$CARGO_HOME/registry/src`)
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use tracing::debug;
+
+use super::version_key;
+
+/// Returns the `cargo` home directory: `$CARGO_HOME` or `~/.cargo`.
+pub(crate) fn cargo_home_dir() -> Option<PathBuf> {
+    std::env::var_os("CARGO_HOME")
+        .filter(|h| !h.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::home_dir().map(|h| h.join(".cargo")))
+}
+
+/// Metadata of a package, read from its manifest in the local registry
+/// cache
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct CachedPackage {
+    /// URL of the source code repository
+    pub(crate) repository: Option<String>,
+    /// URL of the home page
+    pub(crate) homepage: Option<String>,
+    /// URL of the documentation
+    pub(crate) documentation: Option<String>,
+}
+
+/// Returns the metadata of a package, as found in the manifest of
+/// one of its versions in the local registry cache.
+///
+/// cargo_home_dir_path: the `cargo` home directory.
+///
+/// name: name of the package.
+///
+/// versions: versions to look for first (e.g. from `Cargo.lock`); any
+/// cached version is used otherwise.
+pub(crate) fn cached_package(
+    cargo_home_dir_path: &Path,
+    name: &str,
+    versions: &[String],
+) -> Option<CachedPackage> {
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    // One directory per registry e.g. `index.crates.io-1949cf8c6b5b557f`
+    for registry in std::fs::read_dir(cargo_home_dir_path.join("registry").join("src"))
+        .ok()?
+        .flatten()
+    {
+        let registry = registry.path();
+        for v in versions {
+            dirs.push(registry.join(format!("{name}-{v}")));
+        }
+        let prefix = format!("{name}-");
+        let Ok(entries) = std::fs::read_dir(&registry) else {
+            continue;
+        };
+        // e.g. `serde-1.0.200` but not `serde-json-1.0.0`
+        let mut others: Vec<(String, PathBuf)> = entries
+            .flatten()
+            .filter_map(|e| {
+                let version = e
+                    .file_name()
+                    .to_str()?
+                    .strip_prefix(&prefix)
+                    .filter(|v| v.starts_with(|c: char| c.is_ascii_digit()))?
+                    .to_string();
+                Some((version, e.path()))
+            })
+            .collect();
+        // Most recent version first, e.g. `1.0.10` before `1.0.9`
+        others.sort_by(|(a, _), (b, _)| version_key(b).cmp(&version_key(a)));
+        dirs.extend(others.into_iter().map(|(_, p)| p));
+    }
+    dirs.iter()
+        .find_map(|dir| read_cached_manifest(&dir.join("Cargo.toml")))
+}
+
+/// Returns the `package` metadata of a (published, hence normalized)
+/// manifest.
+fn read_cached_manifest(cargo_toml_path: &Path) -> Option<CachedPackage> {
+    let contents = std::fs::read_to_string(cargo_toml_path).ok()?;
+    let manifest: toml::Table = toml::from_str(&contents)
+        .inspect_err(|e| debug!("[cached_package] {}: {e}", cargo_toml_path.display()))
+        .ok()?;
+    let package = manifest.get("package")?;
+    let get = |key: &str| package.get(key).and_then(|v| v.as_str()).map(String::from);
+    Some(CachedPackage {
+        repository: get("repository"),
+        homepage: get("homepage"),
+        documentation: get("documentation"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cached_package() {
+        let home = tempfile::tempdir().unwrap();
+        let registry = home.path().join("registry/src/index.crates.io-0");
+        for (dir, repo) in [
+            ("serde-1.0.1", "https://github.com/serde-rs/serde/v1"),
+            ("serde-1.0.2", "https://github.com/serde-rs/serde/v2"),
+            ("serde-1.0.10", "https://github.com/serde-rs/serde/v10"),
+            ("serde-json-1.0.0", "https://github.com/serde-rs/json"),
+        ] {
+            std::fs::create_dir_all(registry.join(dir)).unwrap();
+            std::fs::write(
+                registry.join(dir).join("Cargo.toml"),
+                format!("[package]\nname = \"x\"\nrepository = \"{repo}\"\n"),
+            )
+            .unwrap();
+        }
+        let repository = |versions: &[String]| {
+            cached_package(home.path(), "serde", versions).and_then(|p| p.repository)
+        };
+        assert_eq!(
+            repository(&["1.0.1".into()]).as_deref(),
+            Some("https://github.com/serde-rs/serde/v1")
+        );
+        assert_eq!(
+            repository(&[]).as_deref(),
+            Some("https://github.com/serde-rs/serde/v10")
+        );
+        assert_eq!(cached_package(home.path(), "anyhow", &[]), None);
+    }
+}
@@ -0,0 +1,107 @@
+//! Read the packages locked in a `Cargo.lock` file
+
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::Error;
+use crate::Result;
+
+/// A `[[package]]` entry of `Cargo.lock`
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub(crate) struct LockedPackage {
+    /// Name of the package
+    pub(crate) name: String,
+    /// Exact version
+    pub(crate) version: String,
+    /// e.g. `registry+https://github.com/rust-lang/crates.io-index`.
+    /// `None` for workspace members and path dependencies.
+    pub(crate) source: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CargoLock {
+    #[serde(default)]
+    package: Vec<LockedPackage>,
+}
+
+/// Parse the contents of a `Cargo.lock` file.
+pub(crate) fn parse_cargo_lock(contents: &str) -> Result<Vec<LockedPackage>> {
+    let lock: CargoLock = toml::from_str(contents).map_err(|e| Error::parse("Cargo.lock", e))?;
+    Ok(lock.package)
+}
+
+/// Read and parse a `Cargo.lock` file.
+///
+/// cargo_lock_path: path to the `Cargo.lock` file.
+pub(crate) fn read_cargo_lock<P: AsRef<Path>>(cargo_lock_path: P) -> Result<Vec<LockedPackage>> {
+    let cargo_lock_path = cargo_lock_path.as_ref();
+    let contents =
+        std::fs::read_to_string(cargo_lock_path).map_err(|e| Error::io(cargo_lock_path, e))?;
+    parse_cargo_lock(&contents).map_err(|e| e.in_file(cargo_lock_path))
+}
+
+/// Returns the most recent of a list of versions e.g. `1.0.10` rather than
+/// `1.0.9`. Pre-releases are older than the corresponding release.
+pub(crate) fn latest_version(versions: &[String]) -> Option<&str> {
+    versions
+        .iter()
+        .max_by_key(|v| version_key(v))
+        .map(String::as_str)
+}
+
+/// Sort key of a version: numeric `major.minor.patch`, then `true` for
+/// releases and `false` for pre-releases, then the pre-release itself.
+pub(crate) fn version_key(version: &str) -> (Vec<u64>, bool, &str) {
+    // Build metadata is ignored
+    let version = version.split('+').next().unwrap_or_default();
+    let (release, pre) = version.split_once('-').unwrap_or((version, ""));
+    let numbers = release
+        .split('.')
+        .map(|n| n.parse().unwrap_or_default())
+        .collect();
+    (numbers, pre.is_empty(), pre)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cargo_lock() {
+        let lock = r#"
+version = 4
+
+[[package]]
+name = "anyhow"
+version = "1.0.86"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "b3d1d046238990b9cf5bcde22a3fb3584ee5cf65fb2765f454ed428c7a0063da"
+
+[[package]]
+name = "my_book"
+version = "0.1.0"
+dependencies = ["anyhow"]
+"#;
+        let packages = parse_cargo_lock(lock).unwrap();
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[0].name, "anyhow");
+        assert!(packages[0].source.is_some());
+        assert_eq!(packages[1].source, None);
+    }
+
+    #[test]
+    fn test_latest_version() {
+        let versions: Vec<String> = ["1.0.9", "1.0.10", "1.0.11-rc.1", "0.9.0"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        assert_eq!(latest_version(&versions), Some("1.0.11-rc.1"));
+        assert_eq!(latest_version(&versions[..2]), Some("1.0.10"));
+        assert_eq!(
+            latest_version(&["2.0.0-alpha".into(), "1.9.0".into(), "2.0.0".into()]),
+            Some("2.0.0")
+        );
+        assert_eq!(latest_version(&[]), None);
+    }
+}
@@ -0,0 +1,92 @@
+//! Retrieve package metadata from `cargo metadata`
+
+use std::path::Path;
+use std::process::Command;
+
+use serde::Deserialize;
+use tracing::debug;
+
+use crate::Error;
+use crate::Result;
+
+/// Metadata of a package, as returned by `cargo metadata`
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+pub(crate) struct PackageMetadata {
+    pub(crate) name: String,
+    pub(crate) version: String,
+    #[serde(default)]
+    pub(crate) description: Option<String>,
+    #[serde(default)]
+    pub(crate) repository: Option<String>,
+    #[serde(default)]
+    pub(crate) documentation: Option<String>,
+    #[serde(default)]
+    pub(crate) homepage: Option<String>,
+    #[serde(default)]
+    pub(crate) categories: Vec<String>,
+    /// `None` for workspace members and path dependencies
+    #[serde(default)]
+    pub(crate) source: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Metadata {
+    packages: Vec<PackageMetadata>,
+}
+
+/// Parse the JSON output of `cargo metadata --format-version 1`.
+pub(crate) fn parse_cargo_metadata(json: &str) -> Result<Vec<PackageMetadata>> {
+    let metadata: Metadata =
+        serde_json::from_str(json).map_err(|e| Error::parse("cargo metadata", e))?;
+    Ok(metadata.packages)
+}
+
+/// Run `cargo metadata --offline` in a directory containing `Cargo.toml`
+/// and return the metadata of all packages in the dependency graph.
+///
+/// cargo_toml_dir_path: path to the directory containing `Cargo.toml`.
+pub(crate) fn run_cargo_metadata<P: AsRef<Path>>(
+    cargo_toml_dir_path: P,
+) -> Result<Vec<PackageMetadata>> {
+    let cargo_toml_dir_path = cargo_toml_dir_path.as_ref();
+    debug!(
+        "Running `cargo metadata` in {}",
+        cargo_toml_dir_path.display()
+    );
+    let output = Command::new("cargo")
+        .current_dir(cargo_toml_dir_path)
+        .args(["metadata", "--format-version", "1", "--offline"])
+        .output()
+        .map_err(|e| {
+            Error::command(
+                "cargo metadata",
+                format!("could not be run ({e}). Is `cargo` installed?"),
+            )
+        })?;
+    if !output.status.success() {
+        return Err(Error::command(
+            "cargo metadata",
+            format!(
+                "in {}: {}",
+                cargo_toml_dir_path.display(),
+                String::from_utf8_lossy(&output.stderr)
+            ),
+        ));
+    }
+    let json = String::from_utf8(output.stdout).map_err(|e| Error::parse("cargo metadata", e))?;
+    parse_cargo_metadata(&json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cargo_metadata() {
+        let json = r#"{"packages":[{"name":"anyhow","version":"1.0.86","description":"Flexible error type","repository":"https://github.com/dtolnay/anyhow","categories":["rust-patterns"],"source":"registry+https://github.com/rust-lang/crates.io-index","license":"MIT"}],"version":1}"#;
+        let packages = parse_cargo_metadata(json).unwrap();
+        assert_eq!(packages.len(), 1);
+        assert_eq!(packages[0].categories, vec!["rust-patterns"]);
+        assert_eq!(packages[0].homepage, None);
+    }
+}
@@ -0,0 +1,103 @@
+//! crates.io categories and crate -> category mappings
+//!
+//! A snapshot of the crates.io categories is bundled with `mdbook-utils`.
+//! A local mapping file, in the same TOML format, can add categories and
+//! assign categories to crates:
+//!
+//! ```toml
+//! [crates]
+//! tokio = ["asynchronous", "network-programming"]
+//!
+//! [categories."my-category"]
+//! name = "My category"
+//! description = "Crates I like."
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::Error;
+use crate::Result;
+
+/// Bundled snapshot of the crates.io categories
+const BUNDLED_CATEGORIES: &str = include_str!("categories.toml");
+
+/// A crates.io category
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+pub(crate) struct Category {
+    /// Display name e.g. `Web programming::WebSocket`
+    pub(crate) name: String,
+    /// One-line description
+    #[serde(default)]
+    pub(crate) description: String,
+}
+
+/// Categories (indexed by slug) and crate -> category slugs
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+pub(crate) struct CategoryMapping {
+    /// Category slugs of each crate
+    #[serde(default)]
+    pub(crate) crates: BTreeMap<String, Vec<String>>,
+    /// Categories, indexed by slug e.g. `web-programming::websocket`
+    #[serde(default)]
+    pub(crate) categories: BTreeMap<String, Category>,
+}
+
+/// Parse a category mapping written in TOML.
+pub(crate) fn parse_category_mapping(contents: &str) -> Result<CategoryMapping> {
+    toml::from_str(contents).map_err(|e| Error::parse("categories.toml", e))
+}
+
+/// Returns the bundled snapshot of the crates.io categories.
+pub(crate) fn bundled_categories() -> Result<CategoryMapping> {
+    parse_category_mapping(BUNDLED_CATEGORIES)
+}
+
+/// Read and parse a category mapping file.
+///
+/// mapping_path: path to the TOML file.
+pub(crate) fn read_category_mapping<P: AsRef<Path>>(mapping_path: P) -> Result<CategoryMapping> {
+    let mapping_path = mapping_path.as_ref();
+    let contents = std::fs::read_to_string(mapping_path).map_err(|e| Error::io(mapping_path, e))?;
+    parse_category_mapping(&contents).map_err(|e| e.in_file(mapping_path))
+}
+
+/// Returns the slug of the parent category, if any
+/// e.g. `web-programming` for `web-programming::websocket`.
+pub(crate) fn parent_category(slug: &str) -> Option<&str> {
+    slug.rsplit_once("::").map(|(parent, _)| parent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_categories() {
+        let mapping = bundled_categories().unwrap();
+        assert!(mapping.crates.is_empty());
+        assert_eq!(
+            mapping.categories["web-programming::websocket"].name,
+            "Web programming::WebSocket"
+        );
+        // Every subcategory has a parent
+        for slug in mapping.categories.keys() {
+            if let Some(parent) = parent_category(slug) {
+                assert!(mapping.categories.contains_key(parent), "{slug}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_category_mapping() {
+        let mapping = parse_category_mapping(
+            "[crates]\ntokio = [\"asynchronous\"]\n\n[categories.\"my-cat\"]\nname = \"Mine\"\n",
+        )
+        .unwrap();
+        assert_eq!(mapping.crates["tokio"], vec!["asynchronous"]);
+        assert_eq!(mapping.categories["my-cat"].name, "Mine");
+        assert_eq!(mapping.categories["my-cat"].description, "");
+    }
+}
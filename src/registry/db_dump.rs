@@ -0,0 +1,203 @@
+//! Read crate and category metadata from the crates.io database dump
+//!
+//! The dump is available at <https://static.crates.io/db-dump.tar.gz>
+//! (see <https://crates.io/data-access>). Once extracted, its `data`
+//! directory contains (among others) `crates.csv`, `categories.csv`
+//! and `crates_categories.csv`.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+
+use super::normalize_crate_name;
+use crate::Error;
+use crate::Result;
+
+/// A row of `crates.csv` (only the columns of interest)
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub(crate) struct CrateRow {
+    pub(crate) id: u64,
+    pub(crate) name: String,
+    #[serde(default)]
+    pub(crate) description: Option<String>,
+    #[serde(default)]
+    pub(crate) documentation: Option<String>,
+    #[serde(default)]
+    pub(crate) homepage: Option<String>,
+    #[serde(default)]
+    pub(crate) repository: Option<String>,
+}
+
+/// A row of `categories.csv` (only the columns of interest)
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub(crate) struct CategoryRow {
+    pub(crate) id: u64,
+    /// Display name e.g. `Web programming::WebSocket`
+    pub(crate) category: String,
+    /// e.g. `web-programming::websocket`
+    pub(crate) slug: String,
+    #[serde(default)]
+    pub(crate) description: String,
+}
+
+/// A row of `crates_categories.csv`
+#[derive(Debug, Deserialize)]
+struct CrateCategoryRow {
+    category_id: u64,
+    crate_id: u64,
+}
+
+/// The subset of the crates.io database dump used by `mdbook-utils`
+#[derive(Debug, Default)]
+pub(crate) struct DbDump {
+    /// Crates, indexed by normalized name
+    pub(crate) crates: BTreeMap<String, CrateRow>,
+    /// All categories, indexed by slug
+    pub(crate) categories: BTreeMap<String, CategoryRow>,
+    /// Category slugs of each crate, indexed by normalized crate name
+    crate_categories: HashMap<String, Vec<String>>,
+}
+
+impl DbDump {
+    /// Returns the category slugs of a crate (empty if unknown).
+    pub(crate) fn categories_of(&self, crate_name: &str) -> Vec<String> {
+        self.crate_categories
+            .get(&normalize_crate_name(crate_name))
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+/// Read the crates.io database dump.
+///
+/// db_dump_dir_path: path to the extracted dump, or to its `data`
+/// subdirectory.
+///
+/// crate_names: if set, only these crates are loaded (the full dump
+/// lists more than 150,000 crates).
+pub(crate) fn read_db_dump<P: AsRef<Path>>(
+    db_dump_dir_path: P,
+    crate_names: Option<&BTreeSet<String>>,
+) -> Result<DbDump> {
+    let data_dir = data_dir(db_dump_dir_path.as_ref());
+    let wanted: Option<BTreeSet<String>> =
+        crate_names.map(|names| names.iter().map(|n| normalize_crate_name(n)).collect());
+
+    let mut crates = BTreeMap::new();
+    let mut names_by_id = HashMap::new();
+    for row in read_csv::<CrateRow>(&data_dir.join("crates.csv"))? {
+        let key = normalize_crate_name(&row.name);
+        if wanted.as_ref().is_some_and(|w| !w.contains(&key)) {
+            continue;
+        }
+        names_by_id.insert(row.id, key.clone());
+        crates.insert(key, row);
+    }
+
+    let categories: BTreeMap<String, CategoryRow> =
+        read_csv::<CategoryRow>(&data_dir.join("categories.csv"))?
+            .into_iter()
+            .map(|c| (c.slug.clone(), c))
+            .collect();
+    let slugs_by_id: HashMap<u64, &str> = categories
+        .values()
+        .map(|c| (c.id, c.slug.as_str()))
+        .collect();
+
+    let mut crate_categories: HashMap<String, Vec<String>> = HashMap::new();
+    for row in read_csv::<CrateCategoryRow>(&data_dir.join("crates_categories.csv"))? {
+        if let (Some(name), Some(slug)) = (
+            names_by_id.get(&row.crate_id),
+            slugs_by_id.get(&row.category_id),
+        ) {
+            crate_categories
+                .entry(name.clone())
+                .or_default()
+                .push(slug.to_string());
+        }
+    }
+    for slugs in crate_categories.values_mut() {
+        slugs.sort();
+    }
+
+    Ok(DbDump {
+        crates,
+        categories,
+        crate_categories,
+    })
+}
+
+/// Locate the directory that contains the CSV files.
+fn data_dir(db_dump_dir_path: &Path) -> PathBuf {
+    let data = db_dump_dir_path.join("data");
+    if data.join("crates.csv").is_file() {
+        data
+    } else {
+        db_dump_dir_path.to_path_buf()
+    }
+}
+
+/// Deserialize all rows of a CSV file with headers.
+fn read_csv<T: DeserializeOwned>(csv_path: &Path) -> Result<Vec<T>> {
+    let file = std::fs::File::open(csv_path).map_err(|e| Error::io(csv_path, e))?;
+    csv::Reader::from_reader(file)
+        .deserialize()
+        .collect::<Result<Vec<T>, _>>()
+        .map_err(|e| Error::parse(csv_path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write a tiny database dump to a temporary directory.
+    fn write_test_db_dump(dir: &Path) {
+        let data = dir.join("data");
+        std::fs::create_dir_all(&data).unwrap();
+        std::fs::write(
+            data.join("crates.csv"),
+            "created_at,description,documentation,downloads,homepage,id,name,readme,repository\n\
+             2020,\"Async runtime\",,10,,1,tokio,,https://github.com/tokio-rs/tokio\n\
+             2020,WebSocket library,,5,,2,tungstenite,,\n\
+             2020,Unused,,5,,3,other,,\n",
+        )
+        .unwrap();
+        std::fs::write(
+            data.join("categories.csv"),
+            "category,crates_cnt,created_at,description,id,path,slug\n\
+             Asynchronous,1,2020,Async stuff,10,root.asynchronous,asynchronous\n\
+             Web programming,1,2020,Web stuff,11,root.web_programming,web-programming\n\
+             Web programming::WebSocket,1,2020,WebSocket stuff,12,root.web_programming.websocket,web-programming::websocket\n",
+        )
+        .unwrap();
+        std::fs::write(
+            data.join("crates_categories.csv"),
+            "category_id,crate_id\n10,1\n12,2\n11,2\n10,3\n",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_read_db_dump() {
+        let dir = tempfile::tempdir().unwrap();
+        write_test_db_dump(dir.path());
+        let names: BTreeSet<String> = ["tokio".to_string(), "tungstenite".to_string()].into();
+        let dump = read_db_dump(dir.path(), Some(&names)).unwrap();
+        assert_eq!(dump.crates.len(), 2);
+        assert_eq!(
+            dump.crates["tokio"].repository.as_deref(),
+            Some("https://github.com/tokio-rs/tokio")
+        );
+        assert_eq!(dump.categories.len(), 3);
+        assert_eq!(
+            dump.categories_of("tungstenite"),
+            vec!["web-programming", "web-programming::websocket"]
+        );
+        assert!(dump.categories_of("other").is_empty());
+    }
+}
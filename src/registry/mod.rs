@@ -0,0 +1,194 @@
+//! Offline sources of crate metadata: `Cargo.lock`, `cargo metadata`,
+//! the local registry cache and the crates.io database dump.
+mod cargo_home;
+mod cargo_lock;
+mod cargo_metadata;
+mod categories;
+mod db_dump;
+
+use std::collections::BTreeMap;
+
+pub(crate) use cargo_home::*;
+pub(crate) use cargo_lock::*;
+pub(crate) use cargo_metadata::*;
+pub(crate) use categories::*;
+pub(crate) use db_dump::*;
+
+/// Metadata about a crate, merged from all available sources
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct CrateInfo {
+    /// Name of the crate, as published on crates.io
+    pub(crate) name: String,
+    /// Versions found in `Cargo.lock` (or `cargo metadata`)
+    pub(crate) versions: Vec<String>,
+    /// One-line description
+    pub(crate) description: Option<String>,
+    /// URL of the source code repository
+    pub(crate) repository: Option<String>,
+    /// URL of the documentation
+    pub(crate) documentation: Option<String>,
+    /// URL of the home page
+    pub(crate) homepage: Option<String>,
+    /// crates.io category slugs e.g. `web-programming::websocket`
+    pub(crate) categories: Vec<String>,
+}
+
+/// Crate metadata indexed by normalized crate name
+#[derive(Debug, Default)]
+pub(crate) struct CrateRegistry {
+    crates: BTreeMap<String, CrateInfo>,
+    /// Categories, indexed by slug e.g. `web-programming::websocket`
+    categories: BTreeMap<String, Category>,
+}
+
+impl CrateRegistry {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the metadata of a crate, if known.
+    /// `-` and `_` are interchangeable, as on crates.io.
+    pub(crate) fn get(&self, name: &str) -> Option<&CrateInfo> {
+        self.crates.get(&normalize_crate_name(name))
+    }
+
+    /// Returns a category, if known.
+    pub(crate) fn category(&self, slug: &str) -> Option<&Category> {
+        self.categories.get(slug)
+    }
+
+    /// Returns the display name of a category, if known.
+    pub(crate) fn category_name(&self, slug: &str) -> Option<&str> {
+        self.category(slug).map(|c| c.name.as_str())
+    }
+
+    /// Returns the metadata of a crate, creating an empty entry if needed.
+    fn entry(&mut self, name: &str) -> &mut CrateInfo {
+        self.crates
+            .entry(normalize_crate_name(name))
+            .or_insert_with(|| CrateInfo {
+                name: name.to_string(),
+                ..CrateInfo::default()
+            })
+    }
+
+    /// Add the versions of the packages locked in `Cargo.lock`.
+    pub(crate) fn add_locked_packages(&mut self, packages: &[LockedPackage]) {
+        for p in packages {
+            let info = self.entry(&p.name);
+            if !info.versions.contains(&p.version) {
+                info.versions.push(p.version.clone());
+            }
+        }
+    }
+
+    /// Add the metadata of the packages returned by `cargo metadata`.
+    /// Versions are only added if `Cargo.lock` did not provide any.
+    pub(crate) fn add_package_metadata(&mut self, packages: &[PackageMetadata]) {
+        for p in packages {
+            let info = self.entry(&p.name);
+            if info.versions.is_empty() {
+                info.versions.push(p.version.clone());
+            }
+            merge_field(&mut info.description, &p.description);
+            merge_field(&mut info.repository, &p.repository);
+            merge_field(&mut info.documentation, &p.documentation);
+            merge_field(&mut info.homepage, &p.homepage);
+            if info.categories.is_empty() {
+                info.categories.clone_from(&p.categories);
+            }
+        }
+    }
+
+    /// Add categories and crate categories from a category mapping (e.g.
+    /// the bundled list of crates.io categories or a local file).
+    /// The mapping takes precedence over the sources added before.
+    pub(crate) fn add_category_mapping(&mut self, mapping: &CategoryMapping) {
+        self.categories.extend(
+            mapping
+                .categories
+                .iter()
+                .map(|(slug, c)| (slug.clone(), c.clone())),
+        );
+        for (name, categories) in mapping.crates.iter() {
+            self.entry(name).categories.clone_from(categories);
+        }
+    }
+
+    /// Add the metadata found in the crates.io database dump.
+    /// Crate metadata already provided by other sources takes precedence;
+    /// category names and descriptions from the dump replace the known
+    /// ones, which may be outdated.
+    pub(crate) fn add_db_dump(&mut self, dump: &DbDump) {
+        for c in dump.categories.values() {
+            self.categories.insert(
+                c.slug.clone(),
+                Category {
+                    name: c.category.clone(),
+                    description: c.description.clone(),
+                },
+            );
+        }
+        for row in dump.crates.values() {
+            let categories = dump.categories_of(&row.name);
+            let info = self.entry(&row.name);
+            merge_field(&mut info.description, &row.description);
+            merge_field(&mut info.repository, &row.repository);
+            merge_field(&mut info.documentation, &row.documentation);
+            merge_field(&mut info.homepage, &row.homepage);
+            if info.categories.is_empty() {
+                info.categories = categories;
+            }
+        }
+    }
+}
+
+/// Normalize a crate name for lookups: crates.io treats `-` and `_` as
+/// equivalent and names as case-insensitive.
+pub(crate) fn normalize_crate_name(name: &str) -> String {
+    name.to_lowercase().replace('-', "_")
+}
+
+/// Set a field if it is not set yet and the new value is not blank.
+fn merge_field(field: &mut Option<String>, value: &Option<String>) {
+    if field.is_none() {
+        *field = value
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(String::from);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_merge() {
+        let mut registry = CrateRegistry::new();
+        registry.add_locked_packages(&[
+            LockedPackage {
+                name: "serde_json".into(),
+                version: "1.0.0".into(),
+                source: None,
+            },
+            LockedPackage {
+                name: "serde_json".into(),
+                version: "1.0.1".into(),
+                source: None,
+            },
+        ]);
+        registry.add_package_metadata(&[PackageMetadata {
+            name: "serde_json".into(),
+            version: "2.0.0".into(),
+            description: Some(" JSON ".into()),
+            repository: Some(String::new()),
+            ..PackageMetadata::default()
+        }]);
+        let info = registry.get("serde-json").unwrap();
+        assert_eq!(info.versions, vec!["1.0.0", "1.0.1"]);
+        assert_eq!(info.description.as_deref(), Some("JSON"));
+        assert_eq!(info.repository, None);
+    }
+}
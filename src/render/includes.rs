@@ -0,0 +1,259 @@
+//! Expand mdBook's `{{#include}}`, `{{#rustdoc_include}}` and
+//! `{{#playground}}` statements, as `mdbook build` does.
+//!
+//! See the [mdBook documentation](https://rust-lang.github.io/mdBook/format/mdbook.html#including-files).
+use std::ops::Range;
+use std::path::Path;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use tracing::warn;
+
+use crate::fs;
+use crate::parser;
+use crate::parser::IncludeKind;
+
+/// Maximum depth of nested includes, as in mdBook
+const MAX_DEPTH: usize = 10;
+
+/// Regex to find the anchor comments e.g. `// ANCHOR: name`
+static ANCHOR_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"ANCHOR(?<end>_END)?:\s*(?<name>[\w-]+)").unwrap());
+
+/// Lines to include from a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Selection {
+    All,
+    /// 0-based range of lines
+    Lines(Range<usize>),
+    Anchor(String),
+}
+
+impl Selection {
+    /// Parse the suffix of an included path e.g. `2`, `2:`, `:10`, `2:10`
+    /// or `anchor_name`, see [`parser::IncludeStatement::selector`]
+    fn parse(s: Option<&str>) -> Self {
+        let Some(s) = s.filter(|s| !s.is_empty()) else {
+            return Self::All;
+        };
+        let line = |n: &str| n.parse::<usize>().ok();
+        match s.split_once(':') {
+            None => match line(s) {
+                Some(n) => Self::Lines(n.saturating_sub(1)..n),
+                None => Self::Anchor(s.to_string()),
+            },
+            Some((start, end)) => {
+                let start = if start.is_empty() {
+                    Some(1)
+                } else {
+                    line(start)
+                };
+                let end = if end.is_empty() {
+                    Some(usize::MAX)
+                } else {
+                    line(end)
+                };
+                match (start, end) {
+                    (Some(start), Some(end)) => Self::Lines(start.saturating_sub(1)..end),
+                    _ => Self::All,
+                }
+            }
+        }
+    }
+
+    /// Returns the lines of a file and whether they are selected. Anchor
+    /// comments are left out when an anchor is selected.
+    fn lines<'a>(&self, contents: &'a str) -> Vec<(&'a str, bool)> {
+        match self {
+            Self::All => contents.lines().map(|l| (l, true)).collect(),
+            Self::Lines(range) => contents
+                .lines()
+                .enumerate()
+                .map(|(i, l)| (l, range.contains(&i)))
+                .collect(),
+            Self::Anchor(name) => {
+                let mut inside = false;
+                contents
+                    .lines()
+                    .filter_map(|l| match ANCHOR_REGEX.captures(l) {
+                        Some(caps) => {
+                            if &caps["name"] == name {
+                                inside = caps.name("end").is_none();
+                            }
+                            None
+                        }
+                        None => Some((l, inside)),
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Returns the lines of a file selected by an `{{#include}}` statement
+fn take_lines(contents: &str, selection: &Selection) -> String {
+    if *selection == Selection::All {
+        return contents.to_string();
+    }
+    selection
+        .lines(contents)
+        .into_iter()
+        .filter_map(|(l, selected)| selected.then_some(l))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Returns the lines of a file for a `{{#rustdoc_include}}` statement:
+/// the lines that are not selected are hidden with `# `, so that the
+/// code still compiles.
+fn take_rustdoc_lines(contents: &str, selection: &Selection) -> String {
+    if *selection == Selection::All {
+        return contents.to_string();
+    }
+    selection
+        .lines(contents)
+        .into_iter()
+        .map(|(l, selected)| {
+            if selected {
+                l.to_string()
+            } else {
+                format!("# {l}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Replace the include statements of a Markdown document by the
+/// (selected lines of the) included files, recursively.
+///
+/// Statements whose file cannot be read, or is outside of the root
+/// directory, are left as written; escaped statements (`\{{#include
+/// ...}}`) lose their backslash.
+///
+/// contents: Markdown to expand.
+///
+/// dir_path: directory of the Markdown file, which the paths are
+/// relative to.
+///
+/// root_dir_path: directory that the included files must be in e.g. the
+/// book's root directory.
+pub(crate) fn expand_includes(contents: &str, dir_path: &Path, root_dir_path: &Path) -> String {
+    expand(contents, dir_path, root_dir_path, 0)
+}
+
+fn expand(contents: &str, dir_path: &Path, root_dir_path: &Path, depth: usize) -> String {
+    let mut expanded = String::with_capacity(contents.len());
+    let mut last = 0;
+    for include in parser::find_all_includes(contents) {
+        expanded.push_str(&contents[last..include.span.start]);
+        last = include.span.end;
+        let statement = &contents[include.span.clone()];
+        if include.escaped {
+            expanded.push_str(&statement[1..]);
+            continue;
+        }
+        if depth >= MAX_DEPTH {
+            warn!("Too many nested includes: {statement} is left as written");
+            expanded.push_str(statement);
+            continue;
+        }
+        let path = dir_path.join(include.path);
+        let included = fs::is_path_within(root_dir_path, &path)
+            .and_then(|p| std::fs::read_to_string(&p).map_err(|e| crate::Error::io(&p, e)));
+        let included = match included {
+            Ok(s) => s,
+            Err(e) => {
+                warn!("{statement} is left as written: {e}");
+                expanded.push_str(statement);
+                continue;
+            }
+        };
+        let included_dir_path = path.parent().unwrap_or(dir_path);
+        let selection = Selection::parse(include.selector);
+        let replacement = match include.kind {
+            IncludeKind::RustdocInclude => expand(
+                &take_rustdoc_lines(&included, &selection),
+                included_dir_path,
+                root_dir_path,
+                depth + 1,
+            ),
+            IncludeKind::Playground => {
+                let info: Vec<&str> = std::iter::once("rust").chain(include.attrs).collect();
+                let code = expand(&included, included_dir_path, root_dir_path, depth + 1);
+                let newline = if code.ends_with('\n') { "" } else { "\n" };
+                format!("```{}\n{code}{newline}```\n", info.join(","))
+            }
+            IncludeKind::Include => expand(
+                &take_lines(&included, &selection),
+                included_dir_path,
+                root_dir_path,
+                depth + 1,
+            ),
+        };
+        expanded.push_str(&replacement);
+    }
+    expanded.push_str(&contents[last..]);
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_selection_parse() {
+        assert_eq!(Selection::parse(None), Selection::All);
+        assert_eq!(Selection::parse(Some("2")), Selection::Lines(1..2));
+        assert_eq!(
+            Selection::parse(Some("2:")),
+            Selection::Lines(1..usize::MAX)
+        );
+        assert_eq!(Selection::parse(Some(":10")), Selection::Lines(0..10));
+        assert_eq!(Selection::parse(Some("2:10")), Selection::Lines(1..10));
+        assert_eq!(
+            Selection::parse(Some("main")),
+            Selection::Anchor("main".to_string())
+        );
+    }
+
+    #[test]
+    fn test_take_lines() {
+        let code = "use std::io;\n// ANCHOR: main\nfn main() {}\n// ANCHOR_END: main\n";
+        let main = Selection::Anchor("main".to_string());
+        assert_eq!(take_lines(code, &main), "fn main() {}");
+        assert_eq!(take_lines(code, &Selection::Lines(0..1)), "use std::io;");
+        assert_eq!(
+            take_rustdoc_lines(code, &main),
+            "# use std::io;\nfn main() {}"
+        );
+    }
+
+    #[test]
+    fn test_expand_includes() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let src_dir = dir.path().join("src");
+        let code_dir = dir.path().join("code");
+        std::fs::create_dir_all(src_dir.join("sub"))?;
+        std::fs::create_dir_all(&code_dir)?;
+        std::fs::write(code_dir.join("main.rs"), "fn main() {}\n")?;
+        std::fs::write(
+            src_dir.join("sub/part.md"),
+            "Part\n{{#include ../../code/main.rs:1}}\n",
+        )?;
+
+        let md = "{{#include sub/part.md}}\n\\{{#include sub/part.md}}\n{{#playground ../code/main.rs editable}}\n{{#include missing.md}}\n";
+        assert_eq!(
+            expand_includes(md, &src_dir, dir.path()),
+            "Part\nfn main() {}\n\n{{#include sub/part.md}}\n```rust,editable\nfn main() {}\n```\n\n{{#include missing.md}}\n"
+        );
+        // Outside of the root directory
+        assert_eq!(
+            expand_includes("{{#include ../code/main.rs}}", &src_dir, &src_dir),
+            "{{#include ../code/main.rs}}"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,271 @@
+//! Render a chapter to HTML without building the whole book: the
+//! include statements are expanded, the references are resolved with
+//! the reference definitions of the whole book, and the links to other
+//! chapters point to their HTML files, as in the book built by mdBook.
+mod includes;
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use once_cell::sync::Lazy;
+use pulldown_cmark::CowStr;
+use pulldown_cmark::Event;
+use pulldown_cmark::Tag;
+use pulldown_cmark::TagEnd;
+use regex::Regex;
+
+use crate::Error;
+use crate::Result;
+use crate::book::Book;
+use crate::fs;
+use crate::parser;
+use crate::parser::ChapterMetadata;
+use crate::parser::ParserOptions;
+use crate::sitemap;
+use crate::write_from_parser;
+
+/// Minimal standalone HTML page, for [`RenderOptions::template`]
+pub const DEFAULT_HTML_TEMPLATE: &str = "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">
+<title>{{title}}</title>
+</head>
+<body>
+<main>
+{{content}}</main>
+</body>
+</html>
+";
+
+/// Regex to find the placeholders of a template
+static PLACEHOLDER_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{\{\s*(?<name>title|content)\s*\}\}").unwrap());
+
+/// Options of [`crate::render_chapter`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// Resolve the references that the chapter does not define with the
+    /// reference definitions of the other files of the book. Default:
+    /// `true`.
+    pub resolve_refdefs: bool,
+
+    /// Turn the links to Markdown files (e.g. `other.md#section`) into
+    /// links to the HTML files that mdBook renders (`other.html#section`).
+    /// Default: `true`.
+    pub html_links: bool,
+
+    /// HTML template that the rendered chapter is inserted into: its
+    /// `{{title}}` and `{{content}}` placeholders are replaced by the
+    /// (escaped) title of the chapter and its HTML, see
+    /// [`DEFAULT_HTML_TEMPLATE`]. Default: `None`, an HTML fragment.
+    pub template: Option<String>,
+
+    /// Markdown dialect of the book. Default: the default parser
+    /// options.
+    pub parser: ParserOptions,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            resolve_refdefs: true,
+            html_links: true,
+            template: None,
+            parser: ParserOptions::default(),
+        }
+    }
+}
+
+/// Render a chapter of a book to HTML.
+///
+/// src_dir_path: path to the source directory.
+///
+/// chapter_path: path of the chapter's Markdown file, as given or
+/// relative to the source directory.
+pub(crate) fn render_chapter(
+    src_dir_path: &Path,
+    chapter_path: &Path,
+    options: &RenderOptions,
+) -> Result<String> {
+    let src_dir_path = fs::check_is_dir(src_dir_path)?;
+    let chapter_path = if chapter_path.is_file() {
+        chapter_path.to_path_buf()
+    } else {
+        src_dir_path.join(chapter_path)
+    };
+    let contents =
+        std::fs::read_to_string(&chapter_path).map_err(|e| Error::io(&chapter_path, e))?;
+
+    // Included files, e.g. code examples, can be anywhere in the book
+    let src_dir_canon = src_dir_path
+        .canonicalize()
+        .map_err(|e| Error::io(&src_dir_path, e))?;
+    let root_dir_path = src_dir_canon.parent().unwrap_or(&src_dir_canon);
+    let chapter_dir_path = chapter_path.parent().unwrap_or(&src_dir_path);
+    let markdown = includes::expand_includes(&contents, chapter_dir_path, root_dir_path);
+
+    let refdefs: HashMap<String, String> = if options.resolve_refdefs {
+        Book::load_with_options(&src_dir_path, &options.parser)?
+            .refdefs_by_label()
+            .into_iter()
+            .map(|(label, d)| (label, d.dest.clone()))
+            .collect()
+    } else {
+        HashMap::new()
+    };
+    let default_title = chapter_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    Ok(render_str(&markdown, &refdefs, &default_title, options))
+}
+
+/// Render Markdown to HTML.
+///
+/// refdefs: lowercased label -> URL, used to resolve the references that
+/// the Markdown does not define.
+///
+/// default_title: title of the page if the Markdown has neither a
+/// `title` in its front matter nor a heading.
+pub(crate) fn render_str(
+    markdown: &str,
+    refdefs: &HashMap<String, String>,
+    default_title: &str,
+    options: &RenderOptions,
+) -> String {
+    let markdown_body = parser::skip_front_matter(markdown, &options.parser);
+    let events: Vec<Event<'_>> =
+        parser::get_parser_with_refdefs(markdown_body, refdefs, &options.parser)
+            .map(|event| match event {
+                Event::Start(Tag::Link {
+                    link_type,
+                    dest_url,
+                    title,
+                    id,
+                }) if options.html_links => {
+                    let dest_url = html_link(&dest_url).map_or(dest_url, CowStr::from);
+                    Event::Start(Tag::Link {
+                        link_type,
+                        dest_url,
+                        title,
+                        id,
+                    })
+                }
+                event => event,
+            })
+            .collect();
+
+    let Some(template) = options.template.as_deref() else {
+        return write_from_parser::write_html_to_string(events.into_iter());
+    };
+    let title = ChapterMetadata::from_markdown(markdown)
+        .and_then(|m| m.title().map(str::to_string))
+        .or_else(|| first_heading(&events))
+        .unwrap_or_else(|| default_title.to_string());
+    let content = write_from_parser::write_html_to_string(events.into_iter());
+    PLACEHOLDER_REGEX
+        .replace_all(template, |caps: &regex::Captures<'_>| match &caps["name"] {
+            "title" => quick_xml::escape::escape(title.as_str()).into_owned(),
+            _ => content.clone(),
+        })
+        .into_owned()
+}
+
+/// Returns the text of the first heading, if any
+fn first_heading(events: &[Event<'_>]) -> Option<String> {
+    let start = events
+        .iter()
+        .position(|e| matches!(e, Event::Start(Tag::Heading { .. })))?;
+    let text: String = events[start + 1..]
+        .iter()
+        .take_while(|e| !matches!(e, Event::End(TagEnd::Heading(_))))
+        .filter_map(|e| match e {
+            Event::Text(t) | Event::Code(t) => Some(t.as_ref()),
+            _ => None,
+        })
+        .collect();
+    Some(text.trim().to_string()).filter(|t| !t.is_empty())
+}
+
+/// Returns the link to the HTML file of a relative link to a Markdown
+/// file, if it is one.
+fn html_link(dest_url: &str) -> Option<String> {
+    if dest_url.contains(':') || dest_url.starts_with(['#', '/']) {
+        return None;
+    }
+    let (path, fragment) = match dest_url.find('#') {
+        Some(i) => dest_url.split_at(i),
+        None => (dest_url, ""),
+    };
+    path.ends_with(".md")
+        .then(|| sitemap::chapter_html_path(path) + fragment)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_render_str() {
+        let options = RenderOptions::default();
+        let refdefs = HashMap::from([("serde".to_string(), "https://docs.rs/serde".to_string())]);
+        let md = "---\ntitle: A & B\n---\n# Heading\n\nSee [serde], [next](next.md#usage) and [home](https://example.com/a.md).\n";
+        assert_eq!(
+            render_str(md, &refdefs, "chapter", &options),
+            "<h1>Heading</h1>\n<p>See <a href=\"https://docs.rs/serde\">serde</a>, <a href=\"next.html#usage\">next</a> and <a href=\"https://example.com/a.md\">home</a>.</p>\n"
+        );
+        let options = RenderOptions {
+            template: Some("<title>{{ title }}</title>\n{{content}}".to_string()),
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            render_str(md, &refdefs, "chapter", &options),
+            "<title>A &amp; B</title>\n<h1>Heading</h1>\n<p>See <a href=\"https://docs.rs/serde\">serde</a>, <a href=\"next.html#usage\">next</a> and <a href=\"https://example.com/a.md\">home</a>.</p>\n"
+        );
+        assert_eq!(
+            render_str("## `Code` title\n", &refdefs, "chapter", &options),
+            "<title>Code title</title>\n<h2><code>Code</code> title</h2>\n"
+        );
+    }
+
+    #[test]
+    fn test_html_link() {
+        assert_eq!(html_link("a/README.md").as_deref(), Some("a/index.html"));
+        assert_eq!(html_link("../b.md#c").as_deref(), Some("../b.html#c"));
+        assert_eq!(html_link("#c"), None);
+        assert_eq!(html_link("image.png"), None);
+        assert_eq!(html_link("mailto:a@b.md"), None);
+    }
+
+    #[test]
+    fn test_render_chapter() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let src_dir = dir.path().join("src");
+        std::fs::create_dir(&src_dir)?;
+        std::fs::write(
+            src_dir.join("intro.md"),
+            "{{#include refs.md}}\n\nSee [a] and [b].\n",
+        )?;
+        std::fs::write(src_dir.join("refs.md"), "[a]: https://a.com\n")?;
+        std::fs::write(src_dir.join("other.md"), "[b]: https://b.com\n")?;
+
+        let html = render_chapter(&src_dir, Path::new("intro.md"), &RenderOptions::default())?;
+        assert_eq!(
+            html,
+            "<p>See <a href=\"https://a.com\">a</a> and <a href=\"https://b.com\">b</a>.</p>\n"
+        );
+        let options = RenderOptions {
+            resolve_refdefs: false,
+            template: Some(DEFAULT_HTML_TEMPLATE.to_string()),
+            ..RenderOptions::default()
+        };
+        let html = render_chapter(&src_dir, &src_dir.join("intro.md"), &options)?;
+        assert!(html.contains("<title>intro</title>"));
+        assert!(html.contains("<a href=\"https://a.com\">a</a> and [b].</p>\n</main>"));
+        Ok(())
+    }
+}
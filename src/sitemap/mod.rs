@@ -1,75 +1,246 @@
 //! Sitemap file generation.
+mod robots;
 mod xml;
 
+use std::collections::HashMap;
 use std::io::Write;
+use std::path::Path;
 
-use anyhow::Context;
-use anyhow::Error;
-use anyhow::Result;
+pub(crate) use robots::*;
+use time::Date;
+use tracing::debug;
 use tracing::info;
+use url::Url;
 
-/// Create a sitemap.xml file from a list of links and a base URL.
+use crate::Error;
+use crate::Result;
+use crate::fs;
+use crate::summary::Chapter;
+
+/// Options for the generation of `sitemap.xml` (and `robots.txt`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SitemapOptions {
+    /// Path of the book relative to the base URL's host, as set by
+    /// `output.html.site-url` in `book.toml` e.g. `/mybook/`.
+    /// If `None`, the base URL is used as is.
+    pub site_url: Option<String>,
+
+    /// Glob patterns of chapter paths (relative to the source
+    /// directory) to leave out of the sitemap e.g. `*refs.md` or
+    /// `drafts/**`.
+    pub exclude: Vec<String>,
+
+    /// Chapter paths to replace before computing URLs e.g.
+    /// `("intro.md", "index.md")`. The source path must match exactly.
+    pub map_index: Vec<(String, String)>,
+
+    /// If `true`, write a `robots.txt` file pointing at the sitemap,
+    /// in the same directory as `sitemap.xml`, replacing any existing
+    /// one. It is skipped, with a warning, if the book is not served
+    /// from the root of its host (e.g. `site_url` is `/mybook/`), since
+    /// crawlers only read `/robots.txt`. Default: `true`.
+    pub robots_txt: bool,
+}
+
+impl Default for SitemapOptions {
+    fn default() -> Self {
+        Self {
+            site_url: None,
+            exclude: vec!["*refs.md".to_string()],
+            map_index: Vec::new(),
+            robots_txt: true,
+        }
+    }
+}
+
+/// Compute the URL of the root of the book from the base URL and
+/// (optionally) `output.html.site-url`.
+///
+/// If `site_url` is an absolute path, it replaces the path of the base
+/// URL; otherwise, it is appended to it.
+pub(crate) fn site_root_url(base_url: &Url, site_url: Option<&str>) -> Result<Url> {
+    let mut base_url = base_url.clone();
+    // Without a trailing slash, `join` would replace the last segment.
+    if !base_url.path().ends_with('/') {
+        base_url.set_path(&format!("{}/", base_url.path()));
+    }
+    match site_url
+        .map(str::trim)
+        .filter(|s| !s.is_empty() && *s != "/")
+    {
+        None => Ok(base_url),
+        Some(site_url) => {
+            let site_url = if site_url.ends_with('/') {
+                site_url.to_string()
+            } else {
+                format!("{site_url}/")
+            };
+            base_url.join(&site_url).map_err(|e| {
+                Error::config(format!(
+                    "Invalid site URL {site_url} for base URL {base_url}: {e}"
+                ))
+            })
+        }
+    }
+}
+
+/// Convert the path of a chapter's Markdown file into the path of the
+/// HTML file that mdBook renders, following mdBook's rules:
+/// `README.md` files become `index.html` and `.md` becomes `.html`.
+/// Nested directories are kept.
+///
+/// chapter_path: path of the chapter, relative to the source directory
+/// e.g. `chapter_1/README.md`.
+pub(crate) fn chapter_html_path(chapter_path: &str) -> String {
+    let path = Path::new(chapter_path);
+    let is_readme = path
+        .file_name()
+        .and_then(|f| f.to_str())
+        .is_some_and(|f| f.eq_ignore_ascii_case("README.md"));
+    let html_path = if is_readme {
+        path.with_file_name("index.html")
+    } else {
+        path.with_extension("html")
+    };
+    html_path.to_string_lossy().replace('\\', "/")
+}
+
+/// Create a sitemap.xml file from the chapters listed in SUMMARY.md.
+///
+/// chapters: the chapters of the book, in SUMMARY.md order. Draft
+/// chapters are skipped.
+///
+/// site_root: the URL of the root of the book (see [`site_root_url`]).
 ///
-/// links: the list of links to Markdown files / book chapters (e.g. from
-/// SUMMARY.md).
+/// options: exclusion globs and chapter path mappings.
 ///
-/// base_url: the base URL used as the prefix for HTML files.
+/// lastmod: date each chapter was last modified (e.g. from its front
+/// matter), by chapter path as written in SUMMARY.md. Chapters without a
+/// date have no `<lastmod>`.
 ///
 /// w: a writer (e.g. a File) to write the sitemap to.
 pub(crate) fn generate_sitemap<W>(
-    links: Vec<crate::link::Link<'_>>,
-    base_url: url::Url,
+    chapters: &[Chapter],
+    site_root: &Url,
+    options: &SitemapOptions,
+    lastmod: &HashMap<String, Date>,
     w: &mut W,
-    map_index: Option<(String, String)>,
 ) -> Result<()>
 where
     W: Write,
 {
-    // Remove a few exceptions
-    let exclude = ["refs.md", "SUMMARY.md"];
-    let ls = links
-        .into_iter()
-        .filter(|l| !exclude.iter().any(|&ex| l.get_url().ends_with(ex)));
-    // debug: let l = l.map(|l| { tracing::debug!("{l:?}"); l });
-
-    // Change the extension and replace index file if requested
-    let ls = ls.map(|l| {
-        let mut url = l.get_url().to_string();
-        if let Some((from, to)) = &map_index {
-            url = url.replace(from, to);
-        }
-        base_url.join(url.replace(".md", ".html").as_str())
-    });
+    let exclude = fs::build_globset(&options.exclude)?;
+
+    let urls = chapters
+        .iter()
+        .filter(|c| {
+            if c.is_draft() {
+                debug!("Draft chapter skipped: {}", c.title);
+            }
+            !c.is_draft()
+        })
+        .filter_map(|c| c.path.as_deref())
+        .filter(|p| {
+            let excluded = exclude.is_match(p);
+            if excluded {
+                debug!("Excluded from the sitemap: {p}");
+            }
+            !excluded
+        })
+        .map(|p| {
+            let date = lastmod.get(p).copied();
+            let p = options
+                .map_index
+                .iter()
+                .find(|(from, _)| from == p)
+                .map_or(p, |(_, to)| to.as_str());
+            site_root.join(&chapter_html_path(p)).map(|url| (url, date))
+        });
 
     // Separate links from errors and print errors if any
-    let mut links: Vec<String> = Vec::new();
-    let mut errors: Vec<Error> = Vec::new();
-    for r in ls {
+    let mut links: Vec<(String, Option<Date>)> = Vec::new();
+    let mut errors: Vec<url::ParseError> = Vec::new();
+    for r in urls {
         match r {
-            Ok(url) => links.push(url.to_string()),
-            Err(e) => errors.push(e.into()),
+            Ok((url, date)) => links.push((url.to_string(), date)),
+            Err(e) => errors.push(e),
         }
     }
-    // debug: tracing::debug!("Links: {links:?}");
     if !errors.is_empty() {
         tracing::error!("Errors: {errors:?}");
     }
 
-    // Deduplicate and sort links
-    links.dedup();
-    links.sort();
+    // Deduplicate links, preserving the SUMMARY.md order
+    let mut seen = std::collections::HashSet::new();
+    links.retain(|(l, _)| seen.insert(l.clone()));
 
     // Write the sitemap
-    xml::write_xml(links, w).context("[generate_sitemap] Failed to write the XML.")?;
+    xml::write_xml(links, w)?;
     info!("sitemap.xml created.");
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    // use super::*;
+    use super::*;
+    use crate::summary::parse_summary;
+
+    #[test]
+    fn test_chapter_html_path() {
+        assert_eq!(chapter_html_path("intro.md"), "intro.html");
+        assert_eq!(chapter_html_path("README.md"), "index.html");
+        assert_eq!(chapter_html_path("a/b/readme.md"), "a/b/index.html");
+        assert_eq!(chapter_html_path("a/index.md"), "a/index.html");
+        assert_eq!(chapter_html_path("a/b.md"), "a/b.html");
+    }
 
-    // #[test]
-    // fn test() {
-    // }
+    #[test]
+    fn test_site_root_url() {
+        let base = Url::parse("https://example.com/books").unwrap();
+        assert_eq!(
+            site_root_url(&base, None).unwrap().as_str(),
+            "https://example.com/books/"
+        );
+        assert_eq!(
+            site_root_url(&base, Some("mybook")).unwrap().as_str(),
+            "https://example.com/books/mybook/"
+        );
+        assert_eq!(
+            site_root_url(&base, Some("/mybook/")).unwrap().as_str(),
+            "https://example.com/mybook/"
+        );
+    }
+
+    #[test]
+    fn test_generate_sitemap() {
+        let summary = "# Summary\n\n[Intro](intro.md)\n\n- [One](one/README.md)\n  - [Two](one/two.md)\n  - [Draft]()\n- [Refs](refs.md)\n- [Secret](drafts/secret.md)\n- [One again](one/README.md)\n";
+        let chapters = parse_summary(summary);
+        let site_root = Url::parse("https://example.com/book/").unwrap();
+        let options = SitemapOptions {
+            exclude: vec!["*refs.md".into(), "drafts/**".into()],
+            map_index: vec![("intro.md".into(), "index.md".into())],
+            ..Default::default()
+        };
+        let lastmod = HashMap::from([(
+            "one/two.md".to_string(),
+            time::macros::date!(2024 - 05 - 01),
+        )]);
+        let mut buf = Vec::new();
+        generate_sitemap(&chapters, &site_root, &options, &lastmod, &mut buf).unwrap();
+        let xml = String::from_utf8(buf).unwrap();
+        let locs: Vec<&str> = xml
+            .lines()
+            .filter_map(|l| l.trim().strip_prefix("<loc>"))
+            .filter_map(|l| l.strip_suffix("</loc>"))
+            .collect();
+        assert_eq!(
+            locs,
+            vec![
+                "https://example.com/book/index.html",
+                "https://example.com/book/one/index.html",
+                "https://example.com/book/one/two.html",
+            ]
+        );
+        assert_eq!(xml.matches("<lastmod>2024-05-01</lastmod>").count(), 1);
+    }
 }
@@ -0,0 +1,32 @@
+//! Write a robots.txt file that points to the sitemap
+
+use std::io::Write;
+
+use url::Url;
+
+use crate::Result;
+
+/// Write a permissive `robots.txt` that references the sitemap.
+///
+/// sitemap_url: the absolute URL of `sitemap.xml`.
+///
+/// w: a writer (e.g. a File) to write to.
+pub(crate) fn write_robots_txt<W: Write>(sitemap_url: &Url, w: &mut W) -> Result<()> {
+    write!(w, "User-agent: *\nAllow: /\n\nSitemap: {sitemap_url}\n")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_robots_txt() {
+        let url = Url::parse("https://example.com/book/sitemap.xml").unwrap();
+        let mut buf = Vec::new();
+        write_robots_txt(&url, &mut buf).unwrap();
+        let s = String::from_utf8(buf).unwrap();
+        assert!(s.starts_with("User-agent: *\n"));
+        assert!(s.ends_with("Sitemap: https://example.com/book/sitemap.xml\n"));
+    }
+}
@@ -6,40 +6,44 @@
 
 use std::io::Write;
 
-use anyhow::anyhow;
 use quick_xml::events::BytesText;
 use quick_xml::writer::Writer;
+use time::Date;
 
-// Write in the sitemap.xml format to a file, given a list of links.
-pub(super) fn write_xml<W: Write>(links: Vec<String>, w: &mut W) -> anyhow::Result<()> {
+use crate::Result;
+
+// Write in the sitemap.xml format to a file, given a list of links and
+// the dates they were last modified, if known.
+pub(super) fn write_xml<W: Write>(links: Vec<(String, Option<Date>)>, w: &mut W) -> Result<()> {
     let mut writer = Writer::new_with_indent(w, b' ', 2);
 
-    writer.write_bom().map_err(|_e| {
-        anyhow!("[write_xml] Failed to write byte-order-marks to the XML document.")
-    })?;
+    writer.write_bom()?;
     // Insert <?xml version="1.0" encoding="UTF-8"?>
     writer
         .get_mut()
-        .write_all(b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n")
-        .map_err(|_e| anyhow!("[write_xml] Failed to write to the XML document."))?;
+        .write_all(b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n")?;
     // <urlset>
     writer
         .create_element("urlset")
         .with_attribute(("xmlns", "http://www.sitemaps.org/schemas/sitemap/0.9"))
         .write_inner_content(|writer| {
-            for link in links.iter() {
+            for (link, lastmod) in links.iter() {
                 // <url><loc>
                 writer.create_element("url").write_inner_content(|w| {
                     w.create_element("loc")
                         .write_text_content(BytesText::from_escaped(quick_xml::escape::escape(
                             link.as_str(),
                         )))?;
+                    // <lastmod>YYYY-MM-DD
+                    if let Some(lastmod) = lastmod {
+                        w.create_element("lastmod")
+                            .write_text_content(BytesText::new(&lastmod.to_string()))?;
+                    }
                     Ok(())
                 })?;
             }
             Ok(())
-        })
-        .map_err(|_e| anyhow!("[write_xml] Failed to write the url set."))?;
+        })?;
     Ok(())
 }
 
@@ -51,12 +55,29 @@ mod tests {
 
     #[test]
     fn test_write_xml_escapes_url() {
-        let links = vec!["http://example.com/test?a=1&b=2<script>alert(1)</script>'\"".to_string()];
+        let links = vec![(
+            "http://example.com/test?a=1&b=2<script>alert(1)</script>'\"".to_string(),
+            None,
+        )];
         let mut w = Cursor::new(Vec::new());
         write_xml(links, &mut w).unwrap();
 
         let result = String::from_utf8(w.into_inner()).unwrap();
         // The URL should be properly escaped
         assert!(result.contains("<loc>http://example.com/test?a=1&amp;b=2&lt;script&gt;alert(1)&lt;/script&gt;&apos;&quot;</loc>"));
+        assert!(!result.contains("<lastmod>"));
+    }
+
+    #[test]
+    fn test_write_xml_lastmod() {
+        let links = vec![(
+            "https://example.com/a.html".to_string(),
+            Some(time::macros::date!(2024 - 05 - 01)),
+        )];
+        let mut w = Cursor::new(Vec::new());
+        write_xml(links, &mut w).unwrap();
+
+        let result = String::from_utf8(w.into_inner()).unwrap();
+        assert!(result.contains("<lastmod>2024-05-01</lastmod>"));
     }
 }
@@ -0,0 +1,257 @@
+//! Generate a `SUMMARY.md` file from the Markdown files of a source
+//! directory and their front matter
+
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+use std::io::Write;
+
+use pulldown_cmark::Event;
+use pulldown_cmark::Tag;
+use pulldown_cmark::TagEnd;
+
+use crate::Result;
+use crate::chapters;
+use crate::parser;
+use crate::parser::ParserOptions;
+
+/// Chapter of the generated `SUMMARY.md`
+#[derive(Debug)]
+struct Entry {
+    /// Title, from the front matter, the first heading or the file name
+    title: String,
+    /// Path relative to the source directory, `None` for drafts
+    path: Option<String>,
+    /// `weight` (or `order`) of the front matter, if any
+    weight: Option<i64>,
+    /// Key used to sort entries with the same weight
+    sort_key: String,
+    /// Nested chapters
+    children: Vec<Entry>,
+}
+
+/// Chapters of a directory, before nesting
+#[derive(Debug, Default)]
+struct DirNode {
+    /// `README.md` or `index.md` of the directory
+    index: Option<Entry>,
+    /// Other chapters of the directory
+    files: Vec<Entry>,
+    /// Subdirectories, by name
+    dirs: BTreeMap<String, DirNode>,
+}
+
+/// Write a `SUMMARY.md` that lists the Markdown files of a book.
+///
+/// `SUMMARY.md` itself and the files included by other files (e.g.
+/// `refs.md`) are left out. Chapters are nested by directory, under the
+/// directory's `README.md` (or `index.md`), or under a draft chapter
+/// named after the directory if there is none. The root `README.md` (or
+/// `index.md`) becomes a prefix chapter. Siblings are sorted by the
+/// `weight` (or `order`) key of their front matter, then by path; a
+/// `draft: true` key turns the chapter into a draft chapter.
+///
+/// files: (path relative to the source directory, using `/` as
+/// separator; contents) of each Markdown file.
+///
+/// parser_options: the Markdown dialect of the files.
+///
+/// w: a writer (e.g. a File) to write the SUMMARY.md to.
+pub(crate) fn write_summary<W: Write>(
+    files: &[(String, String)],
+    parser_options: &ParserOptions,
+    w: &mut W,
+) -> Result<()> {
+    let included: HashSet<String> = files
+        .iter()
+        .flat_map(|(path, contents)| {
+            let dir = path.rsplit_once('/').map_or("", |(dir, _)| dir);
+            parser::find_includes(contents).map(move |i| chapters::resolve(dir, i.path))
+        })
+        .collect();
+
+    let mut root = DirNode::default();
+    for (path, contents) in files {
+        if path == "SUMMARY.md" || included.contains(path) {
+            continue;
+        }
+        let mut node = &mut root;
+        let mut segments: Vec<&str> = path.split('/').collect();
+        let file_name = segments.pop().unwrap_or_default();
+        for segment in segments {
+            node = node.dirs.entry(segment.to_string()).or_default();
+        }
+        let entry = chapter_entry(path, file_name, contents, parser_options);
+        if is_index(file_name) && node.index.is_none() {
+            node.index = Some(entry);
+        } else {
+            node.files.push(entry);
+        }
+    }
+
+    writeln!(w, "# Summary\n")?;
+    let prefix = root.index.take();
+    let entries = nest(root, "");
+    if let Some(prefix) = prefix {
+        writeln!(w, "{}\n", link(&prefix))?;
+    }
+    write_entries(&entries, 0, w)?;
+    Ok(())
+}
+
+/// Create the entry of a chapter, given its path, file name and contents
+fn chapter_entry(
+    path: &str,
+    file_name: &str,
+    contents: &str,
+    parser_options: &ParserOptions,
+) -> Entry {
+    let (metadata, markdown) = parser::split_front_matter(contents);
+    let metadata = metadata.unwrap_or_default();
+    let title = metadata
+        .title()
+        .map(str::to_string)
+        .or_else(|| first_heading(markdown, parser_options))
+        .unwrap_or_else(|| title_from_name(file_name.trim_end_matches(".md")));
+    Entry {
+        title,
+        path: (!metadata.is_draft()).then(|| path.to_string()),
+        weight: metadata.weight(),
+        sort_key: path.to_string(),
+        children: Vec::new(),
+    }
+}
+
+/// Turn a directory into a list of sorted entries: its chapters, then
+/// one entry per subdirectory, with the subdirectory's chapters nested
+/// under it.
+fn nest(node: DirNode, dir_path: &str) -> Vec<Entry> {
+    let mut entries = node.files;
+    for (name, mut sub) in node.dirs {
+        let sub_path = if dir_path.is_empty() {
+            name.clone()
+        } else {
+            format!("{dir_path}/{name}")
+        };
+        let mut parent = sub.index.take().unwrap_or_else(|| Entry {
+            title: title_from_name(&name),
+            path: None,
+            weight: None,
+            sort_key: String::new(),
+            children: Vec::new(),
+        });
+        // Sort the directory among the files of its parent by its name
+        parent.sort_key = sub_path.clone();
+        parent.children = nest(sub, &sub_path);
+        entries.push(parent);
+    }
+    entries.sort_by(|a, b| {
+        (a.weight.is_none(), a.weight, &a.sort_key).cmp(&(
+            b.weight.is_none(),
+            b.weight,
+            &b.sort_key,
+        ))
+    });
+    entries
+}
+
+/// Write entries as a nested list
+fn write_entries<W: Write>(entries: &[Entry], depth: usize, w: &mut W) -> Result<()> {
+    for e in entries {
+        writeln!(w, "{}- {}", "  ".repeat(depth), link(e))?;
+        write_entries(&e.children, depth + 1, w)?;
+    }
+    Ok(())
+}
+
+/// Returns the Markdown link of an entry e.g. `[Title](path.md)`
+fn link(entry: &Entry) -> String {
+    let title = entry.title.replace('[', "\\[").replace(']', "\\]");
+    let path = entry
+        .path
+        .as_deref()
+        .unwrap_or_default()
+        .replace(' ', "%20");
+    format!("[{title}]({path})")
+}
+
+/// Returns `true` for `README.md` and `index.md`, the chapters that
+/// mdBook renders as `index.html`
+fn is_index(file_name: &str) -> bool {
+    file_name.eq_ignore_ascii_case("README.md") || file_name.eq_ignore_ascii_case("index.md")
+}
+
+/// Returns the text of the first heading of a Markdown document, if any
+fn first_heading(markdown: &str, parser_options: &ParserOptions) -> Option<String> {
+    let mut title: Option<String> = None;
+    for event in parser::get_parser(markdown, parser_options) {
+        match event {
+            Event::Start(Tag::Heading { .. }) => title = Some(String::new()),
+            Event::Text(t) | Event::Code(t) => {
+                if let Some(ref mut title) = title {
+                    title.push_str(&t);
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                return title.filter(|t| !t.trim().is_empty());
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Create a title from a file or directory name e.g. `getting_started`
+/// becomes `Getting started`
+fn title_from_name(name: &str) -> String {
+    let name = name.replace(['_', '-'], " ");
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_summary() {
+        let files: Vec<(String, String)> = [
+            ("README.md", "# Introduction\n"),
+            ("SUMMARY.md", "# Summary\n"),
+            ("refs.md", "[a]: https://a.com\n"),
+            (
+                "z.md",
+                "+++\nweight = 1\n+++\n# Zed\n{{#include refs.md}}\n",
+            ),
+            ("b.md", "---\ntitle: Bee\n---\n# Not this\n"),
+            ("getting_started/README.md", "# Getting Started\n"),
+            ("getting_started/two.md", "---\norder: 2\n---\n# Two\n"),
+            ("getting_started/one.md", "---\norder: 1\n---\n# One\n"),
+            ("misc/wip.md", "---\ndraft: true\n---\n# WIP\n"),
+        ]
+        .into_iter()
+        .map(|(p, c)| (p.to_string(), c.to_string()))
+        .collect();
+        let mut buf = Vec::new();
+        write_summary(&files, &ParserOptions::default(), &mut buf).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "# Summary\n\n[Introduction](README.md)\n\n\
+             - [Zed](z.md)\n\
+             - [Bee](b.md)\n\
+             - [Getting Started](getting_started/README.md)\n  \
+               - [One](getting_started/one.md)\n  \
+               - [Two](getting_started/two.md)\n\
+             - [Misc]()\n  \
+               - [WIP]()\n"
+        );
+    }
+
+    #[test]
+    fn test_title_from_name() {
+        assert_eq!(title_from_name("getting_started"), "Getting started");
+        assert_eq!(title_from_name("a-b"), "A b");
+    }
+}
@@ -0,0 +1,7 @@
+//! Read the structure of a book from its `SUMMARY.md` file, or generate
+//! one
+mod generate_summary;
+mod parse_summary;
+
+pub(crate) use generate_summary::*;
+pub(crate) use parse_summary::*;
@@ -0,0 +1,161 @@
+//! Parse `SUMMARY.md` into an ordered list of chapters
+//!
+//! See <https://rust-lang.github.io/mdBook/format/summary.html>.
+
+use std::path::Path;
+
+use pulldown_cmark::Event;
+use pulldown_cmark::Tag;
+use pulldown_cmark::TagEnd;
+use tracing::debug;
+
+use crate::Error;
+use crate::Result;
+use crate::parser;
+use crate::parser::ParserOptions;
+
+/// A chapter listed in `SUMMARY.md`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Chapter {
+    /// Chapter title, as written in the link text
+    pub(crate) title: String,
+    /// Path of the chapter's Markdown file, relative to the source
+    /// directory, without any leading `./`.
+    /// `None` for draft chapters e.g. `[Draft chapter]()`.
+    pub(crate) path: Option<String>,
+    /// Nesting level: 0 for prefix / suffix chapters, 1 for top-level
+    /// numbered chapters, 2 for their sub-chapters, etc.
+    pub(crate) depth: usize,
+}
+
+impl Chapter {
+    /// Returns `true` if the chapter is a draft (i.e. has no file)
+    pub(crate) fn is_draft(&self) -> bool {
+        self.path.is_none()
+    }
+}
+
+/// Parse the contents of `SUMMARY.md` and return the chapters,
+/// in the order in which they appear.
+///
+/// Part titles, separators and links to external websites are skipped.
+/// `SUMMARY.md` is parsed with the default parser options: its syntax
+/// does not depend on the Markdown dialect of the chapters.
+///
+/// markdown: the contents of `SUMMARY.md`.
+pub(crate) fn parse_summary(markdown: &str) -> Vec<Chapter> {
+    let mut chapters = Vec::new();
+    let mut depth = 0usize;
+    // Title and path of the link being parsed, if any
+    let mut current: Option<(String, Option<String>)> = None;
+
+    for event in parser::get_parser(markdown, &ParserOptions::default()) {
+        match event {
+            Event::Start(Tag::List(_)) => depth += 1,
+            Event::End(TagEnd::List(_)) => depth = depth.saturating_sub(1),
+            Event::Start(Tag::Link { dest_url, .. }) if current.is_none() => {
+                current = Some((String::new(), normalize_chapter_path(&dest_url)));
+            }
+            Event::Text(t) | Event::Code(t) => {
+                if let Some((title, _)) = current.as_mut() {
+                    title.push_str(&t);
+                }
+            }
+            Event::End(TagEnd::Link) => {
+                if let Some((title, path)) = current.take() {
+                    if path.as_deref().is_some_and(is_external) {
+                        debug!("Skipping external link in SUMMARY.md: {path:?}");
+                        continue;
+                    }
+                    chapters.push(Chapter { title, path, depth });
+                }
+            }
+            _ => {}
+        }
+    }
+    chapters
+}
+
+/// Read and parse the `SUMMARY.md` file of a source directory.
+///
+/// markdown_src_dir_path: path to the source directory.
+pub(crate) fn read_summary<P: AsRef<Path>>(markdown_src_dir_path: P) -> Result<Vec<Chapter>> {
+    let summary_md_path = markdown_src_dir_path.as_ref().join("SUMMARY.md");
+    debug!("SUMMARY.md path: {}", summary_md_path.display());
+    let markdown =
+        std::fs::read_to_string(&summary_md_path).map_err(|e| Error::io(&summary_md_path, e))?;
+    Ok(parse_summary(&markdown))
+}
+
+/// Clean up a chapter path as written in `SUMMARY.md`:
+/// remove any fragment, leading `./` and convert backslashes.
+///
+/// Returns `None` for draft chapters (empty destination).
+pub(crate) fn normalize_chapter_path(dest_url: &str) -> Option<String> {
+    let path = dest_url.split('#').next().unwrap_or_default().trim();
+    let mut path = path.replace('\\', "/");
+    while let Some(stripped) = path.strip_prefix("./") {
+        path = stripped.to_string();
+    }
+    let path = path.trim_start_matches('/');
+    if path.is_empty() {
+        None
+    } else {
+        Some(path.to_string())
+    }
+}
+
+/// Returns `true` if the destination is not a local file
+fn is_external(path: &str) -> bool {
+    path.contains("://") || path.starts_with("mailto:")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_summary() {
+        let markdown = r#"# Summary
+
+[Introduction](./intro.md)
+
+# Part one
+
+- [Chapter 1](chapter_1.md)
+- [Chapter 2](./chapter_2/README.md)
+  - [Another Page](./chapter_2/another_page.md#section)
+  - [Draft]()
+
+---
+
+- [External](https://example.com)
+
+[Appendix](appendix.md)
+"#;
+        let chapters = parse_summary(markdown);
+        let paths: Vec<_> = chapters
+            .iter()
+            .map(|c| (c.title.as_str(), c.path.as_deref(), c.depth))
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                ("Introduction", Some("intro.md"), 0),
+                ("Chapter 1", Some("chapter_1.md"), 1),
+                ("Chapter 2", Some("chapter_2/README.md"), 1),
+                ("Another Page", Some("chapter_2/another_page.md"), 2),
+                ("Draft", None, 2),
+                ("Appendix", Some("appendix.md"), 0),
+            ]
+        );
+        assert!(chapters[4].is_draft());
+    }
+
+    #[test]
+    fn test_normalize_chapter_path() {
+        assert_eq!(normalize_chapter_path("./a/b.md"), Some("a/b.md".into()));
+        assert_eq!(normalize_chapter_path("a\\b.md#x"), Some("a/b.md".into()));
+        assert_eq!(normalize_chapter_path(""), None);
+    }
+}
@@ -1,12 +1,10 @@
 //! Generate links and reference definitions for GitHub repos.
-use std::borrow::Cow;
 use std::collections::BTreeMap;
 use std::io::Write;
 
-use anyhow::Result;
-use pulldown_cmark::Parser;
 use tracing::debug;
 
+use crate::Result;
 use crate::link::Link;
 use crate::link::LinkBuilder;
 use crate::link::write_badge_refdefs_and_links_to_two;
@@ -21,41 +19,33 @@
 // [github]: https://github.com/john-cd/mdbook-utils
 // [github-badge]: https://img.shields.io/badge/mdbook-utils-steelblue?logo=github
 
-/// Get existing reference definitions from a Markdown parser,
-/// identify URLs that are GitHub repos, create badge URLs for these
-/// links, and write to a writer / file.
+/// Given reference definitions sorted by label, identify URLs that are
+/// GitHub repos, create badge URLs for these links, and write to a
+/// writer / file.
 ///
-/// parser: Markdown parser.
+/// refdefs: (label, URL) pairs, sorted by label.
 ///
 /// w: writer (e.g. File) to write to.
-pub(crate) fn write_github_repo_badge_refdefs<'input, W>(
-    parser: &'input mut Parser<'input>,
+pub(crate) fn write_github_repo_badge_refdefs<W>(
+    refdefs: BTreeMap<&str, &str>,
     w: &mut W,
 ) -> Result<()>
 where
     W: Write,
 {
-    let sorted_refdefs: BTreeMap<_, _> = parser.reference_definitions().iter().collect();
-
-    let rule = &crate::link::GLOBAL_RULES["github repo"];
-    let re = &crate::link::COMPILED_RULES["github repo"];
+    let rule = &crate::link::DEFAULT_RULES["github repo"];
 
     let mut links = Vec::new();
 
     // Iterate through all ref defs
-    for (lbl, linkdef) in sorted_refdefs {
-        // if the URL is a github repo...
-        if let Some(capture) = re.captures(linkdef.dest.as_ref()) {
-            debug!("dest_url: {} -> {capture:?}", linkdef.dest);
-
-            // ...create the URL for the badge...
-            let badge_image_url: Cow<'_, str> =
-                re.replace(linkdef.dest.as_ref(), rule.badge_url_pattern);
-            debug!("badge_image_url: {badge_image_url}");
+    for (lbl, dest) in refdefs {
+        // if the URL is a github repo, create the URL for the badge...
+        if let Some(badge_image_url) = rule.badge_url(dest) {
+            debug!("dest_url: {dest} -> badge_image_url: {badge_image_url}");
 
-            let link: Link<'input> = LinkBuilder::default()
-                .set_label(Cow::from(lbl))
-                .set_url(Cow::from(linkdef.dest.as_ref()))
+            let link: Link = LinkBuilder::default()
+                .set_label(lbl)
+                .set_url(dest)
                 // .add_image_alt_text( )
                 .set_image_url(badge_image_url)
                 .build();
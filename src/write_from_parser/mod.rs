@@ -1,10 +1,14 @@
 //! Functions that take a Markdown parser
 //! and write (parts of) its contents to a file.
 mod github;
+mod write_events;
 mod write_raw_to;
 mod write_refdefs;
 mod write_whole;
 
 pub(crate) use github::*;
+pub use write_events::EventFormat;
+pub(crate) use write_events::*;
 pub(crate) use write_raw_to::*;
 pub(crate) use write_refdefs::*;
+pub(crate) use write_whole::*;
@@ -0,0 +1,426 @@
+//! Output the parser events of Markdown files, with their positions in
+//! the source, as an indented tree or as JSON.
+use std::fmt;
+use std::io::Write;
+use std::ops::Range;
+use std::str::FromStr;
+
+use pulldown_cmark::CodeBlockKind;
+use pulldown_cmark::Event;
+use pulldown_cmark::Tag;
+use serde::Serialize;
+
+use crate::Result;
+use crate::parser;
+use crate::parser::ParserOptions;
+
+/// Output format of the parser events
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EventFormat {
+    /// One line per event, including end events, with its line, column
+    /// and byte range
+    #[default]
+    Text,
+    /// Events nested by depth, one per line, with their line, column
+    /// and byte range
+    Tree,
+    /// Events nested by depth, with their line, column and byte range
+    Json,
+}
+
+impl FromStr for EventFormat {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "tree" => Ok(Self::Tree),
+            "json" => Ok(Self::Json),
+            _ => Err(crate::Error::config(format!(
+                "Unknown event format: {s}. Use `text`, `tree` or `json`."
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for EventFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Text => "text",
+            Self::Tree => "tree",
+            Self::Json => "json",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// Parser event, with the events it contains (for start events)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct EventNode {
+    /// Tag (e.g. `Heading`) or event (e.g. `Text`) name
+    pub(crate) event: &'static str,
+    /// Attributes of the tag, or contents of the event
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) detail: Option<String>,
+    /// Byte range in the file
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    /// Line and column (in bytes) of the start, starting at 1
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+    /// Line of the end
+    pub(crate) end_line: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) children: Vec<EventNode>,
+}
+
+/// Events of a file, in the JSON output
+#[derive(Debug, Serialize)]
+struct FileEvents<'a> {
+    file: &'a str,
+    events: Vec<EventNode>,
+}
+
+/// Parse Markdown and nest its events by depth. End events are implied
+/// by the nesting and are left out.
+///
+/// The front matter, if any, is skipped, but positions are those of
+/// the whole contents.
+pub(crate) fn event_tree(contents: &str, options: &ParserOptions) -> Vec<EventNode> {
+    let lines = Lines::new(contents);
+    let mut roots = Vec::new();
+    let mut stack: Vec<EventNode> = Vec::new();
+    for (event, Range { start, end }) in events_with_offsets(contents, options) {
+        let (name, detail) = match event {
+            Event::End(_) => {
+                if let Some(node) = stack.pop() {
+                    stack
+                        .last_mut()
+                        .map_or(&mut roots, |n| &mut n.children)
+                        .push(node);
+                }
+                continue;
+            }
+            Event::Start(ref tag) => tag_name_and_detail(tag),
+            ref event => event_name_and_detail(event),
+        };
+        let (line, column) = lines.position(start);
+        let node = EventNode {
+            event: name,
+            detail,
+            start,
+            end,
+            line,
+            column,
+            end_line: lines.position(end.max(start + 1) - 1).0,
+            children: Vec::new(),
+        };
+        if matches!(event, Event::Start(_)) {
+            stack.push(node);
+        } else {
+            stack
+                .last_mut()
+                .map_or(&mut roots, |n| &mut n.children)
+                .push(node);
+        }
+    }
+    roots
+}
+
+/// Parse Markdown and returns its events, with their byte ranges in the
+/// whole contents. The front matter, if any, is skipped.
+fn events_with_offsets<'a>(
+    contents: &'a str,
+    options: &ParserOptions,
+) -> impl Iterator<Item = (Event<'a>, Range<usize>)> {
+    let markdown = parser::skip_front_matter(contents, options);
+    let offset = contents.len() - markdown.len();
+    parser::get_parser(markdown, options)
+        .into_offset_iter()
+        .map(move |(event, range)| (event, range.start + offset..range.end + offset))
+}
+
+/// Start offsets of the lines of a text
+struct Lines(Vec<usize>);
+
+impl Lines {
+    fn new(contents: &str) -> Self {
+        Self(
+            std::iter::once(0)
+                .chain(contents.match_indices('\n').map(|(i, _)| i + 1))
+                .collect(),
+        )
+    }
+
+    /// Returns the line and column (in bytes) of an offset, starting
+    /// at 1
+    fn position(&self, offset: usize) -> (usize, usize) {
+        let line = self.0.partition_point(|&s| s <= offset);
+        (line, offset - self.0[line - 1] + 1)
+    }
+}
+
+/// Returns the name and attributes of a tag
+fn tag_name_and_detail(tag: &Tag<'_>) -> (&'static str, Option<String>) {
+    match tag {
+        Tag::Paragraph => ("Paragraph", None),
+        Tag::Heading {
+            level,
+            id,
+            classes,
+            attrs,
+        } => {
+            let mut detail = format!("level: {}", *level as usize);
+            if let Some(id) = id {
+                detail.push_str(&format!(", id: {:?}", id.as_ref()));
+            }
+            if !classes.is_empty() {
+                let classes: Vec<&str> = classes.iter().map(|c| c.as_ref()).collect();
+                detail.push_str(&format!(", classes: {classes:?}"));
+            }
+            if !attrs.is_empty() {
+                let attrs: Vec<(&str, Option<&str>)> = attrs
+                    .iter()
+                    .map(|(k, v)| (k.as_ref(), v.as_deref()))
+                    .collect();
+                detail.push_str(&format!(", attrs: {attrs:?}"));
+            }
+            ("Heading", Some(detail))
+        }
+        Tag::BlockQuote(kind) => ("BlockQuote", kind.map(|k| format!("kind: {k:?}"))),
+        Tag::CodeBlock(CodeBlockKind::Fenced(info)) => (
+            "CodeBlock",
+            Some(format!("fenced, info: {:?}", info.as_ref())),
+        ),
+        Tag::CodeBlock(CodeBlockKind::Indented) => ("CodeBlock", Some("indented".into())),
+        Tag::HtmlBlock => ("HtmlBlock", None),
+        Tag::List(Some(first)) => ("List", Some(format!("ordered, start: {first}"))),
+        Tag::List(None) => ("List", Some("unordered".into())),
+        Tag::Item => ("Item", None),
+        Tag::FootnoteDefinition(label) => (
+            "FootnoteDefinition",
+            Some(format!("label: {:?}", label.as_ref())),
+        ),
+        Tag::DefinitionList => ("DefinitionList", None),
+        Tag::DefinitionListTitle => ("DefinitionListTitle", None),
+        Tag::DefinitionListDefinition => ("DefinitionListDefinition", None),
+        Tag::Table(alignments) => ("Table", Some(format!("alignments: {alignments:?}"))),
+        Tag::TableHead => ("TableHead", None),
+        Tag::TableRow => ("TableRow", None),
+        Tag::TableCell => ("TableCell", None),
+        Tag::Emphasis => ("Emphasis", None),
+        Tag::Strong => ("Strong", None),
+        Tag::Strikethrough => ("Strikethrough", None),
+        Tag::Superscript => ("Superscript", None),
+        Tag::Subscript => ("Subscript", None),
+        Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        } => ("Link", Some(link_detail(link_type, dest_url, title, id))),
+        Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        } => ("Image", Some(link_detail(link_type, dest_url, title, id))),
+        Tag::MetadataBlock(kind) => ("MetadataBlock", Some(format!("{kind:?}"))),
+    }
+}
+
+/// Returns the attributes of a link or image
+fn link_detail(
+    link_type: &pulldown_cmark::LinkType,
+    dest_url: &str,
+    title: &str,
+    id: &str,
+) -> String {
+    let mut detail = format!("type: {link_type:?}, url: {dest_url:?}");
+    if !title.is_empty() {
+        detail.push_str(&format!(", title: {title:?}"));
+    }
+    if !id.is_empty() {
+        detail.push_str(&format!(", id: {id:?}"));
+    }
+    detail
+}
+
+/// Returns the name and contents of an event other than a start or end
+/// event
+fn event_name_and_detail(event: &Event<'_>) -> (&'static str, Option<String>) {
+    let quoted = |s: &str| Some(format!("{s:?}"));
+    match event {
+        Event::Text(s) => ("Text", quoted(s)),
+        Event::Code(s) => ("Code", quoted(s)),
+        Event::InlineMath(s) => ("InlineMath", quoted(s)),
+        Event::DisplayMath(s) => ("DisplayMath", quoted(s)),
+        Event::Html(s) => ("Html", quoted(s)),
+        Event::InlineHtml(s) => ("InlineHtml", quoted(s)),
+        Event::FootnoteReference(s) => ("FootnoteReference", quoted(s)),
+        Event::SoftBreak => ("SoftBreak", None),
+        Event::HardBreak => ("HardBreak", None),
+        Event::Rule => ("Rule", None),
+        Event::TaskListMarker(checked) => ("TaskListMarker", Some(format!("checked: {checked}"))),
+        Event::Start(_) | Event::End(_) => ("", None),
+    }
+}
+
+/// Write events one per line, including end events, e.g.
+/// `Start(Heading) level: 1 1:1 0..8`, `Text "Title" 1:3 2..7` and
+/// `End(Heading) 1:1 0..8`
+fn write_text<W: Write>(contents: &str, options: &ParserOptions, w: &mut W) -> Result<()> {
+    let lines = Lines::new(contents);
+    let mut names: Vec<&str> = Vec::new();
+    for (event, range) in events_with_offsets(contents, options) {
+        let (line, column) = lines.position(range.start);
+        match event {
+            Event::Start(ref tag) => {
+                let (name, detail) = tag_name_and_detail(tag);
+                names.push(name);
+                write!(w, "Start({name})")?;
+                if let Some(detail) = detail {
+                    write!(w, " {detail}")?;
+                }
+            }
+            Event::End(_) => write!(w, "End({})", names.pop().unwrap_or_default())?,
+            ref event => {
+                let (name, detail) = event_name_and_detail(event);
+                write!(w, "{name}")?;
+                if let Some(detail) = detail {
+                    write!(w, " {detail}")?;
+                }
+            }
+        }
+        writeln!(w, " {line}:{column} {}..{}", range.start, range.end)?;
+    }
+    Ok(())
+}
+
+/// Write events as an indented tree, one event per line e.g.
+/// `Text "Title" 1:3 2..7`
+fn write_tree<W: Write>(nodes: &[EventNode], depth: usize, w: &mut W) -> Result<()> {
+    for n in nodes {
+        write!(w, "{}{}", "  ".repeat(depth), n.event)?;
+        if let Some(ref detail) = n.detail {
+            write!(w, " {detail}")?;
+        }
+        writeln!(w, " {}:{} {}..{}", n.line, n.column, n.start, n.end)?;
+        write_tree(&n.children, depth + 1, w)?;
+    }
+    Ok(())
+}
+
+/// Write the events of a Markdown document.
+pub(crate) fn write_events_to<W: Write>(
+    contents: &str,
+    format: EventFormat,
+    options: &ParserOptions,
+    w: &mut W,
+) -> Result<()> {
+    match format {
+        EventFormat::Text => write_text(contents, options, w),
+        EventFormat::Tree => write_tree(&event_tree(contents, options), 0, w),
+        EventFormat::Json => {
+            serde_json::to_writer_pretty(&mut *w, &event_tree(contents, options))
+                .map_err(std::io::Error::from)?;
+            writeln!(w)?;
+            Ok(())
+        }
+    }
+}
+
+/// Write the events of several Markdown files, each parsed on its own.
+///
+/// files: (path, contents) of each file.
+///
+/// The `text` and `tree` formats start each file with a `# path` line;
+/// the `json` format is an array of `{"file": ..., "events": [...]}`
+/// objects.
+pub(crate) fn write_events_of_files_to<W: Write>(
+    files: &[(String, String)],
+    format: EventFormat,
+    options: &ParserOptions,
+    w: &mut W,
+) -> Result<()> {
+    if format == EventFormat::Json {
+        let files: Vec<FileEvents<'_>> = files
+            .iter()
+            .map(|(path, contents)| FileEvents {
+                file: path,
+                events: event_tree(contents, options),
+            })
+            .collect();
+        serde_json::to_writer_pretty(&mut *w, &files).map_err(std::io::Error::from)?;
+        writeln!(w)?;
+        return Ok(());
+    }
+    for (i, (path, contents)) in files.iter().enumerate() {
+        if i > 0 {
+            writeln!(w)?;
+        }
+        writeln!(w, "# {path}")?;
+        write_events_to(contents, format, options, w)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_tree() {
+        let md = "---\ntitle: T\n---\n# Title\n\nSee [a](https://a.com).\n";
+        let tree = event_tree(md, &ParserOptions::default());
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree[0].event, "Heading");
+        assert_eq!(tree[0].detail.as_deref(), Some("level: 1"));
+        assert_eq!((tree[0].line, tree[0].column), (4, 1));
+        assert_eq!(&md[tree[0].start..tree[0].end], "# Title\n");
+        let link = &tree[1].children[1];
+        assert_eq!(link.event, "Link");
+        assert_eq!(&md[link.start..link.end], "[a](https://a.com)");
+        assert_eq!((link.line, link.column, link.end_line), (6, 5, 6));
+        assert_eq!(link.children[0].detail.as_deref(), Some("\"a\""));
+    }
+
+    #[test]
+    fn test_write_events_to() -> anyhow::Result<()> {
+        let md = "Some *text*\n";
+        let mut buf = Vec::new();
+        write_events_to(md, EventFormat::Tree, &ParserOptions::default(), &mut buf)?;
+        assert_eq!(
+            String::from_utf8(buf)?,
+            "Paragraph 1:1 0..12\n  Text \"Some \" 1:1 0..5\n  Emphasis 1:6 5..11\n    Text \"text\" 1:7 6..10\n"
+        );
+
+        let mut buf = Vec::new();
+        write_events_to(md, EventFormat::Text, &ParserOptions::default(), &mut buf)?;
+        assert_eq!(
+            String::from_utf8(buf)?,
+            "Start(Paragraph) 1:1 0..12\nText \"Some \" 1:1 0..5\nStart(Emphasis) 1:6 5..11\n\
+             Text \"text\" 1:7 6..10\nEnd(Emphasis) 1:6 5..11\nEnd(Paragraph) 1:1 0..12\n"
+        );
+
+        let mut buf = Vec::new();
+        let files = [("a.md".to_string(), md.to_string())];
+        write_events_of_files_to(
+            &files,
+            EventFormat::Json,
+            &ParserOptions::default(),
+            &mut buf,
+        )?;
+        let json: serde_json::Value = serde_json::from_slice(&buf)?;
+        assert_eq!(json[0]["file"], "a.md");
+        assert_eq!(json[0]["events"][0]["children"][1]["event"], "Emphasis");
+        assert_eq!(json[0]["events"][0]["end_line"], 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_event_format_from_str() {
+        assert_eq!("JSON".parse::<EventFormat>().unwrap(), EventFormat::Json);
+        assert!("xml".parse::<EventFormat>().is_err());
+    }
+}
@@ -1,11 +1,12 @@
 //! Output markdown parsing debugging information.
 use std::io::Write;
 
-use anyhow::Result;
 use pulldown_cmark::Event;
 use pulldown_cmark::Parser;
 use pulldown_cmark::Tag;
 
+use crate::Result;
+
 /// Parse Markdown and write all raw events to e.g. a file
 /// for debugging purposes.
 ///
@@ -1,27 +1,23 @@
 //! Write reference definitions
 use std::io::Write;
 
-use anyhow::Result;
-use pulldown_cmark::Parser;
+use crate::Result;
 
-/// Write reference definitions parsed from a Markdown parser to a
-/// file / writer.
+/// Write reference definitions, sorted by label, to a file / writer.
 ///
-/// parser: Markdown parser.
-///
-/// w: Writer e.g. File
-pub(crate) fn write_refdefs_to<W>(parser: &mut Parser<'_>, w: &mut W) -> Result<()>
+/// refdefs: label -> (URL, optional title).
+pub(crate) fn write_sorted_refdefs_to<W>(
+    refdefs: std::collections::BTreeMap<&str, (&str, Option<&str>)>,
+    w: &mut W,
+) -> Result<()>
 where
     W: Write,
 {
-    let sorted_linkdefs: std::collections::BTreeMap<_, _> =
-        parser.reference_definitions().iter().collect();
-
-    for (s, linkdef) in sorted_linkdefs {
-        if let Some(t) = &linkdef.title {
-            writeln!(w, "[{s}]: {} \"{t:?}\"", linkdef.dest)?;
+    for (s, (dest, title)) in refdefs {
+        if let Some(t) = title {
+            writeln!(w, "[{s}]: {dest} \"{t:?}\"")?;
         } else {
-            writeln!(w, "[{s}]: {}", linkdef.dest)?;
+            writeln!(w, "[{s}]: {dest}")?;
         }
     }
     Ok(())